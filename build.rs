@@ -37,9 +37,18 @@ fn exec(command: &str, work_dir: &str) -> Result<String> {
     }
 }
 
+// The bindings are written against this version, other releases of the same
+// major version can be selected with `WEW_CEF_VERSION`.
+const CEF_VERSION: &str = "137.0.17+gf354b0e+chromium-137.0.7151.104";
+
+fn get_cef_version() -> String {
+    env::var("WEW_CEF_VERSION").unwrap_or_else(|_| CEF_VERSION.to_string())
+}
+
 fn get_binary_name() -> String {
     format!(
-        "cef_binary_137.0.17+gf354b0e+chromium-137.0.7151.104_{}{}_minimal",
+        "cef_binary_{}_{}{}_minimal",
+        get_cef_version(),
         if cfg!(target_os = "macos") {
             "macos"
         } else if cfg!(target_os = "windows") {
@@ -55,6 +64,8 @@ fn get_binary_name() -> String {
             } else {
                 "64"
             }
+        } else if cfg!(target_arch = "arm") {
+            "arm"
         } else {
             "32"
         }
@@ -88,13 +99,6 @@ fn download_cef(outdir: &str) -> Result<()> {
     exec("rm -f ./cef.tar.bz2", outdir)?;
     exec(&format!("mv ./{} ./cef", get_binary_name()), outdir)?;
 
-    if is_exsit(&join(outdir, "./cef/Release/cef_sandbox.a")) {
-        exec(
-            "mv ./cef/Release/cef_sandbox.a ./cef/Release/libcef_sandbox.a",
-            outdir,
-        )?;
-    }
-
     Ok(())
 }
 
@@ -136,28 +140,54 @@ fn download_cef(outdir: &str) -> Result<()> {
     Ok(())
 }
 
-fn make_cef(cef_dir: &str) -> Result<()> {
+// The macOS sandbox library is named without the `lib` prefix in the
+// distribution, so it cannot be found by the linker. It is copied into the
+// output directory, which is searched by the linker, so that the distribution
+// is never modified.
+#[cfg(target_os = "macos")]
+fn copy_sandbox_library(cef_dir: &str, outdir: &str) -> Result<()> {
+    let library = join(cef_dir, "./Release/cef_sandbox.a");
+    if is_exsit(&library) {
+        fs::copy(&library, join(outdir, "./libcef_sandbox.a"))?;
+    }
+
+    Ok(())
+}
+
+// The wrapper is built in `build_dir`, which is the distribution itself when it
+// has been downloaded into the output directory.
+fn make_cef(cef_dir: &str, build_dir: &str) -> Result<()> {
     if which("cmake").is_err() {
         panic!("
             You don't have cmake installed, compiling srt requires cmake to do it, now it's unavoidable, you need to install cmake.
                 On debian/ubuntu, you can install it with `sudo apt install cmake`.
+                On fedora, you can install it with `sudo dnf install cmake`.
                 On window, it requires you to go to the official cmake website to load the installation file.
         ");
     }
 
+    // The warning flag is only known by clang, the CEF cmake files build with
+    // `-Werror` on Linux, where the default compiler is usually gcc.
+    fs::create_dir_all(build_dir)?;
     exec(
         &format!(
-            "cmake {} -DCMAKE_BUILD_TYPE=Release .",
-            if cfg!(not(target_os = "windows")) {
+            "cmake {} -DCMAKE_BUILD_TYPE=Release -S \"{}\" -B .",
+            if cfg!(target_os = "macos") {
                 "-DCMAKE_CXX_FLAGS=\"-Wno-deprecated-builtins\""
             } else {
                 ""
-            }
+            },
+            cef_dir,
         ),
-        cef_dir,
+        build_dir,
     )?;
 
-    exec("cmake --build . --config Release", cef_dir)?;
+    // Only the wrapper is needed, the sample applications of the distribution
+    // require additional system packages on Linux.
+    exec(
+        "cmake --build . --config Release --target libcef_dll_wrapper",
+        build_dir,
+    )?;
 
     Ok(())
 }
@@ -187,6 +217,18 @@ fn make_bindgen(outdir: &str, cef_dir: &str) -> Result<()> {
     Ok(())
 }
 
+fn is_sandbox_enabled() -> bool {
+    env::var("CARGO_FEATURE_SANDBOX").is_ok()
+}
+
+fn is_windowless_enabled() -> bool {
+    env::var("CARGO_FEATURE_WINDOWLESS").is_ok()
+}
+
+fn is_request_enabled() -> bool {
+    env::var("CARGO_FEATURE_REQUEST").is_ok()
+}
+
 fn make_library(outdir: &str, cef_dir: &str) -> Result<()> {
     let is_debug = env::var("DEBUG")
         .map(|label| label == "true")
@@ -207,13 +249,30 @@ fn make_library(outdir: &str, cef_dir: &str) -> Result<()> {
             "-std=c++20"
         })
         .include(cef_dir)
+        .define(
+            "WEW_VERSION",
+            Some(format!("\"{}\"", env::var("CARGO_PKG_VERSION")?).as_str()),
+        )
         .file("./cxx/wew.cpp")
         .file("./cxx/util.cpp")
         .file("./cxx/runtime.cpp")
-        .file("./cxx/request.cpp")
         .file("./cxx/subprocess.cpp")
         .file("./cxx/webview.cpp");
 
+    // The optional parts are separate translation units, they are only compiled
+    // when the corresponding feature is enabled.
+    if is_windowless_enabled() {
+        compiler
+            .file("./cxx/render.cpp")
+            .define("WEW_WINDOWLESS", Some("1"));
+    }
+
+    if is_request_enabled() {
+        compiler
+            .file("./cxx/request.cpp")
+            .define("WEW_REQUEST", Some("1"));
+    }
+
     #[cfg(target_os = "windows")]
     compiler
         .define("WIN32", Some("1"))
@@ -242,6 +301,10 @@ fn make_library(outdir: &str, cef_dir: &str) -> Result<()> {
     #[cfg(target_os = "macos")]
     compiler.define("MACOS", Some("1"));
 
+    if is_sandbox_enabled() {
+        compiler.define("WEW_SANDBOX", Some("1"));
+    }
+
     compiler.compile("wew-sys");
 
     Ok(())
@@ -250,9 +313,27 @@ fn make_library(outdir: &str, cef_dir: &str) -> Result<()> {
 fn main() -> Result<()> {
     println!("cargo:rerun-if-changed=./cxx");
     println!("cargo:rerun-if-changed=./build.rs");
+    println!("cargo:rerun-if-env-changed=WEW_CEF_ROOT");
+    println!("cargo:rerun-if-env-changed=WEW_CEF_VERSION");
+
+    let version = get_cef_version();
+    if version.split('.').next() != CEF_VERSION.split('.').next() {
+        println!(
+            "cargo:warning=CEF {} is not supported, only the releases of CEF {} are compatible with the bindings",
+            version,
+            CEF_VERSION.split('.').next().unwrap()
+        );
+    }
 
     let outdir = env::var("OUT_DIR")?;
-    let cef_dir: &str = &join(&outdir, "./cef");
+
+    // A pre-downloaded CEF distribution can be used instead of downloading it
+    // into the output directory, such as for offline builds.
+    let cef_root = env::var("WEW_CEF_ROOT").ok();
+    let cef_dir: &str = &match &cef_root {
+        Some(it) => it.replace("\\", "/"),
+        None => join(&outdir, "./cef"),
+    };
 
     make_bindgen(&outdir, cef_dir)?;
 
@@ -260,12 +341,52 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // The downloaded distribution is replaced when another version is selected.
+    let version_file = join(&outdir, "./cef.version");
+    if cef_root.is_none()
+        && is_exsit(cef_dir)
+        && fs::read_to_string(&version_file).ok() != Some(get_binary_name())
+    {
+        fs::remove_dir_all(cef_dir)?;
+    }
+
     if !is_exsit(cef_dir) {
+        if cef_root.is_some() {
+            return Err(anyhow!(
+                "WEW_CEF_ROOT is set, but the directory does not exist: {}",
+                cef_dir
+            ));
+        }
+
         download_cef(&outdir)?;
+        fs::write(&version_file, get_binary_name())?;
     }
 
-    if !is_exsit(&join(cef_dir, "./libcef_dll_wrapper")) {
-        make_cef(cef_dir)?;
+    #[cfg(target_os = "macos")]
+    copy_sandbox_library(cef_dir, &outdir)?;
+
+    // A distribution given by `WEW_CEF_ROOT` is only read, a build script must
+    // not write outside of its output directory. Its wrapper is used if it has
+    // already been built in place, otherwise it is built in the output
+    // directory.
+    let build_dir = if cef_root.is_some() && !is_exsit(&join(cef_dir, "./libcef_dll_wrapper")) {
+        let build_dir = join(&outdir, "./cef_build");
+
+        // The build directory is configured for one distribution, it is
+        // replaced when another one is selected.
+        let root_file = join(&outdir, "./cef_build.root");
+        if is_exsit(&build_dir) && fs::read_to_string(&root_file).ok().as_deref() != Some(cef_dir) {
+            fs::remove_dir_all(&build_dir)?;
+        }
+
+        fs::write(&root_file, cef_dir)?;
+        build_dir
+    } else {
+        cef_dir.to_string()
+    };
+
+    if !is_exsit(&join(&build_dir, "./libcef_dll_wrapper")) {
+        make_cef(cef_dir, &build_dir)?;
     }
 
     make_library(&outdir, cef_dir)?;
@@ -279,10 +400,24 @@ fn main() -> Result<()> {
         println!("cargo:rustc-link-lib=libcef_dll_wrapper");
         println!(
             "cargo:rustc-link-search=all={}",
-            join(cef_dir, "./libcef_dll_wrapper/Release")
+            join(&build_dir, "./libcef_dll_wrapper/Release")
         );
 
         println!("cargo:rustc-link-search=all={}", join(cef_dir, "./Release"));
+
+        // The sandbox library must be linked into the executable, along with
+        // the system libraries it depends on.
+        if is_sandbox_enabled() {
+            println!("cargo:rustc-link-lib=cef_sandbox");
+
+            for lib in [
+                "advapi32", "dbghelp", "delayimp", "ntdll", "oleaut32", "powrprof", "propsys",
+                "psapi", "setupapi", "shell32", "shcore", "userenv", "version", "wbemuuid",
+                "winmm",
+            ] {
+                println!("cargo:rustc-link-lib={}", lib);
+            }
+        }
     }
 
     #[cfg(target_os = "linux")]
@@ -291,10 +426,16 @@ fn main() -> Result<()> {
         println!("cargo:rustc-link-lib=cef_dll_wrapper");
         println!(
             "cargo:rustc-link-search=all={}",
-            join(cef_dir, "./libcef_dll_wrapper")
+            join(&build_dir, "./libcef_dll_wrapper")
         );
 
         println!("cargo:rustc-link-search=all={}", join(cef_dir, "./Release"));
+
+        // `libcef.so` is placed next to the executable, the dynamic loader
+        // does not search the directory of the executable by default. This only
+        // applies to the targets of this crate, such as its examples and tests,
+        // applications have to add the rpath in their own build script.
+        println!("cargo:rustc-link-arg=-Wl,-rpath,$ORIGIN");
     }
 
     #[cfg(target_os = "macos")]
@@ -308,13 +449,17 @@ fn main() -> Result<()> {
         println!("cargo:rustc-link-lib=cef_dll_wrapper");
         println!(
             "cargo:rustc-link-search=all={}",
-            join(cef_dir, "./libcef_dll_wrapper")
+            join(&build_dir, "./libcef_dll_wrapper")
         );
 
         println!(
             "cargo:rustc-link-search=native={}",
             join(cef_dir, "Release")
         );
+
+        if is_sandbox_enabled() {
+            println!("cargo:rustc-link-lib=static=cef_sandbox");
+        }
     }
 
     Ok(())
@@ -0,0 +1,160 @@
+//
+//  render.cpp
+//  webview
+//
+//  Created by mycrl on 2025/6/19.
+//
+
+#include "render.h"
+
+// clang-format off
+IWebViewRender::IWebViewRender(const WebViewSettings *settings, WebViewHandler &handler)
+    : _handler(handler)
+    , _device_scale_factor(settings->device_scale_factor)
+{
+    assert(settings != nullptr);
+
+    _view_rect.width = settings->width;
+    _view_rect.height = settings->height;
+}
+// clang-format on
+
+bool IWebViewRender::GetScreenInfo(CefRefPtr<CefBrowser> browser, CefScreenInfo &info)
+{
+    ScreenInfo screen_info;
+    screen_info.device_scale_factor = _device_scale_factor;
+    screen_info.depth = info.depth;
+    screen_info.depth_per_component = info.depth_per_component;
+    screen_info.is_monochrome = info.is_monochrome;
+    screen_info.rect = Rect{0, 0, 0, 0};
+    screen_info.available_rect = Rect{0, 0, 0, 0};
+
+    if (!_handler.on_screen_info(&screen_info, _handler.context))
+    {
+        info.device_scale_factor = _device_scale_factor;
+
+        return true;
+    }
+
+    info.device_scale_factor = screen_info.device_scale_factor;
+    info.depth = screen_info.depth;
+    info.depth_per_component = screen_info.depth_per_component;
+    info.is_monochrome = screen_info.is_monochrome;
+    info.rect = CefRect(screen_info.rect.x, screen_info.rect.y, screen_info.rect.width, screen_info.rect.height);
+    info.available_rect = CefRect(screen_info.available_rect.x,
+                                  screen_info.available_rect.y,
+                                  screen_info.available_rect.width,
+                                  screen_info.available_rect.height);
+
+    return true;
+}
+
+void IWebViewRender::OnImeCompositionRangeChanged(CefRefPtr<CefBrowser> browser,
+                                                  const CefRange &selected_range,
+                                                  const RectList &character_bounds)
+{
+    if (character_bounds.size() == 0)
+    {
+        return;
+    }
+
+    auto first_rect = character_bounds[0];
+
+    Rect rect;
+    rect.x = first_rect.x;
+    rect.y = first_rect.y;
+    rect.width = first_rect.width;
+    rect.height = first_rect.height;
+
+    _handler.on_ime_rect(rect, _handler.context);
+}
+
+bool IWebViewRender::StartDragging(CefRefPtr<CefBrowser> browser,
+                                   CefRefPtr<CefDragData> drag_data,
+                                   DragOperationsMask allowed_ops,
+                                   int x,
+                                   int y)
+{
+    std::vector<CefString> file_names;
+    drag_data->GetFileNames(file_names);
+
+    std::vector<std::string> files;
+    std::vector<const char *> files_ptr;
+    for (auto &it : file_names)
+    {
+        files.push_back(it.ToString());
+    }
+
+    for (auto &it : files)
+    {
+        files_ptr.push_back(it.c_str());
+    }
+
+    std::string text = drag_data->GetFragmentText().ToString();
+    std::string url = drag_data->GetLinkURL().ToString();
+
+    DragData data;
+    data.files = files_ptr.data();
+    data.files_size = files_ptr.size();
+    data.text = text.empty() ? nullptr : text.c_str();
+    data.url = url.empty() ? nullptr : url.c_str();
+
+    return _handler.on_start_dragging(&data, allowed_ops, x, y, _handler.context);
+}
+
+void IWebViewRender::UpdateDragCursor(CefRefPtr<CefBrowser> browser, DragOperation operation)
+{
+    _handler.on_update_drag_cursor(operation, _handler.context);
+}
+
+void IWebViewRender::GetViewRect(CefRefPtr<CefBrowser> browser, CefRect &rect)
+{
+    rect.x = _view_rect.x;
+    rect.y = _view_rect.y;
+    rect.width = _view_rect.width;
+    rect.height = _view_rect.height;
+}
+
+void IWebViewRender::OnPaint(CefRefPtr<CefBrowser> browser,
+                             PaintElementType type,
+                             const RectList &dirtyRects,
+                             const void *buffer, // BGRA32
+                             int width,
+                             int height)
+{
+    if (buffer == nullptr)
+    {
+        return;
+    }
+
+    Frame frame;
+    frame.width = width;
+    frame.height = height;
+    frame.buffer = buffer;
+    frame.is_popup = type == PaintElementType::PET_POPUP;
+
+    auto rect = dirtyRects[0];
+    frame.x = frame.is_popup ? _popup_rect.x : rect.x;
+    frame.y = frame.is_popup ? _popup_rect.y : rect.y;
+
+    _handler.on_frame(&frame, _handler.context);
+}
+
+void IWebViewRender::OnPopupSize(CefRefPtr<CefBrowser> browser, const CefRect &rect)
+{
+    _popup_rect.x = rect.x;
+    _popup_rect.y = rect.y;
+    _popup_rect.width = rect.width;
+    _popup_rect.height = rect.height;
+}
+
+void IWebViewRender::Resize(int width, int height)
+{
+    _view_rect.width = width;
+    _view_rect.height = height;
+}
+
+void IWebViewRender::SetDeviceScaleFactor(float device_scale_factor)
+{
+    _device_scale_factor = device_scale_factor;
+}
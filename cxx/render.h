@@ -0,0 +1,90 @@
+//
+//  render.h
+//  webview
+//
+//  Created by mycrl on 2025/6/19.
+//
+
+#ifndef render_h
+#define render_h
+#pragma once
+
+#include <string>
+#include <vector>
+
+#include "include/cef_render_handler.h"
+
+#include "wew.h"
+
+class IWebViewRender : public CefRenderHandler
+{
+  public:
+    IWebViewRender(const WebViewSettings *settings, WebViewHandler &handler);
+
+    ///
+    /// Called to allow the client to fill in the CefScreenInfo object with
+    /// appropriate values.
+    ///
+    bool GetScreenInfo(CefRefPtr<CefBrowser> browser, CefScreenInfo &screen_info) override;
+
+    ///
+    /// Called when the IME composition range has changed.
+    ///
+    void OnImeCompositionRangeChanged(CefRefPtr<CefBrowser> browser,
+                                      const CefRange &selected_range,
+                                      const RectList &character_bounds) override;
+
+    ///
+    /// Called to retrieve the view rectangle in screen DIP coordinates. This
+    /// method must always provide a non-empty rectangle.
+    ///
+    void GetViewRect(CefRefPtr<CefBrowser> browser, CefRect &rect) override;
+
+    ///
+    /// Called when an element should be painted. Pixel values passed to this
+    /// method are scaled relative to view coordinates based on the value of
+    /// CefScreenInfo.device_scale_factor returned from GetScreenInfo.
+    ///
+    void OnPaint(CefRefPtr<CefBrowser> browser,
+                 PaintElementType type,
+                 const RectList &dirtyRects,
+                 const void *buffer,
+                 int width,
+                 int height) override;
+
+    ///
+    /// Called when the browser wants to move or resize the popup widget.
+    ///
+    virtual void OnPopupSize(CefRefPtr<CefBrowser> browser, const CefRect &rect) override;
+
+    ///
+    /// Called when the user starts dragging content in the web view.
+    ///
+    /// Return false to abort the drag operation, or true to handle the drag operation, then call
+    /// CefBrowserHost::DragSourceEndedAt and DragSourceSystemDragEnded once it is completed.
+    ///
+    bool StartDragging(CefRefPtr<CefBrowser> browser,
+                       CefRefPtr<CefDragData> drag_data,
+                       DragOperationsMask allowed_ops,
+                       int x,
+                       int y) override;
+
+    ///
+    /// Called when the web view wants to update the mouse cursor during a drag & drop operation.
+    ///
+    void UpdateDragCursor(CefRefPtr<CefBrowser> browser, DragOperation operation) override;
+
+    void Resize(int width, int height);
+    void SetDeviceScaleFactor(float device_scale_factor);
+
+  private:
+    float _device_scale_factor;
+    WebViewHandler &_handler;
+    CefRect _popup_rect;
+    CefRect _view_rect;
+    Rect _texture_rect;
+
+    IMPLEMENT_REFCOUNTING(IWebViewRender);
+};
+
+#endif /* render_h */
@@ -25,11 +25,39 @@ IResourceHandler::~IResourceHandler()
 
 bool IResourceHandler::Open(CefRefPtr<CefRequest> request, bool &handle_request, CefRefPtr<CefCallback> callback)
 {
+    uint64_t delay = _handler->delay != nullptr ? _handler->delay(_handler->context) : 0;
+    if (delay > 0)
+    {
+        // The request is handled asynchronously, it is opened by a delayed task instead of blocking the IO thread.
+        handle_request = false;
+        CefPostDelayedTask(TID_IO,
+                           base::BindOnce(&IResourceHandler::DelayedOpen, CefRefPtr<IResourceHandler>(this), callback),
+                           delay);
+        return true;
+    }
+
     bool result = _handler->open(_handler->context);
     handle_request = result;
     return result;
 }
 
+void IResourceHandler::DelayedOpen(CefRefPtr<CefCallback> callback)
+{
+    if (_canceled)
+    {
+        return;
+    }
+
+    if (_handler->open(_handler->context))
+    {
+        callback->Continue();
+    }
+    else
+    {
+        callback->Cancel();
+    }
+}
+
 void IResourceHandler::GetResponseHeaders(CefRefPtr<CefResponse> response,
                                           int64_t &response_length,
                                           CefString &redirectUrl)
@@ -66,6 +94,7 @@ bool IResourceHandler::Read(void *data_out,
 
 void IResourceHandler::Cancel()
 {
+    _canceled = true;
     _handler->cancel(_handler->context);
 }
 
@@ -105,7 +134,9 @@ CefRefPtr<CefResourceHandler> IResourceRequestHandler::GetResourceHandler(CefRef
                                                                           CefRefPtr<CefFrame> frame,
                                                                           CefRefPtr<CefRequest> req)
 {
-    if (_factory == nullptr)
+    // The factory can be replaced from another thread, the request uses the one that is current now.
+    const RequestHandlerFactory *factory = _factory.load();
+    if (factory == nullptr)
     {
         return nullptr;
     }
@@ -115,11 +146,16 @@ CefRefPtr<CefResourceHandler> IResourceRequestHandler::GetResourceHandler(CefRef
     std::string url = req->GetURL().ToString();
 
     Request request = {.url = url.c_str(), .method = method.c_str(), .referrer = referrer.c_str()};
-    auto handler = _factory->request(&request, _factory->context);
+    auto handler = factory->request(&request, factory->context);
     if (handler == nullptr)
     {
         return nullptr;
     }
 
-    return new IResourceHandler(_factory, handler);
+    return new IResourceHandler(factory, handler);
+}
+
+void IResourceRequestHandler::SetFactory(const RequestHandlerFactory *factory)
+{
+    _factory.store(factory);
 }
@@ -9,10 +9,13 @@
 #define request_h
 #pragma once
 
+#include <atomic>
 #include <string>
 
+#include "include/base/cef_callback.h"
 #include "include/cef_request_handler.h"
 #include "include/cef_scheme.h"
+#include "include/wrapper/cef_closure_task.h"
 
 #include "wew.h"
 
@@ -56,8 +59,11 @@ class IResourceHandler : public CefResourceHandler
     void Cancel() override;
 
   private:
+    void DelayedOpen(CefRefPtr<CefCallback> callback);
+
     RequestHandler *_handler;
     const RequestHandlerFactory *_factory;
+    bool _canceled = false;
 
     IMPLEMENT_REFCOUNTING(IResourceHandler);
 };
@@ -94,8 +100,13 @@ class IResourceRequestHandler : public CefResourceRequestHandler
                                                      CefRefPtr<CefFrame> frame,
                                                      CefRefPtr<CefRequest> request) override;
 
+    ///
+    /// Replace the factory of the requests made after this call, nullptr lets the requests load normally.
+    ///
+    void SetFactory(const RequestHandlerFactory *factory);
+
   private:
-    const RequestHandlerFactory *_factory = nullptr;
+    std::atomic<const RequestHandlerFactory *> _factory = nullptr;
 
     IMPLEMENT_REFCOUNTING(IResourceRequestHandler);
     DISALLOW_COPY_AND_ASSIGN(IResourceRequestHandler);
@@ -5,13 +5,255 @@
 //  Created by mycrl on 2025/6/19.
 //
 
+#include <memory>
+#include <mutex>
+
 #include "runtime.h"
 
+#ifdef LINUX
+#include <spawn.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#endif
+
+/* CefDevToolsMessageObserver */
+
+// clang-format off
+IGpuInfoObserver::IGpuInfoObserver(void (*callback)(const GpuInfo *info, void *context), void *context)
+    : _callback(callback)
+    , _context(context)
+{
+}
+// clang-format on
+
+void IGpuInfoObserver::Start(CefRefPtr<CefBrowser> browser)
+{
+    _registration = browser->GetHost()->AddDevToolsMessageObserver(this);
+    _message_id = browser->GetHost()->ExecuteDevToolsMethod(0, "SystemInfo.getInfo", nullptr);
+    if (_message_id == 0)
+    {
+        Done(nullptr);
+    }
+}
+
+void IGpuInfoObserver::OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                              int message_id,
+                                              bool success,
+                                              const void *result,
+                                              size_t result_size)
+{
+    if (message_id != _message_id)
+    {
+        return;
+    }
+
+    auto value = success ? CefParseJSON(result, result_size, JSON_PARSER_RFC) : nullptr;
+    if (value == nullptr || value->GetType() != VTYPE_DICTIONARY)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    auto gpu = value->GetDictionary()->GetDictionary("gpu");
+    if (gpu == nullptr)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    // The first device is the active GPU.
+    std::string vendor;
+    std::string device;
+    std::string driver_vendor;
+    std::string driver_version;
+
+    auto devices = gpu->GetList("devices");
+    if (devices != nullptr && devices->GetSize() > 0)
+    {
+        auto it = devices->GetDictionary(0);
+        vendor = it->GetString("vendorString").ToString();
+        device = it->GetString("deviceString").ToString();
+        driver_vendor = it->GetString("driverVendor").ToString();
+        driver_version = it->GetString("driverVersion").ToString();
+    }
+
+    // The status is "enabled", "enabled_on" etc. if the feature is accelerated,
+    // and "disabled_software", "unavailable_off" etc. otherwise.
+    auto status = gpu->GetDictionary("featureStatus");
+    auto is_enabled = [&](const char *name) {
+        return status != nullptr && status->GetString(name).ToString().rfind("enabled", 0) == 0;
+    };
+
+    GpuInfo info;
+    info.vendor = vendor.c_str();
+    info.device = device.c_str();
+    info.driver_vendor = driver_vendor.c_str();
+    info.driver_version = driver_version.c_str();
+    info.gpu_compositing = is_enabled("gpu_compositing");
+    info.rasterization = is_enabled("rasterization");
+    info.video_decode = is_enabled("video_decode");
+
+    Done(&info);
+}
+
+void IGpuInfoObserver::OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser)
+{
+    Done(nullptr);
+}
+
+void IGpuInfoObserver::Done(const GpuInfo *info)
+{
+    if (_done)
+    {
+        return;
+    }
+
+    _done = true;
+    _callback(info, _context);
+
+    // The registration holds a reference to this observer.
+    _registration = nullptr;
+}
+
+static void RequestGpuInfo(void (*callback)(const GpuInfo *info, void *context), void *context)
+{
+    auto &browsers = IWebViewLifeSpan::GetBrowsers();
+    if (browsers.empty())
+    {
+        callback(nullptr, context);
+        return;
+    }
+
+    CefRefPtr<IGpuInfoObserver> observer = new IGpuInfoObserver(callback, context);
+    observer->Start(browsers.begin()->second);
+}
+
+#ifdef LINUX
+
+/* CefPrintHandler */
+
+// The paper is A4, in micrometers.
+static constexpr int PAPER_WIDTH = 210000;
+static constexpr int PAPER_HEIGHT = 297000;
+static constexpr int PRINT_DPI = 300;
+
+static CefSize GetPaperSize(int device_units_per_inch)
+{
+    return CefSize(PAPER_WIDTH * device_units_per_inch / 25400, PAPER_HEIGHT * device_units_per_inch / 25400);
+}
+
+static void SetDefaultPrintSettings(CefRefPtr<CefPrintSettings> settings)
+{
+    auto size = GetPaperSize(PRINT_DPI);
+
+    settings->SetOrientation(false);
+    settings->SetDPI(PRINT_DPI);
+    settings->SetPrinterPrintableArea(size, CefRect(0, 0, size.width, size.height), false);
+    settings->SetColorModel(COLOR_MODEL_COLOR);
+    settings->SetDuplexMode(DUPLEX_MODE_SIMPLEX);
+    settings->SetCopies(1);
+}
+
+// All browsers are created with an IWebView as their client.
+static CefRefPtr<IWebView> GetWebView(CefRefPtr<CefBrowser> browser)
+{
+    return static_cast<IWebView *>(browser->GetHost()->GetClient().get());
+}
+
+// The document is sent to the default printer with the CUPS client, it copies
+// the file to the spool before it exits, so the file can be deleted afterwards.
+static void SubmitPrintJob(std::string document_name, std::string pdf_file_path)
+{
+    std::string title = "-t" + document_name;
+    char *argv[] = {(char *)"lp", title.data(), (char *)"--", pdf_file_path.data(), nullptr};
+
+    pid_t pid;
+    if (posix_spawnp(&pid, "lp", nullptr, nullptr, argv, environ) == 0)
+    {
+        waitpid(pid, nullptr, 0);
+    }
+}
+
+void IPrintHandler::OnPrintStart(CefRefPtr<CefBrowser> browser)
+{
+}
+
+void IPrintHandler::OnPrintSettings(CefRefPtr<CefBrowser> browser,
+                                    CefRefPtr<CefPrintSettings> settings,
+                                    bool get_defaults)
+{
+    // There is no printer state to synchronize, the settings of the dialog are
+    // always the default settings.
+    if (get_defaults)
+    {
+        SetDefaultPrintSettings(settings);
+    }
+}
+
+bool IPrintHandler::OnPrintDialog(CefRefPtr<CefBrowser> browser,
+                                  bool has_selection,
+                                  CefRefPtr<CefPrintDialogCallback> callback)
+{
+    auto webview = GetWebView(browser);
+    if (webview == nullptr || !webview->OnPrintDialog(has_selection))
+    {
+        callback->Cancel();
+        return true;
+    }
+
+    auto settings = CefPrintSettings::Create();
+    SetDefaultPrintSettings(settings);
+    callback->Continue(settings);
+
+    return true;
+}
+
+bool IPrintHandler::OnPrintJob(CefRefPtr<CefBrowser> browser,
+                               const CefString &document_name,
+                               const CefString &pdf_file_path,
+                               CefRefPtr<CefPrintJobCallback> callback)
+{
+    auto webview = GetWebView(browser);
+    if (webview == nullptr || !webview->OnPrintJob(document_name.ToString(), pdf_file_path.ToString()))
+    {
+        SubmitPrintJob(document_name.ToString(), pdf_file_path.ToString());
+    }
+
+    callback->Continue();
+    return true;
+}
+
+void IPrintHandler::OnPrintReset(CefRefPtr<CefBrowser> browser)
+{
+}
+
+CefSize IPrintHandler::GetPdfPaperSize(CefRefPtr<CefBrowser> browser, int device_units_per_inch)
+{
+    return GetPaperSize(device_units_per_inch);
+}
+
+#endif
+
 // clang-format off
 IRuntime::IRuntime(const RuntimeSettings *settings, CefSettings cef_settings, RuntimeHandler handler)
     : _handler(handler)
     , _cef_settings(cef_settings)
+    , _disable_accelerated_2d_canvas(settings->disable_accelerated_2d_canvas)
+    , _disable_hardware_video_decode(settings->disable_hardware_video_decode)
+    , _disable_gpu_compositing(settings->disable_gpu_compositing)
+    , _software_rendering(settings->software_rendering)
+    , _allow_running_insecure_content(settings->allow_running_insecure_content)
+    , _allow_file_access_from_files(settings->allow_file_access_from_files)
+    , _disable_web_security(settings->disable_web_security)
+    , _autoplay_policy(settings->autoplay_policy)
+    , _disable_autofill(settings->disable_autofill)
+    , _disable_password_manager(settings->disable_password_manager)
+    , _force_renderer_accessibility(settings->force_renderer_accessibility)
+    , _screen_reader_present(settings->screen_reader_present)
+    , _caret_browsing(settings->caret_browsing)
+    , _spatial_navigation(settings->spatial_navigation)
 {
+#ifdef WEW_REQUEST
     if (settings->custom_scheme != nullptr)
     {
         assert(settings->custom_scheme->factory != nullptr);
@@ -22,6 +264,7 @@ IRuntime::IRuntime(const RuntimeSettings *settings, CefSettings cef_settings, Ru
             .factory = settings->custom_scheme->factory,
         };
     }
+#endif
 }
 // clang-format on
 
@@ -39,29 +282,132 @@ CefRefPtr<CefBrowserProcessHandler> IRuntime::GetBrowserProcessHandler()
 
 void IRuntime::OnRegisterCustomSchemes(CefRawPtr<CefSchemeRegistrar> registrar)
 {
+#ifdef WEW_REQUEST
     if (_custom_scheme.has_value())
     {
         registrar->AddCustomScheme(_custom_scheme.value().name,
                                    CEF_SCHEME_OPTION_STANDARD | CEF_SCHEME_OPTION_SECURE |
                                        CEF_SCHEME_OPTION_CORS_ENABLED | CEF_SCHEME_OPTION_FETCH_ENABLED);
     }
+#endif
 }
 
 void IRuntime::OnBeforeCommandLineProcessing(const CefString &process_type, CefRefPtr<CefCommandLine> command_line)
 {
     command_line->AppendSwitch("use-mock-keychain");
+
+    // These options are not available in CefBrowserSettings, they apply to all
+    // browsers of the process.
+    if (_disable_accelerated_2d_canvas)
+    {
+        command_line->AppendSwitch("disable-accelerated-2d-canvas");
+    }
+
+    if (_disable_hardware_video_decode)
+    {
+        command_line->AppendSwitch("disable-accelerated-video-decode");
+    }
+
+    if (_disable_gpu_compositing || _software_rendering)
+    {
+        command_line->AppendSwitch("disable-gpu-compositing");
+    }
+
+    if (_software_rendering)
+    {
+        command_line->AppendSwitch("disable-gpu");
+    }
+
+    if (_allow_running_insecure_content)
+    {
+        command_line->AppendSwitch("allow-running-insecure-content");
+    }
+
+    if (_allow_file_access_from_files)
+    {
+        command_line->AppendSwitch("allow-file-access-from-files");
+    }
+
+    if (_disable_web_security)
+    {
+        command_line->AppendSwitch("disable-web-security");
+    }
+
+    // The complete mode includes the screen reader mode, which exposes the
+    // extended properties that screen readers rely on. The basic mode only
+    // exposes the content of the page.
+    if (_screen_reader_present)
+    {
+        command_line->AppendSwitchWithValue("force-renderer-accessibility", "complete");
+    }
+    else if (_force_renderer_accessibility)
+    {
+        command_line->AppendSwitchWithValue("force-renderer-accessibility", "basic");
+    }
+
+    if (_caret_browsing)
+    {
+        command_line->AppendSwitch("enable-caret-browsing");
+    }
+
+    if (_spatial_navigation)
+    {
+        command_line->AppendSwitch("enable-spatial-navigation");
+    }
+
+    switch (_autoplay_policy)
+    {
+    case AutoplayPolicy::WEW_AUTOPLAY_NO_USER_GESTURE_REQUIRED:
+        command_line->AppendSwitchWithValue("autoplay-policy", "no-user-gesture-required");
+        break;
+    case AutoplayPolicy::WEW_AUTOPLAY_DOCUMENT_USER_ACTIVATION_REQUIRED:
+        command_line->AppendSwitchWithValue("autoplay-policy", "document-user-activation-required");
+        break;
+    case AutoplayPolicy::WEW_AUTOPLAY_USER_GESTURE_REQUIRED:
+        command_line->AppendSwitchWithValue("autoplay-policy", "user-gesture-required");
+        break;
+    default:
+        break;
+    }
+
+    if (_handler.on_before_command_line_processing != nullptr)
+    {
+        _handler.on_before_command_line_processing(command_line.get(), _handler.context);
+    }
 }
 
 void IRuntime::OnContextInitialized()
 {
     CHECK_REFCOUNTING();
 
+#ifdef WEW_REQUEST
     if (_custom_scheme.has_value())
     {
         CefRegisterSchemeHandlerFactory(_custom_scheme.value().name,
                                         _custom_scheme.value().domain,
                                         new ISchemeHandlerFactory(_custom_scheme.value()));
     }
+#endif
+
+    // These are preferences of the profile, they apply to all browsers that use
+    // the global request context. They cannot be set per browser, a request
+    // context created from the global one shares its profile and preferences.
+    auto request_context = CefRequestContext::GetGlobalContext();
+    auto disabled = CefValue::Create();
+    disabled->SetBool(false);
+
+    CefString error;
+    if (_disable_autofill)
+    {
+        request_context->SetPreference("autofill.profile_enabled", disabled, error);
+        request_context->SetPreference("autofill.credit_card_enabled", disabled, error);
+    }
+
+    if (_disable_password_manager)
+    {
+        request_context->SetPreference("credentials_enable_service", disabled, error);
+        request_context->SetPreference("credentials_enable_autosignin", disabled, error);
+    }
 
     _handler.on_context_initialized(_handler.context);
 }
@@ -80,21 +426,100 @@ void IRuntime::OnScheduleMessagePumpWork(int64_t delay_ms)
 
 void IRuntime::OnBeforeChildProcessLaunch(CefRefPtr<CefCommandLine> command_line)
 {
+#ifdef WEW_REQUEST
     if (_custom_scheme.has_value())
     {
         command_line->AppendSwitchWithValue("scheme-name", _custom_scheme.value().name);
     }
+#endif
 }
 
+#ifdef LINUX
+CefRefPtr<CefPrintHandler> IRuntime::GetPrintHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _print_handler;
+}
+#endif
+
 CefSettings &IRuntime::GetCefSettings()
 {
     return _cef_settings;
 }
 
+// The calling thread stops waiting for the UI thread after this time, such as
+// when the UI thread is itself waiting for the calling thread.
+static constexpr int64_t CREATE_WEBVIEW_TIMEOUT_MS = 30000;
+
+// The state shared by the calling thread and the task that creates the webview
+// on the UI thread, the calling thread may stop waiting before the task runs.
+struct PendingWebView
+{
+    std::mutex mutex;
+    bool canceled = false;
+    CefRefPtr<IWebView> webview = nullptr;
+    CefRefPtr<CefWaitableEvent> event = CefWaitableEvent::CreateWaitableEvent(true, false);
+};
+
+// Wakes up the calling thread when the task is destroyed, whether it has run
+// or has been discarded without running.
+class PendingWebViewSignal
+{
+  public:
+    explicit PendingWebViewSignal(std::shared_ptr<PendingWebView> pending) : _pending(pending)
+    {
+    }
+
+    ~PendingWebViewSignal()
+    {
+        _pending->event->Signal();
+    }
+
+    std::shared_ptr<PendingWebView> _pending;
+};
+
 CefRefPtr<IWebView> IRuntime::CreateWebView(std::string url, const WebViewSettings *settings, WebViewHandler handler)
 {
     CHECK_REFCOUNTING(nullptr);
 
+    // The browser is created synchronously on the UI thread, the calling thread
+    // is blocked until it exists. The settings are only read while the calling
+    // thread is waiting, so they can be borrowed by the task, the task does not
+    // run once the calling thread has stopped waiting.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        auto pending = std::make_shared<PendingWebView>();
+
+        // clang-format off
+        auto task = base::BindOnce(
+            [](CefRefPtr<IRuntime> self,
+               std::string url,
+               const WebViewSettings *settings,
+               WebViewHandler handler,
+               std::unique_ptr<PendingWebViewSignal> signal)
+            {
+                std::lock_guard<std::mutex> lock(signal->_pending->mutex);
+                if (!signal->_pending->canceled)
+                {
+                    signal->_pending->webview = self->CreateWebView(url, settings, handler);
+                }
+            },
+            CefRefPtr<IRuntime>(this), url, settings, handler, std::make_unique<PendingWebViewSignal>(pending));
+        // clang-format on
+
+        if (!CefPostTask(TID_UI, std::move(task)))
+        {
+            return nullptr;
+        }
+
+        pending->event->TimedWait(CREATE_WEBVIEW_TIMEOUT_MS);
+
+        std::lock_guard<std::mutex> lock(pending->mutex);
+        pending->canceled = true;
+        return pending->webview;
+    }
+
     CefBrowserSettings broswer_settings;
 
     // clang-format off
@@ -105,6 +530,7 @@ CefRefPtr<IWebView> IRuntime::CreateWebView(std::string url, const WebViewSettin
     broswer_settings.webgl = settings->webgl ? STATE_ENABLED : STATE_DISABLED;
     broswer_settings.databases = settings->databases ? STATE_ENABLED : STATE_DISABLED;
     broswer_settings.local_storage = settings->local_storage ? STATE_ENABLED : STATE_DISABLED;
+    broswer_settings.image_loading = settings->images ? STATE_ENABLED : STATE_DISABLED;
     broswer_settings.javascript = settings->javascript ? STATE_ENABLED : STATE_DISABLED;
     broswer_settings.javascript_access_clipboard = settings->javascript_access_clipboard ? STATE_ENABLED : STATE_DISABLED;
     broswer_settings.javascript_dom_paste = settings->javascript_dom_paste ? STATE_ENABLED : STATE_DISABLED;
@@ -113,6 +539,41 @@ CefRefPtr<IWebView> IRuntime::CreateWebView(std::string url, const WebViewSettin
     broswer_settings.windowless_frame_rate = settings->windowless_frame_rate;
     // clang-format on
 
+    if (settings->standard_font_family != nullptr)
+    {
+        CefString(&broswer_settings.standard_font_family).FromString(settings->standard_font_family);
+    }
+
+    if (settings->fixed_font_family != nullptr)
+    {
+        CefString(&broswer_settings.fixed_font_family).FromString(settings->fixed_font_family);
+    }
+
+    if (settings->serif_font_family != nullptr)
+    {
+        CefString(&broswer_settings.serif_font_family).FromString(settings->serif_font_family);
+    }
+
+    if (settings->sans_serif_font_family != nullptr)
+    {
+        CefString(&broswer_settings.sans_serif_font_family).FromString(settings->sans_serif_font_family);
+    }
+
+    if (settings->cursive_font_family != nullptr)
+    {
+        CefString(&broswer_settings.cursive_font_family).FromString(settings->cursive_font_family);
+    }
+
+    if (settings->fantasy_font_family != nullptr)
+    {
+        CefString(&broswer_settings.fantasy_font_family).FromString(settings->fantasy_font_family);
+    }
+
+    if (settings->default_encoding != nullptr)
+    {
+        CefString(&broswer_settings.default_encoding).FromString(settings->default_encoding);
+    }
+
     CefWindowInfo window_info;
     if (_cef_settings.windowless_rendering_enabled)
     {
@@ -131,8 +592,16 @@ CefRefPtr<IWebView> IRuntime::CreateWebView(std::string url, const WebViewSettin
         }
     }
 
+    // These values are needed by the render process, they are passed to
+    // CefRenderProcessHandler::OnBrowserCreated.
+    CefRefPtr<CefDictionaryValue> extra_info = CefDictionaryValue::Create();
+    extra_info->SetInt("max_in_flight_messages", settings->max_in_flight_messages);
+    extra_info->SetInt("message_overflow_policy", settings->message_overflow_policy);
+    extra_info->SetInt("max_pending_messages", settings->max_pending_messages);
+    extra_info->SetBool("clipboard_policy", settings->clipboard_policy);
+
     CefRefPtr<IWebView> webview = new IWebView(_cef_settings, settings, handler);
-    if (!CefBrowserHost::CreateBrowser(window_info, webview, url, broswer_settings, nullptr, nullptr))
+    if (!CefBrowserHost::CreateBrowserSync(window_info, webview, url, broswer_settings, extra_info, nullptr))
     {
         return nullptr;
     }
@@ -140,6 +609,46 @@ CefRefPtr<IWebView> IRuntime::CreateWebView(std::string url, const WebViewSettin
     return webview;
 }
 
+void IRuntime::CloseBrowsers()
+{
+    CHECK_REFCOUNTING();
+
+    // The browsers are tracked on the UI thread.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI, base::BindOnce(&IRuntime::CloseBrowsers, CefRefPtr<IRuntime>(this)));
+        return;
+    }
+
+    for (auto &[_, browser] : IWebViewLifeSpan::GetBrowsers())
+    {
+        browser->GetHost()->CloseBrowser(true);
+    }
+}
+
+size_t IRuntime::GetBrowserCount()
+{
+    return IWebViewLifeSpan::GetBrowserCount();
+}
+
+void IRuntime::GetGpuInfo(void (*callback)(const GpuInfo *info, void *context), void *context)
+{
+    // The callback is always called, so that the caller can release the
+    // context.
+    if (!_is_running || !CefPostTask(TID_UI, base::BindOnce(&RequestGpuInfo, callback, context)))
+    {
+        callback(nullptr, context);
+    }
+}
+
+void IRuntime::Shutdown()
+{
+    CHECK_REFCOUNTING();
+    CLOSE_RUNNING;
+
+    CefShutdown();
+}
+
 void IRuntime::Close()
 {
     CLOSE_RUNNING;
@@ -13,11 +13,110 @@
 #include <string>
 
 #include "include/cef_app.h"
+#include "include/cef_devtools_message_observer.h"
+#include "include/cef_parser.h"
+#include "include/cef_print_handler.h"
+#include "include/cef_request_context.h"
+#include "include/cef_waitable_event.h"
 
-#include "request.h"
 #include "webview.h"
 #include "wew.h"
 
+#ifdef WEW_REQUEST
+#include "request.h"
+#endif
+
+class IGpuInfoObserver : public CefDevToolsMessageObserver
+{
+  public:
+    IGpuInfoObserver(void (*callback)(const GpuInfo *info, void *context), void *context);
+
+    /* CefDevToolsMessageObserver */
+
+    ///
+    /// Method that will be called after attempted execution of a DevTools protocol method.
+    ///
+    void OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                int message_id,
+                                bool success,
+                                const void *result,
+                                size_t result_size) override;
+
+    ///
+    /// Method that will be called when the DevTools agent has detached.
+    ///
+    void OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser) override;
+
+    /* custom impl */
+
+    void Start(CefRefPtr<CefBrowser> browser);
+
+  private:
+    void Done(const GpuInfo *info);
+
+    void (*_callback)(const GpuInfo *info, void *context);
+    void *_context;
+    CefRefPtr<CefRegistration> _registration = nullptr;
+    int _message_id = 0;
+    bool _done = false;
+
+    IMPLEMENT_REFCOUNTING(IGpuInfoObserver);
+};
+
+#ifdef LINUX
+class IPrintHandler : public CefPrintHandler
+{
+  public:
+    /* CefPrintHandler */
+
+    ///
+    /// Called when printing has started for the specified |browser|.
+    ///
+    void OnPrintStart(CefRefPtr<CefBrowser> browser) override;
+
+    ///
+    /// Synchronize |settings| with client state.
+    ///
+    /// If |get_defaults| is true then populate |settings| with the default print settings.
+    ///
+    void OnPrintSettings(CefRefPtr<CefBrowser> browser, CefRefPtr<CefPrintSettings> settings, bool get_defaults) override;
+
+    ///
+    /// Show the print dialog.
+    ///
+    /// Execute |callback| once the dialog is dismissed. Return true if the dialog will be displayed or false to cancel
+    /// the printing immediately.
+    ///
+    bool OnPrintDialog(CefRefPtr<CefBrowser> browser,
+                       bool has_selection,
+                       CefRefPtr<CefPrintDialogCallback> callback) override;
+
+    ///
+    /// Send the print job to the printer.
+    ///
+    /// Execute |callback| once the job is completed. Return true if the job will proceed or false to cancel the job
+    /// immediately.
+    ///
+    bool OnPrintJob(CefRefPtr<CefBrowser> browser,
+                    const CefString &document_name,
+                    const CefString &pdf_file_path,
+                    CefRefPtr<CefPrintJobCallback> callback) override;
+
+    ///
+    /// Reset client state related to printing.
+    ///
+    void OnPrintReset(CefRefPtr<CefBrowser> browser) override;
+
+    ///
+    /// Return the PDF paper size in device units.
+    ///
+    CefSize GetPdfPaperSize(CefRefPtr<CefBrowser> browser, int device_units_per_inch) override;
+
+  private:
+    IMPLEMENT_REFCOUNTING(IPrintHandler);
+};
+#endif
+
 class IRuntime : public CefApp, public CefBrowserProcessHandler
 {
   public:
@@ -65,16 +164,48 @@ class IRuntime : public CefApp, public CefBrowserProcessHandler
     ///
     void OnBeforeChildProcessLaunch(CefRefPtr<CefCommandLine> command_line) override;
 
+#ifdef LINUX
+    ///
+    /// Return the handler for printing on Linux.
+    ///
+    /// If a print handler is not provided then printing will not be supported on the Linux platform.
+    ///
+    CefRefPtr<CefPrintHandler> GetPrintHandler() override;
+#endif
+
     /* custom impl */
 
     CefRefPtr<IWebView> CreateWebView(std::string url, const WebViewSettings *settings, WebViewHandler handler);
     CefSettings &GetCefSettings();
+    void CloseBrowsers();
+    size_t GetBrowserCount();
+    void GetGpuInfo(void (*callback)(const GpuInfo *info, void *context), void *context);
+    void Shutdown();
     void Close();
 
   private:
+#ifdef WEW_REQUEST
     std::optional<ICustomSchemeAttributes> _custom_scheme = std::nullopt;
+#endif
     CefSettings _cef_settings;
     RuntimeHandler _handler;
+    bool _disable_accelerated_2d_canvas = false;
+    bool _disable_hardware_video_decode = false;
+    bool _disable_gpu_compositing = false;
+    bool _software_rendering = false;
+    bool _allow_running_insecure_content = false;
+    bool _allow_file_access_from_files = false;
+    bool _disable_web_security = false;
+    AutoplayPolicy _autoplay_policy = AutoplayPolicy::WEW_AUTOPLAY_DEFAULT;
+    bool _disable_autofill = false;
+    bool _disable_password_manager = false;
+    bool _force_renderer_accessibility = false;
+    bool _screen_reader_present = false;
+    bool _caret_browsing = false;
+    bool _spatial_navigation = false;
+#ifdef LINUX
+    CefRefPtr<IPrintHandler> _print_handler = new IPrintHandler();
+#endif
 
     IMPLEMENT_RUNNING;
     IMPLEMENT_REFCOUNTING(IRuntime);
@@ -6,6 +6,134 @@
 //
 
 #include "subprocess.h"
+#include "util.h"
+
+#ifdef WIN32
+#include <windows.h>
+#else
+#include <unistd.h>
+#endif
+
+#ifndef WEW_VERSION
+#define WEW_VERSION "unknown"
+#endif
+
+// The methods of the async clipboard API are replaced on the prototype, so that
+// every call asks the browser process for the policy of the frame first. The
+// function receives the native `request(access, callback)` function. This is
+// advisory only, the page can reach the clipboard through other APIs.
+static const char *CLIPBOARD_GUARD_SCRIPT = R"((function (request) {
+    if (typeof Clipboard === 'undefined') {
+        return;
+    }
+
+    const READ = 0;
+    const WRITE = 1;
+    const ALLOW = 0;
+    const SANITIZE_HTML = 2;
+
+    const toText = (html) => new DOMParser().parseFromString(html, 'text/html').body.textContent || '';
+
+    // The HTML representation of the items is replaced with its plain text.
+    const sanitize = async (items) => {
+        const result = [];
+        for (const item of items) {
+            const types = {};
+            for (const type of item.types) {
+                if (type !== 'text/html') {
+                    types[type] = item.getType(type);
+                }
+            }
+
+            if (item.types.includes('text/html') && !item.types.includes('text/plain')) {
+                const html = await (await item.getType('text/html')).text();
+                types['text/plain'] = new Blob([toText(html)], { type: 'text/plain' });
+            }
+
+            if (Object.keys(types).length > 0) {
+                result.push(new ClipboardItem(types));
+            }
+        }
+
+        return result;
+    };
+
+    const guard = (method, access, sanitized) => {
+        const original = Clipboard.prototype[method];
+        Clipboard.prototype[method] = function (...args) {
+            return new Promise((resolve, reject) => {
+                request(access, (policy) => {
+                    if (policy === ALLOW) {
+                        original.apply(this, args).then(resolve, reject);
+                    } else if (policy === SANITIZE_HTML) {
+                        sanitized.call(this, original, args).then(resolve, reject);
+                    } else {
+                        reject(new DOMException('Clipboard access is denied by the host.', 'NotAllowedError'));
+                    }
+                });
+            });
+        };
+    };
+
+    guard('read', READ, async function (original, args) {
+        return sanitize(await original.apply(this, args));
+    });
+
+    guard('readText', READ, function (original, args) {
+        return original.apply(this, args);
+    });
+
+    guard('write', WRITE, async function (original, args) {
+        return original.call(this, await sanitize(args[0] || []));
+    });
+
+    guard('writeText', WRITE, function (original, args) {
+        return original.apply(this, args);
+    });
+}))";
+
+// The built-in PDF viewer keeps the state of the document in its own fields,
+// they are read until the document has loaded. These are private fields of the
+// viewer of Chromium 137 (`loadState_`, `documentDimensions_`, `bookmarks_` and
+// `originalUrl_`), a renamed field makes the document never look loaded, so the
+// polling stops after 30 seconds. The function receives the native
+// `report(url, pageCount, outline)` function.
+static const char *PDF_OBSERVER_SCRIPT = R"((function (report) {
+    const deadline = Date.now() + 30000;
+
+    const outline = (bookmarks) => (bookmarks || []).map((it) => ({
+        title: it.title || '',
+        page: typeof it.page === 'number' ? it.page : null,
+        uri: it.uri || null,
+        children: outline(it.children),
+    }));
+
+    const poll = () => {
+        const viewer = document.querySelector('pdf-viewer');
+        const state = viewer ? viewer.loadState_ : undefined;
+        if (state === 'failed') {
+            return;
+        }
+
+        const dimensions = viewer ? viewer.documentDimensions_ : undefined;
+        if (state !== 'success' || !dimensions) {
+            if (Date.now() < deadline) {
+                setTimeout(poll, 100);
+            }
+
+            return;
+        }
+
+        // The document has loaded, it is reported once and the polling stops.
+        report(viewer.originalUrl_ || '', dimensions.pageDimensions.length, JSON.stringify(outline(viewer.bookmarks_)));
+    };
+
+    poll();
+}))";
+
+ISubProcess::ISubProcess(std::optional<SubProcessHandler> handler) : _handler(handler)
+{
+}
 
 CefRefPtr<CefRenderProcessHandler> ISubProcess::GetRenderProcessHandler()
 {
@@ -23,18 +151,189 @@ void ISubProcess::OnRegisterCustomSchemes(CefRawPtr<CefSchemeRegistrar> registra
     }
 }
 
+void ISubProcess::OnWebKitInitialized()
+{
+    if (_handler.has_value() && _handler->on_webkit_initialized != nullptr)
+    {
+        _handler->on_webkit_initialized(_handler->context);
+    }
+}
+
+void ISubProcess::OnBrowserCreated(CefRefPtr<CefBrowser> browser, CefRefPtr<CefDictionaryValue> extra_info)
+{
+    if (extra_info == nullptr)
+    {
+        return;
+    }
+
+    _sender->SetLimits(browser->GetIdentifier(),
+                       extra_info->GetInt("max_in_flight_messages"),
+                       static_cast<MessageOverflowPolicy>(extra_info->GetInt("message_overflow_policy")));
+    _receiver->SetLimit(browser->GetIdentifier(), static_cast<size_t>(extra_info->GetInt("max_pending_messages")));
+
+    if (extra_info->GetBool("clipboard_policy"))
+    {
+        _clipboard_policy_browsers.insert(browser->GetIdentifier());
+    }
+}
+
+void ISubProcess::OnBrowserDestroyed(CefRefPtr<CefBrowser> browser)
+{
+    _clipboard_policy_browsers.erase(browser->GetIdentifier());
+    _sender->RemoveLimits(browser->GetIdentifier());
+    _receiver->RemoveLimit(browser->GetIdentifier());
+}
+
 void ISubProcess::OnContextCreated(CefRefPtr<CefBrowser> browser,
                                    CefRefPtr<CefFrame> frame,
                                    CefRefPtr<CefV8Context> context)
 {
     _sender->SetBrowser(browser);
 
+    // A navigation can move the page to another render process, the browser
+    // process is told which process currently renders the main frame.
+    if (frame->IsMain())
+    {
+        auto msg = CefProcessMessage::Create("PROCESS_INFO");
+#ifdef WIN32
+        msg->GetArgumentList()->SetInt(0, static_cast<int>(GetCurrentProcessId()));
+#else
+        msg->GetArgumentList()->SetInt(0, static_cast<int>(getpid()));
+#endif
+        frame->SendProcessMessage(PID_BROWSER, msg);
+    }
+
+    _receiver->Reset(frame->GetIdentifier());
+
     CefRefPtr<CefV8Value> native = CefV8Value::CreateObject(nullptr, nullptr);
     native->SetValue("send", CefV8Value::CreateFunction("send", _sender), V8_PROPERTY_ATTRIBUTE_NONE);
     native->SetValue("on", CefV8Value::CreateFunction("on", _receiver), V8_PROPERTY_ATTRIBUTE_NONE);
+    native->SetValue("channel", CefV8Value::CreateFunction("channel", _channels), V8_PROPERTY_ATTRIBUTE_NONE);
+
+    // The bridge is installed before any script of the page runs, the promise
+    // is already resolved and only lets pages await the host uniformly.
+    context->Enter();
+    CefRefPtr<CefV8Value> ready = CefV8Value::CreatePromise();
+    ready->ResolvePromise(CefV8Value::CreateUndefined());
+    context->Exit();
+
+    CefRefPtr<CefV8Value> wew = CefV8Value::CreateObject(nullptr, nullptr);
+    wew->SetValue("version", CefV8Value::CreateString(WEW_VERSION), V8_PROPERTY_ATTRIBUTE_READONLY);
+    wew->SetValue("ready", ready, V8_PROPERTY_ATTRIBUTE_READONLY);
+    wew->SetValue("postMessage", CefV8Value::CreateFunction("postMessage", _sender), V8_PROPERTY_ATTRIBUTE_NONE);
+    wew->SetValue("on", CefV8Value::CreateFunction("on", _emitter), V8_PROPERTY_ATTRIBUTE_NONE);
 
     CefRefPtr<CefV8Value> global = context->GetGlobal();
     global->SetValue("MessageTransport", std::move(native), V8_PROPERTY_ATTRIBUTE_NONE);
+    global->SetValue("wew", std::move(wew), V8_PROPERTY_ATTRIBUTE_NONE);
+
+    // The print events of the main frame are reported to the browser process,
+    // they are fired for the printing started by the host and by the page.
+    if (frame->IsMain())
+    {
+        context->Enter();
+
+        auto add_event_listener = global->GetValue("addEventListener");
+        for (std::string event : {"beforeprint", "afterprint"})
+        {
+            CefV8ValueList arguments = {CefV8Value::CreateString(event),
+                                        CefV8Value::CreateFunction(event, _print_observer)};
+            add_event_listener->ExecuteFunction(global, arguments);
+        }
+
+        context->Exit();
+    }
+
+    // The viewer is a frame of its own, it reports the documents it loads.
+    if (frame->GetURL().ToString().rfind(PDF_VIEWER_ORIGIN, 0) == 0)
+    {
+        _pdf_observer->Install(context);
+    }
+
+    // Every frame is guarded, a child frame of another origin has its own
+    // policy.
+    if (_clipboard_policy_browsers.count(browser->GetIdentifier()) > 0)
+    {
+        _clipboard_guard->Install(context);
+    }
+
+    // The user handler runs after the built-in objects are injected, so that
+    // its bindings can rely on them.
+    if (_handler.has_value())
+    {
+        CallContextHandler(_handler->on_context_created, frame, context);
+    }
+}
+
+void ISubProcess::OnContextReleased(CefRefPtr<CefBrowser> browser,
+                                    CefRefPtr<CefFrame> frame,
+                                    CefRefPtr<CefV8Context> context)
+{
+    // The main frame lives as long as the browser, messages sent to it while
+    // the page is being reloaded are kept for the next context.
+    if (!frame->IsMain())
+    {
+        _receiver->Remove(frame->GetIdentifier());
+    }
+
+    _emitter->Remove(frame->GetIdentifier());
+    _clipboard_guard->Remove(context);
+
+    if (_handler.has_value())
+    {
+        CallContextHandler(_handler->on_context_released, frame, context);
+    }
+}
+
+void ISubProcess::CallContextHandler(void (*callback)(void *v8_context, FrameInfo frame, void *context),
+                                     CefRefPtr<CefFrame> frame,
+                                     CefRefPtr<CefV8Context> context)
+{
+    if (callback == nullptr)
+    {
+        return;
+    }
+
+    std::string id = frame->GetIdentifier().ToString();
+    std::string name = frame->GetName().ToString();
+    std::string url = frame->GetURL().ToString();
+    std::string origin = get_url_origin(url);
+
+    FrameInfo info;
+    info.identifier = id.c_str();
+    info.name = name.c_str();
+    info.url = url.c_str();
+    info.origin = origin.c_str();
+    info.is_main = frame->IsMain();
+
+    callback(context.get(), info, _handler->context);
+}
+
+void ISubProcess::OnUncaughtException(CefRefPtr<CefBrowser> browser,
+                                      CefRefPtr<CefFrame> frame,
+                                      CefRefPtr<CefV8Context> context,
+                                      CefRefPtr<CefV8Exception> exception,
+                                      CefRefPtr<CefV8StackTrace> stackTrace)
+{
+    std::string stack;
+    for (int i = 0; stackTrace != nullptr && i < stackTrace->GetFrameCount(); i++)
+    {
+        auto it = stackTrace->GetFrame(i);
+        std::string function = it->GetFunctionName();
+
+        stack += "    at " + (function.empty() ? "<anonymous>" : function) + " (" + it->GetScriptName().ToString() +
+                 ":" + std::to_string(it->GetLineNumber()) + ":" + std::to_string(it->GetColumn()) + ")\n";
+    }
+
+    auto msg = CefProcessMessage::Create("PAGE_ERROR");
+    CefRefPtr<CefListValue> args = msg->GetArgumentList();
+    args->SetSize(5);
+    args->SetString(0, exception->GetMessage());
+    args->SetString(1, exception->GetScriptResourceName());
+    args->SetInt(2, exception->GetLineNumber());
+    args->SetInt(3, exception->GetStartColumn() + 1);
+    args->SetString(4, stack);
+    frame->SendProcessMessage(PID_BROWSER, msg);
 }
 
 bool ISubProcess::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
@@ -42,9 +341,26 @@ bool ISubProcess::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
                                            CefProcessId source_process,
                                            CefRefPtr<CefProcessMessage> message)
 {
+    if (message->GetName() == "MESSAGE_TRANSPORT_ACK")
+    {
+        _sender->Ack(browser->GetIdentifier());
+
+        return true;
+    }
+
     auto args = message->GetArgumentList();
-    std::string payload = args->GetString(0);
-    _receiver->Recv(payload);
+    if (message->GetName() == "EVENT_EMITTER")
+    {
+        _emitter->Emit(frame->GetIdentifier(), args->GetString(0), args->GetString(1));
+    }
+    else if (message->GetName() == "CLIPBOARD_POLICY")
+    {
+        _clipboard_guard->Resolve(args->GetInt(0), static_cast<ClipboardPolicy>(args->GetInt(1)));
+    }
+    else
+    {
+        _receiver->Recv(browser->GetIdentifier(), frame->GetIdentifier(), args->Copy());
+    }
 
     return true;
 }
@@ -55,18 +371,65 @@ bool MessageSender::Execute(const CefString &name,
                             CefRefPtr<CefV8Value> &retval,
                             CefString &exception)
 {
-    if (_browser.has_value() && arguments.size() == 1 && arguments[0]->IsString())
+    return Send("", arguments, retval, exception);
+}
+
+bool MessageSender::Send(std::string channel,
+                         const CefV8ValueList &arguments,
+                         CefRefPtr<CefV8Value> &retval,
+                         CefString &exception)
+{
+    if (_browser.has_value() && arguments.size() == 1 && (arguments[0]->IsString() || arguments[0]->IsArrayBuffer()))
     {
-        CefRefPtr<CefV8Context> context = CefV8Context::GetCurrentContext();
-        std::string message = arguments[0]->GetStringValue();
+        auto context = CefV8Context::GetCurrentContext();
+        auto limits = _limits.find(context->GetBrowser()->GetIdentifier());
+
+        // The answers of the DOM queries are sent on the reserved channel, they
+        // are not counted so that the overflow policy never drops them.
+        bool reserved = channel == "__wew_dom";
+        if (!reserved && limits != _limits.end() && limits->second.max_in_flight_messages > 0)
+        {
+            if (limits->second.in_flight_messages >= limits->second.max_in_flight_messages)
+            {
+                if (limits->second.overflow_policy == WEW_MESSAGE_OVERFLOW_THROW)
+                {
+                    exception = "too many messages have not been handled by the host";
+                }
+                else
+                {
+                    retval = CefV8Value::CreateBool(false);
+                }
+
+                return true;
+            }
+
+            limits->second.in_flight_messages++;
+        }
 
         auto msg = CefProcessMessage::Create("MESSAGE_TRANSPORT");
+        auto frame = context->GetFrame();
+
         CefRefPtr<CefListValue> args = msg->GetArgumentList();
-        args->SetSize(1);
-        args->SetString(0, message);
+        args->SetSize(2);
+        args->SetString(1, channel);
 
-        _browser.value()->GetMainFrame()->SendProcessMessage(PID_BROWSER, msg);
-        retval = CefV8Value::CreateUndefined();
+        // ArrayBuffer is passed as binary value, avoiding any encoding of the
+        // payload between processes.
+        if (arguments[0]->IsArrayBuffer())
+        {
+            args->SetBinary(0,
+                            CefBinaryValue::Create(arguments[0]->GetArrayBufferData(),
+                                                   arguments[0]->GetArrayBufferByteLength()));
+        }
+        else
+        {
+            args->SetString(0, arguments[0]->GetStringValue());
+        }
+
+        // The message is sent from the frame that called `send`, which may be
+        // a child frame.
+        frame->SendProcessMessage(PID_BROWSER, msg);
+        retval = CefV8Value::CreateBool(true);
 
         return true;
     }
@@ -76,18 +439,43 @@ bool MessageSender::Execute(const CefString &name,
     }
 }
 
+void MessageSender::Ack(int browser)
+{
+    auto limits = _limits.find(browser);
+    if (limits != _limits.end() && limits->second.in_flight_messages > 0)
+    {
+        limits->second.in_flight_messages--;
+    }
+}
+
 bool MessageReceiver::Execute(const CefString &name,
                               CefRefPtr<CefV8Value> object,
                               const CefV8ValueList &arguments,
                               CefRefPtr<CefV8Value> &retval,
                               CefString &exception)
+{
+    return On("", arguments, retval);
+}
+
+bool MessageReceiver::On(std::string channel, const CefV8ValueList &arguments, CefRefPtr<CefV8Value> &retval)
 {
     if (arguments.size() == 1 && arguments[0]->IsFunction())
     {
-        _context = std::optional(CefV8Context::GetCurrentContext());
-        _callback = std::optional(arguments[0]);
+        auto context = CefV8Context::GetCurrentContext();
+        auto &listener = _listeners[std::make_pair(context->GetFrame()->GetIdentifier().ToString(), channel)];
+        listener.context = std::optional(context);
+        listener.callback = std::optional(arguments[0]);
         retval = CefV8Value::CreateUndefined();
 
+        // Deliver the messages that arrived before the page registered the
+        // listener, in the order they were sent.
+        while (!listener.pending_messages.empty())
+        {
+            auto message = listener.pending_messages.front();
+            listener.pending_messages.pop_front();
+            Dispatch(listener, message);
+        }
+
         return true;
     }
     else
@@ -96,14 +484,391 @@ bool MessageReceiver::Execute(const CefString &name,
     }
 }
 
-void MessageReceiver::Recv(std::string message)
+void MessageReceiver::Recv(int browser, std::string frame, CefRefPtr<CefListValue> message)
+{
+    std::string channel = message->GetSize() > 1 ? message->GetString(1).ToString() : "";
+    auto &listener = _listeners[std::make_pair(frame, channel)];
+    if (listener.context.has_value() && listener.callback.has_value())
+    {
+        Dispatch(listener, message);
+
+        return;
+    }
+
+    // No listener has been registered yet, the page is probably still loading,
+    // keep the message until `MessageTransport.on` is called.
+    auto limit = _max_pending_messages.find(browser);
+    size_t max_pending_messages = limit != _max_pending_messages.end() ? limit->second : 0;
+    if (max_pending_messages == 0)
+    {
+        return;
+    }
+
+    while (listener.pending_messages.size() >= max_pending_messages)
+    {
+        listener.pending_messages.pop_front();
+    }
+
+    listener.pending_messages.push_back(message);
+}
+
+void MessageReceiver::Reset(std::string frame)
+{
+    for (auto &[key, listener] : _listeners)
+    {
+        if (key.first == frame)
+        {
+            listener.context = std::nullopt;
+            listener.callback = std::nullopt;
+        }
+    }
+}
+
+void MessageReceiver::Remove(std::string frame)
+{
+    for (auto it = _listeners.begin(); it != _listeners.end();)
+    {
+        if (it->first.first == frame)
+        {
+            it = _listeners.erase(it);
+        }
+        else
+        {
+            it++;
+        }
+    }
+}
+
+void MessageReceiver::Dispatch(Listener &listener, CefRefPtr<CefListValue> message)
+{
+    listener.context.value()->Enter();
+
+    CefV8ValueList arguments;
+    if (message->GetType(0) == VTYPE_BINARY)
+    {
+        auto binary = message->GetBinary(0);
+        std::vector<uint8_t> buffer(binary->GetSize());
+        binary->GetData(buffer.data(), buffer.size(), 0);
+
+        arguments.push_back(CefV8Value::CreateArrayBufferWithCopy(buffer.data(), buffer.size()));
+    }
+    else
+    {
+        arguments.push_back(CefV8Value::CreateString(message->GetString(0)));
+    }
+
+    listener.callback.value()->ExecuteFunction(nullptr, arguments);
+    listener.context.value()->Exit();
+}
+
+bool MessageChannel::Execute(const CefString &name,
+                             CefRefPtr<CefV8Value> object,
+                             const CefV8ValueList &arguments,
+                             CefRefPtr<CefV8Value> &retval,
+                             CefString &exception)
+{
+    if (name == "send")
+    {
+        return _sender->Send(_name, arguments, retval, exception);
+    }
+    else if (name == "on")
+    {
+        return _receiver->On(_name, arguments, retval);
+    }
+    else
+    {
+        return false;
+    }
+}
+
+bool MessageChannelFactory::Execute(const CefString &name,
+                                    CefRefPtr<CefV8Value> object,
+                                    const CefV8ValueList &arguments,
+                                    CefRefPtr<CefV8Value> &retval,
+                                    CefString &exception)
+{
+    // The empty name is reserved for the default channel of `MessageTransport`.
+    if (arguments.size() == 1 && arguments[0]->IsString() && !arguments[0]->GetStringValue().empty())
+    {
+        std::string channel_name = arguments[0]->GetStringValue();
+        CefRefPtr<MessageChannel> handler = new MessageChannel(channel_name, _sender, _receiver);
+
+        CefRefPtr<CefV8Value> channel = CefV8Value::CreateObject(nullptr, nullptr);
+        channel->SetValue("name", CefV8Value::CreateString(channel_name), V8_PROPERTY_ATTRIBUTE_READONLY);
+        channel->SetValue("send", CefV8Value::CreateFunction("send", handler), V8_PROPERTY_ATTRIBUTE_NONE);
+        channel->SetValue("on", CefV8Value::CreateFunction("on", handler), V8_PROPERTY_ATTRIBUTE_NONE);
+        retval = channel;
+
+        return true;
+    }
+    else
+    {
+        return false;
+    }
+}
+
+bool V8ExtensionHandler::Execute(const CefString &name,
+                                 CefRefPtr<CefV8Value> object,
+                                 const CefV8ValueList &arguments,
+                                 CefRefPtr<CefV8Value> &retval,
+                                 CefString &exception)
+{
+    // The strings and buffers are copied here, they must outlive the values
+    // passed to the native function.
+    std::vector<std::string> storage;
+    storage.reserve(arguments.size());
+
+    std::vector<V8Value> values;
+    for (auto &argument : arguments)
+    {
+        V8Value value = {};
+        value.type = WEW_V8_VALUE_UNDEFINED;
+
+        if (argument->IsNull())
+        {
+            value.type = WEW_V8_VALUE_NULL;
+        }
+        else if (argument->IsBool())
+        {
+            value.type = WEW_V8_VALUE_BOOL;
+            value.bool_value = argument->GetBoolValue();
+        }
+        else if (argument->IsInt())
+        {
+            value.type = WEW_V8_VALUE_INT;
+            value.int_value = argument->GetIntValue();
+        }
+        else if (argument->IsDouble())
+        {
+            value.type = WEW_V8_VALUE_DOUBLE;
+            value.double_value = argument->GetDoubleValue();
+        }
+        else if (argument->IsString())
+        {
+            auto &it = storage.emplace_back(argument->GetStringValue().ToString());
+            value.type = WEW_V8_VALUE_STRING;
+            value.data = reinterpret_cast<const uint8_t *>(it.data());
+            value.size = it.size();
+        }
+        else if (argument->IsArrayBuffer())
+        {
+            value.type = WEW_V8_VALUE_ARRAY_BUFFER;
+            value.data = static_cast<const uint8_t *>(argument->GetArrayBufferData());
+            value.size = argument->GetArrayBufferByteLength();
+        }
+
+        values.push_back(value);
+    }
+
+    V8ExtensionResult result;
+    std::string function = name.ToString();
+    _execute(function.c_str(), values.data(), values.size(), &result, _context);
+
+    if (result.exception.has_value())
+    {
+        exception = result.exception.value();
+    }
+    else
+    {
+        retval = result.retval;
+    }
+
+    return true;
+}
+
+bool EventEmitter::Execute(const CefString &name,
+                           CefRefPtr<CefV8Value> object,
+                           const CefV8ValueList &arguments,
+                           CefRefPtr<CefV8Value> &retval,
+                           CefString &exception)
+{
+    if (arguments.size() == 2 && arguments[0]->IsString() && arguments[1]->IsFunction())
+    {
+        auto context = CefV8Context::GetCurrentContext();
+        auto &listeners = _listeners[context->GetFrame()->GetIdentifier()];
+
+        // A new context means the page has been reloaded, the listeners of the
+        // previous page are no longer valid.
+        if (listeners.context == nullptr || !listeners.context->IsSame(context))
+        {
+            listeners.callbacks.clear();
+            listeners.context = context;
+        }
+
+        listeners.callbacks[arguments[0]->GetStringValue()].push_back(arguments[1]);
+        retval = CefV8Value::CreateUndefined();
+
+        return true;
+    }
+    else
+    {
+        return false;
+    }
+}
+
+bool PrintObserver::Execute(const CefString &name,
+                            CefRefPtr<CefV8Value> object,
+                            const CefV8ValueList &arguments,
+                            CefRefPtr<CefV8Value> &retval,
+                            CefString &exception)
+{
+    auto msg = CefProcessMessage::Create("PRINT_STATE");
+    msg->GetArgumentList()->SetBool(0, name == "beforeprint");
+    CefV8Context::GetCurrentContext()->GetFrame()->SendProcessMessage(PID_BROWSER, msg);
+
+    retval = CefV8Value::CreateUndefined();
+    return true;
+}
+
+bool PdfObserver::Execute(const CefString &name,
+                          CefRefPtr<CefV8Value> object,
+                          const CefV8ValueList &arguments,
+                          CefRefPtr<CefV8Value> &retval,
+                          CefString &exception)
+{
+    retval = CefV8Value::CreateUndefined();
+    if (arguments.size() != 3 || !arguments[0]->IsString() || !arguments[1]->IsInt() || !arguments[2]->IsString())
+    {
+        return true;
+    }
+
+    auto msg = CefProcessMessage::Create("PDF_LOADED");
+    CefRefPtr<CefListValue> args = msg->GetArgumentList();
+    args->SetSize(3);
+    args->SetString(0, arguments[0]->GetStringValue());
+    args->SetInt(1, arguments[1]->GetIntValue());
+    args->SetString(2, arguments[2]->GetStringValue());
+    CefV8Context::GetCurrentContext()->GetFrame()->SendProcessMessage(PID_BROWSER, msg);
+
+    return true;
+}
+
+void PdfObserver::Install(CefRefPtr<CefV8Context> context)
+{
+    CefRefPtr<CefV8Value> observer;
+    CefRefPtr<CefV8Exception> exception;
+    if (!context->Eval(PDF_OBSERVER_SCRIPT, CefString(), 0, observer, exception))
+    {
+        return;
+    }
+
+    observer->ExecuteFunctionWithContext(context, nullptr, {CefV8Value::CreateFunction("report", this)});
+}
+
+void EventEmitter::Emit(std::string frame, std::string event, std::string payload)
+{
+    auto listeners = _listeners.find(frame);
+    if (listeners == _listeners.end())
+    {
+        return;
+    }
+
+    auto context = listeners->second.context;
+    if (context == nullptr || !context->IsValid())
+    {
+        _listeners.erase(listeners);
+
+        return;
+    }
+
+    auto callbacks = listeners->second.callbacks.find(event);
+    if (callbacks == listeners->second.callbacks.end())
+    {
+        return;
+    }
+
+    // The listeners may register new listeners, the list is copied so that it
+    // is not changed while it is iterated.
+    auto functions = callbacks->second;
+
+    context->Enter();
+
+    // The payload is JSON encoded on the Rust side, it is decoded here with the
+    // page's own JSON object so that listeners receive a plain value.
+    CefRefPtr<CefV8Value> json = context->GetGlobal()->GetValue("JSON");
+    CefRefPtr<CefV8Value> value = json->GetValue("parse")->ExecuteFunction(json, {CefV8Value::CreateString(payload)});
+    if (value != nullptr)
+    {
+        for (auto &callback : functions)
+        {
+            CefV8ValueList arguments;
+            arguments.push_back(value);
+            callback->ExecuteFunction(nullptr, arguments);
+        }
+    }
+
+    context->Exit();
+}
+
+void EventEmitter::Remove(std::string frame)
+{
+    _listeners.erase(frame);
+}
+
+bool ClipboardGuard::Execute(const CefString &name,
+                             CefRefPtr<CefV8Value> object,
+                             const CefV8ValueList &arguments,
+                             CefRefPtr<CefV8Value> &retval,
+                             CefString &exception)
+{
+    if (arguments.size() == 2 && arguments[0]->IsInt() && arguments[1]->IsFunction())
+    {
+        auto context = CefV8Context::GetCurrentContext();
+        int id = ++_next_id;
+        _requests[id] = Request{context, arguments[1]};
+
+        auto msg = CefProcessMessage::Create("CLIPBOARD_ACCESS");
+        msg->GetArgumentList()->SetInt(0, id);
+        msg->GetArgumentList()->SetInt(1, arguments[0]->GetIntValue());
+        context->GetFrame()->SendProcessMessage(PID_BROWSER, msg);
+
+        retval = CefV8Value::CreateUndefined();
+        return true;
+    }
+    else
+    {
+        return false;
+    }
+}
+
+void ClipboardGuard::Install(CefRefPtr<CefV8Context> context)
+{
+    CefRefPtr<CefV8Value> guard;
+    CefRefPtr<CefV8Exception> exception;
+    if (!context->Eval(CLIPBOARD_GUARD_SCRIPT, CefString(), 0, guard, exception))
+    {
+        return;
+    }
+
+    guard->ExecuteFunctionWithContext(context, nullptr, {CefV8Value::CreateFunction("request", this)});
+}
+
+void ClipboardGuard::Resolve(int id, ClipboardPolicy policy)
+{
+    auto it = _requests.find(id);
+    if (it == _requests.end())
+    {
+        return;
+    }
+
+    auto request = it->second;
+    _requests.erase(it);
+
+    request.callback->ExecuteFunctionWithContext(request.context, nullptr, {CefV8Value::CreateInt(policy)});
+}
+
+void ClipboardGuard::Remove(CefRefPtr<CefV8Context> context)
 {
-    if (_context.has_value() && _callback.has_value())
+    // The promises of a released context can no longer be settled.
+    for (auto it = _requests.begin(); it != _requests.end();)
     {
-        _context.value()->Enter();
-        CefV8ValueList arguments;
-        arguments.push_back(CefV8Value::CreateString(message));
-        _callback.value()->ExecuteFunction(nullptr, arguments);
-        _context.value()->Exit();
+        if (it->second.context->IsSame(context))
+        {
+            it = _requests.erase(it);
+        }
+        else
+        {
+            it++;
+        }
     }
 }
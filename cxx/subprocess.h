@@ -9,8 +9,12 @@
 #define subprocess_h
 #pragma once
 
+#include <deque>
+#include <map>
 #include <optional>
+#include <set>
 #include <string>
+#include <vector>
 
 #include "include/cef_app.h"
 #include "wew.h"
@@ -24,14 +28,44 @@ class MessageSender : public CefV8Handler
                  CefRefPtr<CefV8Value> &retval,
                  CefString &exception) override;
 
+    bool Send(std::string channel,
+              const CefV8ValueList &arguments,
+              CefRefPtr<CefV8Value> &retval,
+              CefString &exception);
+
     void SetBrowser(CefRefPtr<CefBrowser> browser)
     {
         _browser = std::optional(browser);
     }
 
+    void SetLimits(int browser, uint32_t max_in_flight_messages, MessageOverflowPolicy policy)
+    {
+        auto &limits = _limits[browser];
+        limits.max_in_flight_messages = max_in_flight_messages;
+        limits.overflow_policy = policy;
+    }
+
+    void RemoveLimits(int browser)
+    {
+        _limits.erase(browser);
+    }
+
+    void Ack(int browser);
+
   private:
+    struct Limits
+    {
+        MessageOverflowPolicy overflow_policy = WEW_MESSAGE_OVERFLOW_DROP;
+        uint32_t max_in_flight_messages = 0;
+        uint32_t in_flight_messages = 0;
+    };
+
     std::optional<CefRefPtr<CefBrowser>> _browser = std::nullopt;
 
+    // A render process can host several browsers, each of them has its own
+    // limits, kept by browser identifier.
+    std::map<int, Limits> _limits;
+
     IMPLEMENT_REFCOUNTING(MessageSender);
 };
 
@@ -44,18 +78,207 @@ class MessageReceiver : public CefV8Handler
                  CefRefPtr<CefV8Value> &retval,
                  CefString &exception) override;
 
-    void Recv(std::string message);
+    bool On(std::string channel, const CefV8ValueList &arguments, CefRefPtr<CefV8Value> &retval);
+    void Recv(int browser, std::string frame, CefRefPtr<CefListValue> message);
+    void Reset(std::string frame);
+    void Remove(std::string frame);
+
+    void SetLimit(int browser, size_t max_pending_messages)
+    {
+        _max_pending_messages[browser] = max_pending_messages;
+    }
+
+    void RemoveLimit(int browser)
+    {
+        _max_pending_messages.erase(browser);
+    }
 
   private:
-    std::optional<CefRefPtr<CefV8Context>> _context = std::nullopt;
-    std::optional<CefRefPtr<CefV8Value>> _callback = std::nullopt;
+    struct Listener
+    {
+        std::optional<CefRefPtr<CefV8Context>> context = std::nullopt;
+        std::optional<CefRefPtr<CefV8Value>> callback = std::nullopt;
+        std::deque<CefRefPtr<CefListValue>> pending_messages;
+    };
+
+    void Dispatch(Listener &listener, CefRefPtr<CefListValue> message);
+
+    // Every frame has its own JavaScript context, the listeners are kept per
+    // frame identifier and channel name.
+    std::map<std::pair<std::string, std::string>, Listener> _listeners;
+    // The number of messages kept until a listener is registered, set for each
+    // browser by its attributes.
+    std::map<int, size_t> _max_pending_messages;
 
     IMPLEMENT_REFCOUNTING(MessageReceiver);
 };
 
+class MessageChannel : public CefV8Handler
+{
+  public:
+    // clang-format off
+    MessageChannel(std::string name, CefRefPtr<MessageSender> sender, CefRefPtr<MessageReceiver> receiver)
+        : _name(name)
+        , _sender(sender)
+        , _receiver(receiver)
+    {
+    }
+    // clang-format on
+
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+  private:
+    std::string _name;
+    CefRefPtr<MessageSender> _sender;
+    CefRefPtr<MessageReceiver> _receiver;
+
+    IMPLEMENT_REFCOUNTING(MessageChannel);
+};
+
+class MessageChannelFactory : public CefV8Handler
+{
+  public:
+    // clang-format off
+    MessageChannelFactory(CefRefPtr<MessageSender> sender, CefRefPtr<MessageReceiver> receiver)
+        : _sender(sender)
+        , _receiver(receiver)
+    {
+    }
+    // clang-format on
+
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+  private:
+    CefRefPtr<MessageSender> _sender;
+    CefRefPtr<MessageReceiver> _receiver;
+
+    IMPLEMENT_REFCOUNTING(MessageChannelFactory);
+};
+
+class EventEmitter : public CefV8Handler
+{
+  public:
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+    void Emit(std::string frame, std::string event, std::string payload);
+    void Remove(std::string frame);
+
+  private:
+    struct Listeners
+    {
+        CefRefPtr<CefV8Context> context;
+        std::map<std::string, std::vector<CefRefPtr<CefV8Value>>> callbacks;
+    };
+
+    // The render process can host several browsers and frames, each of them
+    // has its own JavaScript context, the listeners are kept per frame
+    // identifier.
+    std::map<std::string, Listeners> _listeners;
+
+    IMPLEMENT_REFCOUNTING(EventEmitter);
+};
+
+class PrintObserver : public CefV8Handler
+{
+  public:
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+  private:
+    IMPLEMENT_REFCOUNTING(PrintObserver);
+};
+
+class PdfObserver : public CefV8Handler
+{
+  public:
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+    void Install(CefRefPtr<CefV8Context> context);
+
+  private:
+    IMPLEMENT_REFCOUNTING(PdfObserver);
+};
+
+class ClipboardGuard : public CefV8Handler
+{
+  public:
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+    void Install(CefRefPtr<CefV8Context> context);
+    void Resolve(int id, ClipboardPolicy policy);
+    void Remove(CefRefPtr<CefV8Context> context);
+
+  private:
+    struct Request
+    {
+        CefRefPtr<CefV8Context> context;
+        CefRefPtr<CefV8Value> callback;
+    };
+
+    std::map<int, Request> _requests;
+    int _next_id = 0;
+
+    IMPLEMENT_REFCOUNTING(ClipboardGuard);
+};
+
+struct V8ExtensionResult
+{
+    CefRefPtr<CefV8Value> retval = CefV8Value::CreateUndefined();
+    std::optional<std::string> exception = std::nullopt;
+};
+
+class V8ExtensionHandler : public CefV8Handler
+{
+  public:
+    // clang-format off
+    V8ExtensionHandler(V8Extension extension)
+        : _execute(extension.execute)
+        , _context(extension.context)
+    {
+    }
+    // clang-format on
+
+    bool Execute(const CefString &name,
+                 CefRefPtr<CefV8Value> object,
+                 const CefV8ValueList &arguments,
+                 CefRefPtr<CefV8Value> &retval,
+                 CefString &exception) override;
+
+  private:
+    void (*_execute)(const char *function, const V8Value *arguments, size_t size, void *result, void *context);
+    void *_context;
+
+    IMPLEMENT_REFCOUNTING(V8ExtensionHandler);
+};
+
 class ISubProcess : public CefApp, public CefRenderProcessHandler
 {
   public:
+    ISubProcess(std::optional<SubProcessHandler> handler);
+
     /* CefApp */
 
     ///
@@ -70,6 +293,21 @@ class ISubProcess : public CefApp, public CefRenderProcessHandler
 
     /* CefRenderProcessHandler */
 
+    ///
+    /// Called after WebKit has been initialized.
+    ///
+    void OnWebKitInitialized() override;
+
+    ///
+    /// Called after a browser has been created.
+    ///
+    void OnBrowserCreated(CefRefPtr<CefBrowser> browser, CefRefPtr<CefDictionaryValue> extra_info) override;
+
+    ///
+    /// Called before a browser is destroyed.
+    ///
+    void OnBrowserDestroyed(CefRefPtr<CefBrowser> browser) override;
+
     ///
     /// Called immediately after the V8 context for a frame has been created.
     ///
@@ -77,6 +315,22 @@ class ISubProcess : public CefApp, public CefRenderProcessHandler
                           CefRefPtr<CefFrame> frame,
                           CefRefPtr<CefV8Context> context) override;
 
+    ///
+    /// Called immediately before the V8 context for a frame is released.
+    ///
+    void OnContextReleased(CefRefPtr<CefBrowser> browser,
+                           CefRefPtr<CefFrame> frame,
+                           CefRefPtr<CefV8Context> context) override;
+
+    ///
+    /// Called for global uncaught exceptions in a frame.
+    ///
+    void OnUncaughtException(CefRefPtr<CefBrowser> browser,
+                             CefRefPtr<CefFrame> frame,
+                             CefRefPtr<CefV8Context> context,
+                             CefRefPtr<CefV8Exception> exception,
+                             CefRefPtr<CefV8StackTrace> stackTrace) override;
+
     ///
     /// Called when a new message is received from a different process.
     ///
@@ -89,8 +343,19 @@ class ISubProcess : public CefApp, public CefRenderProcessHandler
                                   CefRefPtr<CefProcessMessage> message) override;
 
   private:
+    void CallContextHandler(void (*callback)(void *v8_context, FrameInfo frame, void *context),
+                            CefRefPtr<CefFrame> frame,
+                            CefRefPtr<CefV8Context> context);
+
+    std::optional<SubProcessHandler> _handler;
     CefRefPtr<MessageSender> _sender = new MessageSender();
     CefRefPtr<MessageReceiver> _receiver = new MessageReceiver();
+    CefRefPtr<EventEmitter> _emitter = new EventEmitter();
+    CefRefPtr<PrintObserver> _print_observer = new PrintObserver();
+    CefRefPtr<PdfObserver> _pdf_observer = new PdfObserver();
+    CefRefPtr<ClipboardGuard> _clipboard_guard = new ClipboardGuard();
+    std::set<int> _clipboard_policy_browsers;
+    CefRefPtr<MessageChannelFactory> _channels = new MessageChannelFactory(_sender, _receiver);
 
     IMPLEMENT_REFCOUNTING(ISubProcess);
 };
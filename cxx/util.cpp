@@ -7,6 +7,8 @@
 
 #include "util.h"
 
+#include "include/cef_parser.h"
+
 CefMainArgs get_main_args(int argc, const char **argv)
 {
 #ifdef WIN32
@@ -17,3 +19,23 @@ CefMainArgs get_main_args(int argc, const char **argv)
 
     return main_args;
 }
+
+std::string get_url_origin(const CefString &url)
+{
+    CefURLParts parts;
+    if (CefParseURL(url, parts))
+    {
+        std::string origin = CefString(&parts.origin).ToString();
+        if (!origin.empty() && origin.back() == '/')
+        {
+            origin.pop_back();
+        }
+
+        if (!origin.empty())
+        {
+            return origin;
+        }
+    }
+
+    return "null";
+}
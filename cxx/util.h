@@ -9,6 +9,8 @@
 #define util_h
 #pragma once
 
+#include <string>
+
 #include "include/cef_app.h"
 
 // clang-format off
@@ -27,8 +29,16 @@
 
 // clang-format on
 
+// The origin of the built-in PDF viewer, the viewer is loaded in a frame of
+// this origin inside the frame that shows the document.
+#define PDF_VIEWER_ORIGIN "chrome-extension://mhjfbmdgcfjbbpaeojofohoefgiehjai/"
+
 CefMainArgs get_main_args(int argc, const char **argv);
 
+// The serialized origin of the URL, such as `https://example.com`, an opaque
+// origin is serialized as `null`.
+std::string get_url_origin(const CefString &url);
+
 typedef void (*ITaskCallback)(void *context);
 
 class ITask : public CefTask
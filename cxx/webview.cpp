@@ -7,14 +7,431 @@
 
 #include "webview.h"
 
+/* CefDownloadImageCallback */
+
+// clang-format off
+IDownloadImageCallback::IDownloadImageCallback(void (*callback)(const Image *image, void *context), void *context)
+    : _callback(callback)
+    , _context(context)
+{
+}
+// clang-format on
+
+void IDownloadImageCallback::OnDownloadImageFinished(const CefString &image_url,
+                                                     int http_status_code,
+                                                     CefRefPtr<CefImage> image)
+{
+    if (image == nullptr || image->IsEmpty())
+    {
+        _callback(nullptr, _context);
+        return;
+    }
+
+    int width = 0;
+    int height = 0;
+    auto bitmap = image->GetAsBitmap(1.0f, CEF_COLOR_TYPE_RGBA_8888, CEF_ALPHA_TYPE_POSTMULTIPLIED, width, height);
+    if (bitmap == nullptr)
+    {
+        _callback(nullptr, _context);
+        return;
+    }
+
+    std::vector<uint8_t> data(bitmap->GetSize());
+    bitmap->GetData(data.data(), data.size(), 0);
+
+    Image it;
+    it.data = data.data();
+    it.size = data.size();
+    it.width = width;
+    it.height = height;
+
+    _callback(&it, _context);
+}
+
+/* CefDevToolsMessageObserver */
+
+// clang-format off
+ILayoutMetricsObserver::ILayoutMetricsObserver(void (*callback)(const LayoutMetrics *metrics, void *context), void *context)
+    : _callback(callback)
+    , _context(context)
+{
+}
+// clang-format on
+
+void ILayoutMetricsObserver::Start(CefRefPtr<CefBrowser> browser)
+{
+    _registration = browser->GetHost()->AddDevToolsMessageObserver(this);
+    _message_id = browser->GetHost()->ExecuteDevToolsMethod(0, "Page.getLayoutMetrics", nullptr);
+    if (_message_id == 0)
+    {
+        Done(nullptr);
+    }
+}
+
+void ILayoutMetricsObserver::OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                                    int message_id,
+                                                    bool success,
+                                                    const void *result,
+                                                    size_t result_size)
+{
+    if (message_id != _message_id)
+    {
+        return;
+    }
+
+    auto value = success ? CefParseJSON(result, result_size, JSON_PARSER_RFC) : nullptr;
+    if (value == nullptr || value->GetType() != VTYPE_DICTIONARY)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    // The css prefixed sizes are in CSS pixels, the others are in device pixels.
+    auto content = value->GetDictionary()->GetDictionary("cssContentSize");
+    auto viewport = value->GetDictionary()->GetDictionary("cssLayoutViewport");
+    if (content == nullptr || viewport == nullptr)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    LayoutMetrics metrics;
+    metrics.content_width = content->GetDouble("width");
+    metrics.content_height = content->GetDouble("height");
+    metrics.viewport_width = viewport->GetDouble("clientWidth");
+    metrics.viewport_height = viewport->GetDouble("clientHeight");
+    metrics.scroll_x = viewport->GetDouble("pageX");
+    metrics.scroll_y = viewport->GetDouble("pageY");
+
+    Done(&metrics);
+}
+
+void ILayoutMetricsObserver::OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser)
+{
+    Done(nullptr);
+}
+
+void ILayoutMetricsObserver::Done(const LayoutMetrics *metrics)
+{
+    if (_done)
+    {
+        return;
+    }
+
+    _done = true;
+    _callback(metrics, _context);
+
+    // The registration holds a reference to this observer.
+    _registration = nullptr;
+}
+
+// clang-format off
+IThumbnailObserver::IThumbnailObserver(uint32_t max_width,
+                                       uint32_t max_height,
+                                       void (*callback)(const Image *image, void *context),
+                                       void *context)
+    : _max_width(max_width)
+    , _max_height(max_height)
+    , _callback(callback)
+    , _context(context)
+{
+}
+// clang-format on
+
+void IThumbnailObserver::Start(CefRefPtr<CefBrowser> browser)
+{
+    _registration = browser->GetHost()->AddDevToolsMessageObserver(this);
+    _metrics_message_id = browser->GetHost()->ExecuteDevToolsMethod(0, "Page.getLayoutMetrics", nullptr);
+    if (_metrics_message_id == 0)
+    {
+        Done(nullptr);
+    }
+}
+
+void IThumbnailObserver::OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                                int message_id,
+                                                bool success,
+                                                const void *result,
+                                                size_t result_size)
+{
+    if (message_id != _metrics_message_id && message_id != _capture_message_id)
+    {
+        return;
+    }
+
+    auto value = success ? CefParseJSON(result, result_size, JSON_PARSER_RFC) : nullptr;
+    if (value == nullptr || value->GetType() != VTYPE_DICTIONARY)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    if (message_id == _metrics_message_id)
+    {
+        Capture(browser, value->GetDictionary());
+    }
+    else
+    {
+        Decode(value->GetDictionary());
+    }
+}
+
+void IThumbnailObserver::OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser)
+{
+    Done(nullptr);
+}
+
+void IThumbnailObserver::Capture(CefRefPtr<CefBrowser> browser, CefRefPtr<CefDictionaryValue> metrics)
+{
+    // The css prefixed viewport is in CSS pixels, the other one is in device
+    // pixels, their ratio is the device scale factor of the capture.
+    auto css_viewport = metrics->GetDictionary("cssLayoutViewport");
+    auto viewport = metrics->GetDictionary("layoutViewport");
+    if (css_viewport == nullptr || viewport == nullptr)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    double width = css_viewport->GetDouble("clientWidth");
+    double height = css_viewport->GetDouble("clientHeight");
+    if (width <= 0 || height <= 0)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    double device_scale_factor = std::max(viewport->GetDouble("clientWidth") / width, 1.0);
+    double scale = std::min({_max_width / (width * device_scale_factor),
+                             _max_height / (height * device_scale_factor),
+                             1.0});
+
+    auto clip = CefDictionaryValue::Create();
+    clip->SetDouble("x", css_viewport->GetDouble("pageX"));
+    clip->SetDouble("y", css_viewport->GetDouble("pageY"));
+    clip->SetDouble("width", width);
+    clip->SetDouble("height", height);
+    clip->SetDouble("scale", scale);
+
+    // JPEG is much cheaper to encode and decode than PNG, the loss of quality
+    // does not matter for a thumbnail.
+    auto params = CefDictionaryValue::Create();
+    params->SetString("format", "jpeg");
+    params->SetInt("quality", 80);
+    params->SetDictionary("clip", clip);
+
+    _capture_message_id = browser->GetHost()->ExecuteDevToolsMethod(0, "Page.captureScreenshot", params);
+    if (_capture_message_id == 0)
+    {
+        Done(nullptr);
+    }
+}
+
+void IThumbnailObserver::Decode(CefRefPtr<CefDictionaryValue> result)
+{
+    auto data = CefBase64Decode(result->GetString("data"));
+    if (data == nullptr || data->GetSize() == 0)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    std::vector<uint8_t> jpeg(data->GetSize());
+    data->GetData(jpeg.data(), jpeg.size(), 0);
+
+    auto image = CefImage::CreateImage();
+    if (!image->AddJPEG(1.0f, jpeg.data(), jpeg.size()))
+    {
+        Done(nullptr);
+        return;
+    }
+
+    int width = 0;
+    int height = 0;
+    auto bitmap = image->GetAsBitmap(1.0f, CEF_COLOR_TYPE_RGBA_8888, CEF_ALPHA_TYPE_OPAQUE, width, height);
+    if (bitmap == nullptr)
+    {
+        Done(nullptr);
+        return;
+    }
+
+    std::vector<uint8_t> pixels(bitmap->GetSize());
+    bitmap->GetData(pixels.data(), pixels.size(), 0);
+
+    Image it;
+    it.data = pixels.data();
+    it.size = pixels.size();
+    it.width = width;
+    it.height = height;
+
+    Done(&it);
+}
+
+void IThumbnailObserver::Done(const Image *image)
+{
+    if (_done)
+    {
+        return;
+    }
+
+    _done = true;
+    _callback(image, _context);
+
+    // The registration holds a reference to this observer.
+    _registration = nullptr;
+}
+
+// clang-format off
+IScreencastObserver::IScreencastObserver(ScreencastOptions options,
+                                         bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                                         void *context)
+    : _options(options)
+    , _on_frame(on_frame)
+    , _context(context)
+{
+}
+// clang-format on
+
+void IScreencastObserver::Start(CefRefPtr<CefBrowser> browser)
+{
+    _browser = browser;
+
+    auto params = CefDictionaryValue::Create();
+    params->SetString("format", _options.format == WEW_SCREENCAST_FORMAT_PNG ? "png" : "jpeg");
+    params->SetInt("quality", std::clamp(_options.quality, 0, 100));
+    params->SetInt("everyNthFrame", std::max(_options.every_nth_frame, 1u));
+
+    if (_options.max_width > 0)
+    {
+        params->SetInt("maxWidth", _options.max_width);
+    }
+
+    if (_options.max_height > 0)
+    {
+        params->SetInt("maxHeight", _options.max_height);
+    }
+
+    _registration = browser->GetHost()->AddDevToolsMessageObserver(this);
+    _message_id = browser->GetHost()->ExecuteDevToolsMethod(0, "Page.startScreencast", params);
+    if (_message_id == 0)
+    {
+        Done();
+    }
+}
+
+void IScreencastObserver::Stop()
+{
+    if (_done)
+    {
+        return;
+    }
+
+    _browser->GetHost()->ExecuteDevToolsMethod(0, "Page.stopScreencast", nullptr);
+    Done();
+}
+
+void IScreencastObserver::OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                                 int message_id,
+                                                 bool success,
+                                                 const void *result,
+                                                 size_t result_size)
+{
+    if (message_id == _message_id && !success)
+    {
+        Done();
+    }
+}
+
+void IScreencastObserver::OnDevToolsEvent(CefRefPtr<CefBrowser> browser,
+                                          const CefString &method,
+                                          const void *params,
+                                          size_t params_size)
+{
+    if (_done || method != "Page.screencastFrame")
+    {
+        return;
+    }
+
+    auto value = CefParseJSON(params, params_size, JSON_PARSER_RFC);
+    if (value == nullptr || value->GetType() != VTYPE_DICTIONARY)
+    {
+        return;
+    }
+
+    OnFrame(value->GetDictionary());
+}
+
+void IScreencastObserver::OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser)
+{
+    Done();
+}
+
+void IScreencastObserver::OnFrame(CefRefPtr<CefDictionaryValue> params)
+{
+    // The next frame is only sent after this one is acknowledged, which keeps
+    // the renderer from producing frames faster than they are consumed.
+    auto ack = CefDictionaryValue::Create();
+    ack->SetInt("sessionId", params->GetInt("sessionId"));
+
+    auto data = CefBase64Decode(params->GetString("data"));
+    auto metadata = params->GetDictionary("metadata");
+    if (data == nullptr || metadata == nullptr)
+    {
+        _browser->GetHost()->ExecuteDevToolsMethod(0, "Page.screencastFrameAck", ack);
+        return;
+    }
+
+    std::vector<uint8_t> buffer(data->GetSize());
+    data->GetData(buffer.data(), buffer.size(), 0);
+
+    ScreencastFrame frame;
+    frame.data = buffer.data();
+    frame.size = buffer.size();
+    frame.format = _options.format;
+    frame.offset_top = metadata->GetDouble("offsetTop");
+    frame.page_scale_factor = metadata->GetDouble("pageScaleFactor");
+    frame.device_width = metadata->GetDouble("deviceWidth");
+    frame.device_height = metadata->GetDouble("deviceHeight");
+    frame.scroll_offset_x = metadata->GetDouble("scrollOffsetX");
+    frame.scroll_offset_y = metadata->GetDouble("scrollOffsetY");
+    frame.timestamp = metadata->HasKey("timestamp") ? metadata->GetDouble("timestamp") : 0;
+
+    if (!_on_frame(&frame, _context))
+    {
+        Stop();
+        return;
+    }
+
+    _browser->GetHost()->ExecuteDevToolsMethod(0, "Page.screencastFrameAck", ack);
+}
+
+void IScreencastObserver::Done()
+{
+    if (_done)
+    {
+        return;
+    }
+
+    _done = true;
+    _on_frame(nullptr, _context);
+
+    // The registration holds a reference to this observer.
+    _registration = nullptr;
+}
+
 /* CefContextMenuHandler */
 
+IWebViewContextMenu::IWebViewContextMenu(const WebViewSettings *settings)
+{
+    _disabled = settings->kiosk_policy != nullptr && settings->kiosk_policy->disable_context_menu;
+}
+
 void IWebViewContextMenu::OnBeforeContextMenu(CefRefPtr<CefBrowser> browser,
                                               CefRefPtr<CefFrame> frame,
                                               CefRefPtr<CefContextMenuParams> params,
                                               CefRefPtr<CefMenuModel> model)
 {
-    if (params->GetTypeFlags() & (CM_TYPEFLAG_SELECTION | CM_TYPEFLAG_EDITABLE))
+    if (!_disabled && params->GetTypeFlags() & (CM_TYPEFLAG_SELECTION | CM_TYPEFLAG_EDITABLE))
     {
         return;
     }
@@ -33,19 +450,41 @@ bool IWebViewContextMenu::OnContextMenuCommand(CefRefPtr<CefBrowser> browser,
 
 /* CefLoadHandler */
 
-IWebViewLoad::IWebViewLoad(WebViewHandler &handler) : _handler(handler)
+IWebViewLoad::IWebViewLoad(uint32_t &reload_attempts, WebViewHandler &handler)
+    : _reload_attempts(reload_attempts)
+    , _handler(handler)
 {
 }
 
 void IWebViewLoad::OnLoadStart(CefRefPtr<CefBrowser> browser, CefRefPtr<CefFrame> frame, TransitionType transition_type)
 {
-    _handler.on_state_change(WebViewState::WEW_BEFORE_LOAD, _handler.context);
+    std::string url = frame->GetURL().ToString();
+
+    WebViewStateChange change{};
+    change.state = WebViewState::WEW_BEFORE_LOAD;
+    change.url = url.c_str();
+    change.is_main_frame = frame->IsMain();
+
+    _handler.on_state_change(&change, _handler.context);
 }
 
 void IWebViewLoad::OnLoadEnd(CefRefPtr<CefBrowser> browser, CefRefPtr<CefFrame> frame, int httpStatusCode)
 {
-    _handler.on_state_change(WebViewState::WEW_LOADED, _handler.context);
+    std::string url = frame->GetURL().ToString();
+
+    WebViewStateChange change{};
+    change.state = WebViewState::WEW_LOADED;
+    change.url = url.c_str();
+    change.is_main_frame = frame->IsMain();
+    change.http_status = httpStatusCode;
+
+    _handler.on_state_change(&change, _handler.context);
     browser->GetHost()->SetFocus(true);
+
+    if (frame->IsMain())
+    {
+        _reload_attempts = 0;
+    }
 }
 
 void IWebViewLoad::OnLoadError(CefRefPtr<CefBrowser> browser,
@@ -54,29 +493,144 @@ void IWebViewLoad::OnLoadError(CefRefPtr<CefBrowser> browser,
                                const CefString &error_text,
                                const CefString &failed_url)
 {
-    _handler.on_state_change(WebViewState::WEW_LOAD_ERROR, _handler.context);
+    std::string url = failed_url.ToString();
+    std::string text = error_text.ToString();
+
+    WebViewStateChange change{};
+    change.state = WebViewState::WEW_LOAD_ERROR;
+    change.url = url.c_str();
+    change.is_main_frame = frame->IsMain();
+    change.error_code = error_code;
+    change.error_text = text.c_str();
+
+    _handler.on_state_change(&change, _handler.context);
 }
 
 /* CefLifeSpanHandler */
 
+// The media features are emulated through the DevTools protocol, an empty value
+// removes the emulation so that the features follow the system again.
+static CefRefPtr<CefDictionaryValue> CreateEmulatedMediaParams(ForcedColors forced_colors)
+{
+    std::string forced_colors_value;
+    std::string prefers_contrast_value;
+    switch (forced_colors)
+    {
+    case ForcedColors::WEW_FORCED_COLORS_ACTIVE:
+        forced_colors_value = "active";
+        prefers_contrast_value = "more";
+        break;
+    case ForcedColors::WEW_FORCED_COLORS_NONE:
+        forced_colors_value = "none";
+        prefers_contrast_value = "no-preference";
+        break;
+    default:
+        break;
+    }
+
+    auto features = CefListValue::Create();
+    for (auto &[name, value] : {std::make_pair("forced-colors", forced_colors_value),
+                                std::make_pair("prefers-contrast", prefers_contrast_value)})
+    {
+        auto feature = CefDictionaryValue::Create();
+        feature->SetString("name", name);
+        feature->SetString("value", value);
+        features->SetDictionary(features->GetSize(), feature);
+    }
+
+    auto params = CefDictionaryValue::Create();
+    params->SetList("features", features);
+    return params;
+}
+
+// CefBrowserSettings has no user agent, the user agent of a single browser can
+// only be overridden through the DevTools protocol.
+static CefRefPtr<CefDictionaryValue> CreateUserAgentOverrideParams(const char *user_agent,
+                                                                   const char *user_agent_metadata)
+{
+    auto params = CefDictionaryValue::Create();
+    params->SetString("userAgent", user_agent);
+
+    if (user_agent_metadata != nullptr)
+    {
+        auto metadata = CefParseJSON(std::string(user_agent_metadata), JSON_PARSER_RFC);
+        if (metadata != nullptr && metadata->GetType() == VTYPE_DICTIONARY)
+        {
+            params->SetDictionary("userAgentMetadata", metadata->GetDictionary());
+        }
+    }
+
+    return params;
+}
+
 // clang-format off
-IWebViewLifeSpan::IWebViewLifeSpan(std::optional<CefRefPtr<CefBrowser>> &browser, WebViewHandler &handler)
+IWebViewLifeSpan::IWebViewLifeSpan(const WebViewSettings *settings,
+                                   std::optional<CefRefPtr<CefBrowser>> &browser,
+                                   bool &is_running,
+                                   WebViewHandler &handler)
     : _handler(handler)
     , _browser(browser)
+    , _is_running(is_running)
 {
+    if (settings->kiosk_policy != nullptr)
+    {
+        _block_popups = settings->kiosk_policy->block_popups;
+    }
+
+    _forced_colors = settings->forced_colors;
+
+    if (settings->user_agent != nullptr)
+    {
+        _user_agent_override = CreateUserAgentOverrideParams(settings->user_agent, settings->user_agent_metadata);
+    }
 }
 // clang-format on
 
+// The map is only used on the UI thread, the count mirrors its size for the other threads.
+static std::atomic<size_t> BROWSER_COUNT = 0;
+
+std::map<int, CefRefPtr<CefBrowser>> &IWebViewLifeSpan::GetBrowsers()
+{
+    static std::map<int, CefRefPtr<CefBrowser>> browsers;
+    return browsers;
+}
+
+size_t IWebViewLifeSpan::GetBrowserCount()
+{
+    return BROWSER_COUNT.load();
+}
+
 void IWebViewLifeSpan::OnAfterCreated(CefRefPtr<CefBrowser> browser)
 {
+    GetBrowsers()[browser->GetIdentifier()] = browser;
+    BROWSER_COUNT.store(GetBrowsers().size());
+
     _browser = browser;
 
+    if (_user_agent_override != nullptr)
+    {
+        browser->GetHost()->ExecuteDevToolsMethod(0, "Emulation.setUserAgentOverride", _user_agent_override);
+    }
+
+    if (_forced_colors != ForcedColors::WEW_FORCED_COLORS_SYSTEM)
+    {
+        browser->GetHost()->ExecuteDevToolsMethod(0,
+                                                  "Emulation.setEmulatedMedia",
+                                                  CreateEmulatedMediaParams(_forced_colors));
+    }
+
     browser->GetHost()->WasResized();
 }
 
 bool IWebViewLifeSpan::DoClose(CefRefPtr<CefBrowser> browser)
 {
-    _handler.on_state_change(WebViewState::WEW_REQUEST_CLOSE, _handler.context);
+    if (_is_running)
+    {
+        WebViewStateChange change{};
+        change.state = WebViewState::WEW_REQUEST_CLOSE;
+
+        _handler.on_state_change(&change, _handler.context);
+    }
 
     return false;
 }
@@ -95,25 +649,47 @@ bool IWebViewLifeSpan::OnBeforePopup(CefRefPtr<CefBrowser> browser,
                                      CefRefPtr<CefDictionaryValue> &extra_info,
                                      bool *no_javascript_access)
 {
-    browser->GetMainFrame()->LoadURL(target_url);
+    if (!_block_popups)
+    {
+        browser->GetMainFrame()->LoadURL(target_url);
+    }
 
     return true;
 }
 
 void IWebViewLifeSpan::OnBeforeClose(CefRefPtr<CefBrowser> browser)
 {
+    GetBrowsers().erase(browser->GetIdentifier());
+    BROWSER_COUNT.store(GetBrowsers().size());
+
+    // The handler is no longer valid once the webview has been closed.
+    if (!_is_running)
+    {
+        return;
+    }
+
     _browser = std::nullopt;
 
-    _handler.on_state_change(WebViewState::WEW_CLOSE, _handler.context);
+    WebViewStateChange change{};
+    change.state = WebViewState::WEW_CLOSE;
+
+    _handler.on_state_change(&change, _handler.context);
 }
 
 /* CefDragHandler */
 
+IWebViewDrag::IWebViewDrag(bool windowless) : _windowless(windowless)
+{
+}
+
 bool IWebViewDrag::OnDragEnter(CefRefPtr<CefBrowser> browser,
                                CefRefPtr<CefDragData> dragData,
                                CefDragHandler::DragOperationsMask mask)
 {
-    return true;
+    // In windowless rendering mode the drag events are forwarded by the host,
+    // so they are delivered to the page. Drags of a native window are
+    // cancelled.
+    return !_windowless;
 }
 
 /* CefDisplayHandler */
@@ -133,221 +709,1109 @@ void IWebViewDisplay::OnFullscreenModeChange(CefRefPtr<CefBrowser> browser, bool
     _handler.on_fullscreen_change(fullscreen, _handler.context);
 };
 
-bool IWebViewDisplay::OnCursorChange(CefRefPtr<CefBrowser> browser,
-                                     CefCursorHandle cursor,
-                                     cef_cursor_type_t type,
-                                     const CefCursorInfo &custom_cursor_info)
+void IWebViewDisplay::OnFaviconURLChange(CefRefPtr<CefBrowser> browser, const std::vector<CefString> &icon_urls)
+{
+    std::vector<std::string> values;
+    for (auto &url : icon_urls)
+    {
+        values.push_back(url.ToString());
+    }
+
+    std::vector<const char *> urls;
+    for (auto &url : values)
+    {
+        urls.push_back(url.c_str());
+    }
+
+    _handler.on_favicon_change(urls.data(), urls.size(), _handler.context);
+}
+
+void IWebViewDisplay::OnStatusMessage(CefRefPtr<CefBrowser> browser, const CefString &value)
+{
+    std::string message = value.ToString();
+    _handler.on_status_message(message.c_str(), _handler.context);
+}
+
+bool IWebViewDisplay::OnConsoleMessage(CefRefPtr<CefBrowser> browser,
+                                       cef_log_severity_t level,
+                                       const CefString &message,
+                                       const CefString &source,
+                                       int line)
+{
+    std::string message_ = message.ToString();
+    std::string source_ = source.ToString();
+    _handler.on_console_message(static_cast<LogLevel>(level), message_.c_str(), source_.c_str(), line, _handler.context);
+
+    // The message is still written to the log.
+    return false;
+}
+
+bool IWebViewDisplay::OnCursorChange(CefRefPtr<CefBrowser> browser,
+                                     CefCursorHandle cursor,
+                                     cef_cursor_type_t type,
+                                     const CefCursorInfo &custom_cursor_info)
 {
     _handler.on_cursor(static_cast<CursorType>(static_cast<int>(type)), _handler.context);
 
     return true;
 }
 
-/* CefRenderHandler */
+/* CefFocusHandler */
+
+IWebViewFocus::IWebViewFocus(WebViewHandler &handler) : _handler(handler)
+{
+}
+
+void IWebViewFocus::OnTakeFocus(CefRefPtr<CefBrowser> browser, bool next)
+{
+    _handler.on_take_focus(next, _handler.context);
+}
+
+bool IWebViewFocus::OnSetFocus(CefRefPtr<CefBrowser> browser, FocusSource source)
+{
+    auto source_ = source == FOCUS_SOURCE_NAVIGATION ? WEW_FOCUS_SOURCE_NAVIGATION : WEW_FOCUS_SOURCE_SYSTEM;
+    return _handler.on_set_focus(source_, _handler.context);
+}
+
+void IWebViewFocus::OnGotFocus(CefRefPtr<CefBrowser> browser)
+{
+    _handler.on_got_focus(_handler.context);
+}
+
+/* CefFindHandler */
+
+IWebViewFind::IWebViewFind(WebViewHandler &handler) : _handler(handler)
+{
+}
+
+void IWebViewFind::OnFindResult(CefRefPtr<CefBrowser> browser,
+                                int identifier,
+                                int count,
+                                const CefRect &selectionRect,
+                                int activeMatchOrdinal,
+                                bool finalUpdate)
+{
+    Rect rect;
+    rect.x = selectionRect.x;
+    rect.y = selectionRect.y;
+    rect.width = selectionRect.width;
+    rect.height = selectionRect.height;
+
+    _handler.on_find_result(count, activeMatchOrdinal, rect, finalUpdate, _handler.context);
+}
+
+/* CefKeyboardHandler */
+
+// The Windows virtual key codes, which are used on all platforms.
+static constexpr int VKEY_TAB = 0x09;
+static constexpr int VKEY_ESCAPE = 0x1B;
+static constexpr int VKEY_LWIN = 0x5B;
+static constexpr int VKEY_RWIN = 0x5C;
+static constexpr int VKEY_F4 = 0x73;
+static constexpr int VKEY_F12 = 0x7B;
+
+// clang-format off
+IWebViewKeyboard::IWebViewKeyboard(const KioskPolicy *policy)
+    : _block_devtools(policy != nullptr && policy->block_devtools)
+    , _block_app_switch_shortcuts(policy != nullptr && policy->block_app_switch_shortcuts)
+{
+}
+// clang-format on
+
+bool IWebViewKeyboard::OnPreKeyEvent(CefRefPtr<CefBrowser> browser,
+                                     const CefKeyEvent &event,
+                                     CefEventHandle os_event,
+                                     bool *is_keyboard_shortcut)
+{
+    int key = event.windows_key_code;
+    bool alt = event.modifiers & EVENTFLAG_ALT_DOWN;
+    bool ctrl = event.modifiers & EVENTFLAG_CONTROL_DOWN;
+    bool shift = event.modifiers & EVENTFLAG_SHIFT_DOWN;
+    bool command = event.modifiers & EVENTFLAG_COMMAND_DOWN;
+
+    // F12, Ctrl+Shift+I and Cmd+Alt+I.
+    if (_block_devtools && (key == VKEY_F12 || (key == 'I' && ((ctrl && shift) || (command && alt)))))
+    {
+        return true;
+    }
+
+    // Alt+Tab, Alt+Esc, Alt+F4, Ctrl+Esc, the Windows keys, Cmd+Tab and Cmd+Q.
+    if (_block_app_switch_shortcuts)
+    {
+        if ((alt && (key == VKEY_TAB || key == VKEY_ESCAPE || key == VKEY_F4)) || (ctrl && key == VKEY_ESCAPE) ||
+            key == VKEY_LWIN || key == VKEY_RWIN || (command && (key == VKEY_TAB || key == 'Q')))
+        {
+            return true;
+        }
+    }
+
+    return false;
+}
+
+/* CefDownloadHandler */
+
+bool IWebViewDownload::CanDownload(CefRefPtr<CefBrowser> browser,
+                                   const CefString &url,
+                                   const CefString &request_method)
+{
+    return false;
+}
+
+bool IWebViewDownload::OnBeforeDownload(CefRefPtr<CefBrowser> browser,
+                                        CefRefPtr<CefDownloadItem> download_item,
+                                        const CefString &suggested_name,
+                                        CefRefPtr<CefBeforeDownloadCallback> callback)
+{
+    return false;
+}
+
+/* CefResourceRequestHandler */
+
+// clang-format off
+IWebViewResourceRequest::IWebViewResourceRequest(const RequestHandlerFactory *factory, WebViewHandler &handler)
+    : _handler(handler)
+{
+#ifdef WEW_REQUEST
+    _filter = new IResourceRequestHandler(factory);
+#endif
+}
+// clang-format on
+
+#ifdef WEW_REQUEST
+CefRefPtr<CefResourceHandler> IWebViewResourceRequest::GetResourceHandler(CefRefPtr<CefBrowser> browser,
+                                                                          CefRefPtr<CefFrame> frame,
+                                                                          CefRefPtr<CefRequest> request)
+{
+    return _filter->GetResourceHandler(browser, frame, request);
+}
+
+void IWebViewResourceRequest::SetRequestHandlerFactory(const RequestHandlerFactory *factory)
+{
+    _filter->SetFactory(factory);
+}
+#endif
+
+void IWebViewResourceRequest::OnResourceLoadComplete(CefRefPtr<CefBrowser> browser,
+                                                     CefRefPtr<CefFrame> frame,
+                                                     CefRefPtr<CefRequest> request,
+                                                     CefRefPtr<CefResponse> response,
+                                                     URLRequestStatus status,
+                                                     int64_t received_content_length)
+{
+    if (frame == nullptr || !frame->IsMain() || request->GetResourceType() != RT_MAIN_FRAME)
+    {
+        return;
+    }
+
+    CefResponse::HeaderMap header_map;
+    response->GetHeaderMap(header_map);
+
+    std::vector<std::pair<std::string, std::string>> values;
+    for (auto &[name, value] : header_map)
+    {
+        values.push_back({name.ToString(), value.ToString()});
+    }
+
+    std::vector<HttpHeader> headers;
+    for (auto &[name, value] : values)
+    {
+        headers.push_back(HttpHeader{name.c_str(), value.c_str()});
+    }
+
+    std::string url = response->GetURL().ToString();
+    if (url.empty())
+    {
+        url = request->GetURL().ToString();
+    }
+
+    std::string status_text = response->GetStatusText().ToString();
+    std::string mime_type = response->GetMimeType().ToString();
+
+    HttpResponse it;
+    it.url = url.c_str();
+    it.status = response->GetStatus();
+    it.status_text = status_text.c_str();
+    it.mime_type = mime_type.c_str();
+    it.headers = headers.data();
+    it.headers_size = headers.size();
+
+    _handler.on_main_frame_response(&it, _handler.context);
+}
+
+/* CefRequestHandler */
+
+// clang-format off
+IWebViewRequest::IWebViewRequest(const WebViewSettings *settings, uint32_t &reload_attempts, WebViewHandler &handler)
+    : _pdf_viewer(settings->pdf_viewer)
+    , _auto_reload_max_attempts(settings->auto_reload_max_attempts)
+    , _auto_reload_delay(settings->auto_reload_delay)
+    , _reload_attempts(reload_attempts)
+    , _handler(handler)
+{
+    assert(settings != nullptr);
+
+    _resource_request_handler = new IWebViewResourceRequest(settings->request_handler_factory, handler);
+
+    if (settings->kiosk_policy != nullptr)
+    {
+        for (size_t i = 0; i < settings->kiosk_policy->allowed_origins_size; i++)
+        {
+            _allowed_origins.push_back(std::string(settings->kiosk_policy->allowed_origins[i]));
+        }
+    }
+}
+// clang-format on
+
+CefRefPtr<CefResourceRequestHandler> IWebViewRequest::GetResourceRequestHandler(CefRefPtr<CefBrowser> browser,
+                                                                                CefRefPtr<CefFrame> frame,
+                                                                                CefRefPtr<CefRequest> request,
+                                                                                bool is_navigation,
+                                                                                bool is_download,
+                                                                                const CefString &request_initiator,
+                                                                                bool &disable_default_handling)
+{
+    return _resource_request_handler;
+}
+
+#ifdef WEW_REQUEST
+void IWebViewRequest::SetRequestHandlerFactory(const RequestHandlerFactory *factory)
+{
+    _resource_request_handler->SetRequestHandlerFactory(factory);
+}
+#endif
+
+bool IWebViewRequest::OnBeforeBrowse(CefRefPtr<CefBrowser> browser,
+                                     CefRefPtr<CefFrame> frame,
+                                     CefRefPtr<CefRequest> request,
+                                     bool user_gesture,
+                                     bool is_redirect)
+{
+    // The document stays in the frame that embeds it, only the viewer is not
+    // loaded.
+    if (!_pdf_viewer && request->GetURL().ToString().rfind(PDF_VIEWER_ORIGIN, 0) == 0)
+    {
+        return true;
+    }
+
+    if (_allowed_origins.empty() || !frame->IsMain())
+    {
+        return false;
+    }
+
+    CefURLParts parts;
+    if (!CefParseURL(request->GetURL(), parts))
+    {
+        return true;
+    }
+
+    std::string origin = CefString(&parts.scheme).ToString() + "://" + CefString(&parts.host).ToString();
+    std::string port = CefString(&parts.port).ToString();
+    if (!port.empty())
+    {
+        origin += ":" + port;
+    }
+
+    return std::find(_allowed_origins.begin(), _allowed_origins.end(), origin) == _allowed_origins.end();
+}
+
+void IWebViewRequest::OnRenderProcessTerminated(CefRefPtr<CefBrowser> browser,
+                                                TerminationStatus status,
+                                                int error_code,
+                                                const CefString &error_string)
+{
+    _handler.on_render_process_terminated(static_cast<RenderProcessTerminationStatus>(status), error_code, _handler.context);
+
+    if (_reload_attempts >= _auto_reload_max_attempts)
+    {
+        return;
+    }
+
+    // The delay is doubled for every attempt, a page that keeps crashing is
+    // not reloaded in a tight loop.
+    int64_t delay = static_cast<int64_t>(_auto_reload_delay) << std::min(_reload_attempts, 16u);
+    _reload_attempts++;
+
+    CefPostDelayedTask(TID_UI, base::BindOnce([](CefRefPtr<CefBrowser> browser) { browser->Reload(); }, browser), delay);
+}
+
+/* IWebView */
 
 // clang-format off
-IWebViewRender::IWebViewRender(const WebViewSettings *settings, WebViewHandler &handler)
+IWebView::IWebView(CefSettings &cef_settings, const WebViewSettings *settings, WebViewHandler handler)
     : _handler(handler)
-    , _device_scale_factor(settings->device_scale_factor)
+    , _max_in_flight_messages(settings->max_in_flight_messages)
 {
     assert(settings != nullptr);
 
-    _view_rect.width = settings->width;
-    _view_rect.height = settings->height;
+    _drag_handler = new IWebViewDrag(cef_settings.windowless_rendering_enabled);
+    _load_handler = new IWebViewLoad(_reload_attempts, _handler);
+    _display_handler = new IWebViewDisplay(_handler);
+    _focus_handler = new IWebViewFocus(_handler);
+    _life_span_handler = new IWebViewLifeSpan(settings, _browser, _is_running, _handler);
+    _context_menu_handler = new IWebViewContextMenu(settings);
+    _keyboard_handler = new IWebViewKeyboard(settings->kiosk_policy);
+    _find_handler = new IWebViewFind(_handler);
+
+    if (settings->kiosk_policy != nullptr)
+    {
+        _block_devtools = settings->kiosk_policy->block_devtools;
+
+        if (settings->kiosk_policy->block_downloads)
+        {
+            _download_handler = new IWebViewDownload();
+        }
+    }
+
+#ifdef WEW_WINDOWLESS
+    if (cef_settings.windowless_rendering_enabled)
+    {
+        _render_handler = new IWebViewRender(settings, _handler);
+    }
+#endif
+
+    _request_handler = new IWebViewRequest(settings, _reload_attempts, _handler);
 }
 // clang-format on
 
-bool IWebViewRender::GetScreenInfo(CefRefPtr<CefBrowser> browser, CefScreenInfo &info)
+IWebView::~IWebView()
 {
-    info.device_scale_factor = _device_scale_factor;
+    this->Close();
+}
+
+CefRefPtr<CefDragHandler> IWebView::GetDragHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _drag_handler;
+}
+
+CefRefPtr<CefDisplayHandler> IWebView::GetDisplayHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _display_handler;
+}
+
+CefRefPtr<CefFocusHandler> IWebView::GetFocusHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _focus_handler;
+}
+
+CefRefPtr<CefKeyboardHandler> IWebView::GetKeyboardHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _keyboard_handler;
+}
+
+CefRefPtr<CefFindHandler> IWebView::GetFindHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _find_handler;
+}
+
+CefRefPtr<CefDownloadHandler> IWebView::GetDownloadHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _download_handler;
+}
+
+CefRefPtr<CefLifeSpanHandler> IWebView::GetLifeSpanHandler()
+{
+    // The life span handler is still needed after the webview is closed, it
+    // tracks the browser until it is destroyed.
+    return _life_span_handler;
+}
+
+CefRefPtr<CefLoadHandler> IWebView::GetLoadHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _load_handler;
+}
+
+CefRefPtr<CefRenderHandler> IWebView::GetRenderHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+#ifdef WEW_WINDOWLESS
+    return _render_handler;
+#else
+    return nullptr;
+#endif
+}
+
+CefRefPtr<CefRequestHandler> IWebView::GetRequestHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _request_handler;
+}
+
+CefRefPtr<CefContextMenuHandler> IWebView::GetContextMenuHandler()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _context_menu_handler;
+}
+
+bool IWebView::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
+                                        CefRefPtr<CefFrame> frame,
+                                        CefProcessId source_process,
+                                        CefRefPtr<CefProcessMessage> message)
+{
+    CHECK_REFCOUNTING(false);
+
+    if (!_browser.has_value())
+    {
+        return false;
+    }
+
+    auto args = message->GetArgumentList();
+    if (message->GetName() == "PAGE_ERROR")
+    {
+        std::string id = frame->GetIdentifier().ToString();
+        std::string name = frame->GetName().ToString();
+        std::string url = frame->GetURL().ToString();
+        std::string origin = get_url_origin(url);
+
+        FrameInfo info;
+        info.identifier = id.c_str();
+        info.name = name.c_str();
+        info.url = url.c_str();
+        info.origin = origin.c_str();
+        info.is_main = frame->IsMain();
+
+        std::string error_message = args->GetString(0);
+        std::string source = args->GetString(1);
+        std::string stack = args->GetString(4);
+
+        PageError error;
+        error.message = error_message.c_str();
+        error.source = source.c_str();
+        error.line = args->GetInt(2);
+        error.column = args->GetInt(3);
+        error.stack = stack.c_str();
+
+        _handler.on_page_error(&error, info, _handler.context);
+
+        return true;
+    }
+
+    if (message->GetName() == "PROCESS_INFO")
+    {
+        _render_process_id = args->GetInt(0);
+
+        return true;
+    }
+
+    if (message->GetName() == "CLIPBOARD_ACCESS")
+    {
+        // The origin is taken from the URL of the frame known to the browser
+        // process, so that the page cannot claim another origin.
+        CefURLParts parts;
+        std::string origin;
+        if (CefParseURL(frame->GetURL(), parts))
+        {
+            origin = CefString(&parts.origin).ToString();
+            if (!origin.empty() && origin.back() == '/')
+            {
+                origin.pop_back();
+            }
+        }
+
+        auto access = static_cast<ClipboardAccess>(args->GetInt(1));
+        auto policy = _handler.on_clipboard_access(origin.c_str(), access, _handler.context);
+
+        auto reply = CefProcessMessage::Create("CLIPBOARD_POLICY");
+        reply->GetArgumentList()->SetInt(0, args->GetInt(0));
+        reply->GetArgumentList()->SetInt(1, policy);
+        frame->SendProcessMessage(PID_RENDERER, reply);
+
+        return true;
+    }
+
+    if (message->GetName() == "PDF_LOADED")
+    {
+        std::string url = args->GetString(0);
+        std::string outline = args->GetString(2);
+
+        PdfDocument document;
+        document.url = url.c_str();
+        document.page_count = static_cast<uint32_t>(args->GetInt(1));
+        document.outline = outline.c_str();
+
+        _handler.on_pdf_load(&document, _handler.context);
+
+        return true;
+    }
+
+    if (message->GetName() == "PRINT_STATE")
+    {
+        if (args->GetBool(0))
+        {
+            _handler.on_print_start(_handler.context);
+        }
+        else
+        {
+            _handler.on_print_finished(_handler.context);
+        }
+
+        return true;
+    }
+
+    // The default channel has an empty name, it is reported as NULL.
+    std::string channel = args->GetSize() > 1 ? args->GetString(1).ToString() : "";
+    const char *channel_name = channel.empty() ? nullptr : channel.c_str();
+
+    std::string id = frame->GetIdentifier().ToString();
+    std::string name = frame->GetName().ToString();
+    // The URL is taken from the frame known to the browser process, so that the
+    // page cannot claim another URL or origin.
+    std::string url = frame->GetURL().ToString();
+    std::string origin = get_url_origin(url);
+
+    FrameInfo info;
+    info.identifier = id.c_str();
+    info.name = name.c_str();
+    info.url = url.c_str();
+    info.origin = origin.c_str();
+    info.is_main = frame->IsMain();
+
+    if (args->GetType(0) == VTYPE_BINARY)
+    {
+        auto binary = args->GetBinary(0);
+        std::vector<uint8_t> payload(binary->GetSize());
+        binary->GetData(payload.data(), payload.size(), 0);
+        _handler.on_binary_message(channel_name, payload.data(), payload.size(), info, _handler.context);
+    }
+    else
+    {
+        std::string payload = args->GetString(0);
+        _handler.on_message(channel_name, payload.c_str(), info, _handler.context);
+    }
+
+    // The message is acknowledged with IWebView::AckMessage once the handler
+    // has handled it, which may be after the callback has returned.
+    return true;
+}
+
+void IWebView::SetDevToolsOpenState(bool is_open)
+{
+    CHECK_REFCOUNTING();
+
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    if (is_open)
+    {
+        if (_block_devtools)
+        {
+            return;
+        }
+
+        _browser.value()->GetHost()->ShowDevTools(CefWindowInfo(), nullptr, CefBrowserSettings(), CefPoint());
+    }
+    else
+    {
+        _browser.value()->GetHost()->CloseDevTools();
+    }
+}
+
+void IWebView::SetRequestHandlerFactory(const RequestHandlerFactory *factory)
+{
+#ifdef WEW_REQUEST
+    _request_handler->SetRequestHandlerFactory(factory);
+#endif
+}
+
+bool IWebView::GetWindowHandle(RawWindowHandle *handle)
+{
+    CHECK_REFCOUNTING(false);
+
+    if (!_browser.has_value())
+    {
+        return false;
+    }
+
+    *handle = _browser.value()->GetHost()->GetWindowHandle();
+    return true;
+}
+
+CefBrowser *IWebView::GetBrowser()
+{
+    CHECK_REFCOUNTING(nullptr);
+
+    return _browser.has_value() ? _browser.value().get() : nullptr;
+}
+
+int IWebView::GetRenderProcessId()
+{
+    CHECK_REFCOUNTING(0);
+
+    return _render_process_id;
+}
+
+void IWebView::GetFrames(void (*callback)(FrameInfo info, void *context), void *context)
+{
+    CHECK_REFCOUNTING();
+
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    std::vector<CefString> identifiers;
+    _browser.value()->GetFrameIdentifiers(identifiers);
+
+    for (auto &identifier : identifiers)
+    {
+        auto frame = _browser.value()->GetFrameByIdentifier(identifier);
+        if (frame == nullptr)
+        {
+            continue;
+        }
+
+        std::string id = identifier.ToString();
+        std::string name = frame->GetName().ToString();
+        std::string url = frame->GetURL().ToString();
+        std::string origin = get_url_origin(url);
+
+        FrameInfo info;
+        info.identifier = id.c_str();
+        info.name = name.c_str();
+        info.url = url.c_str();
+        info.origin = origin.c_str();
+        info.is_main = frame->IsMain();
+
+        callback(info, context);
+    }
+}
+
+void IWebView::DownloadImage(std::string url,
+                             bool is_favicon,
+                             uint32_t max_size,
+                             void (*callback)(const Image *image, void *context),
+                             void *context)
+{
+    // The callback must always be called, it owns the context.
+    if (!_is_running || !_browser.has_value())
+    {
+        callback(nullptr, context);
+        return;
+    }
+
+    _browser.value()->GetHost()->DownloadImage(url,
+                                               is_favicon,
+                                               max_size,
+                                               false,
+                                               new IDownloadImageCallback(callback, context));
+}
+
+void IWebView::GetLayoutMetrics(void (*callback)(const LayoutMetrics *metrics, void *context), void *context)
+{
+    // The callback must always be called, it owns the context.
+    if (!_is_running || !_browser.has_value())
+    {
+        callback(nullptr, context);
+        return;
+    }
+
+    // DevTools methods can only be executed on the UI thread.
+    auto task = base::BindOnce(
+        [](CefRefPtr<CefBrowser> browser,
+           void (*callback)(const LayoutMetrics *metrics, void *context),
+           void *context)
+        {
+            CefRefPtr<ILayoutMetricsObserver> observer = new ILayoutMetricsObserver(callback, context);
+            observer->Start(browser);
+        },
+        _browser.value(), callback, context);
+
+    if (!CefPostTask(TID_UI, std::move(task)))
+    {
+        callback(nullptr, context);
+    }
+}
+
+void IWebView::CaptureThumbnail(uint32_t max_width,
+                                uint32_t max_height,
+                                void (*callback)(const Image *image, void *context),
+                                void *context)
+{
+    // The callback must always be called, it owns the context.
+    if (!_is_running || !_browser.has_value() || max_width == 0 || max_height == 0)
+    {
+        callback(nullptr, context);
+        return;
+    }
+
+    // DevTools methods can only be executed on the UI thread.
+    auto task = base::BindOnce(
+        [](CefRefPtr<CefBrowser> browser,
+           uint32_t max_width,
+           uint32_t max_height,
+           void (*callback)(const Image *image, void *context),
+           void *context)
+        {
+            CefRefPtr<IThumbnailObserver> observer = new IThumbnailObserver(max_width, max_height, callback, context);
+            observer->Start(browser);
+        },
+        _browser.value(), max_width, max_height, callback, context);
+
+    if (!CefPostTask(TID_UI, std::move(task)))
+    {
+        callback(nullptr, context);
+    }
+}
+
+void IWebView::StartScreencast(ScreencastOptions options,
+                               bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                               void *context)
+{
+    // The callback must always be called, it owns the context.
+    if (!_is_running || !_browser.has_value())
+    {
+        on_frame(nullptr, context);
+        return;
+    }
+
+    // The DevTools methods can only be executed on the UI thread.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        auto task = base::BindOnce(&IWebView::StartScreencast, CefRefPtr<IWebView>(this), options, on_frame, context);
+        if (!CefPostTask(TID_UI, std::move(task)))
+        {
+            on_frame(nullptr, context);
+        }
+
+        return;
+    }
+
+    // A browser only has one screencast, starting a new one replaces the
+    // settings of the running one.
+    if (_screencast != nullptr)
+    {
+        _screencast->Stop();
+    }
+
+    _screencast = new IScreencastObserver(options, on_frame, context);
+    _screencast->Start(_browser.value());
+}
+
+void IWebView::StopScreencast()
+{
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI, base::BindOnce(&IWebView::StopScreencast, CefRefPtr<IWebView>(this)));
+        return;
+    }
+
+    if (_screencast != nullptr)
+    {
+        _screencast->Stop();
+        _screencast = nullptr;
+    }
+}
+
+CefRefPtr<CefFrame> IWebView::GetFrame(std::optional<std::string> frame)
+{
+    return frame.has_value() ? _browser.value()->GetFrameByIdentifier(frame.value())
+                             : _browser.value()->GetMainFrame();
+}
+
+void IWebView::SendProcessMessage(CefRefPtr<CefProcessMessage> message, std::optional<std::string> frame)
+{
+    CHECK_REFCOUNTING();
+
+    // The browser is only used on the UI thread.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI,
+                    base::BindOnce(&IWebView::SendProcessMessage, CefRefPtr<IWebView>(this), message, frame));
+        return;
+    }
+
+    // The browser is created before the webview is returned, it only no longer
+    // exists once the webview is closed.
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    auto target = GetFrame(frame);
+    if (target != nullptr)
+    {
+        target->SendProcessMessage(PID_RENDERER, message);
+    }
+}
+
+void IWebView::SendMessage(std::string message, std::string channel, std::optional<std::string> frame)
+{
+    CHECK_REFCOUNTING();
+
+    auto msg = CefProcessMessage::Create("MESSAGE_TRANSPORT");
+    CefRefPtr<CefListValue> args = msg->GetArgumentList();
+    args->SetSize(2);
+    args->SetString(0, message);
+    args->SetString(1, channel);
+    SendProcessMessage(msg, frame);
+}
+
+void IWebView::SendBinaryMessage(const uint8_t *message,
+                                 size_t size,
+                                 std::string channel,
+                                 std::optional<std::string> frame)
+{
+    CHECK_REFCOUNTING();
+
+    auto msg = CefProcessMessage::Create("MESSAGE_TRANSPORT");
+    CefRefPtr<CefListValue> args = msg->GetArgumentList();
+    args->SetSize(2);
+    args->SetBinary(0, CefBinaryValue::Create(message, size));
+    args->SetString(1, channel);
+    SendProcessMessage(msg, frame);
+}
+
+void IWebView::ExecuteJavaScript(std::string script, std::optional<std::string> frame)
+{
+    CHECK_REFCOUNTING();
+
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    auto target = GetFrame(frame);
+    if (target != nullptr)
+    {
+        target->ExecuteJavaScript(script, target->GetURL(), 0);
+    }
+}
+
+void IWebView::Emit(std::string event, std::string payload)
+{
+    CHECK_REFCOUNTING();
+
+    auto msg = CefProcessMessage::Create("EVENT_EMITTER");
+    CefRefPtr<CefListValue> args = msg->GetArgumentList();
+    args->SetSize(2);
+    args->SetString(0, event);
+    args->SetString(1, payload);
+    SendProcessMessage(msg, std::nullopt);
+}
+
+void IWebView::AckMessage(std::string frame)
+{
+    CHECK_REFCOUNTING();
+
+    // The render process only counts the messages when there is a limit.
+    if (_max_in_flight_messages == 0)
+    {
+        return;
+    }
+
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI, base::BindOnce(&IWebView::AckMessage, CefRefPtr<IWebView>(this), frame));
+        return;
+    }
+
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    // The render process counts the messages of each browser, the main frame
+    // is notified if the frame that sent the message has since been removed.
+    auto target = GetFrame(frame);
+    if (target == nullptr)
+    {
+        target = GetFrame(std::nullopt);
+    }
 
-    return true;
+    if (target != nullptr)
+    {
+        target->SendProcessMessage(PID_RENDERER, CefProcessMessage::Create("MESSAGE_TRANSPORT_ACK"));
+    }
 }
 
-void IWebViewRender::OnImeCompositionRangeChanged(CefRefPtr<CefBrowser> browser,
-                                                  const CefRange &selected_range,
-                                                  const RectList &character_bounds)
+void IWebView::Close()
 {
-    if (character_bounds.size() == 0)
+    CHECK_REFCOUNTING();
+
+    if (!_browser.has_value())
     {
         return;
     }
 
-    auto first_rect = character_bounds[0];
+    // The screencast owns the context of its callback, so it is ended before
+    // the browser goes away.
+    StopScreencast();
 
-    Rect rect;
-    rect.x = first_rect.x;
-    rect.y = first_rect.y;
-    rect.width = first_rect.width;
-    rect.height = first_rect.height;
+    _browser.value()->GetHost()->CloseBrowser(true);
+    _browser = std::nullopt;
 
-    _handler.on_ime_rect(rect, _handler.context);
+    CLOSE_RUNNING;
 }
 
-void IWebViewRender::GetViewRect(CefRefPtr<CefBrowser> browser, CefRect &rect)
+void IWebView::OnIMEComposition(std::string input)
 {
-    rect.x = _view_rect.x;
-    rect.y = _view_rect.y;
-    rect.width = _view_rect.width;
-    rect.height = _view_rect.height;
-}
+    CHECK_REFCOUNTING();
 
-void IWebViewRender::OnPaint(CefRefPtr<CefBrowser> browser,
-                             PaintElementType type,
-                             const RectList &dirtyRects,
-                             const void *buffer, // BGRA32
-                             int width,
-                             int height)
-{
-    if (buffer == nullptr)
+    if (!_browser.has_value())
     {
         return;
     }
 
-    Frame frame;
-    frame.width = width;
-    frame.height = height;
-    frame.buffer = buffer;
-    frame.is_popup = type == PaintElementType::PET_POPUP;
-
-    auto rect = dirtyRects[0];
-    frame.x = frame.is_popup ? _popup_rect.x : rect.x;
-    frame.y = frame.is_popup ? _popup_rect.y : rect.y;
-
-    _handler.on_frame(&frame, _handler.context);
+    _browser.value()->GetHost()->ImeCommitText(input, CefRange::InvalidRange(), 0);
 }
 
-void IWebViewRender::OnPopupSize(CefRefPtr<CefBrowser> browser, const CefRect &rect)
+void IWebView::OnIMESetComposition(std::string input, int x, int y)
 {
-    _popup_rect.x = rect.x;
-    _popup_rect.y = rect.y;
-    _popup_rect.width = rect.width;
-    _popup_rect.height = rect.height;
-}
+    CHECK_REFCOUNTING();
 
-void IWebViewRender::Resize(int width, int height)
-{
-    _view_rect.width = width;
-    _view_rect.height = height;
-}
+    if (!_browser.has_value())
+    {
+        return;
+    }
 
-/* CefRequestHandler */
+    CefCompositionUnderline line;
+    line.style = CEF_CUS_DASH;
+    line.range = CefRange(0, y);
 
-IWebViewRequest::IWebViewRequest(const WebViewSettings *settings)
-    : _handler(new IResourceRequestHandler(settings->request_handler_factory))
-{
-    assert(settings != nullptr);
+    _browser.value()->GetHost()->ImeSetComposition(input, {line}, CefRange::InvalidRange(), CefRange(x, y));
 }
-
-CefRefPtr<CefResourceRequestHandler> IWebViewRequest::GetResourceRequestHandler(CefRefPtr<CefBrowser> browser,
-                                                                                CefRefPtr<CefFrame> frame,
-                                                                                CefRefPtr<CefRequest> request,
-                                                                                bool is_navigation,
-                                                                                bool is_download,
-                                                                                const CefString &request_initiator,
-                                                                                bool &disable_default_handling)
+void IWebView::OnMouseClick(cef_mouse_event_t event, cef_mouse_button_type_t button, bool pressed)
 {
-    return _handler;
-}
+    CHECK_REFCOUNTING();
 
-/* IWebView */
+    if (!_browser.has_value())
+    {
+        return;
+    }
 
-IWebView::IWebView(CefSettings &cef_settings, const WebViewSettings *settings, WebViewHandler handler)
-    : _handler(handler)
-{
-    assert(settings != nullptr);
+    _browser.value()->GetHost()->SendMouseClickEvent(event, button, !pressed, 1);
+}
 
-    _drag_handler = new IWebViewDrag();
-    _load_handler = new IWebViewLoad(_handler);
-    _display_handler = new IWebViewDisplay(_handler);
-    _life_span_handler = new IWebViewLifeSpan(_browser, _handler);
-    _context_menu_handler = new IWebViewContextMenu();
+void IWebView::OnMouseMove(cef_mouse_event_t event)
+{
+    CHECK_REFCOUNTING();
 
-    if (cef_settings.windowless_rendering_enabled)
+    if (!_browser.has_value())
     {
-        _render_handler = new IWebViewRender(settings, _handler);
+        return;
     }
 
-    if (settings->request_handler_factory)
-    {
-        _request_handler = new IWebViewRequest(settings);
-    }
+    _browser.value()->GetHost()->SendMouseMoveEvent(event, false);
 }
 
-IWebView::~IWebView()
+void IWebView::OnDragEnter(CefRefPtr<CefDragData> data,
+                           cef_mouse_event_t event,
+                           CefBrowserHost::DragOperationsMask mask)
 {
-    this->Close();
-}
+    CHECK_REFCOUNTING();
 
-CefRefPtr<CefDragHandler> IWebView::GetDragHandler()
-{
-    CHECK_REFCOUNTING(nullptr);
+    if (!_browser.has_value())
+    {
+        return;
+    }
 
-    return _drag_handler;
+    _browser.value()->GetHost()->DragTargetDragEnter(data, event, mask);
 }
 
-CefRefPtr<CefDisplayHandler> IWebView::GetDisplayHandler()
+void IWebView::OnDragOver(cef_mouse_event_t event, CefBrowserHost::DragOperationsMask mask)
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _display_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->DragTargetDragOver(event, mask);
 }
 
-CefRefPtr<CefLifeSpanHandler> IWebView::GetLifeSpanHandler()
+void IWebView::OnDragLeave()
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _life_span_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->DragTargetDragLeave();
 }
 
-CefRefPtr<CefLoadHandler> IWebView::GetLoadHandler()
+void IWebView::OnDrop(cef_mouse_event_t event)
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _load_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->DragTargetDrop(event);
 }
 
-CefRefPtr<CefRenderHandler> IWebView::GetRenderHandler()
+void IWebView::OnDragSourceEnded(int x, int y, CefBrowserHost::DragOperationsMask op)
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _render_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->DragSourceEndedAt(x, y, op);
+    _browser.value()->GetHost()->DragSourceSystemDragEnded();
 }
 
-CefRefPtr<CefRequestHandler> IWebView::GetRequestHandler()
+void IWebView::OnMouseWheel(cef_mouse_event_t event, int x, int y)
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _request_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->SendMouseWheelEvent(event, x, y);
 }
 
-CefRefPtr<CefContextMenuHandler> IWebView::GetContextMenuHandler()
+void IWebView::OnKeyboard(cef_key_event_t event)
 {
-    CHECK_REFCOUNTING(nullptr);
+    CHECK_REFCOUNTING();
 
-    return _context_menu_handler;
+    if (!_browser.has_value())
+    {
+        return;
+    }
+
+    _browser.value()->GetHost()->SendKeyEvent(event);
 }
 
-bool IWebView::OnProcessMessageReceived(CefRefPtr<CefBrowser> browser,
-                                        CefRefPtr<CefFrame> frame,
-                                        CefProcessId source_process,
-                                        CefRefPtr<CefProcessMessage> message)
+void IWebView::OnTouch(cef_touch_event_t event)
 {
-    CHECK_REFCOUNTING(false);
+    CHECK_REFCOUNTING();
 
     if (!_browser.has_value())
     {
-        return false;
+        return;
     }
 
-    auto args = message->GetArgumentList();
-    std::string payload = args->GetString(0);
-    _handler.on_message(payload.c_str(), _handler.context);
-
-    return true;
+    _browser.value()->GetHost()->SendTouchEvent(event);
 }
 
-void IWebView::SetDevToolsOpenState(bool is_open)
+void IWebView::Resize(int width, int height)
 {
     CHECK_REFCOUNTING();
 
@@ -356,46 +1820,48 @@ void IWebView::SetDevToolsOpenState(bool is_open)
         return;
     }
 
-    if (is_open)
-    {
-        _browser.value()->GetHost()->ShowDevTools(CefWindowInfo(), nullptr, CefBrowserSettings(), CefPoint());
-    }
-    else
+#ifdef WEW_WINDOWLESS
+    if (_render_handler != nullptr)
     {
-        _browser.value()->GetHost()->CloseDevTools();
+        _render_handler->Resize(width, height);
+        _browser.value()->GetHost()->WasResized();
     }
+#endif
 }
 
-RawWindowHandle IWebView::GetWindowHandle()
+void IWebView::NotifyScreenInfoChanged()
 {
-#ifdef LINUX
-    CHECK_REFCOUNTING(0);
+    CHECK_REFCOUNTING();
 
-    return _browser.has_value() ? _browser.value()->GetHost()->GetWindowHandle() : 0;
-#else
-    CHECK_REFCOUNTING(nullptr);
+    if (!_browser.has_value())
+    {
+        return;
+    }
 
-    return _browser.has_value() ? _browser.value()->GetHost()->GetWindowHandle() : nullptr;
+#ifdef WEW_WINDOWLESS
+    if (_render_handler != nullptr)
+    {
+        _browser.value()->GetHost()->NotifyScreenInfoChanged();
+        _browser.value()->GetHost()->WasResized();
+    }
 #endif
 }
 
-void IWebView::SendMessage(std::string message)
+void IWebView::SetDeviceScaleFactor(float device_scale_factor)
 {
     CHECK_REFCOUNTING();
 
-    if (!_browser.has_value())
+#ifdef WEW_WINDOWLESS
+    if (_render_handler != nullptr)
     {
-        return;
+        _render_handler->SetDeviceScaleFactor(device_scale_factor);
     }
+#endif
 
-    auto msg = CefProcessMessage::Create("MESSAGE_TRANSPORT");
-    CefRefPtr<CefListValue> args = msg->GetArgumentList();
-    args->SetSize(1);
-    args->SetString(0, message);
-    _browser.value()->GetMainFrame()->SendProcessMessage(PID_RENDERER, msg);
+    NotifyScreenInfoChanged();
 }
 
-void IWebView::Close()
+void IWebView::SetFocus(bool enable)
 {
     CHECK_REFCOUNTING();
 
@@ -404,13 +1870,10 @@ void IWebView::Close()
         return;
     }
 
-    _browser.value()->GetHost()->CloseBrowser(true);
-    _browser = std::nullopt;
-
-    CLOSE_RUNNING;
+    _browser.value()->GetHost()->SetFocus(enable);
 }
 
-void IWebView::OnIMEComposition(std::string input)
+void IWebView::SetZoomLevel(double level)
 {
     CHECK_REFCOUNTING();
 
@@ -419,10 +1882,10 @@ void IWebView::OnIMEComposition(std::string input)
         return;
     }
 
-    _browser.value()->GetHost()->ImeCommitText(input, CefRange::InvalidRange(), 0);
+    _browser.value()->GetHost()->SetZoomLevel(level);
 }
 
-void IWebView::OnIMESetComposition(std::string input, int x, int y)
+void IWebView::ExitFullscreen()
 {
     CHECK_REFCOUNTING();
 
@@ -431,13 +1894,12 @@ void IWebView::OnIMESetComposition(std::string input, int x, int y)
         return;
     }
 
-    CefCompositionUnderline line;
-    line.style = CEF_CUS_DASH;
-    line.range = CefRange(0, y);
-
-    _browser.value()->GetHost()->ImeSetComposition(input, {line}, CefRange::InvalidRange(), CefRange(x, y));
+    // The host does not resize the view when it leaves fullscreen, the view
+    // keeps the size it had in fullscreen mode.
+    _browser.value()->GetHost()->ExitFullscreen(false);
 }
-void IWebView::OnMouseClick(cef_mouse_event_t event, cef_mouse_button_type_t button, bool pressed)
+
+void IWebView::SetForcedColors(ForcedColors forced_colors)
 {
     CHECK_REFCOUNTING();
 
@@ -446,10 +1908,19 @@ void IWebView::OnMouseClick(cef_mouse_event_t event, cef_mouse_button_type_t but
         return;
     }
 
-    _browser.value()->GetHost()->SendMouseClickEvent(event, button, !pressed, 1);
+    // The DevTools methods can only be executed on the UI thread.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI, base::BindOnce(&IWebView::SetForcedColors, CefRefPtr<IWebView>(this), forced_colors));
+        return;
+    }
+
+    _browser.value()->GetHost()->ExecuteDevToolsMethod(0,
+                                                       "Emulation.setEmulatedMedia",
+                                                       CreateEmulatedMediaParams(forced_colors));
 }
 
-void IWebView::OnMouseMove(cef_mouse_event_t event)
+void IWebView::UpdateSettings(const WebViewSettingsDelta *delta)
 {
     CHECK_REFCOUNTING();
 
@@ -458,10 +1929,47 @@ void IWebView::OnMouseMove(cef_mouse_event_t event)
         return;
     }
 
-    _browser.value()->GetHost()->SendMouseMoveEvent(event, false);
+    auto host = _browser.value()->GetHost();
+    if (delta->windowless_frame_rate > 0)
+    {
+        host->SetWindowlessFrameRate(delta->windowless_frame_rate);
+    }
+
+    if (delta->set_muted)
+    {
+        host->SetAudioMuted(delta->muted);
+    }
+
+    // The background color and the user agent can only be changed through the
+    // DevTools protocol, the parameters are created here because |delta| is
+    // only valid during this call.
+    std::vector<std::pair<std::string, CefRefPtr<CefDictionaryValue>>> methods;
+    if (delta->set_background_color)
+    {
+        auto color = CefDictionaryValue::Create();
+        color->SetInt("r", (delta->background_color >> 16) & 0xFF);
+        color->SetInt("g", (delta->background_color >> 8) & 0xFF);
+        color->SetInt("b", delta->background_color & 0xFF);
+        color->SetDouble("a", ((delta->background_color >> 24) & 0xFF) / 255.0);
+
+        auto params = CefDictionaryValue::Create();
+        params->SetDictionary("color", color);
+        methods.push_back({"Emulation.setDefaultBackgroundColorOverride", params});
+    }
+
+    if (delta->user_agent != nullptr)
+    {
+        methods.push_back({"Emulation.setUserAgentOverride",
+                           CreateUserAgentOverrideParams(delta->user_agent, delta->user_agent_metadata)});
+    }
+
+    for (auto &[method, params] : methods)
+    {
+        ExecuteDevToolsMethod(method, params);
+    }
 }
 
-void IWebView::OnMouseWheel(cef_mouse_event_t event, int x, int y)
+void IWebView::ExecuteDevToolsMethod(std::string method, CefRefPtr<CefDictionaryValue> params)
 {
     CHECK_REFCOUNTING();
 
@@ -470,10 +1978,18 @@ void IWebView::OnMouseWheel(cef_mouse_event_t event, int x, int y)
         return;
     }
 
-    _browser.value()->GetHost()->SendMouseWheelEvent(event, x, y);
+    // The DevTools methods can only be executed on the UI thread.
+    if (!CefCurrentlyOn(TID_UI))
+    {
+        CefPostTask(TID_UI,
+                    base::BindOnce(&IWebView::ExecuteDevToolsMethod, CefRefPtr<IWebView>(this), method, params));
+        return;
+    }
+
+    _browser.value()->GetHost()->ExecuteDevToolsMethod(0, method, params);
 }
 
-void IWebView::OnKeyboard(cef_key_event_t event)
+void IWebView::ExecuteEditCommand(EditCommand command)
 {
     CHECK_REFCOUNTING();
 
@@ -482,10 +1998,39 @@ void IWebView::OnKeyboard(cef_key_event_t event)
         return;
     }
 
-    _browser.value()->GetHost()->SendKeyEvent(event);
+    auto frame = _browser.value()->GetFocusedFrame();
+    if (frame == nullptr)
+    {
+        return;
+    }
+
+    switch (command)
+    {
+    case EditCommand::WEW_EDIT_COMMAND_CUT:
+        frame->Cut();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_COPY:
+        frame->Copy();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_PASTE:
+        frame->Paste();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_PASTE_AS_PLAIN_TEXT:
+        frame->PasteAndMatchStyle();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_SELECT_ALL:
+        frame->SelectAll();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_UNDO:
+        frame->Undo();
+        break;
+    case EditCommand::WEW_EDIT_COMMAND_REDO:
+        frame->Redo();
+        break;
+    }
 }
 
-void IWebView::OnTouch(cef_touch_event_t event)
+void IWebView::Print()
 {
     CHECK_REFCOUNTING();
 
@@ -494,10 +2039,24 @@ void IWebView::OnTouch(cef_touch_event_t event)
         return;
     }
 
-    _browser.value()->GetHost()->SendTouchEvent(event);
+    _browser.value()->GetHost()->Print();
 }
 
-void IWebView::Resize(int width, int height)
+bool IWebView::OnPrintDialog(bool has_selection)
+{
+    CHECK_REFCOUNTING(false);
+
+    return _handler.on_print_dialog(has_selection, _handler.context);
+}
+
+bool IWebView::OnPrintJob(std::string document_name, std::string pdf_file_path)
+{
+    CHECK_REFCOUNTING(false);
+
+    return _handler.on_print_job(document_name.c_str(), pdf_file_path.c_str(), _handler.context);
+}
+
+void IWebView::Find(std::string text, bool forward, bool match_case, bool find_next)
 {
     CHECK_REFCOUNTING();
 
@@ -506,14 +2065,10 @@ void IWebView::Resize(int width, int height)
         return;
     }
 
-    if (_render_handler != nullptr)
-    {
-        _render_handler->Resize(width, height);
-        _browser.value()->GetHost()->WasResized();
-    }
+    _browser.value()->GetHost()->Find(text, forward, match_case, find_next);
 }
 
-void IWebView::SetFocus(bool enable)
+void IWebView::StopFinding(bool clear_selection)
 {
     CHECK_REFCOUNTING();
 
@@ -522,5 +2077,5 @@ void IWebView::SetFocus(bool enable)
         return;
     }
 
-    _browser.value()->GetHost()->SetFocus(enable);
+    _browser.value()->GetHost()->StopFinding(clear_selection);
 }
@@ -9,18 +9,187 @@
 #define webview_h
 #pragma once
 
+#include <algorithm>
+#include <atomic>
 #include <float.h>
+#include <map>
 #include <optional>
+#include <vector>
 
+#include "include/base/cef_callback.h"
 #include "include/cef_app.h"
+#include "include/cef_devtools_message_observer.h"
+#include "include/cef_image.h"
+#include "include/cef_parser.h"
+#include "include/cef_request_handler.h"
+#include "include/wrapper/cef_closure_task.h"
 
-#include "request.h"
 #include "util.h"
 #include "wew.h"
 
+#ifdef WEW_REQUEST
+#include "request.h"
+#endif
+
+#ifdef WEW_WINDOWLESS
+#include "render.h"
+#endif
+
+class IDownloadImageCallback : public CefDownloadImageCallback
+{
+  public:
+    IDownloadImageCallback(void (*callback)(const Image *image, void *context), void *context);
+
+    ///
+    /// Method that will be executed when the image download has completed.
+    ///
+    void OnDownloadImageFinished(const CefString &image_url, int http_status_code, CefRefPtr<CefImage> image) override;
+
+  private:
+    void (*_callback)(const Image *image, void *context);
+    void *_context;
+
+    IMPLEMENT_REFCOUNTING(IDownloadImageCallback);
+};
+
+class ILayoutMetricsObserver : public CefDevToolsMessageObserver
+{
+  public:
+    ILayoutMetricsObserver(void (*callback)(const LayoutMetrics *metrics, void *context), void *context);
+
+    /* CefDevToolsMessageObserver */
+
+    ///
+    /// Method that will be called after attempted execution of a DevTools protocol method.
+    ///
+    void OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                int message_id,
+                                bool success,
+                                const void *result,
+                                size_t result_size) override;
+
+    ///
+    /// Method that will be called when the DevTools agent has detached.
+    ///
+    void OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser) override;
+
+    /* custom impl */
+
+    void Start(CefRefPtr<CefBrowser> browser);
+
+  private:
+    void Done(const LayoutMetrics *metrics);
+
+    void (*_callback)(const LayoutMetrics *metrics, void *context);
+    void *_context;
+    CefRefPtr<CefRegistration> _registration = nullptr;
+    int _message_id = 0;
+    bool _done = false;
+
+    IMPLEMENT_REFCOUNTING(ILayoutMetricsObserver);
+};
+
+class IThumbnailObserver : public CefDevToolsMessageObserver
+{
+  public:
+    IThumbnailObserver(uint32_t max_width,
+                       uint32_t max_height,
+                       void (*callback)(const Image *image, void *context),
+                       void *context);
+
+    /* CefDevToolsMessageObserver */
+
+    ///
+    /// Method that will be called after attempted execution of a DevTools protocol method.
+    ///
+    void OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                int message_id,
+                                bool success,
+                                const void *result,
+                                size_t result_size) override;
+
+    ///
+    /// Method that will be called when the DevTools agent has detached.
+    ///
+    void OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser) override;
+
+    /* custom impl */
+
+    void Start(CefRefPtr<CefBrowser> browser);
+
+  private:
+    void Capture(CefRefPtr<CefBrowser> browser, CefRefPtr<CefDictionaryValue> metrics);
+    void Decode(CefRefPtr<CefDictionaryValue> result);
+    void Done(const Image *image);
+
+    uint32_t _max_width;
+    uint32_t _max_height;
+    void (*_callback)(const Image *image, void *context);
+    void *_context;
+    CefRefPtr<CefRegistration> _registration = nullptr;
+    int _metrics_message_id = 0;
+    int _capture_message_id = 0;
+    bool _done = false;
+
+    IMPLEMENT_REFCOUNTING(IThumbnailObserver);
+};
+
+class IScreencastObserver : public CefDevToolsMessageObserver
+{
+  public:
+    IScreencastObserver(ScreencastOptions options,
+                        bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                        void *context);
+
+    /* CefDevToolsMessageObserver */
+
+    ///
+    /// Method that will be called after attempted execution of a DevTools protocol method.
+    ///
+    void OnDevToolsMethodResult(CefRefPtr<CefBrowser> browser,
+                                int message_id,
+                                bool success,
+                                const void *result,
+                                size_t result_size) override;
+
+    ///
+    /// Method that will be called on receipt of a DevTools protocol event.
+    ///
+    void OnDevToolsEvent(CefRefPtr<CefBrowser> browser,
+                         const CefString &method,
+                         const void *params,
+                         size_t params_size) override;
+
+    ///
+    /// Method that will be called when the DevTools agent has detached.
+    ///
+    void OnDevToolsAgentDetached(CefRefPtr<CefBrowser> browser) override;
+
+    /* custom impl */
+
+    void Start(CefRefPtr<CefBrowser> browser);
+    void Stop();
+
+  private:
+    void OnFrame(CefRefPtr<CefDictionaryValue> params);
+    void Done();
+
+    ScreencastOptions _options;
+    bool (*_on_frame)(const ScreencastFrame *frame, void *context);
+    void *_context;
+    CefRefPtr<CefBrowser> _browser = nullptr;
+    CefRefPtr<CefRegistration> _registration = nullptr;
+    int _message_id = 0;
+    bool _done = false;
+
+    IMPLEMENT_REFCOUNTING(IScreencastObserver);
+};
+
 class IWebViewDrag : public CefDragHandler
 {
   public:
+    IWebViewDrag(bool windowless);
+
     ///
     /// Called when an external drag event enters the browser window.
     ///
@@ -29,12 +198,16 @@ class IWebViewDrag : public CefDragHandler
                      CefDragHandler::DragOperationsMask mask) override;
 
   private:
+    bool _windowless = false;
+
     IMPLEMENT_REFCOUNTING(IWebViewDrag);
 };
 
 class IWebViewContextMenu : public CefContextMenuHandler
 {
   public:
+    IWebViewContextMenu(const WebViewSettings *settings);
+
     ///
     /// Called before a context menu is displayed.
     ///
@@ -55,13 +228,15 @@ class IWebViewContextMenu : public CefContextMenuHandler
                               EventFlags event_flags) override;
 
   private:
+    bool _disabled = false;
+
     IMPLEMENT_REFCOUNTING(IWebViewContextMenu);
 };
 
 class IWebViewLoad : public CefLoadHandler
 {
   public:
-    IWebViewLoad(WebViewHandler &handler);
+    IWebViewLoad(uint32_t &reload_attempts, WebViewHandler &handler);
 
     ///
     /// Called after a navigation has been committed and before the browser begins
@@ -84,6 +259,7 @@ class IWebViewLoad : public CefLoadHandler
                      const CefString &failed_url) override;
 
   private:
+    uint32_t &_reload_attempts;
     WebViewHandler &_handler;
 
     IMPLEMENT_REFCOUNTING(IWebViewLoad);
@@ -92,7 +268,24 @@ class IWebViewLoad : public CefLoadHandler
 class IWebViewLifeSpan : public CefLifeSpanHandler
 {
   public:
-    IWebViewLifeSpan(std::optional<CefRefPtr<CefBrowser>> &browser, WebViewHandler &handler);
+    IWebViewLifeSpan(const WebViewSettings *settings,
+                     std::optional<CefRefPtr<CefBrowser>> &browser,
+                     bool &is_running,
+                     WebViewHandler &handler);
+
+    ///
+    /// Returns all browsers that have been created and not yet destroyed.
+    ///
+    /// This method must be called on the UI thread.
+    ///
+    static std::map<int, CefRefPtr<CefBrowser>> &GetBrowsers();
+
+    ///
+    /// Returns the number of browsers that have been created and not yet destroyed.
+    ///
+    /// This method can be called on any thread.
+    ///
+    static size_t GetBrowserCount();
 
     ///
     /// Called after a new browser is created.
@@ -132,6 +325,10 @@ class IWebViewLifeSpan : public CefLifeSpanHandler
 
   private:
     std::optional<CefRefPtr<CefBrowser>> &_browser;
+    CefRefPtr<CefDictionaryValue> _user_agent_override = nullptr;
+    ForcedColors _forced_colors = ForcedColors::WEW_FORCED_COLORS_SYSTEM;
+    bool _block_popups = false;
+    bool &_is_running;
     WebViewHandler &_handler;
 
     IMPLEMENT_REFCOUNTING(IWebViewLifeSpan);
@@ -152,6 +349,25 @@ class IWebViewDisplay : public CefDisplayHandler
     ///
     void OnFullscreenModeChange(CefRefPtr<CefBrowser> browser, bool fullscreen) override;
 
+    ///
+    /// Called when the page icon changes.
+    ///
+    void OnFaviconURLChange(CefRefPtr<CefBrowser> browser, const std::vector<CefString> &icon_urls) override;
+
+    ///
+    /// Called when the browser receives a status message, such as the target of a hovered link.
+    ///
+    void OnStatusMessage(CefRefPtr<CefBrowser> browser, const CefString &value) override;
+
+    ///
+    /// Called to display a console message.
+    ///
+    bool OnConsoleMessage(CefRefPtr<CefBrowser> browser,
+                          cef_log_severity_t level,
+                          const CefString &message,
+                          const CefString &source,
+                          int line) override;
+
     ///
     /// Called when the browser's cursor has changed.
     ///
@@ -166,63 +382,141 @@ class IWebViewDisplay : public CefDisplayHandler
     IMPLEMENT_REFCOUNTING(IWebViewDisplay);
 };
 
-class IWebViewRender : public CefRenderHandler
+class IWebViewFocus : public CefFocusHandler
 {
   public:
-    IWebViewRender(const WebViewSettings *settings, WebViewHandler &handler);
+    IWebViewFocus(WebViewHandler &handler);
 
     ///
-    /// Called to allow the client to fill in the CefScreenInfo object with
-    /// appropriate values.
+    /// Called when the browser component is about to loose focus.
     ///
-    bool GetScreenInfo(CefRefPtr<CefBrowser> browser, CefScreenInfo &screen_info) override;
+    void OnTakeFocus(CefRefPtr<CefBrowser> browser, bool next) override;
 
     ///
-    /// Called when the IME composition range has changed.
+    /// Called when the browser component is requesting focus.
     ///
-    void OnImeCompositionRangeChanged(CefRefPtr<CefBrowser> browser,
-                                      const CefRange &selected_range,
-                                      const RectList &character_bounds) override;
+    /// Return false to allow the focus to be set or true to cancel setting the focus.
+    ///
+    bool OnSetFocus(CefRefPtr<CefBrowser> browser, FocusSource source) override;
 
     ///
-    /// Called to retrieve the view rectangle in screen DIP coordinates. This
-    /// method must always provide a non-empty rectangle.
+    /// Called when the browser component has received focus.
     ///
-    void GetViewRect(CefRefPtr<CefBrowser> browser, CefRect &rect) override;
+    void OnGotFocus(CefRefPtr<CefBrowser> browser) override;
+
+  private:
+    WebViewHandler &_handler;
+
+    IMPLEMENT_REFCOUNTING(IWebViewFocus);
+};
+
+class IWebViewFind : public CefFindHandler
+{
+  public:
+    IWebViewFind(WebViewHandler &handler);
 
     ///
-    /// Called when an element should be painted. Pixel values passed to this
-    /// method are scaled relative to view coordinates based on the value of
-    /// CefScreenInfo.device_scale_factor returned from GetScreenInfo.
+    /// Called to report find results returned by CefBrowserHost::Find().
     ///
-    void OnPaint(CefRefPtr<CefBrowser> browser,
-                 PaintElementType type,
-                 const RectList &dirtyRects,
-                 const void *buffer,
-                 int width,
-                 int height) override;
+    void OnFindResult(CefRefPtr<CefBrowser> browser,
+                      int identifier,
+                      int count,
+                      const CefRect &selectionRect,
+                      int activeMatchOrdinal,
+                      bool finalUpdate) override;
+
+  private:
+    WebViewHandler &_handler;
+
+    IMPLEMENT_REFCOUNTING(IWebViewFind);
+};
+
+class IWebViewKeyboard : public CefKeyboardHandler
+{
+  public:
+    IWebViewKeyboard(const KioskPolicy *policy);
 
     ///
-    /// Called when the browser wants to move or resize the popup widget.
+    /// Called before a keyboard event is sent to the renderer.
     ///
-    virtual void OnPopupSize(CefRefPtr<CefBrowser> browser, const CefRect &rect) override;
+    /// Return true if the event was handled or false otherwise.
+    ///
+    bool OnPreKeyEvent(CefRefPtr<CefBrowser> browser,
+                       const CefKeyEvent &event,
+                       CefEventHandle os_event,
+                       bool *is_keyboard_shortcut) override;
 
-    void Resize(int width, int height);
+  private:
+    bool _block_devtools;
+    bool _block_app_switch_shortcuts;
+
+    IMPLEMENT_REFCOUNTING(IWebViewKeyboard);
+};
+
+class IWebViewDownload : public CefDownloadHandler
+{
+  public:
+    ///
+    /// Called before a download begins, return false to cancel it.
+    ///
+    bool CanDownload(CefRefPtr<CefBrowser> browser,
+                     const CefString &url,
+                     const CefString &request_method) override;
+
+    ///
+    /// Called before a download begins.
+    ///
+    /// Return true to handle the download or false to proceed with default handling.
+    ///
+    bool OnBeforeDownload(CefRefPtr<CefBrowser> browser,
+                          CefRefPtr<CefDownloadItem> download_item,
+                          const CefString &suggested_name,
+                          CefRefPtr<CefBeforeDownloadCallback> callback) override;
+
+  private:
+    IMPLEMENT_REFCOUNTING(IWebViewDownload);
+};
+
+class IWebViewResourceRequest : public CefResourceRequestHandler
+{
+  public:
+    IWebViewResourceRequest(const RequestHandlerFactory *factory, WebViewHandler &handler);
+
+#ifdef WEW_REQUEST
+    ///
+    /// Called on the IO thread before a resource is loaded.
+    ///
+    CefRefPtr<CefResourceHandler> GetResourceHandler(CefRefPtr<CefBrowser> browser,
+                                                     CefRefPtr<CefFrame> frame,
+                                                     CefRefPtr<CefRequest> request) override;
+
+    void SetRequestHandlerFactory(const RequestHandlerFactory *factory);
+#endif
+
+    ///
+    /// Called on the IO thread when a resource load has completed.
+    ///
+    void OnResourceLoadComplete(CefRefPtr<CefBrowser> browser,
+                                CefRefPtr<CefFrame> frame,
+                                CefRefPtr<CefRequest> request,
+                                CefRefPtr<CefResponse> response,
+                                URLRequestStatus status,
+                                int64_t received_content_length) override;
 
   private:
-    float _device_scale_factor;
     WebViewHandler &_handler;
-    CefRect _popup_rect;
-    CefRect _view_rect;
-    Rect _texture_rect;
 
-    IMPLEMENT_REFCOUNTING(IWebViewRender);
+#ifdef WEW_REQUEST
+    CefRefPtr<IResourceRequestHandler> _filter = nullptr;
+#endif
+
+    IMPLEMENT_REFCOUNTING(IWebViewResourceRequest);
 };
 
 class IWebViewRequest : public CefRequestHandler
 {
   public:
-    IWebViewRequest(const WebViewSettings *settings);
+    IWebViewRequest(const WebViewSettings *settings, uint32_t &reload_attempts, WebViewHandler &handler);
 
     ///
     /// Called on the browser process IO thread before a resource request is initiated.
@@ -235,8 +529,37 @@ class IWebViewRequest : public CefRequestHandler
                                                                    const CefString &request_initiator,
                                                                    bool &disable_default_handling) override;
 
+    ///
+    /// Called on the browser process UI thread when the render process terminates unexpectedly.
+    ///
+    void OnRenderProcessTerminated(CefRefPtr<CefBrowser> browser,
+                                   TerminationStatus status,
+                                   int error_code,
+                                   const CefString &error_string) override;
+
+    ///
+    /// Called on the UI thread before browser navigation.
+    ///
+    /// Return true to cancel the navigation or false to allow the navigation to proceed.
+    ///
+    bool OnBeforeBrowse(CefRefPtr<CefBrowser> browser,
+                        CefRefPtr<CefFrame> frame,
+                        CefRefPtr<CefRequest> request,
+                        bool user_gesture,
+                        bool is_redirect) override;
+
+#ifdef WEW_REQUEST
+    void SetRequestHandlerFactory(const RequestHandlerFactory *factory);
+#endif
+
   private:
-    CefRefPtr<CefResourceRequestHandler> _handler = nullptr;
+    CefRefPtr<IWebViewResourceRequest> _resource_request_handler = nullptr;
+    std::vector<std::string> _allowed_origins;
+    bool _pdf_viewer;
+    uint32_t _auto_reload_max_attempts;
+    uint32_t _auto_reload_delay;
+    uint32_t &_reload_attempts;
+    WebViewHandler &_handler;
 
     IMPLEMENT_REFCOUNTING(IWebViewRequest);
 };
@@ -266,6 +589,28 @@ class IWebView : public CefClient
     ///
     CefRefPtr<CefDisplayHandler> GetDisplayHandler() override;
 
+    ///
+    /// Return the handler for focus events.
+    ///
+    CefRefPtr<CefFocusHandler> GetFocusHandler() override;
+
+    ///
+    /// Return the handler for keyboard events.
+    ///
+    CefRefPtr<CefKeyboardHandler> GetKeyboardHandler() override;
+
+    ///
+    /// Return the handler for find result events.
+    ///
+    CefRefPtr<CefFindHandler> GetFindHandler() override;
+
+    ///
+    /// Return the handler for download events.
+    ///
+    /// If no handler is provided the default implementation will be used.
+    ///
+    CefRefPtr<CefDownloadHandler> GetDownloadHandler() override;
+
     ///
     /// Return the handler for browser life span events.
     ///
@@ -298,29 +643,86 @@ class IWebView : public CefClient
 
     void Close();
     void SetFocus(bool enable);
+    void SetZoomLevel(double level);
+    void ExitFullscreen();
+    void ExecuteEditCommand(EditCommand command);
+    void SetForcedColors(ForcedColors forced_colors);
+    void UpdateSettings(const WebViewSettingsDelta *delta);
+    void Print();
+    bool OnPrintDialog(bool has_selection);
+    bool OnPrintJob(std::string document_name, std::string pdf_file_path);
+    void Find(std::string text, bool forward, bool match_case, bool find_next);
+    void StopFinding(bool clear_selection);
     void Resize(int width, int height);
+    void NotifyScreenInfoChanged();
+    void SetDeviceScaleFactor(float device_scale_factor);
     void SetDevToolsOpenState(bool is_open);
-    void SendMessage(std::string message);
+    void SetRequestHandlerFactory(const RequestHandlerFactory *factory);
+    void SendMessage(std::string message, std::string channel, std::optional<std::string> frame);
+    void SendBinaryMessage(const uint8_t *message,
+                           size_t size,
+                           std::string channel,
+                           std::optional<std::string> frame);
+    void ExecuteJavaScript(std::string script, std::optional<std::string> frame);
+    void Emit(std::string event, std::string payload);
+    void AckMessage(std::string frame);
     void OnKeyboard(cef_key_event_t event);
     void OnMouseClick(cef_mouse_event_t event, cef_mouse_button_type_t button, bool pressed);
     void OnMouseMove(cef_mouse_event_t event);
     void OnMouseWheel(cef_mouse_event_t event, int x, int y);
+    void OnDragEnter(CefRefPtr<CefDragData> data, cef_mouse_event_t event, CefBrowserHost::DragOperationsMask mask);
+    void OnDragOver(cef_mouse_event_t event, CefBrowserHost::DragOperationsMask mask);
+    void OnDragLeave();
+    void OnDrop(cef_mouse_event_t event);
+    void OnDragSourceEnded(int x, int y, CefBrowserHost::DragOperationsMask op);
     void OnTouch(cef_touch_event_t event);
     void OnIMEComposition(std::string input);
     void OnIMESetComposition(std::string input, int x, int y);
-    RawWindowHandle GetWindowHandle();
+    bool GetWindowHandle(RawWindowHandle *handle);
+    CefBrowser *GetBrowser();
+    int GetRenderProcessId();
+    void GetFrames(void (*callback)(FrameInfo info, void *context), void *context);
+    void DownloadImage(std::string url,
+                       bool is_favicon,
+                       uint32_t max_size,
+                       void (*callback)(const Image *image, void *context),
+                       void *context);
+    void GetLayoutMetrics(void (*callback)(const LayoutMetrics *metrics, void *context), void *context);
+    void CaptureThumbnail(uint32_t max_width,
+                          uint32_t max_height,
+                          void (*callback)(const Image *image, void *context),
+                          void *context);
+    void StartScreencast(ScreencastOptions options,
+                         bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                         void *context);
+    void StopScreencast();
 
   private:
+    CefRefPtr<CefFrame> GetFrame(std::optional<std::string> frame);
+    void ExecuteDevToolsMethod(std::string method, CefRefPtr<CefDictionaryValue> params);
+    void SendProcessMessage(CefRefPtr<CefProcessMessage> message, std::optional<std::string> frame);
+
     CefRefPtr<IWebViewDrag> _drag_handler = nullptr;
     CefRefPtr<IWebViewLoad> _load_handler = nullptr;
+#ifdef WEW_WINDOWLESS
     CefRefPtr<IWebViewRender> _render_handler = nullptr;
+#endif
     CefRefPtr<IWebViewRequest> _request_handler = nullptr;
     CefRefPtr<IWebViewDisplay> _display_handler = nullptr;
+    CefRefPtr<IWebViewFocus> _focus_handler = nullptr;
+    CefRefPtr<IWebViewKeyboard> _keyboard_handler = nullptr;
+    CefRefPtr<IWebViewFind> _find_handler = nullptr;
+    CefRefPtr<IWebViewDownload> _download_handler = nullptr;
     CefRefPtr<IWebViewLifeSpan> _life_span_handler = nullptr;
     CefRefPtr<IWebViewContextMenu> _context_menu_handler = nullptr;
+    CefRefPtr<IScreencastObserver> _screencast = nullptr;
 
     std::optional<CefRefPtr<CefBrowser>> _browser = std::nullopt;
     WebViewHandler _handler;
+    uint32_t _max_in_flight_messages = 0;
+    uint32_t _reload_attempts = 0;
+    std::atomic<int> _render_process_id = 0;
+    bool _block_devtools = false;
 
     IMPLEMENT_RUNNING;
     IMPLEMENT_REFCOUNTING(IWebView);
@@ -9,22 +9,180 @@
 #include "include/wrapper/cef_library_loader.h"
 #endif
 
+#ifdef WEW_SANDBOX
+#ifdef WIN32
+#include "include/cef_sandbox_win.h"
+#endif
+
+#ifdef MACOS
+#include "include/cef_sandbox_mac.h"
+#endif
+#endif
+
+#include "include/cef_crash_util.h"
+
 #include "runtime.h"
 #include "subprocess.h"
 #include "util.h"
 #include "webview.h"
 #include "wew.h"
 
+// The sandbox information must be created in the executable and stay alive for
+// the lifetime of the process, it is shared by the browser process and the
+// subprocesses.
+void *get_sandbox_info()
+{
+#if defined(WEW_SANDBOX) && defined(WIN32)
+    static CefScopedSandboxInfo scoped_sandbox;
+    return scoped_sandbox.sandbox_info();
+#else
+    return nullptr;
+#endif
+}
+
 bool post_task_with_main_thread(void (*callback)(void *context), void *context)
 {
     return CefPostTask(TID_UI, new ITask(callback, context));
 }
 
+bool post_delayed_task_with_main_thread(void (*callback)(void *context), void *context, int64_t delay)
+{
+    return CefPostDelayedTask(TID_UI, new ITask(callback, context), delay);
+}
+
+bool currently_on_main_thread()
+{
+    return CefCurrentlyOn(TID_UI);
+}
+
 int get_exit_code()
 {
     return CefGetExitCode();
 }
 
+void set_crash_key_value(const char *key, const char *value)
+{
+    assert(key != nullptr);
+    assert(value != nullptr);
+
+    CefSetCrashKeyValue(key, value);
+}
+
+bool register_v8_extension(const V8Extension *extension)
+{
+    assert(extension != nullptr);
+    assert(extension->execute != nullptr);
+
+    return CefRegisterExtension(extension->name, extension->code, new V8ExtensionHandler(*extension));
+}
+
+void v8_extension_set_result(void *result, V8Value value)
+{
+    assert(result != nullptr);
+
+    auto it = static_cast<V8ExtensionResult *>(result);
+    switch (value.type)
+    {
+    case WEW_V8_VALUE_NULL:
+        it->retval = CefV8Value::CreateNull();
+        break;
+    case WEW_V8_VALUE_BOOL:
+        it->retval = CefV8Value::CreateBool(value.bool_value);
+        break;
+    case WEW_V8_VALUE_INT:
+        it->retval = CefV8Value::CreateInt(value.int_value);
+        break;
+    case WEW_V8_VALUE_DOUBLE:
+        it->retval = CefV8Value::CreateDouble(value.double_value);
+        break;
+    case WEW_V8_VALUE_STRING:
+        it->retval = CefV8Value::CreateString(std::string(reinterpret_cast<const char *>(value.data), value.size));
+        break;
+    case WEW_V8_VALUE_ARRAY_BUFFER:
+        it->retval = CefV8Value::CreateArrayBufferWithCopy(const_cast<uint8_t *>(value.data), value.size);
+        break;
+    default:
+        it->retval = CefV8Value::CreateUndefined();
+        break;
+    }
+}
+
+void v8_extension_set_exception(void *result, const char *message)
+{
+    assert(result != nullptr);
+
+    static_cast<V8ExtensionResult *>(result)->exception = std::string(message);
+}
+
+bool command_line_has_switch(void *command_line, const char *name)
+{
+    assert(command_line != nullptr);
+
+    return static_cast<CefCommandLine *>(command_line)->HasSwitch(name);
+}
+
+void command_line_append_switch(void *command_line, const char *name, const char *value)
+{
+    assert(command_line != nullptr);
+
+    if (value == nullptr)
+    {
+        static_cast<CefCommandLine *>(command_line)->AppendSwitch(name);
+    }
+    else
+    {
+        static_cast<CefCommandLine *>(command_line)->AppendSwitchWithValue(name, value);
+    }
+}
+
+void command_line_remove_switch(void *command_line, const char *name)
+{
+    assert(command_line != nullptr);
+
+    auto it = static_cast<CefCommandLine *>(command_line);
+    if (!it->HasSwitch(name))
+    {
+        return;
+    }
+
+    // CefCommandLine cannot remove a single switch, the command line is reset
+    // and rebuilt without it.
+    CefCommandLine::SwitchMap switches;
+    CefCommandLine::ArgumentList arguments;
+    it->GetSwitches(switches);
+    it->GetArguments(arguments);
+    it->Reset();
+
+    for (auto &[key, value] : switches)
+    {
+        if (key == name)
+        {
+            continue;
+        }
+
+        if (value.empty())
+        {
+            it->AppendSwitch(key);
+        }
+        else
+        {
+            it->AppendSwitchWithValue(key, value);
+        }
+    }
+
+    for (auto &argument : arguments)
+    {
+        it->AppendArgument(argument);
+    }
+}
+
+void command_line_append_argument(void *command_line, const char *argument)
+{
+    assert(command_line != nullptr);
+
+    static_cast<CefCommandLine *>(command_line)->AppendArgument(argument);
+}
+
 void run_message_loop()
 {
     CefRunMessageLoop();
@@ -40,9 +198,18 @@ void poll_message_loop()
     CefDoMessageLoopWork();
 }
 
-int execute_subprocess(int argc, const char **argv)
+int execute_subprocess(int argc, const char **argv, const SubProcessHandler *handler)
 {
 #ifdef MACOS
+#ifdef WEW_SANDBOX
+    // The sandbox must be initialized before the framework is loaded.
+    CefScopedSandboxContext sandbox_context;
+    if (!sandbox_context.Initialize(argc, const_cast<char **>(argv)))
+    {
+        return -1;
+    }
+#endif
+
     CefScopedLibraryLoader library_loader;
     if (!library_loader.LoadInHelper())
     {
@@ -51,7 +218,20 @@ int execute_subprocess(int argc, const char **argv)
 #endif
 
     auto main_args = get_main_args(argc, argv);
-    return CefExecuteProcess(main_args, new ISubProcess, nullptr);
+    return CefExecuteProcess(main_args,
+                             new ISubProcess(handler == nullptr ? std::nullopt : std::optional(*handler)),
+                             get_sandbox_info());
+}
+
+bool v8_context_execute_javascript(void *v8_context, const char *script)
+{
+    assert(v8_context != nullptr);
+
+    auto context = static_cast<CefV8Context *>(v8_context);
+
+    CefRefPtr<CefV8Value> retval;
+    CefRefPtr<CefV8Exception> exception;
+    return context->Eval(std::string(script), context->GetFrame()->GetURL(), 0, retval, exception);
 }
 
 void *create_runtime(const RuntimeSettings *settings, RuntimeHandler handler)
@@ -68,8 +248,11 @@ void *create_runtime(const RuntimeSettings *settings, RuntimeHandler handler)
 
     CefSettings cef_settings;
 
-    cef_log_severity_t a;
+#ifdef WEW_SANDBOX
+    cef_settings.no_sandbox = false;
+#else
     cef_settings.no_sandbox = true;
+#endif
     cef_settings.background_color = settings->background_color;
     cef_settings.external_message_pump = settings->external_message_pump;
     cef_settings.persist_session_cookies = settings->persist_session_cookies;
@@ -79,6 +262,9 @@ void *create_runtime(const RuntimeSettings *settings, RuntimeHandler handler)
     cef_settings.multi_threaded_message_loop = settings->multi_threaded_message_loop;
     cef_settings.log_severity = static_cast<cef_log_severity_t>(static_cast<int>(settings->log_severity));
 
+    // Uncaught exceptions are reported to the host with their stack trace.
+    cef_settings.uncaught_exception_stack_size = 32;
+
     if (settings->cache_path != nullptr)
     {
         CefString(&cef_settings.cache_path).FromString(settings->cache_path);
@@ -152,7 +338,36 @@ bool execute_runtime(void *runtime, int argc, const char **argv)
 
     auto rt = static_cast<Runtime *>(runtime);
     auto main_args = get_main_args(argc, argv);
-    return CefInitialize(main_args, rt->ref->GetCefSettings(), rt->ref, nullptr);
+    return CefInitialize(main_args, rt->ref->GetCefSettings(), rt->ref, get_sandbox_info());
+}
+
+void runtime_close_browsers(void *runtime)
+{
+    assert(runtime != nullptr);
+
+    static_cast<Runtime *>(runtime)->ref->CloseBrowsers();
+}
+
+size_t runtime_get_browser_count(void *runtime)
+{
+    assert(runtime != nullptr);
+
+    return static_cast<Runtime *>(runtime)->ref->GetBrowserCount();
+}
+
+void runtime_get_gpu_info(void *runtime, void (*callback)(const GpuInfo *info, void *context), void *context)
+{
+    assert(runtime != nullptr);
+    assert(callback != nullptr);
+
+    static_cast<Runtime *>(runtime)->ref->GetGpuInfo(callback, context);
+}
+
+void shutdown_runtime(void *runtime)
+{
+    assert(runtime != nullptr);
+
+    static_cast<Runtime *>(runtime)->ref->Shutdown();
 }
 
 void close_runtime(void *runtime)
@@ -171,6 +386,11 @@ void *create_webview(void *runtime, const char *url, const WebViewSettings *sett
     assert(url != nullptr);
 
     auto webview = static_cast<Runtime *>(runtime)->ref->CreateWebView(std::string(url), settings, handler);
+    if (webview == nullptr)
+    {
+        return nullptr;
+    }
+
     return new WebView{webview};
 }
 
@@ -222,6 +442,79 @@ void webview_mouse_move(void *webview, MouseEvent event)
     static_cast<WebView *>(webview)->ref->OnMouseMove(cef_event);
 }
 
+void webview_drag_enter(void *webview, const DragData *data, MouseEvent event, uint32_t allowed_ops)
+{
+    assert(webview != nullptr);
+    assert(data != nullptr);
+
+    CefRefPtr<CefDragData> drag_data = CefDragData::Create();
+    for (size_t i = 0; i < data->files_size; i++)
+    {
+        drag_data->AddFile(data->files[i], CefString());
+    }
+
+    if (data->text != nullptr)
+    {
+        drag_data->SetFragmentText(data->text);
+    }
+
+    if (data->url != nullptr)
+    {
+        drag_data->SetLinkURL(data->url);
+    }
+
+    CefMouseEvent cef_event;
+    cef_event.x = event.x;
+    cef_event.y = event.y;
+    cef_event.modifiers = event.modifiers;
+
+    auto mask = static_cast<CefBrowserHost::DragOperationsMask>(allowed_ops);
+
+    static_cast<WebView *>(webview)->ref->OnDragEnter(drag_data, cef_event, mask);
+}
+
+void webview_drag_over(void *webview, MouseEvent event, uint32_t allowed_ops)
+{
+    assert(webview != nullptr);
+
+    CefMouseEvent cef_event;
+    cef_event.x = event.x;
+    cef_event.y = event.y;
+    cef_event.modifiers = event.modifiers;
+
+    auto mask = static_cast<CefBrowserHost::DragOperationsMask>(allowed_ops);
+
+    static_cast<WebView *>(webview)->ref->OnDragOver(cef_event, mask);
+}
+
+void webview_drag_leave(void *webview)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->OnDragLeave();
+}
+
+void webview_drop(void *webview, MouseEvent event)
+{
+    assert(webview != nullptr);
+
+    CefMouseEvent cef_event;
+    cef_event.x = event.x;
+    cef_event.y = event.y;
+    cef_event.modifiers = event.modifiers;
+
+    static_cast<WebView *>(webview)->ref->OnDrop(cef_event);
+}
+
+void webview_drag_source_ended(void *webview, int x, int y, uint32_t op)
+{
+    assert(webview != nullptr);
+
+    auto mask = static_cast<CefBrowserHost::DragOperationsMask>(op);
+
+    static_cast<WebView *>(webview)->ref->OnDragSourceEnded(x, y, mask);
+}
+
 void webview_keyboard(void *webview, KeyEvent event)
 {
     assert(webview != nullptr);
@@ -272,11 +565,53 @@ void webview_ime_set_composition(void *webview, const char *input, int x, int y)
     static_cast<WebView *>(webview)->ref->OnIMESetComposition(input, x, y);
 }
 
-void webview_send_message(void *webview, const char *message)
+static std::optional<std::string> to_frame_identifier(const char *frame)
+{
+    return frame == nullptr ? std::nullopt : std::optional(std::string(frame));
+}
+
+void webview_send_message(void *webview, const char *frame, const char *channel, const char *message)
 {
     assert(webview != nullptr);
 
-    static_cast<WebView *>(webview)->ref->SendMessage(std::string(message));
+    static_cast<WebView *>(webview)->ref->SendMessage(std::string(message),
+                                                      std::string(channel == nullptr ? "" : channel),
+                                                      to_frame_identifier(frame));
+}
+
+void webview_send_binary_message(void *webview,
+                                 const char *frame,
+                                 const char *channel,
+                                 const uint8_t *message,
+                                 size_t size)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->SendBinaryMessage(message,
+                                                            size,
+                                                            std::string(channel == nullptr ? "" : channel),
+                                                            to_frame_identifier(frame));
+}
+
+void webview_ack_message(void *webview, const char *frame)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->AckMessage(std::string(frame));
+}
+
+void webview_execute_javascript(void *webview, const char *frame, const char *script)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->ExecuteJavaScript(std::string(script), to_frame_identifier(frame));
+}
+
+void webview_emit(void *webview, const char *event, const char *payload)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->Emit(std::string(event), std::string(payload));
 }
 
 void webview_set_devtools_state(void *webview, bool is_open)
@@ -293,11 +628,65 @@ void webview_resize(void *webview, int width, int height)
     static_cast<WebView *>(webview)->ref->Resize(width, height);
 }
 
-RawWindowHandle webview_get_window_handle(void *webview)
+void webview_notify_screen_info_changed(void *webview)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->NotifyScreenInfoChanged();
+}
+
+void webview_set_device_scale_factor(void *webview, float device_scale_factor)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->SetDeviceScaleFactor(device_scale_factor);
+}
+
+bool webview_get_window_handle(void *webview, RawWindowHandle *handle)
+{
+    assert(webview != nullptr);
+    assert(handle != nullptr);
+
+    return static_cast<WebView *>(webview)->ref->GetWindowHandle(handle);
+}
+
+int webview_get_render_process_id(void *webview)
 {
     assert(webview != nullptr);
 
-    return static_cast<WebView *>(webview)->ref->GetWindowHandle();
+    return static_cast<WebView *>(webview)->ref->GetRenderProcessId();
+}
+
+void webview_get_frames(void *webview, void (*callback)(FrameInfo info, void *context), void *context)
+{
+    assert(webview != nullptr);
+    assert(callback != nullptr);
+
+    static_cast<WebView *>(webview)->ref->GetFrames(callback, context);
+}
+
+void webview_download_image(void *webview,
+                            const char *url,
+                            bool is_favicon,
+                            uint32_t max_size,
+                            void (*callback)(const Image *image, void *context),
+                            void *context)
+{
+    assert(webview != nullptr);
+    assert(url != nullptr);
+    assert(callback != nullptr);
+
+    static_cast<WebView *>(webview)->ref->DownloadImage(std::string(url), is_favicon, max_size, callback, context);
+}
+
+void webview_get_layout_metrics(void *webview,
+                                void (*callback)(const LayoutMetrics *metrics, void *context),
+                                void *context)
+{
+    assert(webview != nullptr);
+    assert(callback != nullptr);
+
+    static_cast<WebView *>(webview)->ref->GetLayoutMetrics(callback, context);
 }
 
 void webview_set_focus(void *webview, bool enable)
@@ -306,3 +695,113 @@ void webview_set_focus(void *webview, bool enable)
 
     static_cast<WebView *>(webview)->ref->SetFocus(enable);
 }
+
+void webview_set_zoom_level(void *webview, double level)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->SetZoomLevel(level);
+}
+
+void webview_exit_fullscreen(void *webview)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->ExitFullscreen();
+}
+
+void webview_find(void *webview, const char *text, bool forward, bool match_case, bool find_next)
+{
+    assert(webview != nullptr);
+    assert(text != nullptr);
+
+    static_cast<WebView *>(webview)->ref->Find(std::string(text), forward, match_case, find_next);
+}
+
+void webview_stop_finding(void *webview, bool clear_selection)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->StopFinding(clear_selection);
+}
+
+void webview_print(void *webview)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->Print();
+}
+
+void webview_edit_command(void *webview, EditCommand command)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->ExecuteEditCommand(command);
+}
+
+void webview_set_forced_colors(void *webview, ForcedColors forced_colors)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->SetForcedColors(forced_colors);
+}
+
+void webview_update_settings(void *webview, const WebViewSettingsDelta *delta)
+{
+    assert(webview != nullptr);
+    assert(delta != nullptr);
+
+    static_cast<WebView *>(webview)->ref->UpdateSettings(delta);
+}
+
+void *webview_get_browser(void *webview)
+{
+    assert(webview != nullptr);
+
+    return static_cast<WebView *>(webview)->ref->GetBrowser();
+}
+
+void *runtime_get_app(void *runtime)
+{
+    assert(runtime != nullptr);
+
+    return static_cast<CefApp *>(static_cast<Runtime *>(runtime)->ref.get());
+}
+
+void webview_capture_thumbnail(void *webview,
+                               uint32_t max_width,
+                               uint32_t max_height,
+                               void (*callback)(const Image *image, void *context),
+                               void *context)
+{
+    assert(webview != nullptr);
+    assert(callback != nullptr);
+
+    static_cast<WebView *>(webview)->ref->CaptureThumbnail(max_width, max_height, callback, context);
+}
+
+void webview_start_screencast(void *webview,
+                              const ScreencastOptions *options,
+                              bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                              void *context)
+{
+    assert(webview != nullptr);
+    assert(options != nullptr);
+    assert(on_frame != nullptr);
+
+    static_cast<WebView *>(webview)->ref->StartScreencast(*options, on_frame, context);
+}
+
+void webview_stop_screencast(void *webview)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->StopScreencast();
+}
+
+void webview_set_request_handler_factory(void *webview, const RequestHandlerFactory *factory)
+{
+    assert(webview != nullptr);
+
+    static_cast<WebView *>(webview)->ref->SetRequestHandlerFactory(factory);
+}
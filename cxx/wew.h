@@ -44,6 +44,8 @@ typedef struct
 typedef struct
 {
     bool (*open)(void *context);
+    /// The milliseconds to wait before the request is opened, the request is suspended without blocking the IO thread.
+    uint64_t (*delay)(void *context);
     bool (*skip)(size_t size, int *cursor, void *context);
     bool (*read)(uint8_t *buffer, size_t size, int *cursor, void *context);
     void (*get_response)(Response *response, void *context);
@@ -168,6 +170,29 @@ typedef enum
     WEW_LOG_DISABLE = 99
 } LogLevel;
 
+typedef enum
+{
+    ///
+    /// Use the Chromium default policy.
+    ///
+    WEW_AUTOPLAY_DEFAULT,
+
+    ///
+    /// Autoplay is always allowed, including media with sound.
+    ///
+    WEW_AUTOPLAY_NO_USER_GESTURE_REQUIRED,
+
+    ///
+    /// Autoplay is allowed once the user has interacted with the document.
+    ///
+    WEW_AUTOPLAY_DOCUMENT_USER_ACTIVATION_REQUIRED,
+
+    ///
+    /// Autoplay always requires a user gesture.
+    ///
+    WEW_AUTOPLAY_USER_GESTURE_REQUIRED,
+} AutoplayPolicy;
+
 typedef struct
 {
     const CustomSchemeAttributes *custom_scheme;
@@ -242,21 +267,114 @@ typedef struct
 
     /// Specify whether signal handlers must be disabled on POSIX systems.
     bool disable_signal_handlers;
+
+    /// Disable the GPU acceleration of the 2D canvas, the canvas is rendered in software.
+    ///
+    /// This applies to all webviews, Chromium cannot disable it per browser.
+    bool disable_accelerated_2d_canvas;
+
+    /// Disable the hardware decoding of video, the video is decoded in software.
+    ///
+    /// This applies to all webviews, Chromium cannot disable it per browser.
+    bool disable_hardware_video_decode;
+
+    /// Disable the GPU compositing, the pages are composited in software.
+    bool disable_gpu_compositing;
+
+    /// Disable the GPU process entirely, the pages are rasterized and composited in software.
+    bool software_rendering;
+
+    /// Allow https pages to load scripts, styles and other content from http URLs.
+    bool allow_running_insecure_content;
+
+    /// Allow file:// URLs to access other file:// URLs and any other origin.
+    bool allow_file_access_from_files;
+
+    /// Disable the same-origin policy, including CORS checks.
+    bool disable_web_security;
+
+    /// The policy for media autoplay.
+    AutoplayPolicy autoplay_policy;
+
+    /// Disable the autofill of addresses and credit cards.
+    ///
+    /// This applies to all webviews, it is a preference of the shared profile.
+    bool disable_autofill;
+
+    /// Disable the password manager and its save password prompts.
+    ///
+    /// This applies to all webviews, it is a preference of the shared profile.
+    bool disable_password_manager;
+
+    /// Force the accessibility of the renderer, even if no assistive technology is detected.
+    bool force_renderer_accessibility;
+
+    /// Behave as if a screen reader is running, the complete accessibility mode is enabled.
+    bool screen_reader_present;
+
+    /// Enable the caret browsing, a movable cursor is shown in the page to select text with the keyboard.
+    bool caret_browsing;
+
+    /// Enable the spatial navigation, the arrow keys move the focus between the focusable elements.
+    bool spatial_navigation;
 } RuntimeSettings;
 
 typedef struct
 {
     void (*on_context_initialized)(void *context);
     void (*on_schedule_message_pump_work)(int64_t delay_ms, void *context);
+
+    /// Called before the command line of the browser process is processed, |command_line| is only valid during the
+    /// call.
+    void (*on_before_command_line_processing)(void *command_line, void *context);
     void *context;
 } RuntimeHandler;
 
+///
+/// What to do when the web page sends messages faster than the host handles them.
+///
+typedef enum
+{
+    ///
+    /// The message is discarded and `MessageTransport.send` returns false.
+    ///
+    WEW_MESSAGE_OVERFLOW_DROP = 0,
+
+    ///
+    /// An exception is thrown to the web page.
+    ///
+    WEW_MESSAGE_OVERFLOW_THROW,
+} MessageOverflowPolicy;
+
 #ifdef LINUX
 typedef unsigned long RawWindowHandle;
 #else
 typedef const void *RawWindowHandle;
 #endif
 
+typedef struct
+{
+    /// Popups are discarded instead of being loaded in the webview.
+    bool block_popups;
+
+    /// Downloads are cancelled.
+    bool block_downloads;
+
+    /// The developer tools cannot be opened, neither by the host nor with the keyboard shortcuts.
+    bool block_devtools;
+
+    /// The context menu is never shown, not even for selections and editable fields.
+    bool disable_context_menu;
+
+    /// The shortcuts that switch or close applications, such as Alt+Tab and Alt+F4, are not handled by the browser.
+    bool block_app_switch_shortcuts;
+
+    /// The origins the main frame can navigate to, such as "https://example.com", navigations to other origins are
+    /// cancelled. All origins are allowed if |allowed_origins_size| is 0.
+    const char **allowed_origins;
+    size_t allowed_origins_size;
+} KioskPolicy;
+
 typedef struct
 {
     /// window size width.
@@ -280,6 +398,17 @@ typedef struct
     /// The minimum logical font size.
     int minimum_logical_font_size;
 
+    /// The font families, NULL uses the default font family.
+    const char *standard_font_family;
+    const char *fixed_font_family;
+    const char *serif_font_family;
+    const char *sans_serif_font_family;
+    const char *cursive_font_family;
+    const char *fantasy_font_family;
+
+    /// Default encoding for Web content, NULL uses "ISO-8859-1".
+    const char *default_encoding;
+
     /// Controls whether WebGL is enabled.
     bool webgl;
 
@@ -301,6 +430,9 @@ typedef struct
     /// Controls whether local storage can be used.
     bool local_storage;
 
+    /// Controls whether image URLs will be loaded from the network.
+    bool images;
+
     /// END values that map to WebPreferences settings.
     uint32_t background_color;
 
@@ -313,8 +445,72 @@ typedef struct
 
     /// The request handler factory.
     const RequestHandlerFactory *request_handler_factory;
+
+    /// The maximum number of messages sent by the web page that have not yet been handled by the host, 0 means
+    /// unlimited.
+    uint32_t max_in_flight_messages;
+
+    /// What to do when the number of in-flight messages reaches the limit.
+    MessageOverflowPolicy message_overflow_policy;
+
+    /// The maximum number of messages kept for each channel of a frame until the web page has registered a listener,
+    /// the oldest message is dropped when the limit is exceeded.
+    uint32_t max_pending_messages;
+
+    /// The maximum number of times the page is reloaded after the render process terminated, 0 disables the
+    /// automatic reload. The count is reset once the page has loaded.
+    uint32_t auto_reload_max_attempts;
+
+    /// The delay in milliseconds before the first reload, the delay is doubled for every following attempt.
+    uint32_t auto_reload_delay;
+
+    /// The user agent of the webview, NULL uses the user agent of the runtime.
+    const char *user_agent;
+
+    /// The user agent client hints in the JSON format of the DevTools UserAgentMetadata type, NULL uses the default
+    /// client hints. Only used if |user_agent| is set.
+    const char *user_agent_metadata;
+
+    /// The restrictions of a kiosk, NULL applies no restriction.
+    const KioskPolicy *kiosk_policy;
+
+    /// Whether the accesses of the page to the async clipboard API are checked with on_clipboard_access.
+    bool clipboard_policy;
+
+    /// Whether the page is rendered in forced colors mode, such as for a high contrast theme.
+    ForcedColors forced_colors;
+
+    /// Whether PDF documents are shown in the built-in PDF viewer, the viewer is not loaded if it is disabled.
+    bool pdf_viewer;
 } WebViewSettings;
 
+///
+/// The settings that can be changed after the webview is created.
+///
+typedef struct
+{
+    /// The maximum rate in frames per second (fps), 0 keeps the current rate.
+    uint32_t windowless_frame_rate;
+
+    /// Whether |muted| is set.
+    bool set_muted;
+
+    /// Whether the audio of the page is muted.
+    bool muted;
+
+    /// Whether |background_color| is set.
+    bool set_background_color;
+
+    /// The background color in ARGB format.
+    uint32_t background_color;
+
+    /// The user agent, NULL keeps the current user agent.
+    const char *user_agent;
+
+    /// The user agent client hints in JSON format, only used with |user_agent|.
+    const char *user_agent_metadata;
+} WebViewSettingsDelta;
+
 typedef enum
 {
     WEW_BEFORE_LOAD = 1,
@@ -324,6 +520,138 @@ typedef enum
     WEW_CLOSE = 5,
 } WebViewState;
 
+typedef struct
+{
+    WebViewState state;
+
+    /// The URL of the frame, only set for WEW_BEFORE_LOAD, WEW_LOADED and WEW_LOAD_ERROR.
+    const char *url;
+
+    /// Whether the frame is the main (top-level) frame.
+    bool is_main_frame;
+
+    /// The HTTP status code of WEW_LOADED, 0 if the page was not loaded over HTTP.
+    int http_status;
+
+    /// The network error code and its description of WEW_LOAD_ERROR.
+    int error_code;
+    const char *error_text;
+} WebViewStateChange;
+
+typedef struct
+{
+    /// The exception message.
+    const char *message;
+
+    /// The URL of the script that threw the exception.
+    const char *source;
+
+    /// The line and column number where the exception was thrown, 1-based.
+    int line;
+    int column;
+
+    /// The stack trace, one frame per line.
+    const char *stack;
+} PageError;
+
+typedef struct
+{
+    const char *name;
+    const char *value;
+} HttpHeader;
+
+typedef struct
+{
+    /// The URL of the response, after all redirects.
+    const char *url;
+
+    /// The HTTP status code, 0 if the request failed before a response was received.
+    int status;
+    const char *status_text;
+    const char *mime_type;
+    const HttpHeader *headers;
+    size_t headers_size;
+} HttpResponse;
+
+typedef enum
+{
+    WEW_TS_ABNORMAL_TERMINATION,
+    WEW_TS_PROCESS_WAS_KILLED,
+    WEW_TS_PROCESS_CRASHED,
+    WEW_TS_PROCESS_OOM,
+    WEW_TS_LAUNCH_FAILED,
+    WEW_TS_INTEGRITY_FAILURE,
+} RenderProcessTerminationStatus;
+
+typedef enum
+{
+    ///
+    /// The focus is requested by the page, such as by calling `focus()` on an element.
+    ///
+    WEW_FOCUS_SOURCE_NAVIGATION,
+
+    ///
+    /// The focus is requested by the system, such as a click in the page.
+    ///
+    WEW_FOCUS_SOURCE_SYSTEM,
+} FocusSource;
+
+///
+/// Editing commands, they apply to the focused frame.
+///
+typedef enum
+{
+    WEW_EDIT_COMMAND_CUT,
+    WEW_EDIT_COMMAND_COPY,
+    WEW_EDIT_COMMAND_PASTE,
+    WEW_EDIT_COMMAND_PASTE_AS_PLAIN_TEXT,
+    WEW_EDIT_COMMAND_SELECT_ALL,
+    WEW_EDIT_COMMAND_UNDO,
+    WEW_EDIT_COMMAND_REDO,
+} EditCommand;
+
+typedef enum
+{
+    ///
+    /// The forced colors mode follows the high contrast setting of the system.
+    ///
+    WEW_FORCED_COLORS_SYSTEM,
+
+    ///
+    /// The page is rendered in forced colors mode and prefers more contrast.
+    ///
+    WEW_FORCED_COLORS_ACTIVE,
+
+    ///
+    /// The page is never rendered in forced colors mode.
+    ///
+    WEW_FORCED_COLORS_NONE,
+} ForcedColors;
+
+typedef enum
+{
+    ///
+    /// The page reads the clipboard with navigator.clipboard.read or readText.
+    ///
+    WEW_CLIPBOARD_ACCESS_READ,
+
+    ///
+    /// The page writes the clipboard with navigator.clipboard.write or writeText.
+    ///
+    WEW_CLIPBOARD_ACCESS_WRITE,
+} ClipboardAccess;
+
+typedef enum
+{
+    WEW_CLIPBOARD_POLICY_ALLOW,
+    WEW_CLIPBOARD_POLICY_DENY,
+
+    ///
+    /// The access is allowed, but the HTML content is replaced with its plain text.
+    ///
+    WEW_CLIPBOARD_POLICY_SANITIZE_HTML,
+} ClipboardPolicy;
+
 typedef struct
 {
     bool is_popup;
@@ -334,15 +662,278 @@ typedef struct
     uint32_t y;
 } Frame;
 
+typedef struct
+{
+    /// The ratio between physical pixels and DIPs, 1.0 is 96 DPI.
+    float device_scale_factor;
+    /// The color depth in bits per pixel and per color component.
+    int depth;
+    int depth_per_component;
+    bool is_monochrome;
+    /// The monitor rectangle and the area of the monitor available to windows in screen DIP coordinates, the
+    /// rectangle of the view is used if they are empty.
+    Rect rect;
+    Rect available_rect;
+} ScreenInfo;
+
+typedef struct
+{
+    /// The pixels in RGBA format, |size| is |width| * |height| * 4.
+    const uint8_t *data;
+    size_t size;
+    uint32_t width;
+    uint32_t height;
+} Image;
+
+typedef struct
+{
+    /// The size of the content of the page in CSS pixels.
+    double content_width;
+    double content_height;
+
+    /// The size of the viewport in CSS pixels.
+    double viewport_width;
+    double viewport_height;
+
+    /// The scroll offset of the viewport in CSS pixels.
+    double scroll_x;
+    double scroll_y;
+} LayoutMetrics;
+
+typedef struct
+{
+    /// The URL of the document.
+    const char *url;
+
+    /// The number of pages of the document.
+    uint32_t page_count;
+
+    /// The outline of the document in JSON format, an array of items with a |title|, the zero-based |page| or the
+    /// |uri| of the target, and the nested |children| items.
+    const char *outline;
+} PdfDocument;
+
+typedef enum
+{
+    WEW_SCREENCAST_FORMAT_JPEG = 0,
+    WEW_SCREENCAST_FORMAT_PNG,
+} ScreencastFormat;
+
+typedef struct
+{
+    ScreencastFormat format;
+    /// The compression quality from 0 to 100, only used by JPEG.
+    int quality;
+    /// The maximum size of the frames in device pixels, 0 means the size of the view.
+    uint32_t max_width;
+    uint32_t max_height;
+    /// Send every n-th frame, 1 sends all frames.
+    uint32_t every_nth_frame;
+} ScreencastOptions;
+
+typedef struct
+{
+    /// The encoded image in the format of the screencast.
+    const uint8_t *data;
+    size_t size;
+    ScreencastFormat format;
+
+    /// The top offset of the page content in DIP and the page scale factor.
+    double offset_top;
+    double page_scale_factor;
+
+    /// The size of the view in DIP.
+    double device_width;
+    double device_height;
+
+    /// The scroll offset of the page in CSS pixels.
+    double scroll_offset_x;
+    double scroll_offset_y;
+
+    /// The time the frame was swapped in seconds since the UNIX epoch, 0 if it is unknown.
+    double timestamp;
+} ScreencastFrame;
+
+typedef struct
+{
+    /// The vendor and the name of the active GPU.
+    const char *vendor;
+    const char *device;
+    const char *driver_vendor;
+    const char *driver_version;
+    /// Whether the features are hardware accelerated.
+    bool gpu_compositing;
+    bool rasterization;
+    bool video_decode;
+} GpuInfo;
+
+typedef struct
+{
+    /// The globally unique identifier of the frame.
+    const char *identifier;
+
+    /// The name of the frame, the name of the main frame is empty.
+    const char *name;
+
+    /// The URL currently loaded in the frame.
+    const char *url;
+
+    /// The origin of the URL loaded in the frame, such as `https://example.com`, an opaque origin is `null`.
+    const char *origin;
+
+    /// Whether the frame is the main (top-level) frame.
+    bool is_main;
+} FrameInfo;
+
+typedef enum
+{
+    WEW_V8_VALUE_UNDEFINED = 0,
+    WEW_V8_VALUE_NULL,
+    WEW_V8_VALUE_BOOL,
+    WEW_V8_VALUE_INT,
+    WEW_V8_VALUE_DOUBLE,
+    WEW_V8_VALUE_STRING,
+    WEW_V8_VALUE_ARRAY_BUFFER,
+} V8ValueType;
+
+///
+/// A JavaScript value passed to or returned from a native function of a V8 extension.
+///
+typedef struct
+{
+    V8ValueType type;
+    bool bool_value;
+    int32_t int_value;
+    double double_value;
+
+    /// The UTF-8 string or the array buffer content, only valid during the call.
+    const uint8_t *data;
+    size_t size;
+} V8Value;
+
+typedef struct
+{
+    /// The name of the extension.
+    const char *name;
+
+    /// The JavaScript code of the extension, native functions are declared with `native function name();`.
+    const char *code;
+
+    ///
+    /// Called when a native function of the extension is called, the result is set with
+    /// |v8_extension_set_result| or |v8_extension_set_exception| before returning, the result is undefined
+    /// otherwise.
+    ///
+    void (*execute)(const char *function, const V8Value *arguments, size_t size, void *result, void *context);
+
+    void *context;
+} V8Extension;
+
+typedef struct
+{
+    /// Called once WebKit has been initialized, V8 extensions can only be registered during this call.
+    void (*on_webkit_initialized)(void *context);
+
+    /// Called after the JavaScript context of a frame has been created, |v8_context| is only valid during the call.
+    void (*on_context_created)(void *v8_context, FrameInfo frame, void *context);
+
+    /// Called before the JavaScript context of a frame is released, |v8_context| is only valid during the call.
+    void (*on_context_released)(void *v8_context, FrameInfo frame, void *context);
+
+    void *context;
+} SubProcessHandler;
+
+///
+/// Drag operation mask values, they are combined as bit flags.
+///
+typedef enum
+{
+    WEW_DRAG_OPERATION_NONE = 0,
+    WEW_DRAG_OPERATION_COPY = 1,
+    WEW_DRAG_OPERATION_LINK = 2,
+    WEW_DRAG_OPERATION_GENERIC = 4,
+    WEW_DRAG_OPERATION_PRIVATE = 8,
+    WEW_DRAG_OPERATION_MOVE = 16,
+    WEW_DRAG_OPERATION_DELETE = 32,
+} DragOperation;
+
+typedef struct
+{
+    ///
+    /// The paths of the dragged files.
+    ///
+    const char **files;
+    size_t files_size;
+
+    ///
+    /// The dragged text, can be NULL.
+    ///
+    const char *text;
+
+    ///
+    /// The dragged URL, can be NULL.
+    ///
+    const char *url;
+} DragData;
+
 typedef struct
 {
     void (*on_cursor)(CursorType type, void *context);
-    void (*on_state_change)(WebViewState state, void *context);
+    void (*on_state_change)(const WebViewStateChange *change, void *context);
     void (*on_ime_rect)(Rect rect, void *context);
     void (*on_frame)(const Frame *frame, void *context);
     void (*on_title_change)(const char *title, void *context);
     void (*on_fullscreen_change)(bool fullscreen, void *context);
-    void (*on_message)(const char *message, void *context);
+    /// |channel| is NULL for messages sent on the default channel, |frame| is the frame that sent the message, its URL
+    /// is the URL of the document at the time the message was sent. Each message must be acknowledged with
+    /// |webview_ack_message| once it has been handled.
+    void (*on_message)(const char *channel, const char *message, FrameInfo frame, void *context);
+    void (*on_binary_message)(const char *channel,
+                              const uint8_t *message,
+                              size_t size,
+                              FrameInfo frame,
+                              void *context);
+    void (*on_render_process_terminated)(RenderProcessTerminationStatus status, int error_code, void *context);
+    void (*on_page_error)(const PageError *error, FrameInfo frame, void *context);
+    void (*on_console_message)(LogLevel level, const char *message, const char *source, int line, void *context);
+    void (*on_favicon_change)(const char **urls, size_t size, void *context);
+    void (*on_status_message)(const char *message, void *context);
+    /// Called on the IO thread when the main resource of the main frame has been loaded.
+    void (*on_main_frame_response)(const HttpResponse *response, void *context);
+    /// Called on the UI thread in windowless rendering mode, |info| is filled with the default values, return false to
+    /// keep them.
+    bool (*on_screen_info)(ScreenInfo *info, void *context);
+    /// Called when the focus leaves the page, |next| is true when tabbing forward out of the last element of the page
+    /// and false when tabbing backward out of the first one.
+    void (*on_take_focus)(bool next, void *context);
+    /// Called on the UI thread when the page is about to take the focus, return true to cancel it.
+    bool (*on_set_focus)(FocusSource source, void *context);
+    /// Called when the page has received the focus.
+    void (*on_got_focus)(void *context);
+    /// Called with the results of webview_find, |active_index| starts from 1 and is 0 if no match is active. The
+    /// callback is called multiple times while the search progresses, |final_update| is true for the last result.
+    void (*on_find_result)(int count, int active_index, Rect selection_rect, bool final_update, void *context);
+    /// Called when the page is about to be printed, before the print dialog is shown.
+    void (*on_print_start)(void *context);
+    /// Called when the printing of the page is finished or cancelled.
+    void (*on_print_finished)(void *context);
+    /// Called on Linux to show the print dialog, |has_selection| is true if the page has a selection. Return true
+    /// to print with the default settings or false to cancel the printing.
+    bool (*on_print_dialog)(bool has_selection, void *context);
+    /// Called on Linux with the document to print, the PDF file is deleted after the callback returns. Return
+    /// false to send the document to the default printer of the system.
+    bool (*on_print_job)(const char *document_name, const char *pdf_file_path, void *context);
+    /// Called on the UI thread in windowless rendering mode when the page starts a drag, |allowed_ops| is a mask of
+    /// DragOperation values. Return true if the drag is handled, webview_drag_source_ended must then be called once
+    /// it is completed, or false to cancel the drag.
+    bool (*on_start_dragging)(const DragData *data, uint32_t allowed_ops, int x, int y, void *context);
+    /// Called in windowless rendering mode when the operation that the page accepts for the drag changes.
+    void (*on_update_drag_cursor)(uint32_t operation, void *context);
+    /// Called on the UI thread when a frame accesses the clipboard with the async clipboard API, only if
+    /// |clipboard_policy| is enabled in the settings. |origin| is the origin of the frame.
+    ClipboardPolicy (*on_clipboard_access)(const char *origin, ClipboardAccess access, void *context);
+    /// Called when the built-in PDF viewer has loaded a document.
+    void (*on_pdf_load)(const PdfDocument *document, void *context);
     void *context;
 } WebViewHandler;
 
@@ -579,9 +1170,61 @@ extern "C"
 
     EXPORT bool post_task_with_main_thread(void (*callback)(void *context), void *context);
 
+    ///
+    /// Post a task to the main thread that runs after |delay| milliseconds.
+    ///
+    EXPORT bool post_delayed_task_with_main_thread(void (*callback)(void *context), void *context, int64_t delay);
+
+    ///
+    /// Returns true if the current thread is the UI thread of the runtime.
+    ///
+    EXPORT bool currently_on_main_thread();
+
     EXPORT int get_exit_code();
 
-    EXPORT int execute_subprocess(int argc, const char **argv);
+    ///
+    /// Get the sandbox information of the process, it is created on the first call and stays alive until the process
+    /// exits. NULL is returned if the sandbox is disabled or the platform is not Windows.
+    ///
+    EXPORT void *get_sandbox_info();
+
+    ///
+    /// Set the value of a crash key declared in crash_reporter.cfg, an empty |value| clears the key.
+    ///
+    EXPORT void set_crash_key_value(const char *key, const char *value);
+
+    ///
+    /// Execute the subprocess, |handler| is optional and only used by the render process.
+    ///
+    EXPORT int execute_subprocess(int argc, const char **argv, const SubProcessHandler *handler);
+
+    ///
+    /// Execute a string of JavaScript code in a JavaScript context passed to the |SubProcessHandler|, returns false if
+    /// an exception was thrown.
+    ///
+    EXPORT bool v8_context_execute_javascript(void *v8_context, const char *script);
+
+    ///
+    /// Register a V8 extension, the extension is copied and |context| must stay valid as long as the process runs.
+    ///
+    /// This function can only be called during |SubProcessHandler::on_webkit_initialized|.
+    ///
+    EXPORT bool register_v8_extension(const V8Extension *extension);
+
+    EXPORT void v8_extension_set_result(void *result, V8Value value);
+
+    EXPORT void v8_extension_set_exception(void *result, const char *message);
+
+    EXPORT bool command_line_has_switch(void *command_line, const char *name);
+
+    ///
+    /// Append a switch to the command line, |value| is optional.
+    ///
+    EXPORT void command_line_append_switch(void *command_line, const char *name, const char *value);
+
+    EXPORT void command_line_remove_switch(void *command_line, const char *name);
+
+    EXPORT void command_line_append_argument(void *command_line, const char *argument);
 
     EXPORT void run_message_loop();
 
@@ -593,12 +1236,44 @@ extern "C"
 
     EXPORT bool execute_runtime(void *runtime, int argc, const char **argv);
 
+    ///
+    /// Close all browsers that have not been destroyed yet, this can be called on any thread, the browsers are closed
+    /// on the UI thread.
+    ///
+    EXPORT void runtime_close_browsers(void *runtime);
+
+    ///
+    /// Returns the number of browsers that have not been destroyed yet, this can be called on any thread.
+    ///
+    EXPORT size_t runtime_get_browser_count(void *runtime);
+
+    ///
+    /// Get the CefApp of the runtime, no reference is added, the pointer is valid until the runtime is closed.
+    ///
+    EXPORT void *runtime_get_app(void *runtime);
+
+    ///
+    /// Query the GPU information through one of the browsers. The callback is called once on the UI thread, |info| is
+    /// NULL if there is no browser or the query failed.
+    ///
+    EXPORT void runtime_get_gpu_info(void *runtime,
+                                     void (*callback)(const GpuInfo *info, void *context),
+                                     void *context);
+
     ///
     /// This function should be called on the main application thread to shut down
-    /// the CEF browser process before the application exits.
+    /// the CEF browser process before the application exits, all browsers must be
+    /// destroyed before calling this function.
     ///
+    EXPORT void shutdown_runtime(void *runtime);
+
     EXPORT void close_runtime(void *runtime);
 
+    ///
+    /// Create a webview, this can be called on any thread. The browser is created on the UI thread and the function
+    /// returns once it exists, so the UI thread must not be blocked by the calling thread. Returns NULL if the browser
+    /// could not be created or has not been created within 30 seconds.
+    ///
     EXPORT void *create_webview(void *runtime,
                                 const char *url,
                                 const WebViewSettings *settings,
@@ -621,6 +1296,31 @@ extern "C"
     ///
     EXPORT void webview_mouse_move(void *webview, MouseEvent event);
 
+    ///
+    /// Send a drag enter event to the browser, |allowed_ops| is a mask of DragOperation values.
+    ///
+    EXPORT void webview_drag_enter(void *webview, const DragData *data, MouseEvent event, uint32_t allowed_ops);
+
+    ///
+    /// Send a drag over event to the browser, it is called each time the cursor moves during the drag.
+    ///
+    EXPORT void webview_drag_over(void *webview, MouseEvent event, uint32_t allowed_ops);
+
+    ///
+    /// Send a drag leave event to the browser, the drag is cancelled.
+    ///
+    EXPORT void webview_drag_leave(void *webview);
+
+    ///
+    /// Send a drop event to the browser, the drag is completed.
+    ///
+    EXPORT void webview_drop(void *webview, MouseEvent event);
+
+    ///
+    /// Report the end of a drag started by the page, |op| is the DragOperation performed by the drop target.
+    ///
+    EXPORT void webview_drag_source_ended(void *webview, int x, int y, uint32_t op);
+
     ///
     /// Send a key event to the browser.
     ///
@@ -635,16 +1335,183 @@ extern "C"
 
     EXPORT void webview_ime_set_composition(void *webview, const char *input, int x, int y);
 
-    EXPORT void webview_send_message(void *webview, const char *message);
+    ///
+    /// Send a message to a frame of the page, the message is sent to the main frame if |frame| is NULL, and on the
+    /// default channel if |channel| is NULL.
+    ///
+    EXPORT void webview_send_message(void *webview, const char *frame, const char *channel, const char *message);
+
+    ///
+    /// Send a binary message to a frame of the page, the message is sent to the main frame if |frame| is NULL, and on
+    /// the default channel if |channel| is NULL.
+    ///
+    EXPORT void webview_send_binary_message(void *webview,
+                                            const char *frame,
+                                            const char *channel,
+                                            const uint8_t *message,
+                                            size_t size);
+
+    ///
+    /// Notify the render process that a message sent by |frame| has been handled, so that it is no longer counted
+    /// as in flight. Each message passed to |on_message| or |on_binary_message| must be acknowledged once.
+    ///
+    EXPORT void webview_ack_message(void *webview, const char *frame);
+
+    ///
+    /// Execute a string of JavaScript code in a frame of the page, the code is executed in the main frame if |frame|
+    /// is NULL.
+    ///
+    EXPORT void webview_execute_javascript(void *webview, const char *frame, const char *script);
+
+    ///
+    /// Emit an event to the page, the payload is a JSON encoded string.
+    ///
+    EXPORT void webview_emit(void *webview, const char *event, const char *payload);
 
     EXPORT void webview_set_devtools_state(void *webview, bool is_open);
 
+    ///
+    /// Replace the request handler factory of the webview, the factory handles the requests made after this call and
+    /// nullptr lets them load normally. The factory must live as long as the webview.
+    ///
+    EXPORT void webview_set_request_handler_factory(void *webview, const RequestHandlerFactory *factory);
+
     EXPORT void webview_resize(void *webview, int width, int height);
 
-    EXPORT RawWindowHandle webview_get_window_handle(void *webview);
+    ///
+    /// Notify the webview that the screen info has changed, the screen info is requested again from the handler.
+    ///
+    EXPORT void webview_notify_screen_info_changed(void *webview);
+
+    ///
+    /// Set the device scale factor of the default screen info, the webview is notified that the screen info has
+    /// changed.
+    ///
+    EXPORT void webview_set_device_scale_factor(void *webview, float device_scale_factor);
+
+    ///
+    /// Get the native window handle of the webview, false is returned if the browser has not been created yet or has
+    /// been closed. |handle| is set to null if the browser has no native window, such as with the Wayland platform.
+    ///
+    EXPORT bool webview_get_window_handle(void *webview, RawWindowHandle *handle);
+
+    ///
+    /// Get the CefBrowser of the webview, NULL is returned if the browser has not been created yet or has been closed.
+    /// No reference is added, the pointer is only valid on the UI thread while the browser is alive.
+    ///
+    EXPORT void *webview_get_browser(void *webview);
+
+    ///
+    /// Get the process id of the render process of the main frame, 0 is returned if the page has not been rendered
+    /// yet. This function can be called on any thread.
+    ///
+    EXPORT int webview_get_render_process_id(void *webview);
 
+    ///
+    /// Get the main frame and all child frames of the web page, the callback is
+    /// called once for each frame before this function returns.
+    ///
+    EXPORT void webview_get_frames(void *webview, void (*callback)(FrameInfo info, void *context), void *context);
+
+    ///
+    /// Download an image, such as a favicon, and decode it to RGBA. |max_size| limits the width and the height, 0 is
+    /// unlimited. The callback is called once on the UI thread, |image| is NULL if the image could not be downloaded or
+    /// decoded.
+    ///
+    EXPORT void webview_download_image(void *webview,
+                                       const char *url,
+                                       bool is_favicon,
+                                       uint32_t max_size,
+                                       void (*callback)(const Image *image, void *context),
+                                       void *context);
+
+    ///
+    /// Give the focus to the page or remove it, this is used to move the focus into the page when tabbing from the
+    /// host UI.
+    ///
     EXPORT void webview_set_focus(void *webview, bool enable);
 
+    ///
+    /// Query the content size and the scroll offset of the main frame. The callback is called once on the UI thread,
+    /// |metrics| is NULL if there is no browser or the query failed.
+    ///
+    EXPORT void webview_get_layout_metrics(void *webview,
+                                           void (*callback)(const LayoutMetrics *metrics, void *context),
+                                           void *context);
+
+    ///
+    /// Capture the visible part of the page, scaled down to fit in |max_width| x |max_height| while keeping the aspect
+    /// ratio, it is never scaled up. The page is scaled by the renderer, so a large view is not copied at full size.
+    /// The callback is called once on the UI thread with the RGBA pixels, |image| is NULL if there is no browser or
+    /// the capture failed.
+    ///
+    EXPORT void webview_capture_thumbnail(void *webview,
+                                          uint32_t max_width,
+                                          uint32_t max_height,
+                                          void (*callback)(const Image *image, void *context),
+                                          void *context);
+
+    ///
+    /// Start sending the frames of the page as encoded images, this works with native windows as well as with
+    /// windowless rendering. The next frame is only produced after |on_frame| returns, returning false stops the
+    /// screencast. Only one screencast can run in a webview, starting a new one stops the previous one.
+    ///
+    /// |on_frame| is called on the UI thread, a NULL |frame| means that the screencast has ended and is the last
+    /// call, it is always made even if the screencast could not be started.
+    ///
+    EXPORT void webview_start_screencast(void *webview,
+                                         const ScreencastOptions *options,
+                                         bool (*on_frame)(const ScreencastFrame *frame, void *context),
+                                         void *context);
+
+    ///
+    /// Stop the running screencast, if any.
+    ///
+    EXPORT void webview_stop_screencast(void *webview);
+
+    ///
+    /// Change the zoom level, 0.0 is the default level and each step up or down scales the page by 20 percent.
+    ///
+    EXPORT void webview_set_zoom_level(void *webview, double level);
+
+    ///
+    /// Exit the fullscreen mode that was entered by the page with the JavaScript fullscreen API.
+    ///
+    EXPORT void webview_exit_fullscreen(void *webview);
+
+    ///
+    /// Execute an editing command in the focused frame, such as a command of the edit menu.
+    ///
+    EXPORT void webview_edit_command(void *webview, EditCommand command);
+
+    ///
+    /// Override the forced colors mode of the page, the forced-colors and prefers-contrast media features and the
+    /// colors of the page are updated.
+    ///
+    EXPORT void webview_set_forced_colors(void *webview, ForcedColors forced_colors);
+
+    ///
+    /// Apply the settings of |delta| to the webview, the settings that are not set are not changed.
+    ///
+    EXPORT void webview_update_settings(void *webview, const WebViewSettingsDelta *delta);
+
+    ///
+    /// Search for |text| in the page, |find_next| is true to move to the next match of the previous search instead of
+    /// starting a new search. The results are reported with on_find_result.
+    ///
+    EXPORT void webview_find(void *webview, const char *text, bool forward, bool match_case, bool find_next);
+
+    ///
+    /// Cancel the search, the highlighted matches are removed and the active match is selected if |clear_selection|
+    /// is false.
+    ///
+    EXPORT void webview_stop_finding(void *webview, bool clear_selection);
+
+    ///
+    /// Print the page, the print dialog of the system is shown.
+    ///
+    EXPORT void webview_print(void *webview);
+
 #ifdef __cplusplus
 }
 #endif
@@ -3,7 +3,7 @@
 // It builds the frontend, Rust backend, and packages all necessary dependencies
 
 import { exec } from "node:child_process";
-import { cp, readdir, mkdir, rm, readFile, writeFile } from "node:fs/promises";
+import { cp, readdir, mkdir, readFile, writeFile } from "node:fs/promises";
 import { join, dirname, resolve } from "node:path";
 import { fileURLToPath } from "node:url";
 import { existsSync } from "node:fs";
@@ -82,7 +82,78 @@ async function getCrateOutdir(target, crate, dir) {
     throw new Error("not found cargo crate outdir");
 }
 
-void (async () => {
+// Copy the CEF runtime files next to the executable
+// On Windows and Linux, the files of `cef/Release` and `cef/Resources` are
+// placed in |outDir| without the static libraries and debug symbols. On macOS,
+// |outDir| is the app bundle, and the framework is placed in its Frameworks
+// directory, the resources are already part of the framework.
+export async function copyResources(cefDir, outDir) {
+    if (process.platform == "darwin") {
+        const framework = "Chromium Embedded Framework.framework";
+
+        await mkdir(join(outDir, "./Contents/Frameworks"), { recursive: true });
+        await cp(join(cefDir, "./Release", framework), join(outDir, "./Contents/Frameworks", framework), {
+            force: true,
+            recursive: true,
+        });
+
+        return;
+    }
+
+    await mkdir(outDir, { recursive: true });
+
+    for (const dir of ["./Release", "./Resources"]) {
+        await cp(join(cefDir, dir), outDir, {
+            force: true,
+            recursive: true,
+            filter: (path) => ![".lib", ".pdb", ".a"].some((ext) => path.endsWith(ext)),
+        });
+    }
+}
+
+// Generate the helper app bundles required by CEF on macOS
+// Creates "<name> Helper", "<name> Helper (GPU)", "<name> Helper (Plugin)" and
+// "<name> Helper (Renderer)" in the Frameworks directory of the app bundle. The
+// helper executable is copied rather than linked, since a symbolic link to a
+// file outside of the bundle is rejected by code signing.
+export async function generateMacosHelpers(appBundlePath, { name, identifier, executable }) {
+    for (const [suffix, identifierSuffix] of [
+        ["", "helper"],
+        [" (GPU)", "helper.gpu"],
+        [" (Plugin)", "helper.plugin"],
+        [" (Renderer)", "helper.renderer"],
+    ]) {
+        const helperName = `${name} Helper${suffix}`;
+        const helperPath = join(appBundlePath, "./Contents/Frameworks", `./${helperName}.app`);
+        const helperExecutable = join(helperPath, `Contents/MacOS/${helperName}`);
+
+        for (const path of ["Contents/MacOS", "Contents/Resources"]) {
+            await mkdir(join(helperPath, path), { recursive: true });
+        }
+
+        await writeFile(
+            join(helperPath, "Contents/Info.plist"),
+            (await readFile(join(__dirname, "./helper.Info.plist"), "utf8"))
+                .replaceAll("{name}", helperName)
+                .replaceAll("{identifier}", `${identifier}.${identifierSuffix}`)
+        );
+
+        await cp(executable, helperExecutable, { force: true });
+
+        await command(`install_name_tool -change \
+            "@executable_path/../Frameworks/Chromium Embedded Framework.framework/Chromium Embedded Framework" \
+            "@rpath/Chromium Embedded Framework.framework/Chromium Embedded Framework" \
+            "${helperExecutable}"`);
+
+        await command(`install_name_tool \
+            -add_rpath "@executable_path/../../../../Frameworks" \
+            "${helperExecutable}"`);
+    }
+}
+
+// Only build when run directly, so that the helpers above can be imported by
+// the packaging scripts of other projects.
+if (process.argv[1] == __filename) void (async () => {
     const Project = Args.args.project;
 
     await command("cargo build", {
@@ -114,24 +185,18 @@ void (async () => {
                 `../target/debug/${Project}-helper.exe`,
                 `../target/examples/${Project}/${Project}-helper.exe`,
             ],
-            [`${cefOutDir}/Release`, `../target/examples/${Project}/`],
-            [`${cefOutDir}/Resources`, `../target/examples/${Project}/`],
         ]) {
             await cp(...item, { force: true, recursive: true });
         }
 
-        for (const path of [
-            `../target/examples/${Project}/cef_sandbox.lib`,
-            `../target/examples/${Project}/libcef.lib`,
-        ]) {
-            if (existsSync(path)) {
-                await rm(path, { force: true, recursive: true });
-            }
-        }
+        await copyResources(cefOutDir, `../target/examples/${Project}/`);
 
         await command(`../target/examples/${Project}/${Project}.exe`);
     } else if (process.platform == "darwin") {
-        const cefReleasePath = await getCrateOutdir(`../target/debug`, "wew", "./cef/Release");
+        const cefOutDir = join(
+            await getCrateOutdir(`../target/debug`, "wew", "./cef/Release"),
+            "../"
+        );
 
         for (const path of [
             `../target/examples/${Project}.app`,
@@ -144,18 +209,13 @@ void (async () => {
             }
         }
 
-        for (const item of [
-            [
-                `../target/debug/${Project}`,
-                `../target/examples/${Project}.app/Contents/MacOS/${Project}`,
-            ],
-            [
-                join(cefReleasePath, "./Chromium Embedded Framework.framework"),
-                `../target/examples/${Project}.app/Contents/Frameworks/Chromium Embedded Framework.framework`,
-            ],
-        ]) {
-            await cp(...item, { force: true, recursive: true });
-        }
+        await cp(
+            `../target/debug/${Project}`,
+            `../target/examples/${Project}.app/Contents/MacOS/${Project}`,
+            { force: true, recursive: true }
+        );
+
+        await copyResources(cefOutDir, `../target/examples/${Project}.app`);
 
         {
             await writeFile(
@@ -166,54 +226,11 @@ void (async () => {
             );
         }
 
-        // generate helper
-        {
-            for (const [name, identifier] of [
-                [`${Project} Helper`, `com.github.mycrl.wew.${Project}.helper`],
-                [`${Project} Helper (GPU)`, `com.github.mycrl.wew.${Project}.helper.gpu`],
-                [`${Project} Helper (Plugin)`, `com.github.mycrl.wew.${Project}.helper.plugin`],
-                [`${Project} Helper (Renderer)`, `com.github.mycrl.wew.${Project}.helper.renderer`],
-            ]) {
-                const helperPath = join(
-                    __dirname,
-                    `../target/examples/${Project}.app/Contents/Frameworks`,
-                    `./${name}.app`
-                );
-
-                for (const path of ["", "Contents", "Contents/MacOS", "Contents/Resources"]) {
-                    if (!existsSync(join(helperPath, path))) {
-                        await mkdir(join(helperPath, path));
-                    }
-                }
-
-                {
-                    await writeFile(
-                        join(helperPath, "Contents/Info.plist"),
-                        (await readFile("./helper.Info.plist", "utf8"))
-                            .replaceAll("{name}", name)
-                            .replaceAll("{identifier}", identifier)
-                    );
-                }
-
-                for (const item of [
-                    [
-                        `../target/debug/${Project}-helper`,
-                        join(helperPath, `Contents/MacOS/${name}`),
-                    ],
-                ]) {
-                    await cp(...item, { force: true, recursive: true });
-                }
-
-                await command(`install_name_tool -change \
-                    "@executable_path/../Frameworks/Chromium Embedded Framework.framework/Chromium Embedded Framework" \
-                    "@rpath/Chromium Embedded Framework.framework/Chromium Embedded Framework" \
-                    "${join(helperPath, `Contents/MacOS/${name}`)}"`);
-
-                await command(`install_name_tool \
-                    -add_rpath "@executable_path/../../../../Frameworks" \
-                    "${join(helperPath, `Contents/MacOS/${name}`)}"`);
-            }
-        }
+        await generateMacosHelpers(join(__dirname, `../target/examples/${Project}.app`), {
+            name: Project,
+            identifier: `com.github.mycrl.wew.${Project}`,
+            executable: `../target/debug/${Project}-helper`,
+        });
 
         await command(`../target/examples/${Project}.app/Contents/MacOS/${Project}`);
     } else {
@@ -232,13 +249,11 @@ void (async () => {
                 `../target/debug/${Project}-helper`,
                 `../target/examples/${Project}/${Project}-helper`,
             ],
-            [`${cefOutDir}/Release`, `../target/examples/${Project}/`],
-            [`${cefOutDir}/Resources`, `../target/examples/${Project}/`],
         ]) {
             await cp(...item, { force: true, recursive: true });
         }
 
-        console.log(`note: add ${resolve(`../target/examples/${Project} to LD_LIBRARY_PATH env`)}`);
+        await copyResources(cefOutDir, `../target/examples/${Project}/`);
 
         await command(`../target/examples/${Project}/${Project}`);
     }
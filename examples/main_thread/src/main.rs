@@ -5,8 +5,8 @@ use std::{
 
 use wew::{
     MainThreadMessageLoop, MessageLoopAbstract, NativeWindowWebView,
-    runtime::{LogLevel, RuntimeHandler},
-    webview::{WebViewAttributes, WebViewHandler, WebViewState},
+    runtime::{LogSeverity, RuntimeHandler},
+    webview::{WebViewAttributes, WebViewHandler, WebViewId, WebViewState},
 };
 
 struct RuntimeObserver {
@@ -22,7 +22,7 @@ impl RuntimeHandler for RuntimeObserver {
 struct WebViewObserver;
 
 impl WebViewHandler for WebViewObserver {
-    fn on_state_change(&self, state: WebViewState) {
+    fn on_state_change(&self, _id: WebViewId, state: WebViewState) {
         if state == WebViewState::Close {
             std::process::exit(0);
         }
@@ -48,7 +48,7 @@ fn main() {
         // Set cache path, here we use environment variables passed by the build script.
         .with_root_cache_path(option_env!("CACHE_PATH").unwrap())
         .with_cache_path(option_env!("CACHE_PATH").unwrap())
-        .with_log_severity(LogLevel::Info);
+        .with_log_severity(LogSeverity::Info);
 
     let (tx, rx) = channel();
 
@@ -56,6 +56,7 @@ fn main() {
     // before considering the creation successful.
     let runtime = runtime_attributes_builder
         .build()
+        .unwrap()
         .create_runtime(RuntimeObserver { tx })
         .unwrap();
 
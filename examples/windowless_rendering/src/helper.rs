@@ -1,4 +1,4 @@
 // Run subprocess in a separate executable file
 fn main() {
-    wew::execute_subprocess();
+    wew::execute_subprocess_and_exit();
 }
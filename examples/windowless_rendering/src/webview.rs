@@ -14,9 +14,10 @@ use wew::{
     MessageLoopAbstract, MessagePumpLoop, Rect, WindowlessRenderWebView,
     events::EventAdapter,
     raw_window_handle::RawWindowHandle,
-    runtime::{LogLevel, MessagePumpRuntimeHandler, Runtime, RuntimeHandler},
+    runtime::{LogSeverity, MessagePumpRuntimeHandler, Runtime, RuntimeHandler},
     webview::{
-        Frame, WebView, WebViewAttributesBuilder, WebViewHandler, WindowlessRenderWebViewHandler,
+        Frame, WebView, WebViewAttributesBuilder, WebViewHandler, WebViewId,
+        WindowlessRenderWebViewHandler,
     },
 };
 
@@ -52,12 +53,12 @@ impl WindowlessRenderWebViewHandler for WebViewObserver {
     // When the webview needs to render, this function will be called.
     //
     // Here we call the renderer to render the webview's output to the window.
-    fn on_frame(&self, frame: &Frame) {
+    fn on_frame(&self, _id: WebViewId, frame: &Frame) {
         self.render.lock().render(frame);
     }
 
     // Notify winit of the input cursor position.
-    fn on_ime_rect(&self, rect: Rect) {
+    fn on_ime_rect(&self, _id: WebViewId, rect: Rect) {
         let _ = self.event_loop_proxy.send_event(UserEvent::ImeRect(rect));
     }
 }
@@ -147,12 +148,12 @@ impl Webview {
             // Set cache path, here we use environment variables passed by the build script.
             .with_root_cache_path(option_env!("CACHE_PATH").unwrap())
             .with_cache_path(option_env!("CACHE_PATH").unwrap())
-            .with_log_severity(LogLevel::Info);
+            .with_log_severity(LogSeverity::Info);
 
         // Create runtime, wait for the `on_context_initialized` event to be triggered
         // before considering the creation successful.
         let runtime = runtime_attributes_builder
-            .build()
+            .build()?
             .create_runtime(RuntimeObserver::new(event_loop_proxy.clone()))?;
 
         Ok(Self {
@@ -178,7 +179,7 @@ impl Webview {
                 .with_width(WIDTH)
                 .with_height(HEIGHT)
                 .with_window_handle(window_handle)
-                .build(),
+                .build()?,
             WebViewObserver {
                 event_loop_proxy: self.event_loop_proxy.clone(),
                 render: Mutex::new(render),
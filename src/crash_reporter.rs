@@ -0,0 +1,334 @@
+//! This module is used to configure crash reporting.
+//!
+//! CEF uses Crashpad to collect crash dumps of the browser process and all
+//! subprocesses. Crash reporting is only enabled if a `crash_reporter.cfg` file
+//! exists when the process starts, this module generates that file.
+//!
+//! The file is located next to the executable on Windows and Linux, and in the
+//! `Contents/Resources` directory of the app bundle on macOS. It is usually
+//! written when the application is packaged, since the macOS app bundle cannot
+//! be modified after it has been signed.
+//!
+//! The crash dumps are stored in the `Crashpad` directory of the user data
+//! directory named after **`CrashReporter::with_app_name`**, and uploaded to
+//! the server if **`CrashReporter::with_server_url`** is set.
+//!
+//! ```no_run
+//! use wew::crash_reporter::{CrashKeySize, CrashReporter};
+//!
+//! CrashReporter::new("Kyle", "1.0.0")
+//!     .with_server_url("https://crash.example.com/submit")
+//!     .with_crash_key("session", CrashKeySize::Small)
+//!     .install()
+//!     .unwrap();
+//!
+//! wew::crash_reporter::set_crash_key_value("session", "8c2f");
+//! ```
+//!
+//! Crashes of the render process are also reported to the webview, see
+//! **`WebViewHandler::on_render_process_terminated`**.
+//!
+//! ### Limitations
+//!
+//! CEF does not notify the application about crashes of the GPU process or of
+//! utility processes, they are only recorded as crash dumps and uploaded like
+//! the others. The crash dump directory cannot be configured either, it is
+//! derived from the application name by Crashpad and the configuration file
+//! has no option for it.
+
+use std::{
+    env,
+    ffi::CString,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{sys, utils::AnyStringCast};
+
+/// The maximum length of a crash key value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKeySize {
+    /// 64 bytes
+    Small,
+    /// 256 bytes
+    Medium,
+    /// 1024 bytes
+    Large,
+}
+
+impl CrashKeySize {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Small => "small",
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+}
+
+/// Crash reporter configuration
+///
+/// This is used to generate the `crash_reporter.cfg` file.
+#[derive(Debug, Clone)]
+pub struct CrashReporter {
+    product_name: String,
+    product_version: String,
+    app_name: Option<String>,
+    server_url: Option<String>,
+    external_handler: Option<String>,
+    browser_crash_forwarding: bool,
+    rate_limit: bool,
+    max_uploads_per_day: u32,
+    max_database_size: u32,
+    max_database_age: u32,
+    crash_keys: Vec<(String, CrashKeySize)>,
+}
+
+impl CrashReporter {
+    /// Create a crash reporter configuration
+    ///
+    /// The product name and version are sent along with the crash dumps.
+    pub fn new(product_name: &str, product_version: &str) -> Self {
+        Self {
+            product_name: product_name.to_string(),
+            product_version: product_version.to_string(),
+            app_name: None,
+            server_url: None,
+            external_handler: None,
+            browser_crash_forwarding: false,
+            rate_limit: true,
+            max_uploads_per_day: 5,
+            max_database_size: 20,
+            max_database_age: 5,
+            crash_keys: Vec::new(),
+        }
+    }
+
+    /// Set the application name
+    ///
+    /// This function is used to set the name of the directory where the crash
+    /// dumps are stored, it is only supported on Windows and Linux.
+    pub fn with_app_name(mut self, value: &str) -> Self {
+        self.app_name = Some(value.to_string());
+        self
+    }
+
+    /// Set the upload server url
+    ///
+    /// This function is used to set the url that the crash dumps are uploaded
+    /// to, the crash dumps are only stored locally if not set.
+    pub fn with_server_url(mut self, value: &str) -> Self {
+        self.server_url = Some(value.to_string());
+        self
+    }
+
+    /// Set the external crash handler
+    ///
+    /// This function is used to set the path of an executable that handles the
+    /// crashes instead of the default handler, it is only supported on
+    /// Windows.
+    pub fn with_external_handler(mut self, value: &str) -> Self {
+        self.external_handler = Some(value.to_string());
+        self
+    }
+
+    /// Set whether to forward browser crashes to the system crash reporter
+    ///
+    /// This function is only supported on macOS.
+    pub fn with_browser_crash_forwarding(mut self, value: bool) -> Self {
+        self.browser_crash_forwarding = value;
+        self
+    }
+
+    /// Set whether to limit the number of uploads
+    pub fn with_rate_limit(mut self, value: bool) -> Self {
+        self.rate_limit = value;
+        self
+    }
+
+    /// Set the maximum number of uploads per day
+    ///
+    /// This value is only used if the rate limit is enabled.
+    pub fn with_max_uploads_per_day(mut self, value: u32) -> Self {
+        self.max_uploads_per_day = value;
+        self
+    }
+
+    /// Set the maximum size of the crash dump database
+    ///
+    /// The unit is megabytes, older crash dumps are removed first.
+    pub fn with_max_database_size(mut self, value: u32) -> Self {
+        self.max_database_size = value;
+        self
+    }
+
+    /// Set the maximum age of the crash dumps
+    ///
+    /// The unit is days, older crash dumps are removed.
+    pub fn with_max_database_age(mut self, value: u32) -> Self {
+        self.max_database_age = value;
+        self
+    }
+
+    /// Add a crash key
+    ///
+    /// Crash keys are annotations sent along with the crash dumps, the value is
+    /// set by **`set_crash_key_value`**. Only declared keys are recorded.
+    pub fn with_crash_key(mut self, name: &str, size: CrashKeySize) -> Self {
+        self.crash_keys.push((name.to_string(), size));
+        self
+    }
+
+    /// Get the default path of the configuration file
+    ///
+    /// This is where CEF looks for the configuration file of the current
+    /// executable.
+    pub fn default_path() -> io::Result<PathBuf> {
+        let exe = env::current_exe()?;
+        let dir = exe
+            .parent()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+        Ok(if cfg!(target_os = "macos") {
+            dir.join("../Resources/crash_reporter.cfg")
+        } else {
+            dir.join("crash_reporter.cfg")
+        })
+    }
+
+    /// Write the configuration file to the path
+    ///
+    /// Values containing line breaks and crash key names that are empty or
+    /// contain `=` cannot be written to the file, an error of the
+    /// `InvalidInput` kind is returned for them.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.validate()?;
+
+        fs::write(path, self.to_string())
+    }
+
+    // A line break in a value would start a new key in the file.
+    fn validate(&self) -> io::Result<()> {
+        let values = [
+            ("product name", Some(&self.product_name)),
+            ("product version", Some(&self.product_version)),
+            ("app name", self.app_name.as_ref()),
+            ("server url", self.server_url.as_ref()),
+            ("external handler", self.external_handler.as_ref()),
+        ];
+
+        for (name, value) in values {
+            if let Some(value) = value
+                && value.contains(['\r', '\n'])
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the {} contains a line break", name),
+                ));
+            }
+        }
+
+        for (name, _) in &self.crash_keys {
+            if name.is_empty() || name.contains(['\r', '\n', '=']) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the crash key name is not valid: {:?}", name),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the configuration file to the default path
+    ///
+    /// This function must be called before the runtime is created.
+    pub fn install(&self) -> io::Result<PathBuf> {
+        let path = Self::default_path()?;
+        self.write(&path)?;
+
+        Ok(path)
+    }
+}
+
+impl std::fmt::Display for CrashReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut config = String::from("[Config]\n");
+
+        writeln!(config, "ProductName={}", self.product_name)?;
+        writeln!(config, "ProductVersion={}", self.product_version)?;
+
+        if let Some(app_name) = &self.app_name {
+            writeln!(config, "AppName={}", app_name)?;
+        }
+
+        if let Some(server_url) = &self.server_url {
+            writeln!(config, "ServerURL={}", server_url)?;
+        }
+
+        if let Some(external_handler) = &self.external_handler {
+            writeln!(config, "ExternalHandler={}", external_handler)?;
+        }
+
+        if self.browser_crash_forwarding {
+            writeln!(config, "BrowserCrashForwardingEnabled=true")?;
+        }
+
+        writeln!(config, "RateLimitEnabled={}", self.rate_limit)?;
+        writeln!(config, "MaxUploadsPerDay={}", self.max_uploads_per_day)?;
+        writeln!(config, "MaxDatabaseSizeInMb={}", self.max_database_size)?;
+        writeln!(config, "MaxDatabaseAgeInDays={}", self.max_database_age)?;
+
+        if !self.crash_keys.is_empty() {
+            config.push_str("\n[CrashKeys]\n");
+
+            for (name, size) in &self.crash_keys {
+                writeln!(config, "{}={}", name, size.as_str())?;
+            }
+        }
+
+        f.write_str(&config)
+    }
+}
+
+/// Set the value of a crash key
+///
+/// The key must be declared by **`CrashReporter::with_crash_key`**, and the
+/// value is truncated to the size of the key. An empty value clears the key.
+///
+/// This function can be called on any thread of any process after the process
+/// has started.
+pub fn set_crash_key_value(key: &str, value: &str) {
+    let key = CString::new(key).unwrap();
+    let value = CString::new(value).unwrap();
+
+    unsafe { sys::set_crash_key_value(key.as_raw(), value.as_raw()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{CrashKeySize, CrashReporter};
+
+    #[test]
+    fn rejects_line_breaks() {
+        let path = std::env::temp_dir().join("wew_crash_reporter.cfg");
+
+        let reporter = CrashReporter::new("Kyle", "1.0.0\nServerURL=https://example.com");
+        assert_eq!(
+            reporter.write(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let reporter =
+            CrashReporter::new("Kyle", "1.0.0").with_crash_key("a=b", CrashKeySize::Small);
+        assert_eq!(
+            reporter.write(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        assert!(!path.exists());
+    }
+}
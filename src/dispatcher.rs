@@ -0,0 +1,296 @@
+//! This module is used to run the handler callbacks outside of the CEF threads.
+//!
+//! By default the handlers are called directly on the CEF UI thread, calling
+//! back into wew from inside a callback, or blocking in it, can then deadlock
+//! or stall the rendering. A **`Dispatcher`** set with
+//! **`RuntimeAttributesBuilder::with_dispatcher`** receives every callback as
+//! a task instead, and decides where it is executed.
+//!
+//! **`ThreadDispatcher`** runs the tasks in order on a dedicated thread, any
+//! other executor can be used by implementing the trait, or with a closure:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use wew::dispatcher::{Dispatcher, ThreadDispatcher};
+//!
+//! let dispatcher: Arc<dyn Dispatcher> = Arc::new(ThreadDispatcher::new());
+//!
+//! // Or forward the tasks to an existing executor.
+//! let dispatcher: Arc<dyn Dispatcher> = Arc::new(|task: Box<dyn FnOnce() + Send>| {
+//!     std::thread::spawn(task);
+//! });
+//! ```
+//!
+//! The arguments of the callbacks are copied so that they can be moved to the
+//! task, this includes the buffer of **`WindowlessRenderWebViewHandler::on_frame`**.
+//! Messages are acknowledged to the page once the task has handled them, so
+//! the in-flight limit of **`WebViewAttributes::max_in_flight_messages`**
+//! also applies to the dispatched handlers.
+//!
+//! **`RuntimeHandler::on_before_command_line`** is always called directly,
+//! since the command line can only be modified during the callback.
+//!
+//! ## Executors
+//!
+//! The tasks are blocking calls of the handlers, so they are forwarded to the
+//! blocking pool of an async runtime, where a single consumer executes them in
+//! the order they are dispatched. Each adapter is enabled by the feature of
+//! the same name:
+//!
+//! | Feature     | Dispatcher                 |
+//! |-------------|----------------------------|
+//! | `tokio`     | **`TokioDispatcher`**      |
+//! | `async-std` | **`AsyncStdDispatcher`**   |
+//! | `smol`      | **`SmolDispatcher`**       |
+//!
+//! Other executors can be used by implementing the trait, the tasks of a
+//! webview should be executed one by one so that the events are observed in
+//! order.
+//!
+//! The events can also be received without a dispatcher, **`WebView::events`**
+//! returns a stream that can be polled by any executor.
+
+use std::{
+    sync::mpsc::{Sender, channel},
+    thread,
+};
+
+use crate::utils::catch_panic;
+
+/// A task created from a handler callback
+pub type Task = Box<dyn FnOnce() + Send>;
+
+/// Handler callback dispatcher
+///
+/// This trait is used to execute the handler callbacks, the tasks of a webview
+/// should be executed in order for the events to be observed in order.
+pub trait Dispatcher: Send + Sync {
+    /// Execute the task
+    ///
+    /// This function is called on the CEF threads and should not block.
+    fn dispatch(&self, task: Task);
+}
+
+impl<T> Dispatcher for T
+where
+    T: Fn(Task) + Send + Sync,
+{
+    fn dispatch(&self, task: Task) {
+        self(task)
+    }
+}
+
+/// Start the consumer of the tasks
+///
+/// The tasks are executed one by one in the order they are dispatched, `spawn`
+/// runs the consumer on its own thread, which exits once the returned sender
+/// is dropped.
+fn start_consumer<F>(spawn: F) -> Sender<Task>
+where
+    F: FnOnce(Task),
+{
+    let (tx, rx) = channel::<Task>();
+
+    spawn(Box::new(move || {
+        while let Ok(task) = rx.recv() {
+            catch_panic(task);
+        }
+    }));
+
+    tx
+}
+
+/// A dispatcher that executes the tasks on a dedicated thread
+///
+/// The tasks are executed in the order they are dispatched, the thread exits
+/// when the dispatcher is dropped.
+pub struct ThreadDispatcher(Sender<Task>);
+
+impl ThreadDispatcher {
+    /// Create a dispatcher and start its thread
+    pub fn new() -> Self {
+        Self(start_consumer(|consumer| {
+            thread::Builder::new()
+                .name("wew-dispatcher".to_string())
+                .spawn(consumer)
+                .unwrap();
+        }))
+    }
+}
+
+impl Default for ThreadDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher for ThreadDispatcher {
+    fn dispatch(&self, task: Task) {
+        let _ = self.0.send(task);
+    }
+}
+
+/// A dispatcher that executes the tasks on the blocking pool of tokio
+///
+/// The tasks are executed in order by a single consumer that occupies one
+/// thread of the blocking pool until the dispatcher is dropped. The tokio
+/// runtime waits for its blocking threads when it is dropped, so drop the
+/// dispatcher first.
+#[cfg(feature = "tokio")]
+pub struct TokioDispatcher(Sender<Task>);
+
+#[cfg(feature = "tokio")]
+impl TokioDispatcher {
+    /// Create a dispatcher for the current tokio runtime
+    ///
+    /// This function panics if it is not called from inside a tokio runtime.
+    pub fn new() -> Self {
+        Self::with_handle(tokio::runtime::Handle::current())
+    }
+
+    /// Create a dispatcher for the runtime of the handle
+    ///
+    /// The dispatcher is called on the CEF threads, outside of the tokio
+    /// runtime, so the consumer is started with the handle of the runtime.
+    pub fn with_handle(handle: tokio::runtime::Handle) -> Self {
+        Self(start_consumer(|consumer| {
+            handle.spawn_blocking(consumer);
+        }))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Default for TokioDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Dispatcher for TokioDispatcher {
+    fn dispatch(&self, task: Task) {
+        let _ = self.0.send(task);
+    }
+}
+
+/// A dispatcher that executes the tasks on the blocking pool of async-std
+///
+/// The tasks are executed in order by a single consumer that occupies one
+/// thread of the blocking pool until the dispatcher is dropped.
+#[cfg(feature = "async-std")]
+pub struct AsyncStdDispatcher(Sender<Task>);
+
+#[cfg(feature = "async-std")]
+impl AsyncStdDispatcher {
+    /// Create a dispatcher and start its consumer
+    pub fn new() -> Self {
+        Self(start_consumer(|consumer| {
+            async_std::task::spawn_blocking(consumer);
+        }))
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl Default for AsyncStdDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl Dispatcher for AsyncStdDispatcher {
+    fn dispatch(&self, task: Task) {
+        let _ = self.0.send(task);
+    }
+}
+
+/// A dispatcher that executes the tasks on the blocking pool of smol
+///
+/// The tasks are executed in order by a single consumer that occupies one
+/// thread of the blocking pool until the dispatcher is dropped.
+#[cfg(feature = "smol")]
+pub struct SmolDispatcher(Sender<Task>);
+
+#[cfg(feature = "smol")]
+impl SmolDispatcher {
+    /// Create a dispatcher and start its consumer
+    pub fn new() -> Self {
+        Self(start_consumer(|consumer| {
+            smol::unblock(consumer).detach();
+        }))
+    }
+}
+
+#[cfg(feature = "smol")]
+impl Default for SmolDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "smol")]
+impl Dispatcher for SmolDispatcher {
+    fn dispatch(&self, task: Task) {
+        let _ = self.0.send(task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::channel, time::Duration};
+
+    use super::*;
+
+    fn assert_in_order(dispatcher: &dyn Dispatcher) {
+        let (tx, rx) = channel();
+        for i in 0..100 {
+            let tx = tx.clone();
+            dispatcher.dispatch(Box::new(move || {
+                let _ = tx.send(i);
+            }));
+        }
+
+        let received = (0..100)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn thread_dispatcher_keeps_order() {
+        assert_in_order(&ThreadDispatcher::new());
+    }
+
+    #[test]
+    fn panicking_task_does_not_stop_the_consumer() {
+        let dispatcher = ThreadDispatcher::new();
+        dispatcher.dispatch(Box::new(|| panic!("task panicked")));
+
+        assert_in_order(&dispatcher);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_dispatcher_keeps_order() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let dispatcher = TokioDispatcher::with_handle(runtime.handle().clone());
+        assert_in_order(&dispatcher);
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn async_std_dispatcher_keeps_order() {
+        assert_in_order(&AsyncStdDispatcher::new());
+    }
+
+    #[cfg(feature = "smol")]
+    #[test]
+    fn smol_dispatcher_keeps_order() {
+        assert_in_order(&SmolDispatcher::new());
+    }
+}
@@ -0,0 +1,392 @@
+//! This module is used to inspect the structure of the web page.
+//!
+//! **`WebView::query_selector`** and **`WebView::query_selector_all`** run a
+//! helper script in the main frame of the page, which describes the matching
+//! elements and sends them back over the message bridge, so the host does not
+//! need its own protocol to look at the page:
+//!
+//! ```no_run
+//! use wew::{NativeWindowWebView, webview::WebView};
+//!
+//! async fn inspect(webview: &WebView<NativeWindowWebView>) {
+//!     if let Some(title) = webview.query_selector("h1").await {
+//!         println!("title: {}, at {:?}", title.text, title.rect);
+//!     }
+//!
+//!     for link in webview.query_selector_all("a[href]").await {
+//!         println!("link: {:?}", link.attributes.get("href"));
+//!     }
+//! }
+//! ```
+//!
+//! The futures can be awaited by any executor. They resolve to nothing if the
+//! selector is invalid, or if the page cannot answer because JavaScript is
+//! disabled, the main frame navigated, its render process terminated or the
+//! webview was closed. A query that is not answered within 10 seconds, such
+//! as when the page has not loaded yet, also resolves to nothing.
+//!
+//! The answers are not counted by
+//! **`WebViewAttributes::max_in_flight_messages`**, so the overflow policy of
+//! the page never drops them.
+//!
+//! Every query carries a random token that is only given to the helper
+//! script, the answers without the token of a pending query are rejected, so
+//! the page cannot answer a query by sending on the reserved channel itself.
+//! A page that replaces the built-in functions used by the helper script
+//! before it runs can still see its results.
+
+use std::{
+    collections::{HashMap, hash_map::RandomState},
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::{
+    utils::post_main_delayed,
+    webview::{ChannelHandler, FrameInfo},
+};
+
+/// The channel used by the helper script, it is reserved by the webview.
+pub(crate) const DOM_CHANNEL: &str = "__wew_dom";
+
+/// The time after which a query that has not been answered is resolved with
+/// nothing.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Called with the id and the token of the query, the selector and whether all
+// matching elements are queried. An invalid selector throws, which is reported
+// as no result.
+const DOM_HELPER_SCRIPT: &str = r#"(function (id, token, selector, all) {
+    const describe = (element) => {
+        const rect = element.getBoundingClientRect();
+        const attributes = {};
+        for (const attribute of element.attributes) {
+            attributes[attribute.name] = attribute.value;
+        }
+
+        return {
+            tag: element.tagName.toLowerCase(),
+            attributes,
+            rect: { x: rect.x, y: rect.y, width: rect.width, height: rect.height },
+            text: element.innerText ?? element.textContent ?? "",
+        };
+    };
+
+    let result = null;
+    try {
+        if (all) {
+            result = Array.from(document.querySelectorAll(selector), describe);
+        } else {
+            const element = document.querySelector(selector);
+            result = element ? describe(element) : null;
+        }
+    } catch (e) {}
+
+    MessageTransport.channel("__wew_dom").send(JSON.stringify({ id, token, result }));
+})"#;
+
+/// The bounding box of an element
+///
+/// The box is in CSS pixels relative to the viewport, as returned by
+/// `getBoundingClientRect`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Information about an element of the page
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementInfo {
+    /// The tag name in lowercase, such as `div`.
+    pub tag: String,
+    /// The attributes of the element.
+    pub attributes: HashMap<String, String>,
+    /// The bounding box of the element.
+    pub rect: DomRect,
+    /// The rendered text of the element, or its text content if it is not
+    /// rendered.
+    pub text: String,
+}
+
+impl ElementInfo {
+    fn from_value(value: &Value) -> Option<Self> {
+        let rect = value.get("rect")?;
+        let number = |name: &str| rect.get(name).and_then(Value::as_f64).unwrap_or(0.0);
+
+        Some(Self {
+            tag: value.get("tag")?.as_str()?.to_string(),
+            attributes: value
+                .get("attributes")
+                .and_then(Value::as_object)
+                .map(|it| {
+                    it.iter()
+                        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            rect: DomRect {
+                x: number("x"),
+                y: number("y"),
+                width: number("width"),
+                height: number("height"),
+            },
+            text: value
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// The result of a DOM query
+///
+/// Created by **`WebView::query_selector`**,
+/// **`WebView::query_selector_all`** and **`WebView::layout_metrics`**.
+pub struct DomQuery<T>(Arc<Mutex<Slot<T>>>);
+
+impl<T: Send + 'static> DomQuery<T> {
+    /// Create a pending query and the function that resolves it.
+    pub(crate) fn pending() -> (Self, impl FnOnce(T) + Send + 'static) {
+        let query = Self(Arc::new(Mutex::new(Slot {
+            value: None,
+            waker: None,
+        })));
+
+        let slot = query.0.clone();
+        let resolve = move |value| {
+            let mut slot = slot.lock();
+            slot.value = Some(value);
+
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        };
+
+        (query, resolve)
+    }
+}
+
+impl<T> DomQuery<T> {
+    pub(crate) fn resolved(value: T) -> Self {
+        Self(Arc::new(Mutex::new(Slot {
+            value: Some(value),
+            waker: None,
+        })))
+    }
+}
+
+impl<T> Future for DomQuery<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.0.lock();
+        if let Some(value) = slot.value.take() {
+            return Poll::Ready(value);
+        }
+
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+type Resolver = Box<dyn FnOnce(Option<&Value>) + Send>;
+
+/// Create a token that cannot be guessed by the page.
+fn create_token() -> String {
+    let mut token = String::with_capacity(32);
+    for _ in 0..2 {
+        // Every `RandomState` is seeded with new random keys.
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+        );
+
+        token.push_str(&format!("{:016x}", hasher.finish()));
+    }
+
+    token
+}
+
+/// The pending queries of a webview, the answers of the page are received on
+/// the reserved channel.
+pub(crate) struct DomQueries {
+    id: AtomicU64,
+    // `None` once the webview is closed, every query has its token.
+    pending: Mutex<Option<HashMap<u64, (String, Resolver)>>>,
+}
+
+impl Default for DomQueries {
+    fn default() -> Self {
+        Self {
+            id: AtomicU64::new(0),
+            pending: Mutex::new(Some(HashMap::new())),
+        }
+    }
+}
+
+impl DomQueries {
+    /// Register a query, the returned script runs it in the page.
+    ///
+    /// `None` is returned if the webview is closed, the query is then resolved
+    /// immediately with the default value.
+    pub(crate) fn query<T, F>(
+        self: &Arc<Self>,
+        selector: &str,
+        all: bool,
+        parse: F,
+    ) -> (DomQuery<T>, Option<String>)
+    where
+        T: Default + Send + 'static,
+        F: FnOnce(&Value) -> T + Send + 'static,
+    {
+        let mut guard = self.pending.lock();
+        let Some(pending) = guard.as_mut() else {
+            return (DomQuery::resolved(T::default()), None);
+        };
+
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let (query, resolve) = DomQuery::pending();
+
+        let token = create_token();
+        pending.insert(
+            id,
+            (
+                token.clone(),
+                Box::new(move |value| resolve(value.map(parse).unwrap_or_default())),
+            ),
+        );
+
+        drop(guard);
+
+        // The query is resolved with nothing if the page does not answer, the
+        // script may never run or its answer may be lost.
+        let queries = Arc::downgrade(self);
+        let expire = move || {
+            if let Some(queries) = queries.upgrade() {
+                queries.expire(id);
+            }
+        };
+
+        if !post_main_delayed(QUERY_TIMEOUT, expire) {
+            self.expire(id);
+        }
+
+        let script = format!(
+            "{}({}, \"{}\", {}, {});",
+            DOM_HELPER_SCRIPT,
+            id,
+            token,
+            serde_json::to_string(selector).unwrap(),
+            all
+        );
+
+        (query, Some(script))
+    }
+
+    /// Resolve a query with the default value if it is still pending.
+    fn expire(&self, id: u64) {
+        let resolve = self
+            .pending
+            .lock()
+            .as_mut()
+            .and_then(|pending| pending.remove(&id))
+            .map(|(_, resolve)| resolve);
+
+        if let Some(resolve) = resolve {
+            resolve(None);
+        }
+    }
+
+    /// Resolve all pending queries with the default value, the document that
+    /// runs them is gone, such as after a navigation of the main frame.
+    pub(crate) fn reset(&self) {
+        let pending = self
+            .pending
+            .lock()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+
+        for (_, (_, resolve)) in pending {
+            resolve(None);
+        }
+    }
+
+    /// Resolve all pending queries with the default value, the page can no
+    /// longer answer them.
+    pub(crate) fn close(&self) {
+        let pending = self.pending.lock().take().unwrap_or_default();
+        for (_, (_, resolve)) in pending {
+            resolve(None);
+        }
+    }
+}
+
+impl ChannelHandler for DomQueries {
+    fn on_message(&self, message: &str, frame: &FrameInfo) {
+        // The helper only runs in the main frame, other frames cannot answer
+        // the queries.
+        if !frame.is_main {
+            return;
+        }
+
+        let Ok(message) = serde_json::from_str::<Value>(message) else {
+            return;
+        };
+
+        let Some(id) = message.get("id").and_then(Value::as_u64) else {
+            return;
+        };
+
+        // Only the helper script knows the token of the query, other messages
+        // on the channel are rejected.
+        let token = message.get("token").and_then(Value::as_str);
+        let resolve = self.pending.lock().as_mut().and_then(|pending| {
+            let (expected, _) = pending.get(&id)?;
+            if Some(expected.as_str()) != token {
+                return None;
+            }
+
+            pending.remove(&id).map(|(_, resolve)| resolve)
+        });
+
+        if let Some(resolve) = resolve {
+            resolve(message.get("result").filter(|it| !it.is_null()));
+        }
+    }
+}
+
+pub(crate) fn parse_element(value: &Value) -> Option<ElementInfo> {
+    ElementInfo::from_value(value)
+}
+
+pub(crate) fn parse_elements(value: &Value) -> Vec<ElementInfo> {
+    value
+        .as_array()
+        .map(|it| it.iter().filter_map(ElementInfo::from_value).collect())
+        .unwrap_or_default()
+}
@@ -26,12 +26,18 @@
 //! This example shows how to use the built-in `winit` event adapter to
 //! automatically handle `WindowEvent`.
 
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
 use bitflags::bitflags;
 
 /// Represents a position
 ///
 /// This is mainly used for mouse and touch events
 #[derive(Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -41,6 +47,7 @@ pub struct Position {
 ///
 /// This is mainly used for mouse events
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseButton {
     Left,
     Middle,
@@ -51,6 +58,7 @@ pub enum MouseButton {
 ///
 /// This is mainly used for mouse events
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseEvent {
     /// Click a mouse button
     Click(MouseButton, bool, Option<Position>),
@@ -60,10 +68,46 @@ pub enum MouseEvent {
     Wheel(Position),
 }
 
+/// Represents the data of a drag operation
+///
+/// This is mainly used for drag and drop events
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DragData {
+    /// The paths of the dragged files
+    pub files: Vec<PathBuf>,
+    /// The dragged text
+    pub text: Option<String>,
+    /// The dragged URL
+    pub url: Option<String>,
+}
+
+bitflags! {
+    /// Represents the operations of a drag
+    ///
+    /// This is mainly used for drag and drop events
+    #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct DragOperations: u32 {
+        const None = 0;
+        const Copy = 1;
+        const Link = 2;
+        const Generic = 4;
+        const Private = 8;
+        const Move = 16;
+        const Delete = 32;
+    }
+}
+
 /// Represents an IME event
 ///
 /// This is mainly used for IME events
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IMEAction<'a> {
     Composition(&'a str),
     Pre(&'a str, i32, i32),
@@ -74,6 +118,7 @@ bitflags! {
     ///
     /// This is mainly used for keyboard events
     #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct KeyboardModifiers: u8 {
         const None = 0;
         const Shift = 1;
@@ -95,6 +140,7 @@ impl Default for KeyboardModifiers {
 ///
 /// This is mainly used for keyboard events
 #[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyboardEventType {
     #[default]
     KeyDown,
@@ -106,6 +152,11 @@ pub enum KeyboardEventType {
 ///
 /// This is mainly used for keyboard events
 #[derive(Default, Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct KeyboardEvent {
     /// The type of keyboard event.
     pub ty: KeyboardEventType,
@@ -128,6 +179,197 @@ pub struct KeyboardEvent {
     pub focus_on_editable_field: bool,
 }
 
+/// Represents the state of a touch point
+///
+/// A touch starts with one `Pressed` event, followed by any number of `Moved`
+/// events, and ends with one `Released` or `Cancelled` event.
+#[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TouchEventType {
+    #[default]
+    Pressed,
+    Moved,
+    Released,
+    Cancelled,
+}
+
+/// Represents the device type of a touch event
+#[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerType {
+    #[default]
+    Touch,
+    Mouse,
+    Pen,
+    Eraser,
+    Unknown,
+}
+
+/// Represents a touch event
+///
+/// This is mainly used for touch events
+#[derive(Default, Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct TouchEvent {
+    /// The id of the touch point, it must be unique per touch and must not be
+    /// `-1`. At most 16 concurrent touches are tracked.
+    pub id: i32,
+    /// The state of the touch point.
+    pub ty: TouchEventType,
+    /// The position relative to the view.
+    pub x: f32,
+    pub y: f32,
+    /// The radius in pixels, 0 if not applicable.
+    pub radius_x: f32,
+    pub radius_y: f32,
+    /// The rotation angle in radians, 0 if not applicable.
+    pub rotation_angle: f32,
+    /// The normalized pressure in the range of 0 to 1, 0 if not applicable.
+    pub pressure: f32,
+    /// The modifiers of the touch event.
+    pub modifiers: KeyboardModifiers,
+    /// The device type that caused the event.
+    pub pointer_type: PointerType,
+}
+
+/// Represents an input event
+///
+/// This is mainly used to record and replay the input of a webview, unlike
+/// **`IMEAction`** the IME text is owned, so that the event can be stored and
+/// sent later with **`WebView::input`**.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputEvent {
+    Mouse(MouseEvent),
+    Keyboard(KeyboardEvent),
+    Touch(TouchEvent),
+    /// See **`IMEAction::Composition`**
+    ImeComposition(String),
+    /// See **`IMEAction::Pre`**
+    ImePre(String, i32, i32),
+    /// See **`WebView::drag_enter`**
+    DragEnter(DragData, Position, DragOperations),
+    /// See **`WebView::drag_over`**
+    DragOver(Position, DragOperations),
+    /// See **`WebView::drag_leave`**
+    DragLeave,
+    /// See **`WebView::drop`**
+    Drop(Position),
+}
+
+impl InputEvent {
+    /// Get the IME action of the event
+    ///
+    /// Returns `None` if the event is not an IME event.
+    pub fn as_ime_action(&self) -> Option<IMEAction<'_>> {
+        match self {
+            Self::ImeComposition(it) => Some(IMEAction::Composition(it)),
+            Self::ImePre(it, start, end) => Some(IMEAction::Pre(it, *start, *end)),
+            _ => None,
+        }
+    }
+}
+
+impl From<MouseEvent> for InputEvent {
+    fn from(value: MouseEvent) -> Self {
+        Self::Mouse(value)
+    }
+}
+
+impl From<KeyboardEvent> for InputEvent {
+    fn from(value: KeyboardEvent) -> Self {
+        Self::Keyboard(value)
+    }
+}
+
+impl From<TouchEvent> for InputEvent {
+    fn from(value: TouchEvent) -> Self {
+        Self::Touch(value)
+    }
+}
+
+impl From<IMEAction<'_>> for InputEvent {
+    fn from(value: IMEAction<'_>) -> Self {
+        match value {
+            IMEAction::Composition(it) => Self::ImeComposition(it.to_string()),
+            IMEAction::Pre(it, start, end) => Self::ImePre(it.to_string(), start, end),
+        }
+    }
+}
+
+/// Represents a recorded input event
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedInputEvent {
+    /// The time since the recording was started.
+    pub timestamp: Duration,
+    /// The input event.
+    pub event: InputEvent,
+}
+
+/// Input event recorder
+///
+/// The recorder timestamps the events relative to its creation, the recorded
+/// session can be serialized with the `serde` feature and replayed with
+/// **`replay::Recording::replay`**.
+///
+/// ```no_run
+/// use wew::events::{InputRecorder, MouseEvent, Position};
+///
+/// let mut recorder = InputRecorder::new();
+/// recorder.record(MouseEvent::Move(Position { x: 10, y: 20 }));
+///
+/// for it in recorder.events() {
+///     println!("{:?}: {:?}", it.timestamp, it.event);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct InputRecorder {
+    start: Instant,
+    events: Vec<RecordedInputEvent>,
+}
+
+impl InputRecorder {
+    /// Create a recorder, the timestamps start at zero
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Record an input event
+    pub fn record<T>(&mut self, event: T)
+    where
+        T: Into<InputEvent>,
+    {
+        self.events.push(RecordedInputEvent {
+            timestamp: self.start.elapsed(),
+            event: event.into(),
+        });
+    }
+
+    /// Get the recorded events in the order they were recorded
+    pub fn events(&self) -> &[RecordedInputEvent] {
+        &self.events
+    }
+
+    /// Stop the recording and take the recorded events
+    pub fn into_events(self) -> Vec<RecordedInputEvent> {
+        self.events
+    }
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Adapter that automatically handles various external system window events
 ///
 /// Used to adapt window events from various external systems, and the adapter
@@ -139,10 +381,10 @@ pub struct EventAdapter {
     allow_ime: bool,
 }
 
-#[cfg(feature = "winit")]
+#[cfg(all(feature = "winit", feature = "windowless"))]
 mod winit_impl {
     use winit::{
-        event::{Ime, MouseButton as WinitMouseButton, MouseScrollDelta, WindowEvent},
+        event::{Ime, MouseButton as WinitMouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
         keyboard::{Key, KeyCode, ModifiersState, PhysicalKey},
         platform::{
             modifier_supplement::KeyEventExtModifierSupplement, scancode::PhysicalKeyExtScancode,
@@ -153,7 +395,7 @@ mod winit_impl {
         WindowlessRenderWebView,
         events::{
             EventAdapter, IMEAction, KeyboardEvent, KeyboardEventType, KeyboardModifiers,
-            MouseButton, MouseEvent, Position,
+            MouseButton, MouseEvent, Position, TouchEvent, TouchEventType,
         },
         webview::WebView,
     };
@@ -201,13 +443,19 @@ mod winit_impl {
             webview: &WebView<WindowlessRenderWebView>,
             event: &WindowEvent,
         ) {
+            // The input methods fail once the webview is closed, there is nothing
+            // left to forward the events to.
+            if webview.is_closed() {
+                return;
+            }
+
             match event {
                 WindowEvent::Ime(ime) => match ime {
                     Ime::Commit(composition) => {
-                        webview.ime(&IMEAction::Composition(composition));
+                        let _ = webview.ime(&IMEAction::Composition(composition));
                     }
                     Ime::Preedit(preedit, Some((cursor_pos, selection_start))) => {
-                        webview.ime(&IMEAction::Pre(
+                        let _ = webview.ime(&IMEAction::Pre(
                             preedit,
                             *cursor_pos as i32,
                             *selection_start as i32,
@@ -231,11 +479,11 @@ mod winit_impl {
                                 focus_on_editable_field: false,
                             };
 
-                            webview.keyboard(&event);
+                            let _ = webview.keyboard(&event);
 
                             event.ty = KeyboardEventType::KeyUp;
 
-                            webview.keyboard(&event);
+                            let _ = webview.keyboard(&event);
                         }
                     }
                     Ime::Disabled => {
@@ -318,7 +566,7 @@ mod winit_impl {
 
                         event.modifiers = self.modifiers;
 
-                        webview.keyboard(&event);
+                        let _ = webview.keyboard(&event);
 
                         // On Windows, only non-control keys can send char events.
                         if input.state.is_pressed() && is_char(&key_code) {
@@ -346,7 +594,7 @@ mod winit_impl {
 
                             event.ty = KeyboardEventType::Char;
 
-                            webview.keyboard(&event);
+                            let _ = webview.keyboard(&event);
                         }
                     } else {
                         event.modifiers = self.modifiers;
@@ -377,23 +625,50 @@ mod winit_impl {
                             }
                         }
 
-                        if cfg!(target_os = "linux") {
-                            if key_code == KeyCode::Backspace || key_code == KeyCode::Enter {
-                                event.windows_key_code = event.character as u32;
+                        // On Linux, CEF expects the X11 key code and the Windows virtual key
+                        // code. The scancode of winit is the evdev code, X11 key codes are the
+                        // evdev codes offset by 8. The characters are already resolved by winit
+                        // through the XKB keymap of the seat.
+                        #[cfg(target_os = "linux")]
+                        {
+                            if event.native_key_code != 0 {
+                                event.native_key_code += 8;
+                            }
+
+                            event.windows_key_code = get_windows_key_code(&key_code);
+
+                            if let Some(character) = get_control_character(&key_code) {
+                                event.character = character;
+                                event.unmodified_character = character;
+                            } else if event.modifiers.contains(KeyboardModifiers::Ctrl)
+                                && (0x41..=0x5A).contains(&event.windows_key_code)
+                            {
+                                // Ctrl with a letter produces the ASCII control character of
+                                // the letter, like it does on Windows.
+                                event.character = (event.windows_key_code - 0x40) as u16;
+                                event.unmodified_character = event.character;
+                            } else if let Some(character) = match input.key_without_modifiers() {
+                                Key::Character(text) => text.chars().next(),
+                                _ => None,
+                            } {
+                                event.unmodified_character = character as u16;
                             }
                         }
 
-                        webview.keyboard(&event);
+                        let _ = webview.keyboard(&event);
 
-                        if input.state.is_pressed() {
+                        // On Linux, only keys that produce a character can send char events.
+                        if input.state.is_pressed()
+                            && (cfg!(not(target_os = "linux")) || event.character != 0)
+                        {
                             event.ty = KeyboardEventType::Char;
 
-                            webview.keyboard(&event);
+                            let _ = webview.keyboard(&event);
                         }
                     }
                 }
                 WindowEvent::MouseInput { state, button, .. } => {
-                    webview.mouse(&MouseEvent::Click(
+                    let _ = webview.mouse(&MouseEvent::Click(
                         MouseButton::from(*button),
                         state.is_pressed(),
                         None,
@@ -405,14 +680,30 @@ mod winit_impl {
                         MouseScrollDelta::LineDelta(x, y) => ((x * 20.0) as i32, (y * 20.0) as i32),
                     };
 
-                    webview.mouse(&MouseEvent::Wheel(Position { x, y }));
+                    let _ = webview.mouse(&MouseEvent::Wheel(Position { x, y }));
                 }
                 WindowEvent::CursorMoved { position, .. } => {
-                    webview.mouse(&MouseEvent::Move(Position {
+                    let _ = webview.mouse(&MouseEvent::Move(Position {
                         x: position.x as i32,
                         y: position.y as i32,
                     }));
                 }
+                WindowEvent::Touch(touch) => {
+                    let _ = webview.touch(&TouchEvent {
+                        id: touch.id as i32,
+                        ty: match touch.phase {
+                            TouchPhase::Started => TouchEventType::Pressed,
+                            TouchPhase::Moved => TouchEventType::Moved,
+                            TouchPhase::Ended => TouchEventType::Released,
+                            TouchPhase::Cancelled => TouchEventType::Cancelled,
+                        },
+                        x: touch.location.x as f32,
+                        y: touch.location.y as f32,
+                        pressure: touch.force.map(|it| it.normalized() as f32).unwrap_or(0.0),
+                        modifiers: self.modifiers,
+                        ..Default::default()
+                    });
+                }
                 WindowEvent::Focused(state) => {
                     webview.focus(*state);
 
@@ -490,6 +781,134 @@ mod winit_impl {
         .map(|(_, v)| v)
     }
 
+    /// Get the control character of the key
+    ///
+    /// These keys do not produce a text, but CEF expects the control character
+    /// as the character of the key event.
+    #[inline]
+    #[cfg(target_os = "linux")]
+    fn get_control_character(code: &KeyCode) -> Option<u16> {
+        Some(match code {
+            KeyCode::Backspace => 0x08,
+            KeyCode::Tab => 0x09,
+            KeyCode::Enter | KeyCode::NumpadEnter => 0x0D,
+            KeyCode::Escape => 0x1B,
+            _ => return None,
+        })
+    }
+
+    /// Get the Windows virtual key code of the key
+    ///
+    /// CEF uses the Windows virtual key codes on all platforms, on Windows they
+    /// are provided by the system, this table is used where they are not.
+    #[cfg(target_os = "linux")]
+    fn get_windows_key_code(code: &KeyCode) -> u32 {
+        match code {
+            KeyCode::Backspace => 0x08,
+            KeyCode::Tab => 0x09,
+            KeyCode::Enter | KeyCode::NumpadEnter => 0x0D,
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => 0x10,
+            KeyCode::ControlLeft | KeyCode::ControlRight => 0x11,
+            KeyCode::AltLeft | KeyCode::AltRight => 0x12,
+            KeyCode::Pause => 0x13,
+            KeyCode::CapsLock => 0x14,
+            KeyCode::Escape => 0x1B,
+            KeyCode::Space => 0x20,
+            KeyCode::PageUp => 0x21,
+            KeyCode::PageDown => 0x22,
+            KeyCode::End => 0x23,
+            KeyCode::Home => 0x24,
+            KeyCode::ArrowLeft => 0x25,
+            KeyCode::ArrowUp => 0x26,
+            KeyCode::ArrowRight => 0x27,
+            KeyCode::ArrowDown => 0x28,
+            KeyCode::PrintScreen => 0x2C,
+            KeyCode::Insert => 0x2D,
+            KeyCode::Delete => 0x2E,
+            KeyCode::Digit0 => 0x30,
+            KeyCode::Digit1 => 0x31,
+            KeyCode::Digit2 => 0x32,
+            KeyCode::Digit3 => 0x33,
+            KeyCode::Digit4 => 0x34,
+            KeyCode::Digit5 => 0x35,
+            KeyCode::Digit6 => 0x36,
+            KeyCode::Digit7 => 0x37,
+            KeyCode::Digit8 => 0x38,
+            KeyCode::Digit9 => 0x39,
+            KeyCode::KeyA => 0x41,
+            KeyCode::KeyB => 0x42,
+            KeyCode::KeyC => 0x43,
+            KeyCode::KeyD => 0x44,
+            KeyCode::KeyE => 0x45,
+            KeyCode::KeyF => 0x46,
+            KeyCode::KeyG => 0x47,
+            KeyCode::KeyH => 0x48,
+            KeyCode::KeyI => 0x49,
+            KeyCode::KeyJ => 0x4A,
+            KeyCode::KeyK => 0x4B,
+            KeyCode::KeyL => 0x4C,
+            KeyCode::KeyM => 0x4D,
+            KeyCode::KeyN => 0x4E,
+            KeyCode::KeyO => 0x4F,
+            KeyCode::KeyP => 0x50,
+            KeyCode::KeyQ => 0x51,
+            KeyCode::KeyR => 0x52,
+            KeyCode::KeyS => 0x53,
+            KeyCode::KeyT => 0x54,
+            KeyCode::KeyU => 0x55,
+            KeyCode::KeyV => 0x56,
+            KeyCode::KeyW => 0x57,
+            KeyCode::KeyX => 0x58,
+            KeyCode::KeyY => 0x59,
+            KeyCode::KeyZ => 0x5A,
+            KeyCode::SuperLeft => 0x5B,
+            KeyCode::SuperRight => 0x5C,
+            KeyCode::ContextMenu => 0x5D,
+            KeyCode::Numpad0 => 0x60,
+            KeyCode::Numpad1 => 0x61,
+            KeyCode::Numpad2 => 0x62,
+            KeyCode::Numpad3 => 0x63,
+            KeyCode::Numpad4 => 0x64,
+            KeyCode::Numpad5 => 0x65,
+            KeyCode::Numpad6 => 0x66,
+            KeyCode::Numpad7 => 0x67,
+            KeyCode::Numpad8 => 0x68,
+            KeyCode::Numpad9 => 0x69,
+            KeyCode::NumpadMultiply => 0x6A,
+            KeyCode::NumpadAdd => 0x6B,
+            KeyCode::NumpadSubtract => 0x6D,
+            KeyCode::NumpadDecimal => 0x6E,
+            KeyCode::NumpadDivide => 0x6F,
+            KeyCode::F1 => 0x70,
+            KeyCode::F2 => 0x71,
+            KeyCode::F3 => 0x72,
+            KeyCode::F4 => 0x73,
+            KeyCode::F5 => 0x74,
+            KeyCode::F6 => 0x75,
+            KeyCode::F7 => 0x76,
+            KeyCode::F8 => 0x77,
+            KeyCode::F9 => 0x78,
+            KeyCode::F10 => 0x79,
+            KeyCode::F11 => 0x7A,
+            KeyCode::F12 => 0x7B,
+            KeyCode::NumLock => 0x90,
+            KeyCode::ScrollLock => 0x91,
+            KeyCode::Semicolon => 0xBA,
+            KeyCode::Equal => 0xBB,
+            KeyCode::Comma => 0xBC,
+            KeyCode::Minus => 0xBD,
+            KeyCode::Period => 0xBE,
+            KeyCode::Slash => 0xBF,
+            KeyCode::Backquote => 0xC0,
+            KeyCode::BracketLeft => 0xDB,
+            KeyCode::Backslash => 0xDC,
+            KeyCode::BracketRight => 0xDD,
+            KeyCode::Quote => 0xDE,
+            KeyCode::IntlBackslash => 0xE2,
+            _ => 0,
+        }
+    }
+
     #[inline]
     fn is_char(code: &KeyCode) -> bool {
         [
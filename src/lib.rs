@@ -29,8 +29,8 @@
 //!
 //! use wew::{
 //!     MainThreadMessageLoop, MessageLoopAbstract, NativeWindowWebView,
-//!     runtime::{LogLevel, RuntimeHandler},
-//!     webview::{WebViewAttributes, WebViewHandler, WebViewState},
+//!     runtime::{LogSeverity, RuntimeHandler},
+//!     webview::{WebViewAttributes, WebViewHandler, WebViewId, WebViewState},
 //! };
 //!
 //! struct RuntimeObserver {
@@ -46,7 +46,7 @@
 //! struct WebViewObserver;
 //!
 //! impl WebViewHandler for WebViewObserver {
-//!     fn on_state_change(&self, state: WebViewState) {
+//!     fn on_state_change(&self, _id: WebViewId, state: WebViewState) {
 //!         if state == WebViewState::Close {
 //!             std::process::exit(0);
 //!         }
@@ -72,7 +72,7 @@
 //!         // Set cache path, here we use environment variables passed by the build script.
 //!         .with_root_cache_path(option_env!("CACHE_PATH").unwrap())
 //!         .with_cache_path(option_env!("CACHE_PATH").unwrap())
-//!         .with_log_severity(LogLevel::Info);
+//!         .with_log_severity(LogSeverity::Info);
 //!
 //!     let (tx, rx) = channel();
 //!
@@ -80,6 +80,7 @@
 //!     // before considering the creation successful.
 //!     let runtime = runtime_attributes_builder
 //!         .build()
+//!         .unwrap()
 //!         .create_runtime(RuntimeObserver { tx })
 //!         .unwrap();
 //!
@@ -109,15 +110,26 @@
 )]
 #![allow(clippy::needless_doctest_main)]
 
+pub mod crash_reporter;
+pub mod dispatcher;
+pub mod dom;
 pub mod events;
+pub mod metrics;
+pub mod platform;
+#[cfg(feature = "windowless")]
+pub mod replay;
+#[cfg(feature = "request")]
 pub mod request;
 pub mod runtime;
+pub mod screencast;
+pub mod stream;
+pub mod subprocess;
 pub mod utils;
 pub mod webview;
 
-use std::sync::atomic::Ordering;
+use std::ptr::null;
 
-use self::runtime::{RUNTIME_RUNNING, RuntimeAttributesBuilder};
+use self::runtime::{RUNTIME_LIFECYCLE, RuntimeAttributesBuilder};
 
 #[cfg(feature = "winit")]
 pub use winit;
@@ -135,23 +147,225 @@ mod sys {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+/// An operation that can fail with an **`Error::Failed`**
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Loading CEF and creating the runtime.
+    CreateRuntime,
+    /// Creating the cache directory of the runtime before CEF is initialized.
+    CreateCacheDirectory,
+    /// Initializing CEF, the reason is reported in **`Error::Failed::code`**.
+    InitializeRuntime,
+    /// Creating the browser of a webview.
+    CreateWebView,
+}
+
+/// The result code reported by CEF when it failed to initialize
+///
+/// Only the codes that an application can react to have their own variant,
+/// all other codes are reported as **`ResultCode::Other`**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultCode {
+    /// The resources or the locales of CEF are missing.
+    MissingData,
+    /// A setting or a command line switch is not supported.
+    UnsupportedParam,
+    /// The cache directory is already in use by another process.
+    ProfileInUse,
+    /// The cache directory is in use by another process, which has been
+    /// notified to open a new window instead.
+    NormalExitProcessNotified,
+    /// The sandbox could not be initialized.
+    InvalidSandboxState,
+    /// The system resources, such as file handles, are exhausted.
+    SystemResourceExhausted,
+    /// A result code that has no variant, the value is the raw code returned
+    /// by CEF.
+    Other(i32),
+}
+
+impl From<i32> for ResultCode {
+    fn from(value: i32) -> Self {
+        match value {
+            7 => Self::MissingData,
+            13 => Self::UnsupportedParam,
+            21 => Self::ProfileInUse,
+            24 => Self::NormalExitProcessNotified,
+            31 => Self::InvalidSandboxState,
+            37 => Self::SystemResourceExhausted,
+            code => Self::Other(code),
+        }
+    }
+}
+
+impl From<ResultCode> for i32 {
+    fn from(value: ResultCode) -> Self {
+        match value {
+            ResultCode::MissingData => 7,
+            ResultCode::UnsupportedParam => 13,
+            ResultCode::ProfileInUse => 21,
+            ResultCode::NormalExitProcessNotified => 24,
+            ResultCode::InvalidSandboxState => 31,
+            ResultCode::SystemResourceExhausted => 37,
+            ResultCode::Other(code) => code,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     /// The current thread is not the main thread.
     NonUIThread,
-    FailedToCreateRuntime,
+    /// An operation failed.
+    ///
+    /// `code` is the result code reported by CEF and `os_error` is the error
+    /// of the operating system, each of them is only set if the operation
+    /// reports it.
+    Failed {
+        operation: Operation,
+        code: Option<ResultCode>,
+        os_error: Option<std::io::Error>,
+    },
     /// Only one runtime can be created in a process. Repeated creation will
     /// trigger this error.
     RuntimeAlreadyExists,
+    /// CEF cannot be initialized again after a runtime has been created in the
+    /// process, even if that runtime has been dropped or shut down.
+    RuntimeCannotBeRecreated,
     /// If the runtime is not initialized, creating WebView and other operations
     /// will trigger this error.
     RuntimeNotInitialization,
-    FailedToCreateWebView,
+    /// The webview has been closed, such as by the web page calling
+    /// `window.close()`, and can no longer receive input.
+    WebViewClosed,
+    /// The browser of the webview no longer exists, its window and its page
+    /// are not available, such as while the browser is being destroyed.
+    WindowNotCreated,
+    /// The webview has no native window that can be represented by a
+    /// **`RawWindowHandle`**, such as when Chromium runs on the Wayland
+    /// platform, which does not expose its surfaces.
+    NoNativeWindow,
+    /// An attribute has an invalid value or conflicts with another attribute.
+    ///
+    /// `name` is the name of the attribute field and `reason` describes why it
+    /// was rejected.
+    InvalidAttribute {
+        name: &'static str,
+        reason: &'static str,
+    },
+    /// An argument of a function has an invalid value.
+    ///
+    /// `name` is the name of the argument and `reason` describes why it was
+    /// rejected.
+    InvalidArgument {
+        name: &'static str,
+        reason: &'static str,
+    },
+    /// The operation is not allowed by a restriction of the webview, such as
+    /// its kiosk policy.
+    ///
+    /// `reason` describes the restriction that rejected it.
+    NotAllowed { reason: &'static str },
 }
 
-impl std::error::Error for Error {}
+impl Error {
+    /// Get the failed operation
+    ///
+    /// Returns `None` if the error is not an **`Error::Failed`**.
+    pub fn operation(&self) -> Option<Operation> {
+        match self {
+            Self::Failed { operation, .. } => Some(*operation),
+            _ => None,
+        }
+    }
+
+    /// Get the result code reported by CEF
+    pub fn code(&self) -> Option<ResultCode> {
+        match self {
+            Self::Failed { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// Get the error of the operating system
+    pub fn os_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::Failed { os_error, .. } => os_error.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.os_error().map(|it| it as _)
+    }
+}
 
 impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonUIThread => write!(f, "the current thread is not the main thread"),
+            Self::RuntimeAlreadyExists => write!(f, "the runtime already exists"),
+            Self::RuntimeCannotBeRecreated => {
+                write!(f, "the runtime cannot be created again in this process")
+            }
+            Self::RuntimeNotInitialization => write!(f, "the runtime is not initialized"),
+            Self::WebViewClosed => write!(f, "the webview has been closed"),
+            Self::WindowNotCreated => write!(f, "the window of the webview does not exist"),
+            Self::NoNativeWindow => write!(f, "the webview has no native window"),
+            Self::InvalidAttribute { name, reason } => {
+                write!(f, "invalid attribute `{}`: {}", name, reason)
+            }
+            Self::InvalidArgument { name, reason } => {
+                write!(f, "invalid argument `{}`: {}", name, reason)
+            }
+            Self::NotAllowed { reason } => write!(f, "the operation is not allowed: {}", reason),
+            Self::Failed {
+                operation,
+                code,
+                os_error,
+            } => {
+                write!(
+                    f,
+                    "failed to {}",
+                    match operation {
+                        Operation::CreateRuntime => "create the runtime",
+                        Operation::CreateCacheDirectory => "create the cache directory",
+                        Operation::InitializeRuntime => "initialize the runtime",
+                        Operation::CreateWebView => "create the webview",
+                    }
+                )?;
+
+                if let Some(code) = code {
+                    write!(f, ", result code: {:?}", code)?;
+                }
+
+                if let Some(error) = os_error {
+                    write!(f, ", {}", error)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShutdownError {
+    /// The current thread is not the main thread.
+    NonUIThread,
+    /// The multi-threaded message loop cannot be shut down explicitly, the
+    /// runtime is shut down when the process exits.
+    MultiThreadedMessageLoop,
+    /// Some browsers were not destroyed within the timeout, the runtime is not
+    /// shut down.
+    Timeout,
+}
+
+impl std::error::Error for ShutdownError {}
+
+impl std::fmt::Display for ShutdownError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
@@ -159,6 +373,7 @@ impl std::fmt::Display for Error {
 
 /// Represents a rectangular area
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pub x: u32,
     pub y: u32,
@@ -213,13 +428,14 @@ impl MainThreadMessageLoop {
     /// This function is used to run the message loop on main thread.
     ///
     /// Note that this function will block the current thread until the message
-    /// loop ends.
+    /// loop ends. It returns immediately if it is called from inside a callback
+    /// of the runtime, the message loop cannot be run re-entrantly.
     pub fn block_run(&self) {
         if !utils::is_main_thread() {
             panic!("this operation is not allowed in non-main threads!");
         }
 
-        unsafe { sys::run_message_loop() }
+        utils::drive_message_loop(|| unsafe { sys::run_message_loop() });
     }
 
     /// Quit the message loop on main thread
@@ -249,14 +465,16 @@ impl MessagePumpLoop {
     /// This function is used to poll the message loop on main thread.
     ///
     /// Note that this function won't block the current thread, external code
-    /// needs to drive the message loop pump.
+    /// needs to drive the message loop pump. Calling it from inside a callback
+    /// of the runtime does nothing, the message loop cannot be driven
+    /// re-entrantly.
     pub fn poll(&self) {
         if !utils::is_main_thread() {
             panic!("this operation is not allowed in non-main threads!");
         }
 
-        if RUNTIME_RUNNING.load(Ordering::Relaxed) {
-            unsafe { sys::poll_message_loop() }
+        if RUNTIME_LIFECYCLE.is_running() {
+            utils::drive_message_loop(|| unsafe { sys::poll_message_loop() });
         }
     }
 }
@@ -273,9 +491,13 @@ pub trait WebViewAbstract: Default {}
 /// **`WindowlessRenderWebViewHandler::on_frame`**, and you can handle the video
 /// frames yourself. Also, in this mode, mouse and keyboard events need to be
 /// passed to the WebView by yourself.
+///
+/// This mode is only available with the `windowless` feature.
+#[cfg(feature = "windowless")]
 #[derive(Default, Clone, Copy)]
 pub struct WindowlessRenderWebView;
 
+#[cfg(feature = "windowless")]
 impl WebViewAbstract for WindowlessRenderWebView {}
 
 /// Native window mode
@@ -291,6 +513,10 @@ impl WebViewAbstract for NativeWindowWebView {}
 ///
 /// This method is used to start a subprocess in a separate process.
 ///
+/// To hook into the JavaScript contexts of the render process, use
+/// **`subprocess::execute_subprocess_with_handler`** or **`subprocess::run`**
+/// instead.
+///
 /// ## Examples
 ///
 /// ```no_run
@@ -307,13 +533,96 @@ impl WebViewAbstract for NativeWindowWebView {}
 ///
 /// Do not call this function in an asynchronous runtime, such as tokio,
 /// which can lead to unexpected crashes!
-pub fn execute_subprocess() -> bool {
+pub fn execute_subprocess() -> SubProcessExit {
     if !utils::is_main_thread() {
         panic!("this operation is not allowed in non-main threads!");
     }
 
     let args = utils::Args::default();
-    (unsafe { sys::execute_subprocess(args.size() as _, args.as_ptr() as _) }) == 0
+    SubProcessExit::new(unsafe {
+        sys::execute_subprocess(args.size() as _, args.as_ptr() as _, null())
+    })
+}
+
+/// Execute subprocess and exit
+///
+/// This method is the same as **`execute_subprocess`**, except that the
+/// process exits with the exit code of the subprocess once it is finished.
+///
+/// ```no_run
+/// // Run subprocess in a separate executable file
+/// fn main() {
+///     wew::execute_subprocess_and_exit();
+/// }
+/// ```
+pub fn execute_subprocess_and_exit() -> ! {
+    std::process::exit(execute_subprocess().exit_code)
+}
+
+/// The result of a subprocess
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubProcessExit {
+    /// The type of the subprocess.
+    pub process_type: ProcessType,
+    /// The exit code returned by CEF, it is negative if the current process is
+    /// not a subprocess or the subprocess could not be started.
+    pub exit_code: i32,
+}
+
+impl SubProcessExit {
+    fn new(exit_code: i32) -> Self {
+        Self {
+            process_type: process_type(),
+            exit_code,
+        }
+    }
+
+    /// Whether the subprocess exited successfully
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Represents the type of a process
+///
+/// The type is passed by Chromium to the subprocesses with the `--type`
+/// command line switch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProcessType {
+    /// The main process of the application.
+    Browser,
+    Renderer,
+    Gpu,
+    Utility,
+    /// The zygote process, only used on Linux.
+    Zygote,
+    /// Other subprocesses, such as the crashpad handler.
+    Other(String),
+}
+
+impl ProcessType {
+    fn from_switch(value: &str) -> Self {
+        match value {
+            "renderer" => Self::Renderer,
+            "gpu-process" => Self::Gpu,
+            "utility" => Self::Utility,
+            "zygote" => Self::Zygote,
+            _ => Self::Other(value.to_string()),
+        }
+    }
+}
+
+/// Get the type of the current process
+///
+/// This function is used to get the type of the current process from the
+/// `--type=<value>` switch of the command line, the switch must match exactly,
+/// so switches of the application such as `--type` or `--typeface=` are not
+/// mistaken for it.
+pub fn process_type() -> ProcessType {
+    std::env::args()
+        .skip(1)
+        .find_map(|it| it.strip_prefix("--type=").map(ProcessType::from_switch))
+        .unwrap_or(ProcessType::Browser)
 }
 
 /// Check if current process is a subprocess
@@ -323,5 +632,5 @@ pub fn execute_subprocess() -> bool {
 /// Note that if the current process is a subprocess, it will block until the
 /// subprocess exits.
 pub fn is_subprocess() -> bool {
-    std::env::args().any(|it| it.contains("--type="))
+    process_type() != ProcessType::Browser
 }
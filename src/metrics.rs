@@ -0,0 +1,155 @@
+//! This module is used to monitor the rendering of a webview.
+//!
+//! Metrics are only collected if **`WebViewAttributes::metrics`** is enabled,
+//! and can be queried at any time with **`WebView::metrics`**.
+//!
+//! ```no_run
+//! use wew::{WindowlessRenderWebView, webview::WebView};
+//!
+//! fn report(webview: &WebView<WindowlessRenderWebView>) {
+//!     if let Some(metrics) = webview.metrics() {
+//!         println!(
+//!             "frames: {}, dropped: {}, paint latency: {:?}",
+//!             metrics.frames_delivered,
+//!             metrics.dropped_frames,
+//!             metrics.average_frame_callback_latency
+//!         );
+//!     }
+//! }
+//! ```
+//!
+//! Frames are only delivered in windowless rendering mode, the frame metrics
+//! of a native window webview are always zero.
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// A snapshot of the metrics of a webview
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of frames delivered to
+    /// **`WindowlessRenderWebViewHandler::on_frame`**
+    pub frames_delivered: u64,
+    /// The number of frames whose handler took longer than the frame interval
+    /// of **`WebViewAttributes::windowless_frame_rate`**
+    ///
+    /// Frames are delivered on the UI thread, so each of these frames delayed
+    /// the following frames and caused at least one of them to be skipped.
+    pub dropped_frames: u64,
+    /// The average size of the delivered frames in bytes
+    pub average_paint_size: u64,
+    /// The average time spent in **`WindowlessRenderWebViewHandler::on_frame`**
+    pub average_frame_callback_latency: Duration,
+    /// The longest time spent in **`WindowlessRenderWebViewHandler::on_frame`**
+    pub max_frame_callback_latency: Duration,
+    /// The number of messages delivered to the handler or channels
+    pub messages_delivered: u64,
+    /// The average time spent in the message handlers
+    pub average_message_callback_latency: Duration,
+    /// The longest time spent in the message handlers
+    pub max_message_callback_latency: Duration,
+}
+
+#[derive(Default)]
+struct Latency {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Latency {
+    fn record(&mut self, value: Duration) {
+        self.count += 1;
+        self.total += value;
+        self.max = self.max.max(value);
+    }
+
+    fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    frame_interval: Duration,
+    frames: Latency,
+    messages: Latency,
+    dropped_frames: u64,
+    paint_size: u64,
+}
+
+pub(crate) struct MetricsCollector {
+    counters: Mutex<Counters>,
+}
+
+impl MetricsCollector {
+    pub(crate) fn new(frame_rate: u32) -> Self {
+        let collector = Self {
+            counters: Mutex::new(Counters::default()),
+        };
+
+        collector.set_frame_rate(frame_rate);
+        collector
+    }
+
+    /// The frame rate can be changed after the webview is created, the frames
+    /// that have already been counted are not affected.
+    pub(crate) fn set_frame_rate(&self, frame_rate: u32) {
+        self.counters.lock().frame_interval = Duration::from_secs(1) / frame_rate.max(1);
+    }
+
+    pub(crate) fn frame<F>(&self, size: usize, handler: F)
+    where
+        F: FnOnce(),
+    {
+        let latency = measure(handler);
+
+        let mut counters = self.counters.lock();
+        counters.frames.record(latency);
+        counters.paint_size += size as u64;
+
+        if latency > counters.frame_interval {
+            counters.dropped_frames += 1;
+        }
+    }
+
+    pub(crate) fn message<F>(&self, handler: F)
+    where
+        F: FnOnce(),
+    {
+        let latency = measure(handler);
+        self.counters.lock().messages.record(latency);
+    }
+
+    pub(crate) fn snapshot(&self) -> Metrics {
+        let counters = self.counters.lock();
+
+        Metrics {
+            frames_delivered: counters.frames.count,
+            dropped_frames: counters.dropped_frames,
+            average_paint_size: counters
+                .paint_size
+                .checked_div(counters.frames.count)
+                .unwrap_or(0),
+            average_frame_callback_latency: counters.frames.average(),
+            max_frame_callback_latency: counters.frames.max,
+            messages_delivered: counters.messages.count,
+            average_message_callback_latency: counters.messages.average(),
+            max_message_callback_latency: counters.messages.max,
+        }
+    }
+}
+
+fn measure<F>(handler: F) -> Duration
+where
+    F: FnOnce(),
+{
+    let time = Instant::now();
+    handler();
+    time.elapsed()
+}
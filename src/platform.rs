@@ -0,0 +1,8 @@
+//! Platform specific integration helpers.
+//!
+//! These helpers are optional, they drive a windowless webview from the native
+//! window of the platform for the cases that the event adapters do not cover,
+//! so they require the **`windowless`** feature.
+
+#[cfg(all(target_os = "windows", feature = "windowless"))]
+pub mod windows;
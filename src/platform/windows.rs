@@ -0,0 +1,138 @@
+//! This module is used to keep a windowless webview in sync with the
+//! per-monitor DPI of Windows.
+//!
+//! In windowless rendering mode, CEF renders at the device scale factor of
+//! the webview, which is `1.0` unless it is set. On a monitor scaled to 150%,
+//! the frames are then rendered at 96 DPI and stretched by the application,
+//! which makes the content blurry. **`DpiHelper`** reads the DPI of the window,
+//! and updates the webview when the window is moved to another monitor.
+//!
+//! ```ignore
+//! use wew::platform::windows::DpiHelper;
+//!
+//! let mut dpi = DpiHelper::new(window_handle).unwrap();
+//! dpi.attach(&webview);
+//!
+//! // In the window procedure.
+//! match message {
+//!     WM_SIZE => dpi.resize(&webview),
+//!     _ => {
+//!         dpi.on_message(&webview, message, wparam);
+//!     }
+//! }
+//! ```
+//!
+//! The window itself is not moved or resized on `WM_DPICHANGED`, the window
+//! procedure should still apply the rectangle suggested by the system.
+
+use raw_window_handle::RawWindowHandle;
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    UI::{
+        HiDpi::GetDpiForWindow,
+        WindowsAndMessaging::{GetClientRect, WM_DPICHANGED},
+    },
+};
+
+use crate::{WindowlessRenderWebView, webview::WebView};
+
+/// The DPI of a monitor with a scale factor of `1.0`
+pub const DEFAULT_DPI: u32 = 96;
+
+/// Get the scale factor of the monitor the window is displayed on
+///
+/// `1.0` is returned if the DPI of the window cannot be read.
+pub fn get_scale_factor(hwnd: HWND) -> f32 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => 1.0,
+        dpi => dpi as f32 / DEFAULT_DPI as f32,
+    }
+}
+
+/// Per-monitor DPI helper of a window
+///
+/// The helper holds the current scale factor of the window, and forwards the
+/// changes to the webview.
+pub struct DpiHelper {
+    hwnd: HWND,
+    scale_factor: f32,
+}
+
+impl DpiHelper {
+    /// Create a helper for a window
+    ///
+    /// `None` is returned if the handle is not a Win32 window handle.
+    pub fn new(handle: RawWindowHandle) -> Option<Self> {
+        let RawWindowHandle::Win32(it) = handle else {
+            return None;
+        };
+
+        let hwnd = HWND(it.hwnd.get() as _);
+
+        Some(Self {
+            hwnd,
+            scale_factor: get_scale_factor(hwnd),
+        })
+    }
+
+    /// Get the current scale factor of the window
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Apply the scale factor and the size of the window to the webview
+    ///
+    /// This function should be called once after the webview is created.
+    pub fn attach(&self, webview: &WebView<WindowlessRenderWebView>) {
+        webview.set_device_scale_factor(self.scale_factor);
+
+        self.resize(webview);
+    }
+
+    /// Resize the webview to the client area of the window
+    ///
+    /// The client area is in physical pixels, the webview is resized in DIPs,
+    /// so this function should be used instead of **`WebView::resize`** on
+    /// `WM_SIZE`.
+    pub fn resize(&self, webview: &WebView<WindowlessRenderWebView>) {
+        let mut rect = RECT::default();
+        if unsafe { GetClientRect(self.hwnd, &mut rect) }.is_err() {
+            return;
+        }
+
+        let width = ((rect.right - rect.left) as f32 / self.scale_factor).round() as u32;
+        let height = ((rect.bottom - rect.top) as f32 / self.scale_factor).round() as u32;
+
+        if width > 0 && height > 0 {
+            webview.resize(width, height);
+        }
+    }
+
+    /// Handle a window message
+    ///
+    /// Returns `true` if the message is `WM_DPICHANGED`, in which case the
+    /// device scale factor of the webview is updated and the webview is
+    /// resized. Other messages are ignored.
+    pub fn on_message(
+        &mut self,
+        webview: &WebView<WindowlessRenderWebView>,
+        message: u32,
+        wparam: usize,
+    ) -> bool {
+        if message != WM_DPICHANGED {
+            return false;
+        }
+
+        // The low word and the high word are the same DPI, windows only have a
+        // single scale.
+        let dpi = (wparam & 0xFFFF) as u32;
+        if dpi == 0 {
+            return true;
+        }
+
+        self.scale_factor = dpi as f32 / DEFAULT_DPI as f32;
+        self.attach(webview);
+
+        true
+    }
+}
@@ -0,0 +1,126 @@
+//! This module is used to record and replay the input of a webview.
+//!
+//! Bugs of the windowless rendering mode often depend on the exact sequence
+//! and timing of the input, which is hard to describe in words. A recording
+//! captures every input sent to a webview, it can be saved with the `serde`
+//! feature and replayed with the original timing against a fresh webview.
+//!
+//! ```no_run
+//! use wew::{WindowlessRenderWebView, replay::Recording, webview::WebView};
+//!
+//! fn record(webview: &WebView<WindowlessRenderWebView>) {
+//!     webview.start_recording();
+//! }
+//!
+//! fn reproduce(
+//!     webview: &WebView<WindowlessRenderWebView>,
+//!     fresh: &WebView<WindowlessRenderWebView>,
+//! ) {
+//!     if let Some(recording) = webview.stop_recording() {
+//!         recording.replay(fresh).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! The input is recorded as it is passed to the input functions of the
+//! webview, including the events forwarded by **`EventAdapter`**, so the
+//! replay makes the same calls in the same order.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Error, WindowlessRenderWebView,
+    events::{InputRecorder, RecordedInputEvent},
+    webview::WebView,
+};
+
+/// A recorded input session
+///
+/// Created by **`WebView::stop_recording`**, or from the events of an
+/// **`InputRecorder`**.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Recording {
+    /// The recorded events in the order they were sent.
+    pub events: Vec<RecordedInputEvent>,
+}
+
+impl Recording {
+    /// Get the duration of the recording
+    ///
+    /// This is the timestamp of the last event.
+    pub fn duration(&self) -> Duration {
+        self.events
+            .last()
+            .map(|it| it.timestamp)
+            .unwrap_or_default()
+    }
+
+    /// Replay the recording
+    ///
+    /// This function is used to send the recorded events to the webview with
+    /// their original timing, it blocks until the last event has been sent.
+    ///
+    /// The input is handled on the UI thread, so this function should be called
+    /// on another thread to not block the message loop. Replaying stops at the
+    /// first event that fails, such as when the webview is closed.
+    pub fn replay(&self, webview: &WebView<WindowlessRenderWebView>) -> Result<(), Error> {
+        self.replay_with_speed(webview, 1.0)
+    }
+
+    /// Replay the recording at a different speed
+    ///
+    /// This function is the same as **`Recording::replay`**, except that the
+    /// delays between the events are divided by `speed`, such as `2.0` to
+    /// replay the recording twice as fast.
+    pub fn replay_with_speed(
+        &self,
+        webview: &WebView<WindowlessRenderWebView>,
+        speed: f64,
+    ) -> Result<(), Error> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(Error::InvalidArgument {
+                name: "speed",
+                reason: "must be a finite number greater than 0",
+            });
+        }
+
+        // The delays are computed before the first event is replayed, a speed
+        // close to 0 makes them too long to be represented.
+        let delays = self
+            .events
+            .iter()
+            .map(|it| Duration::try_from_secs_f64(it.timestamp.as_secs_f64() / speed))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidArgument {
+                name: "speed",
+                reason: "the delays of the recording are too long at this speed",
+            })?;
+
+        let start = Instant::now();
+        for (it, delay) in self.events.iter().zip(delays) {
+            if let Some(remaining) = delay.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+
+            webview.input(&it.event)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<InputRecorder> for Recording {
+    fn from(value: InputRecorder) -> Self {
+        Self {
+            events: value.into_events(),
+        }
+    }
+}
@@ -19,7 +19,8 @@
 //!                 "localhost",
 //!                 CustomRequestHandlerFactory::new(RequestHandlerWithLocalDisk::new("/assets")),
 //!             ))
-//!             .build();
+//!             .build()
+//!             .unwrap();
 //! }
 //! ```
 //!
@@ -35,21 +36,67 @@
 //! In addition to registering custom scheme protocols globally, you can also
 //! use **`request_handler_factory`** in `WebView` to implement custom request
 //! handling.
+//!
+//! ## Mocking requests in tests
+//!
+//! **`WebView::route`** serves canned responses for the URLs that match a
+//! pattern, so the frontend of an application can be tested without a
+//! server. The requests of the webview can be recorded by its **`Router`**:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use wew::{NativeWindowWebView, request::MockResponse, webview::WebView};
+//!
+//! fn mock(webview: &WebView<NativeWindowWebView>) -> Result<(), serde_json::Error> {
+//!     webview.route(
+//!         "https://api.example.com/users/*",
+//!         MockResponse::json(&["alice", "bob"])?.with_delay(Duration::from_millis(200)),
+//!     );
+//!
+//!     // Simulate a network error.
+//!     webview.route("https://api.example.com/upload", MockResponse::error());
+//!
+//!     // Or build the response from the request.
+//!     webview.route("https://api.example.com/echo?*", |request: &wew::request::Request| {
+//!         MockResponse::text(request.url)
+//!     });
+//!
+//!     Ok(())
+//! }
+//!
+//! fn requests(webview: &WebView<NativeWindowWebView>) {
+//!     webview.router().set_recording(true);
+//!
+//!     // ...
+//!
+//!     for it in webview.router().requests() {
+//!         println!("{} {}", it.method, it.url);
+//!     }
+//! }
+//! ```
+//!
+//! The routes are checked before the **`request_handler_factory`** of the
+//! webview, the requests that do not match a route are handled as usual.
 
 use std::{
+    collections::VecDeque,
     ffi::{CStr, CString, c_void},
     fs::File,
     io::{Read, Seek, SeekFrom},
     path::{Path, PathBuf},
     ptr::null_mut,
     sync::Arc,
+    time::Duration,
 };
 
+use parking_lot::Mutex;
+use serde::Serialize;
 use url::Url;
 
 use crate::{
     sys,
-    utils::{GetSharedRef, ThreadSafePointer},
+    utils::{GetSharedRef, ThreadSafePointer, catch_panic},
 };
 
 struct LocalDiskRequestHandler {
@@ -210,6 +257,18 @@ pub trait RequestHandler: Send + Sync {
     /// This method is generally called first.
     fn open(&mut self) -> bool;
 
+    /// Get the delay of the request
+    ///
+    /// This method is used to wait before the request is opened, such as to
+    /// simulate the latency of a server. The request is suspended while it
+    /// waits, the thread that loads the resources is not blocked.
+    ///
+    /// This method is called before the `open` method, the default is no
+    /// delay.
+    fn delay(&self) -> Duration {
+        Duration::ZERO
+    }
+
     /// Get response
     ///
     /// This method is used to get the response. You can return response
@@ -255,6 +314,10 @@ impl RequestHandler for Box<dyn RequestHandler> {
         self.as_mut().open()
     }
 
+    fn delay(&self) -> Duration {
+        self.as_ref().delay()
+    }
+
     fn get_response(&mut self) -> Option<Response> {
         self.as_mut().get_response()
     }
@@ -348,6 +411,10 @@ impl CustomRequestHandlerFactory {
     pub(crate) fn as_raw(&self) -> &ThreadSafePointer<sys::RequestHandlerFactory> {
         &self.0.raw_handler
     }
+
+    fn factory(&self) -> &dyn RequestHandlerFactory {
+        unsafe { &**self.0.raw.as_ptr() }
+    }
 }
 
 impl GetSharedRef for CustomRequestHandlerFactory {
@@ -358,6 +425,350 @@ impl GetSharedRef for CustomRequestHandlerFactory {
     }
 }
 
+/// A canned response of a route
+///
+/// The body is served as is, with the status code and MIME type of the
+/// response.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    /// Response status code
+    pub status_code: u32,
+    /// Response MIME type
+    pub mime_type: String,
+    /// Response body
+    pub body: Vec<u8>,
+    /// The time to wait before the response is served
+    pub delay: Duration,
+    /// Whether the request fails with a network error instead of a response
+    pub failed: bool,
+}
+
+impl MockResponse {
+    /// Create a response
+    pub fn new<T>(status_code: u32, mime_type: &str, body: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        Self {
+            status_code,
+            mime_type: mime_type.to_string(),
+            body: body.into(),
+            delay: Duration::ZERO,
+            failed: false,
+        }
+    }
+
+    /// Create a `200 OK` response with a plain text body
+    pub fn text(body: &str) -> Self {
+        Self::new(200, "text/plain", body)
+    }
+
+    /// Create a `200 OK` response with a JSON body
+    ///
+    /// Returns an error if the value cannot be serialized as JSON.
+    pub fn json<T>(value: &T) -> Result<Self, serde_json::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(Self::new(
+            200,
+            "application/json",
+            serde_json::to_vec(value)?,
+        ))
+    }
+
+    /// Create a response that fails the request with a network error
+    ///
+    /// The page sees the same failure as for an unreachable server, such as
+    /// a rejected `fetch`.
+    pub fn error() -> Self {
+        Self {
+            failed: true,
+            ..Self::new(0, "text/plain", Vec::new())
+        }
+    }
+
+    /// Set the status code
+    pub fn with_status_code(mut self, value: u32) -> Self {
+        self.status_code = value;
+        self
+    }
+
+    /// Set the time to wait before the response is served
+    ///
+    /// This is used to simulate the latency of a server. Only this request
+    /// waits, the other requests of the runtime are loaded as usual while the
+    /// response is pending.
+    pub fn with_delay(mut self, value: Duration) -> Self {
+        self.delay = value;
+        self
+    }
+}
+
+/// The handler of a route
+///
+/// This is implemented for **`MockResponse`**, which serves the same response
+/// for every request, and for closures that create the response from the
+/// request.
+pub trait RouteHandler: Send + Sync {
+    /// Create the response of the request
+    fn respond(&self, request: &Request) -> MockResponse;
+}
+
+impl RouteHandler for MockResponse {
+    fn respond(&self, _: &Request) -> MockResponse {
+        self.clone()
+    }
+}
+
+impl<F> RouteHandler for F
+where
+    F: Fn(&Request) -> MockResponse + Send + Sync,
+{
+    fn respond(&self, request: &Request) -> MockResponse {
+        self(request)
+    }
+}
+
+/// A request recorded by a **`Router`**
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedRequest {
+    /// Request URL
+    pub url: String,
+    /// Request method
+    pub method: String,
+    /// Request referrer
+    pub referrer: String,
+    /// Whether the request was served by a route
+    pub routed: bool,
+}
+
+struct Route {
+    pattern: String,
+    handler: Arc<dyn RouteHandler>,
+}
+
+/// The maximum number of requests kept by a **`Router`**, the oldest requests
+/// are dropped first.
+pub const MAX_RECORDED_REQUESTS: usize = 1000;
+
+#[derive(Default)]
+struct Routes {
+    routes: Vec<Route>,
+    recording: bool,
+    requests: VecDeque<RecordedRequest>,
+    on_enable: Option<Box<dyn FnOnce() + Send>>,
+}
+
+/// Request router of a webview
+///
+/// The router serves the requests that match its routes with
+/// **`MockResponse`**s and can record the requests of the webview, it is
+/// returned by **`WebView::router`**.
+///
+/// A pattern matches the whole URL, `*` matches any sequence of characters,
+/// such as `https://api.example.com/*`. The routes are checked in the order
+/// they were added, the first matching route serves the request.
+///
+/// The router only sees the requests of the webview once a route has been
+/// added or recording has been enabled, until then the requests are loaded
+/// without going through it.
+#[derive(Clone, Default)]
+pub struct Router(Arc<Mutex<Routes>>);
+
+impl Router {
+    /// Add a route
+    ///
+    /// A route with the same pattern is replaced.
+    pub fn route<T>(&self, pattern: &str, handler: T)
+    where
+        T: RouteHandler + 'static,
+    {
+        let on_enable = {
+            let mut routes = self.0.lock();
+            routes.routes.retain(|it| it.pattern != pattern);
+            routes.routes.push(Route {
+                pattern: pattern.to_string(),
+                handler: Arc::new(handler),
+            });
+
+            routes.on_enable.take()
+        };
+
+        if let Some(on_enable) = on_enable {
+            on_enable();
+        }
+    }
+
+    /// Enable or disable the recording of the requests
+    ///
+    /// Recording is disabled by default. While it is enabled, the requests of
+    /// the webview are recorded in the order they were made, at most
+    /// **`MAX_RECORDED_REQUESTS`** are kept and the oldest are dropped first.
+    pub fn set_recording(&self, enable: bool) {
+        let on_enable = {
+            let mut routes = self.0.lock();
+            routes.recording = enable;
+
+            if enable {
+                routes.on_enable.take()
+            } else {
+                None
+            }
+        };
+
+        if let Some(on_enable) = on_enable {
+            on_enable();
+        }
+    }
+
+    /// Remove a route
+    pub fn unroute(&self, pattern: &str) {
+        self.0.lock().routes.retain(|it| it.pattern != pattern);
+    }
+
+    /// Remove all routes
+    pub fn clear_routes(&self) {
+        self.0.lock().routes.clear();
+    }
+
+    /// Get the recorded requests in the order they were made
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.0.lock().requests.iter().cloned().collect()
+    }
+
+    /// Remove the recorded requests
+    pub fn clear_requests(&self) {
+        self.0.lock().requests.clear();
+    }
+
+    /// Set the hook that installs the router in the webview, it is called once
+    /// when the first route is added or recording is first enabled.
+    pub(crate) fn on_enable<F>(&self, func: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.0.lock().on_enable = Some(Box::new(func));
+    }
+
+    fn respond(&self, request: &Request) -> Option<MockResponse> {
+        let handler = {
+            let mut routes = self.0.lock();
+            let handler = routes
+                .routes
+                .iter()
+                .find(|it| is_match(&it.pattern, request.url))
+                .map(|it| it.handler.clone());
+
+            if routes.recording {
+                if routes.requests.len() >= MAX_RECORDED_REQUESTS {
+                    routes.requests.pop_front();
+                }
+
+                routes.requests.push_back(RecordedRequest {
+                    url: request.url.to_string(),
+                    method: request.method.to_string(),
+                    referrer: request.referrer.to_string(),
+                    routed: handler.is_some(),
+                });
+            }
+
+            handler
+        };
+
+        // The handler is called without the lock, it can use the router.
+        Some(handler?.respond(request))
+    }
+}
+
+struct MockRequestHandler {
+    response: MockResponse,
+    position: usize,
+}
+
+impl RequestHandler for MockRequestHandler {
+    fn open(&mut self) -> bool {
+        !self.response.failed
+    }
+
+    fn delay(&self) -> Duration {
+        self.response.delay
+    }
+
+    fn get_response(&mut self) -> Option<Response> {
+        Some(Response {
+            status_code: self.response.status_code,
+            mime_type: self.response.mime_type.clone(),
+            content_length: self.response.body.len() as u64,
+        })
+    }
+
+    fn skip(&mut self, size: usize) -> Option<usize> {
+        let size = size.min(self.response.body.len() - self.position);
+        self.position += size;
+
+        Some(size)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        let body = &self.response.body[self.position..];
+        let size = buffer.len().min(body.len());
+
+        buffer[..size].copy_from_slice(&body[..size]);
+        self.position += size;
+
+        Some(size)
+    }
+
+    fn cancel(&mut self) {}
+}
+
+/// The request handler factory of a webview, the routes are checked before the
+/// factory of the webview attributes.
+pub(crate) struct RoutedRequestHandlerFactory {
+    pub(crate) router: Router,
+    pub(crate) factory: Option<CustomRequestHandlerFactory>,
+}
+
+impl RequestHandlerFactory for RoutedRequestHandlerFactory {
+    fn request(&self, request: &Request) -> Option<Box<dyn RequestHandler>> {
+        if let Some(response) = self.router.respond(request) {
+            return Some(Box::new(MockRequestHandler {
+                response,
+                position: 0,
+            }));
+        }
+
+        self.factory.as_ref()?.factory().request(request)
+    }
+}
+
+/// Match a URL against a pattern where `*` matches any sequence of characters.
+fn is_match(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // The pattern without `*` must match the whole URL.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
 /// Used to get the MIME type of a file
 fn get_mime_type(path: &Path) -> Option<String> {
     Some(
@@ -371,27 +782,31 @@ extern "C" fn on_create_request_handler(
     request: *mut sys::Request,
     context: *mut c_void,
 ) -> *mut sys::RequestHandler {
-    if request.is_null() {
-        return null_mut();
-    }
-
-    if let Some(request) = Request::from_raw_ptr(request) {
-        if let Some(handler) =
-            unsafe { &*(context as *mut Box<dyn RequestHandlerFactory>) }.request(&request)
-        {
-            return Box::into_raw(Box::new(sys::RequestHandler {
-                open: Some(on_open),
-                skip: Some(on_skip),
-                read: Some(on_read),
-                cancel: Some(on_cancel),
-                destroy: Some(on_destroy),
-                get_response: Some(on_get_response),
-                context: Box::into_raw(Box::new(handler)) as _,
-            })) as _;
+    catch_panic(|| {
+        if request.is_null() {
+            return null_mut();
         }
-    }
 
-    null_mut()
+        if let Some(request) = Request::from_raw_ptr(request) {
+            if let Some(handler) =
+                unsafe { &*(context as *mut Box<dyn RequestHandlerFactory>) }.request(&request)
+            {
+                return Box::into_raw(Box::new(sys::RequestHandler {
+                    open: Some(on_open),
+                    delay: Some(on_delay),
+                    skip: Some(on_skip),
+                    read: Some(on_read),
+                    cancel: Some(on_cancel),
+                    destroy: Some(on_destroy),
+                    get_response: Some(on_get_response),
+                    context: Box::into_raw(Box::new(handler)) as _,
+                })) as _;
+            }
+        }
+
+        null_mut()
+    })
+    .unwrap_or(null_mut())
 }
 
 // This is to destroy `RequestHandler`, not to destroy `SchemeHandlerFactory`.
@@ -400,20 +815,31 @@ extern "C" fn on_destroy_request_handler(handler: *mut sys::RequestHandler) {
 }
 
 extern "C" fn on_open(context: *mut c_void) -> bool {
-    unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.open()
+    catch_panic(|| unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.open())
+        .unwrap_or(false)
+}
+
+extern "C" fn on_delay(context: *mut c_void) -> u64 {
+    catch_panic(|| {
+        unsafe { &*(context as *mut Box<dyn RequestHandler>) }
+            .delay()
+            .as_millis() as u64
+    })
+    .unwrap_or(0)
 }
 
 extern "C" fn on_get_response(response: *mut sys::Response, context: *mut c_void) {
     let response = unsafe { &mut *response };
 
     // Default return 404 response.
-    let res = unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }
-        .get_response()
-        .unwrap_or_else(|| Response {
-            status_code: 404,
-            content_length: 0,
-            mime_type: "text/plain".to_string(),
-        });
+    let res =
+        catch_panic(|| unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.get_response())
+            .flatten()
+            .unwrap_or_else(|| Response {
+                status_code: 404,
+                content_length: 0,
+                mime_type: "text/plain".to_string(),
+            });
 
     {
         let mime_type_bytes = res.mime_type.as_bytes();
@@ -437,7 +863,10 @@ extern "C" fn on_get_response(response: *mut sys::Response, context: *mut c_void
 extern "C" fn on_skip(size: usize, skip_bytes: *mut i32, context: *mut c_void) -> bool {
     let skip_bytes = unsafe { &mut *skip_bytes };
 
-    if let Some(len) = unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.skip(size) {
+    if let Some(len) =
+        catch_panic(|| unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.skip(size))
+            .flatten()
+    {
         *skip_bytes = len as i32;
 
         true
@@ -457,8 +886,11 @@ extern "C" fn on_read(
 ) -> bool {
     let read_bytes = unsafe { &mut *read_bytes };
 
-    if let Some(len) = unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }
-        .read(unsafe { std::slice::from_raw_parts_mut(buffer, size) })
+    if let Some(len) = catch_panic(|| {
+        unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }
+            .read(unsafe { std::slice::from_raw_parts_mut(buffer, size) })
+    })
+    .flatten()
     {
         *read_bytes = len as i32;
 
@@ -473,10 +905,14 @@ extern "C" fn on_read(
 }
 
 extern "C" fn on_cancel(context: *mut c_void) {
-    unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.cancel();
+    catch_panic(|| {
+        unsafe { &mut *(context as *mut Box<dyn RequestHandler>) }.cancel();
+    });
 }
 
 // Destroy `RequestHandler`
 extern "C" fn on_destroy(context: *mut c_void) {
-    drop(unsafe { Box::from_raw(context as *mut Box<dyn RequestHandler>) });
+    catch_panic(|| {
+        drop(unsafe { Box::from_raw(context as *mut Box<dyn RequestHandler>) });
+    });
 }
@@ -68,71 +68,148 @@
 //! starvation.
 
 use std::{
+    any::type_name,
+    collections::HashMap,
     ffi::{CString, c_void},
+    fs,
     marker::PhantomData,
     ops::Deref,
-    ptr::null,
+    path::{Path, PathBuf},
+    ptr::{NonNull, null},
     sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        mpsc::channel,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use parking_lot::Mutex;
 
 use crate::{
     Error, MainThreadMessageLoop, MessagePumpLoop, MultiThreadMessageLoop, NativeWindowWebView,
-    WindowlessRenderWebView,
-    request::{CustomSchemeAttributes, ICustomRequestHandlerFactory},
+    Operation, ShutdownError,
+    dispatcher::Dispatcher,
     sys,
-    utils::{AnyStringCast, Args, GetSharedRef, ThreadSafePointer, is_main_thread},
+    utils::{
+        AnyStringCast, Args, GetSharedRef, ThreadSafePointer, catch_panic, catch_panic_with_name,
+        drive_message_loop, is_in_message_loop, is_main_thread, post_main,
+    },
     webview::{
-        MixWebviewHnadler, WebView, WebViewAttributes, WebViewHandler,
-        WindowlessRenderWebViewHandler,
+        IWebView, MixWebviewHnadler, UserAgentMetadata, WebView, WebViewAttributes, WebViewHandler,
+        WebViewId, from_c_str,
     },
 };
 
-/// Log level, used to filter CEF logs
+#[cfg(feature = "windowless")]
+use crate::{WindowlessRenderWebView, webview::WindowlessRenderWebViewHandler};
+
+#[cfg(feature = "request")]
+use crate::request::{CustomSchemeAttributes, ICustomRequestHandlerFactory};
+
+/// Log severity, used to filter CEF logs
+///
+/// Messages below the severity are neither written to the log file nor to
+/// stderr.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum LogLevel {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogSeverity {
+    /// Disable logging to file for all messages, and to stderr for messages
+    /// with severity less than fatal.
     Off,
     Info,
     Error,
     Warn,
     Debug,
     Trace,
+    Fatal,
+}
+
+#[deprecated = "use `LogSeverity` instead"]
+pub type LogLevel = LogSeverity;
+
+/// GPU information
+///
+/// This is used to diagnose rendering issues caused by the GPU driver, such as
+/// blank frames in windowless rendering mode.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GpuInfo {
+    /// The vendor of the active GPU
+    pub vendor: String,
+    /// The name of the active GPU
+    pub device: String,
+    /// The vendor of the GPU driver
+    pub driver_vendor: String,
+    /// The version of the GPU driver
+    pub driver_version: String,
+    /// Whether the pages are composited by the GPU
+    pub gpu_compositing: bool,
+    /// Whether the pages are rasterized by the GPU
+    pub rasterization: bool,
+    /// Whether the videos are decoded by the GPU
+    pub video_decode: bool,
+}
+
+/// Media autoplay policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AutoplayPolicy {
+    /// Autoplay is always allowed, including media with sound.
+    NoUserGestureRequired,
+    /// Autoplay is allowed once the user has interacted with the document.
+    DocumentUserActivationRequired,
+    /// Autoplay always requires a user gesture.
+    UserGestureRequired,
 }
 
 /// Runtime configuration attributes
+///
+/// With the `serde` feature, the attributes can be loaded from a configuration
+/// file into a **`RuntimeAttributesBuilder`**, the missing fields keep their
+/// default values. The custom scheme, the dispatcher and the options that are
+/// set by **`RuntimeAttributesBuilder::build`** are not serialized.
 #[derive(Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, bound(serialize = "", deserialize = "R: Default, W: Default"))
+)]
 pub struct RuntimeAttributes<R, W> {
+    #[cfg_attr(feature = "serde", serde(skip))]
     _r: PhantomData<R>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     _w: PhantomData<W>,
 
     /// Custom scheme handler
     ///
     /// This is used to handle custom scheme requests.
+    #[cfg(feature = "request")]
+    #[cfg_attr(feature = "serde", serde(skip))]
     custom_scheme: Option<CustomSchemeAttributes>,
 
     /// Whether to enable windowless rendering mode
     ///
     /// Do not enable this value if the application does not use windowless
     /// rendering as it may reduce rendering performance on some systems.
+    #[cfg_attr(feature = "serde", serde(skip))]
     windowless_rendering_enabled: bool,
 
     /// The directory where data for the global browser cache will be stored on
     /// disk
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     cache_path: Option<CString>,
 
     /// The root directory for installation-specific data and the parent
     /// directory for profile-specific data.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     root_cache_path: Option<CString>,
 
     /// The path to a separate executable that will be launched for
     /// sub-processes
     ///
     /// This executable will be launched to handle sub-processes.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     browser_subprocess_path: Option<CString>,
 
     /// The path to the CEF framework directory on macOS
@@ -142,6 +219,7 @@ pub struct RuntimeAttributes<R, W> {
     /// top-level app bundle. If this value is non-empty, it must be an
     /// absolute path. Also configurable using the "framework-dir-path"
     /// command-line switch.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     framework_dir_path: Option<CString>,
 
     /// The path to the main bundle on macOS
@@ -150,15 +228,18 @@ pub struct RuntimeAttributes<R, W> {
     /// "Contents/MacOS/main" in the top-level app bundle. If this value is
     /// non-empty, it must be an absolute path. Also configurable using the
     /// "main-bundle-path" command-line switch.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     main_bundle_path: Option<CString>,
 
     /// Whether to use external message pump
     ///
     /// If this value is true, the application must implement the message pump
     /// driver.
+    #[cfg_attr(feature = "serde", serde(skip))]
     external_message_pump: bool,
 
     /// Whether to use multi-threaded message loop
+    #[cfg_attr(feature = "serde", serde(skip))]
     multi_threaded_message_loop: bool,
 
     /// Whether to disable command line arguments
@@ -168,34 +249,125 @@ pub struct RuntimeAttributes<R, W> {
     persist_session_cookies: bool,
 
     /// The user agent
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     user_agent: Option<CString>,
 
+    /// The user agent client hints
+    user_agent_metadata: Option<UserAgentMetadata>,
+
     /// The user agent product
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     user_agent_product: Option<CString>,
 
     /// The locale
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     locale: Option<CString>,
 
     /// The log file
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     log_file: Option<CString>,
 
     /// The log severity
-    log_severity: Option<LogLevel>,
+    log_severity: Option<LogSeverity>,
 
     /// The javascript flags
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     javascript_flags: Option<CString>,
 
     /// The resources directory path
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     resources_dir_path: Option<CString>,
 
     /// The locales directory path
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_c_string"))]
     locales_dir_path: Option<CString>,
 
     /// The background color
-    background_color: u32,
+    background_color: Option<u32>,
+
+    /// The handler callback dispatcher
+    #[cfg_attr(feature = "serde", serde(skip))]
+    dispatcher: Option<Arc<dyn Dispatcher>>,
 
     /// Whether to disable signal handlers
     disable_signal_handlers: bool,
+
+    /// Whether to disable the GPU acceleration of the 2D canvas
+    disable_accelerated_2d_canvas: bool,
+
+    /// Whether to disable the hardware video decoding
+    disable_hardware_video_decode: bool,
+
+    /// Whether to disable the GPU compositing
+    disable_gpu_compositing: bool,
+
+    /// Whether to render in software
+    software_rendering: bool,
+
+    /// Whether to allow https pages to load insecure content
+    allow_insecure_content: bool,
+
+    /// Whether to allow file URLs to access any origin
+    allow_file_access_from_files: bool,
+
+    /// Whether to disable the same-origin policy
+    disable_web_security: bool,
+
+    /// The media autoplay policy
+    autoplay_policy: Option<AutoplayPolicy>,
+
+    /// Whether to disable the autofill
+    disable_autofill: bool,
+
+    /// Whether to disable the password manager
+    disable_password_manager: bool,
+
+    /// Whether to force the accessibility of the renderer
+    force_renderer_accessibility: bool,
+
+    /// Whether to behave as if a screen reader is running
+    screen_reader_present: bool,
+
+    /// Whether to enable the caret browsing
+    caret_browsing: bool,
+
+    /// Whether to enable the spatial navigation
+    spatial_navigation: bool,
+
+    /// The Chromium switches appended to the command line
+    switches: Vec<(String, Option<String>)>,
+}
+
+impl<R, W> RuntimeAttributes<R, W> {
+    /// The attributes are only validated when they are built, since they
+    /// cannot be modified afterwards.
+    fn validate(self) -> Result<Self, Error> {
+        let invalid = |name, reason| Err(Error::InvalidAttribute { name, reason });
+        let path = |value: &Option<CString>| {
+            value
+                .as_ref()
+                .map(|it| PathBuf::from(it.to_string_lossy().to_string()))
+        };
+
+        if let (Some(root), Some(cache)) = (path(&self.root_cache_path), path(&self.cache_path))
+            && !cache.starts_with(&root)
+        {
+            return invalid(
+                "cache_path",
+                "must be the same as or a child of the root cache path",
+            );
+        }
+
+        if path(&self.framework_dir_path).is_some_and(|it| !it.is_absolute()) {
+            return invalid("framework_dir_path", "must be an absolute path");
+        }
+
+        if path(&self.main_bundle_path).is_some_and(|it| !it.is_absolute()) {
+            return invalid("main_bundle_path", "must be an absolute path");
+        }
+
+        Ok(self)
+    }
 }
 
 impl<W> RuntimeAttributes<MainThreadMessageLoop, W> {
@@ -229,13 +401,32 @@ impl<W> RuntimeAttributes<MessagePumpLoop, W> {
 }
 
 /// Runtime configuration attributes builder
+///
+/// With the `serde` feature, the builder can be deserialized from a
+/// configuration file and then built:
+///
+/// ```ignore
+/// let attr: RuntimeAttributesBuilder<MainThreadMessageLoop, NativeWindowWebView> =
+///     toml::from_str(&config)?;
+///
+/// let runtime = attr.build()?.create_runtime(handler)?;
+/// ```
 #[derive(Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        transparent,
+        bound(serialize = "", deserialize = "R: Default, W: Default")
+    )
+)]
 pub struct RuntimeAttributesBuilder<R, W>(RuntimeAttributes<R, W>);
 
 impl<R, W> RuntimeAttributesBuilder<R, W> {
     /// Set the custom scheme handler
     ///
     /// This is used to handle custom scheme requests.
+    #[cfg(feature = "request")]
     pub fn with_custom_scheme(mut self, scheme: CustomSchemeAttributes) -> Self {
         self.0.custom_scheme = Some(scheme);
         self
@@ -250,6 +441,9 @@ impl<R, W> RuntimeAttributesBuilder<R, W> {
 
     /// Set the root directory for installation-specific data and the parent
     /// directory for profile-specific data.
+    ///
+    /// Components downloaded at runtime, such as the Widevine CDM, are also
+    /// stored in this directory.
     pub fn with_root_cache_path(mut self, value: &str) -> Self {
         self.0.root_cache_path = Some(CString::new(value).unwrap());
         self
@@ -293,6 +487,15 @@ impl<R, W> RuntimeAttributesBuilder<R, W> {
         self
     }
 
+    /// Set the user agent client hints
+    ///
+    /// This function is used to set the default client hints of all webviews,
+    /// they are only used if a user agent is set.
+    pub fn with_user_agent_metadata(mut self, value: UserAgentMetadata) -> Self {
+        self.0.user_agent_metadata = Some(value);
+        self
+    }
+
     /// Set the user agent product
     pub fn with_user_agent_product(mut self, value: &str) -> Self {
         self.0.user_agent_product = Some(CString::new(value).unwrap());
@@ -306,39 +509,79 @@ impl<R, W> RuntimeAttributesBuilder<R, W> {
     }
 
     /// Set the log file
-    pub fn with_log_file(mut self, value: &str) -> Self {
-        self.0.log_file = Some(CString::new(value).unwrap());
+    ///
+    /// This function is used to redirect the CEF log to the file, the default
+    /// is `debug.log` in the current working directory.
+    pub fn with_log_file<P: AsRef<Path>>(mut self, value: P) -> Self {
+        self.0.log_file = Some(CString::new(value.as_ref().to_string_lossy().as_ref()).unwrap());
         self
     }
 
     /// Set the log severity
-    pub fn with_log_severity(mut self, value: LogLevel) -> Self {
+    ///
+    /// This function is used to filter the CEF log, the default is
+    /// `LogSeverity::Off`.
+    pub fn with_log_severity(mut self, value: LogSeverity) -> Self {
         self.0.log_severity = Some(value);
 
         self
     }
 
     /// Set the javascript flags
+    ///
+    /// This function is used to pass custom flags to the V8 engine of all
+    /// render processes, such as `--max-old-space-size=256` to cap the
+    /// javascript heap size in megabytes.
     pub fn with_javascript_flags(mut self, value: &str) -> Self {
         self.0.javascript_flags = Some(CString::new(value).unwrap());
         self
     }
 
     /// Set the resources directory path
-    pub fn with_resources_dir_path(mut self, value: &str) -> Self {
-        self.0.resources_dir_path = Some(CString::new(value).unwrap());
+    ///
+    /// This function is used to locate the CEF resources, such as
+    /// `resources.pak` and `icudtl.dat`, when they are not placed next to the
+    /// executable, such as in an AppImage or a custom install layout.
+    pub fn with_resources_dir_path<P: AsRef<Path>>(mut self, value: P) -> Self {
+        self.0.resources_dir_path =
+            Some(CString::new(value.as_ref().to_string_lossy().as_ref()).unwrap());
         self
     }
 
     /// Set the locales directory path
-    pub fn with_locales_dir_path(mut self, value: &str) -> Self {
-        self.0.locales_dir_path = Some(CString::new(value).unwrap());
+    ///
+    /// This function is used to locate the `locales` directory containing the
+    /// `*.pak` locale files, the default is the `locales` directory next to the
+    /// executable.
+    ///
+    /// This value is ignored on macOS where the locales are always loaded from
+    /// the framework bundle.
+    pub fn with_locales_dir_path<P: AsRef<Path>>(mut self, value: P) -> Self {
+        self.0.locales_dir_path =
+            Some(CString::new(value.as_ref().to_string_lossy().as_ref()).unwrap());
         self
     }
 
     /// Set the background color
+    ///
+    /// This function is used to set the default background color of all
+    /// webviews, shown before the page is painted, in ARGB format. A webview
+    /// that sets its own background color is not affected.
     pub fn with_background_color(mut self, value: u32) -> Self {
-        self.0.background_color = value;
+        self.0.background_color = Some(value);
+        self
+    }
+
+    /// Set the handler callback dispatcher
+    ///
+    /// This function is used to run the callbacks of the runtime handler and
+    /// all webview handlers through the dispatcher instead of on the CEF
+    /// threads, see the **`dispatcher`** module.
+    pub fn with_dispatcher<T>(mut self, value: T) -> Self
+    where
+        T: Dispatcher + 'static,
+    {
+        self.0.dispatcher = Some(Arc::new(value));
         self
     }
 
@@ -348,6 +591,193 @@ impl<R, W> RuntimeAttributesBuilder<R, W> {
         self
     }
 
+    /// Set whether to disable the GPU acceleration of the 2D canvas
+    ///
+    /// This function is used to force the 2D canvas to be rendered in software,
+    /// which is more predictable on low-end hardware. It is not a webview
+    /// attribute because Chromium cannot scope it per view: it is a command
+    /// line switch of the GPU process, so it applies to all webviews.
+    pub fn with_disable_accelerated_2d_canvas(mut self, value: bool) -> Self {
+        self.0.disable_accelerated_2d_canvas = value;
+        self
+    }
+
+    /// Set whether to disable the hardware video decoding
+    ///
+    /// This function is used to force videos to be decoded in software. It is
+    /// not a webview attribute because Chromium cannot scope it per view: it
+    /// is a command line switch of the GPU process, so it applies to all
+    /// webviews.
+    pub fn with_disable_hardware_video_decode(mut self, value: bool) -> Self {
+        self.0.disable_hardware_video_decode = value;
+        self
+    }
+
+    /// Set whether to disable the GPU compositing
+    ///
+    /// This function is used to composite the pages in software, which works
+    /// around GPU drivers that produce blank frames in windowless rendering
+    /// mode. Chromium only supports this option for the whole process, so it
+    /// applies to all webviews.
+    pub fn with_disable_gpu_compositing(mut self, value: bool) -> Self {
+        self.0.disable_gpu_compositing = value;
+        self
+    }
+
+    /// Set whether to render in software
+    ///
+    /// This function is used to disable the GPU entirely, the pages are
+    /// rasterized and composited on the CPU. This is intended for virtual
+    /// machines and headless servers without a usable GPU, it also disables
+    /// the GPU compositing.
+    ///
+    /// It is not needed to recover from an unstable GPU driver, Chromium falls
+    /// back to software rendering by itself after the GPU process crashed
+    /// several times, and **`Runtime::gpu_info`** reports whether the fallback
+    /// happened.
+    pub fn with_software_rendering(mut self, value: bool) -> Self {
+        self.0.software_rendering = value;
+        self
+    }
+
+    /// Set whether to allow insecure content
+    ///
+    /// This function is used to allow https pages to load scripts, styles and
+    /// other content from http URLs, which is blocked as mixed content by
+    /// default.
+    ///
+    /// This option is intended for development only. Chromium only supports it
+    /// for the whole process, so it applies to all webviews.
+    pub fn with_allow_insecure_content(mut self, value: bool) -> Self {
+        self.0.allow_insecure_content = value;
+        self
+    }
+
+    /// Set whether to allow file URLs to access any origin
+    ///
+    /// This function is used to allow pages loaded from `file://` URLs to
+    /// access other files and any other origin.
+    ///
+    /// This option is intended for development only. Chromium only supports it
+    /// for the whole process, so it applies to all webviews.
+    pub fn with_allow_file_access_from_files(mut self, value: bool) -> Self {
+        self.0.allow_file_access_from_files = value;
+        self
+    }
+
+    /// Set whether to disable the web security
+    ///
+    /// This function is used to disable the same-origin policy, including the
+    /// CORS checks, so that pages from `file://` URLs or custom schemes can
+    /// request any origin.
+    ///
+    /// This option is intended for development only. Chromium only supports it
+    /// for the whole process, so it applies to all webviews.
+    pub fn with_disable_web_security(mut self, value: bool) -> Self {
+        self.0.disable_web_security = value;
+        self
+    }
+
+    /// Set the media autoplay policy
+    ///
+    /// This function is used to allow media with sound to play without user
+    /// interaction, which Chromium blocks by default, such as for digital
+    /// signage.
+    pub fn with_autoplay_policy(mut self, value: AutoplayPolicy) -> Self {
+        self.0.autoplay_policy = Some(value);
+        self
+    }
+
+    /// Set whether to disable the autofill
+    ///
+    /// This function is used to disable the autofill of addresses and credit
+    /// cards, whose popups are not attached to the webview in windowless
+    /// rendering mode. It is not a webview attribute because Chromium cannot
+    /// scope it per view: the autofill is a preference of the browser profile,
+    /// which all webviews share through the global request context, so it
+    /// applies to all webviews.
+    pub fn with_disable_autofill(mut self, value: bool) -> Self {
+        self.0.disable_autofill = value;
+        self
+    }
+
+    /// Set whether to disable the password manager
+    ///
+    /// This function is used to disable the password manager and its save
+    /// password prompts. It is not a webview attribute because Chromium cannot
+    /// scope it per view: the password manager is a preference of the browser
+    /// profile, which all webviews share through the global request context,
+    /// so it applies to all webviews.
+    pub fn with_disable_password_manager(mut self, value: bool) -> Self {
+        self.0.disable_password_manager = value;
+        self
+    }
+
+    /// Set whether to force the accessibility of the renderer
+    ///
+    /// Chromium only builds the accessibility tree of a page when it detects
+    /// an assistive technology, which never happens for a webview rendered
+    /// offscreen. This function is used to build it regardless, so that the
+    /// content of the pages is exposed to assistive technologies.
+    pub fn with_force_renderer_accessibility(mut self, value: bool) -> Self {
+        self.0.force_renderer_accessibility = value;
+        self
+    }
+
+    /// Set whether to behave as if a screen reader is running
+    ///
+    /// This function is used to announce the presence of a screen reader to
+    /// the pages, the complete accessibility mode is enabled, including the
+    /// properties only computed for screen readers. It implies
+    /// **`RuntimeAttributesBuilder::with_force_renderer_accessibility`**.
+    pub fn with_screen_reader_present(mut self, value: bool) -> Self {
+        self.0.screen_reader_present = value;
+        self
+    }
+
+    /// Set whether to enable the caret browsing
+    ///
+    /// This function is used to show a movable cursor in the pages, so that
+    /// keyboard-only users can move through the text and select it with the
+    /// arrow keys.
+    ///
+    /// Chromium only supports it for the whole process, so it applies to all
+    /// webviews.
+    pub fn with_caret_browsing(mut self, value: bool) -> Self {
+        self.0.caret_browsing = value;
+        self
+    }
+
+    /// Set whether to enable the spatial navigation
+    ///
+    /// This function is used to move the focus between the focusable elements
+    /// of the pages with the arrow keys, in the direction of the key, so that
+    /// the pages can be navigated without a mouse.
+    ///
+    /// Chromium only supports it for the whole process, so it applies to all
+    /// webviews.
+    pub fn with_spatial_navigation(mut self, value: bool) -> Self {
+        self.0.spatial_navigation = value;
+        self
+    }
+
+    /// Append a Chromium switch
+    ///
+    /// This function is used to set the switches of the browser process, such
+    /// as `ignore-gpu-blocklist` or `enable-features` with `WebGPU`, without
+    /// implementing **`RuntimeHandler::on_before_command_line`**. The name
+    /// does not include the leading `--`.
+    ///
+    /// The switches are appended in order before the handler is called, a
+    /// switch that is set again replaces the previous value, so multiple
+    /// features must be joined with commas in one value.
+    pub fn with_switch(mut self, name: &str, value: Option<&str>) -> Self {
+        self.0
+            .switches
+            .push((name.to_string(), value.map(|it| it.to_string())));
+        self
+    }
+
     /// Set whether to disable command line arguments
     pub fn with_command_line_args_disabled(mut self, value: bool) -> Self {
         self.0.command_line_args_disabled = value;
@@ -362,56 +792,71 @@ impl<R, W> RuntimeAttributesBuilder<R, W> {
 }
 
 impl RuntimeAttributesBuilder<MultiThreadMessageLoop, NativeWindowWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MultiThreadMessageLoop, NativeWindowWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MultiThreadMessageLoop, NativeWindowWebView>, Error> {
         self.0.windowless_rendering_enabled = false;
         self.0.multi_threaded_message_loop = true;
         self.0.external_message_pump = false;
-        self.0
+        self.0.validate()
     }
 }
 
 impl RuntimeAttributesBuilder<MainThreadMessageLoop, NativeWindowWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MainThreadMessageLoop, NativeWindowWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MainThreadMessageLoop, NativeWindowWebView>, Error> {
         self.0.windowless_rendering_enabled = false;
         self.0.multi_threaded_message_loop = false;
         self.0.external_message_pump = false;
-        self.0
+        self.0.validate()
     }
 }
 
 impl RuntimeAttributesBuilder<MessagePumpLoop, NativeWindowWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MessagePumpLoop, NativeWindowWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MessagePumpLoop, NativeWindowWebView>, Error> {
         self.0.windowless_rendering_enabled = false;
         self.0.multi_threaded_message_loop = false;
         self.0.external_message_pump = true;
-        self.0
+        self.0.validate()
     }
 }
 
+#[cfg(feature = "windowless")]
 impl RuntimeAttributesBuilder<MultiThreadMessageLoop, WindowlessRenderWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MultiThreadMessageLoop, WindowlessRenderWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MultiThreadMessageLoop, WindowlessRenderWebView>, Error> {
         self.0.windowless_rendering_enabled = true;
         self.0.multi_threaded_message_loop = true;
         self.0.external_message_pump = false;
-        self.0
+        self.0.validate()
     }
 }
 
+#[cfg(feature = "windowless")]
 impl RuntimeAttributesBuilder<MainThreadMessageLoop, WindowlessRenderWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MainThreadMessageLoop, WindowlessRenderWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MainThreadMessageLoop, WindowlessRenderWebView>, Error> {
         self.0.windowless_rendering_enabled = true;
         self.0.multi_threaded_message_loop = false;
         self.0.external_message_pump = false;
-        self.0
+        self.0.validate()
     }
 }
 
+#[cfg(feature = "windowless")]
 impl RuntimeAttributesBuilder<MessagePumpLoop, WindowlessRenderWebView> {
-    pub fn build(mut self) -> RuntimeAttributes<MessagePumpLoop, WindowlessRenderWebView> {
+    pub fn build(
+        mut self,
+    ) -> Result<RuntimeAttributes<MessagePumpLoop, WindowlessRenderWebView>, Error> {
         self.0.windowless_rendering_enabled = true;
         self.0.multi_threaded_message_loop = false;
         self.0.external_message_pump = true;
-        self.0
+        self.0.validate()
     }
 }
 
@@ -423,6 +868,58 @@ impl<R, W> Deref for RuntimeAttributesBuilder<R, W> {
     }
 }
 
+/// Represents the command line of the browser process
+///
+/// The command line is only valid during the
+/// **`RuntimeHandler::on_before_command_line`** callback it is passed to.
+pub struct CommandLine<'a> {
+    raw: *mut c_void,
+    _p: PhantomData<&'a ()>,
+}
+
+impl CommandLine<'_> {
+    /// Check if the command line has a switch
+    pub fn has_switch(&self, name: &str) -> bool {
+        let name = CString::new(name).unwrap();
+
+        unsafe { sys::command_line_has_switch(self.raw, name.as_raw()) }
+    }
+
+    /// Append a switch
+    ///
+    /// The name does not include the leading `--`, such as
+    /// `disable-gpu`.
+    pub fn append_switch(&mut self, name: &str) {
+        let name = CString::new(name).unwrap();
+
+        unsafe { sys::command_line_append_switch(self.raw, name.as_raw(), null()) }
+    }
+
+    /// Append a switch with a value
+    ///
+    /// Such as `enable-features` with `WebGPU`.
+    pub fn append_switch_with_value(&mut self, name: &str, value: &str) {
+        let name = CString::new(name).unwrap();
+        let value = CString::new(value).unwrap();
+
+        unsafe { sys::command_line_append_switch(self.raw, name.as_raw(), value.as_raw()) }
+    }
+
+    /// Remove a switch
+    pub fn remove_switch(&mut self, name: &str) {
+        let name = CString::new(name).unwrap();
+
+        unsafe { sys::command_line_remove_switch(self.raw, name.as_raw()) }
+    }
+
+    /// Append an argument
+    pub fn append_argument(&mut self, argument: &str) {
+        let argument = CString::new(argument).unwrap();
+
+        unsafe { sys::command_line_append_argument(self.raw, argument.as_raw()) }
+    }
+}
+
 /// Runtime handler
 ///
 /// This trait is used to handle runtime events.
@@ -436,6 +933,13 @@ pub trait RuntimeHandler: Send + Sync {
     /// running, so you need to drive the message loop as soon as possible after
     /// creating the runtime.
     fn on_context_initialized(&self) {}
+
+    /// Called before the command line is processed
+    ///
+    /// This callback is used to append or remove Chromium switches of the
+    /// browser process, such as `--enable-features=...`, before they take
+    /// effect.
+    fn on_before_command_line(&self, command_line: &mut CommandLine) {}
 }
 
 /// Message pump runtime handler
@@ -452,16 +956,73 @@ pub trait MessagePumpRuntimeHandler: RuntimeHandler {
     fn on_schedule_message_pump_work(&self, delay: u64) {}
 }
 
-pub(crate) static RUNTIME_RUNNING: AtomicBool = AtomicBool::new(false);
+/// The maximum time to wait for the webviews to be destroyed during shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The lifecycle of the runtime of a process
+///
+/// CEF can only be initialized once per process, even after it has been shut
+/// down, so the state never goes back once CEF has been initialized. The state
+/// is changed with a single atomic operation, only one caller can claim the
+/// runtime.
+pub(crate) struct RuntimeLifecycle(AtomicU8);
+
+impl RuntimeLifecycle {
+    const UNINITIALIZED: u8 = 0;
+    const RUNNING: u8 = 1;
+    const SHUT_DOWN: u8 = 2;
+
+    pub(crate) const fn new() -> Self {
+        Self(AtomicU8::new(Self::UNINITIALIZED))
+    }
+
+    /// Claim the runtime of the process
+    fn acquire(&self) -> Result<(), Error> {
+        match self.0.compare_exchange(
+            Self::UNINITIALIZED,
+            Self::RUNNING,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(()),
+            Err(Self::RUNNING) => Err(Error::RuntimeAlreadyExists),
+            Err(_) => Err(Error::RuntimeCannotBeRecreated),
+        }
+    }
+
+    /// Give the runtime back before CEF has been initialized, so that it can be
+    /// created again.
+    fn abort(&self) {
+        self.0.store(Self::UNINITIALIZED, Ordering::Release);
+    }
+
+    /// Mark the runtime as shut down, it cannot be created again.
+    fn shut_down(&self) {
+        self.0.store(Self::SHUT_DOWN, Ordering::Release);
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.0.load(Ordering::Acquire) == Self::RUNNING
+    }
+}
+
+pub(crate) static RUNTIME_LIFECYCLE: RuntimeLifecycle = RuntimeLifecycle::new();
 
 pub(crate) struct IRuntime {
     // The runtime may use a custom request interceptor; a reference is kept here to ensure correct
     // lifetime management.
+    #[cfg(feature = "request")]
     #[allow(unused)]
     request_handler_factory: Option<Arc<ICustomRequestHandlerFactory>>,
     // Indicates whether the current runtime has been initialized
     initialized: Arc<AtomicBool>,
     multi_threaded_message_loop: bool,
+    pub(crate) background_color: Option<u32>,
+    pub(crate) dispatcher: Option<Arc<dyn Dispatcher>>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) user_agent_metadata: Option<UserAgentMetadata>,
+    // The live webviews, a webview is removed when it is closed or dropped.
+    pub(crate) webviews: Mutex<HashMap<WebViewId, Weak<IWebView>>>,
     context: ThreadSafePointer<RuntimeContext>,
     raw: Mutex<Arc<ThreadSafePointer<c_void>>>,
 }
@@ -473,7 +1034,7 @@ impl IRuntime {
     ) -> Result<Self, Error> {
         // Only one runtime is allowed per process, mainly because the runtime is bound
         // to the message loop.
-        if RUNTIME_RUNNING.load(Ordering::Relaxed) {
+        if RUNTIME_LIFECYCLE.is_running() {
             return Err(Error::RuntimeAlreadyExists);
         }
 
@@ -481,6 +1042,27 @@ impl IRuntime {
             return Err(Error::NonUIThread);
         }
 
+        RUNTIME_LIFECYCLE.acquire()?;
+
+        // CEF reports a cache directory that cannot be created only as a generic
+        // initialization failure, so it is created here to report the reason.
+        for path in [&attr.root_cache_path, &attr.cache_path]
+            .into_iter()
+            .flatten()
+            .filter_map(|it| it.to_str().ok())
+        {
+            if let Err(e) = fs::create_dir_all(path) {
+                RUNTIME_LIFECYCLE.abort();
+
+                return Err(Error::Failed {
+                    operation: Operation::CreateCacheDirectory,
+                    code: None,
+                    os_error: Some(e),
+                });
+            }
+        }
+
+        #[cfg(feature = "request")]
         let custom_scheme = attr
             .custom_scheme
             .as_ref()
@@ -493,9 +1075,26 @@ impl IRuntime {
         let options = sys::RuntimeSettings {
             cache_path: attr.cache_path.as_raw(),
             root_cache_path: attr.root_cache_path.as_raw(),
-            background_color: attr.background_color,
+            background_color: attr.background_color.unwrap_or(0),
             command_line_args_disabled: attr.command_line_args_disabled,
             disable_signal_handlers: attr.disable_signal_handlers,
+            disable_accelerated_2d_canvas: attr.disable_accelerated_2d_canvas,
+            disable_hardware_video_decode: attr.disable_hardware_video_decode,
+            disable_gpu_compositing: attr.disable_gpu_compositing,
+            software_rendering: attr.software_rendering,
+            allow_running_insecure_content: attr.allow_insecure_content,
+            allow_file_access_from_files: attr.allow_file_access_from_files,
+            disable_web_security: attr.disable_web_security,
+            autoplay_policy: attr
+                .autoplay_policy
+                .map(|it| it.into())
+                .unwrap_or(sys::AutoplayPolicy::WEW_AUTOPLAY_DEFAULT),
+            disable_autofill: attr.disable_autofill,
+            disable_password_manager: attr.disable_password_manager,
+            force_renderer_accessibility: attr.force_renderer_accessibility,
+            screen_reader_present: attr.screen_reader_present,
+            caret_browsing: attr.caret_browsing,
+            spatial_navigation: attr.spatial_navigation,
             javascript_flags: attr.javascript_flags.as_raw(),
             persist_session_cookies: attr.persist_session_cookies,
             user_agent: attr.user_agent.as_raw(),
@@ -510,17 +1109,22 @@ impl IRuntime {
             framework_dir_path: attr.framework_dir_path.as_raw(),
             external_message_pump: attr.external_message_pump,
             multi_threaded_message_loop: attr.multi_threaded_message_loop,
-            log_severity: attr.log_severity.unwrap_or(LogLevel::Off).into(),
+            log_severity: attr.log_severity.unwrap_or(LogSeverity::Off).into(),
+            #[cfg(feature = "request")]
             custom_scheme: custom_scheme
                 .as_ref()
                 .map(|it| it as *const _)
                 .unwrap_or_else(null),
+            #[cfg(not(feature = "request"))]
+            custom_scheme: null(),
         };
 
         let initialized: Arc<AtomicBool> = Default::default();
         let context: *mut RuntimeContext = Box::into_raw(Box::new(RuntimeContext {
+            switches: attr.switches.clone(),
             initialized: initialized.clone(),
-            handler,
+            dispatcher: attr.dispatcher.clone(),
+            handler: Arc::new(handler),
         }));
 
         let ptr = unsafe {
@@ -530,40 +1134,77 @@ impl IRuntime {
                     context: context as _,
                     on_context_initialized: Some(on_context_initialized_callback),
                     on_schedule_message_pump_work: Some(on_schedule_message_pump_work_callback),
+                    on_before_command_line_processing: Some(
+                        on_before_command_line_processing_callback,
+                    ),
                 },
             )
         };
 
         let raw = if ptr.is_null() {
-            return Err(Error::FailedToCreateRuntime);
+            drop(unsafe { Box::from_raw(context) });
+
+            RUNTIME_LIFECYCLE.abort();
+
+            return Err(Error::Failed {
+                operation: Operation::CreateRuntime,
+                code: None,
+                os_error: None,
+            });
         } else {
             Arc::new(ThreadSafePointer::new(ptr))
         };
 
-        {
+        let executed = {
             let args = Args::default();
 
             // If using multi-threaded message loop, run the message loop in a separate
             // thread.
             if attr.multi_threaded_message_loop {
                 let raw = raw.clone();
-                thread::spawn(move || unsafe {
-                    sys::execute_runtime(raw.as_ptr(), args.size() as _, args.as_ptr() as _);
+                let (tx, rx) = channel();
+                thread::spawn(move || {
+                    let _ = tx.send(unsafe {
+                        sys::execute_runtime(raw.as_ptr(), args.size() as _, args.as_ptr() as _)
+                    });
                 });
+
+                rx.recv().unwrap_or(false)
             } else {
-                unsafe {
-                    sys::execute_runtime(raw.as_ptr(), args.size() as _, args.as_ptr() as _);
-                }
+                unsafe { sys::execute_runtime(raw.as_ptr(), args.size() as _, args.as_ptr() as _) }
+            }
+        };
+
+        if !executed {
+            unsafe {
+                sys::close_runtime(raw.as_ptr());
             }
-        }
 
-        RUNTIME_RUNNING.store(true, Ordering::Relaxed);
+            drop(unsafe { Box::from_raw(context) });
+
+            RUNTIME_LIFECYCLE.shut_down();
+
+            return Err(Error::Failed {
+                operation: Operation::InitializeRuntime,
+                code: Some(unsafe { sys::get_exit_code() }.into()),
+                os_error: None,
+            });
+        }
 
         Ok(Self {
             initialized,
             raw: Mutex::new(raw),
             context: ThreadSafePointer::new(context),
             multi_threaded_message_loop: attr.multi_threaded_message_loop,
+            background_color: attr.background_color,
+            dispatcher: attr.dispatcher.clone(),
+            user_agent: attr
+                .user_agent
+                .as_ref()
+                .map(|it| it.to_string_lossy().to_string()),
+            user_agent_metadata: attr.user_agent_metadata.clone(),
+            webviews: Mutex::new(HashMap::new()),
+            #[cfg(feature = "request")]
             request_handler_factory: attr
                 .custom_scheme
                 .as_ref()
@@ -575,6 +1216,93 @@ impl IRuntime {
         self.initialized.load(Ordering::Relaxed)
     }
 
+    fn shutdown(&self) -> Result<(), ShutdownError> {
+        if !RUNTIME_LIFECYCLE.is_running() {
+            return Ok(());
+        }
+
+        if !is_main_thread() {
+            return Err(ShutdownError::NonUIThread);
+        }
+
+        if self.multi_threaded_message_loop {
+            return Err(ShutdownError::MultiThreadedMessageLoop);
+        }
+
+        let raw = self.get_raw();
+        self.close_browsers();
+
+        if !self.wait_until_idle(SHUTDOWN_TIMEOUT) {
+            return Err(ShutdownError::Timeout);
+        }
+
+        self.initialized.store(false, Ordering::Relaxed);
+
+        unsafe {
+            sys::shutdown_runtime(raw.as_ptr());
+        }
+
+        RUNTIME_LIFECYCLE.shut_down();
+
+        Ok(())
+    }
+
+    fn close_browsers(&self) {
+        unsafe {
+            sys::runtime_close_browsers(self.get_raw().as_ptr());
+        }
+    }
+
+    fn wait_until_idle(&self, timeout: Duration) -> bool {
+        // The message loop is only driven here if no other thread is driving it.
+        let poll = !self.multi_threaded_message_loop && is_main_thread();
+
+        // Waiting from inside a callback blocks the message loop that runs the tasks
+        // being waited for, so the runtime can never become idle.
+        if (poll && is_in_message_loop())
+            || (self.multi_threaded_message_loop && unsafe { sys::currently_on_main_thread() })
+        {
+            return false;
+        }
+
+        let deadline = Instant::now() + timeout;
+        let wait = |condition: &dyn Fn() -> bool| {
+            while !condition() {
+                if Instant::now() >= deadline {
+                    return false;
+                }
+
+                if poll {
+                    drive_message_loop(|| unsafe { sys::poll_message_loop() });
+                }
+
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            true
+        };
+
+        // The browsers are destroyed asynchronously, wait until OnBeforeClose has
+        // fired for all of them.
+        let raw = self.get_raw();
+        if !wait(&|| unsafe { sys::runtime_get_browser_count(raw.as_ptr()) } == 0) {
+            return false;
+        }
+
+        // A task posted now runs after the tasks that are already pending on the UI
+        // thread.
+        let drained = Arc::new(AtomicBool::new(false));
+        if !post_main({
+            let drained = drained.clone();
+            move || drained.store(true, Ordering::Relaxed)
+        }) {
+            // The UI thread no longer runs tasks, whether it is idle is unknown.
+            return false;
+        }
+
+        wait(&|| drained.load(Ordering::Relaxed))
+    }
+
     pub(crate) fn get_raw(&self) -> Arc<ThreadSafePointer<c_void>> {
         self.raw.lock().clone()
     }
@@ -587,7 +1315,7 @@ impl Drop for IRuntime {
             MainThreadMessageLoop.quit();
         }
 
-        RUNTIME_RUNNING.store(false, Ordering::Relaxed);
+        RUNTIME_LIFECYCLE.shut_down();
 
         unsafe {
             sys::close_runtime(self.raw.lock().as_ptr());
@@ -618,6 +1346,120 @@ impl<R, W> Runtime<R, W> {
             inner: Arc::new(IRuntime::new(attr, handler)?),
         })
     }
+
+    /// Shut down the runtime
+    ///
+    /// This function closes all remaining webviews, drives the message loop
+    /// until all of them are destroyed, and then shuts down CEF. It must be
+    /// called on the main thread after the message loop has stopped running.
+    ///
+    /// If the webviews are not destroyed within 5 seconds, the runtime is not
+    /// shut down and **`ShutdownError::Timeout`** is returned, the runtime is
+    /// still usable and the shutdown can be retried. Calling it again after
+    /// the runtime has been shut down does nothing.
+    ///
+    /// Note that the runtime cannot be used after it has been shut down, and
+    /// webviews created by it can only be dropped. A new runtime cannot be
+    /// created in the same process either.
+    pub fn shutdown(&self) -> Result<(), ShutdownError> {
+        self.inner.shutdown()
+    }
+
+    /// Query the GPU information
+    ///
+    /// This function is used to get the active GPU, its driver, and which
+    /// features are hardware accelerated. The information is queried through
+    /// one of the webviews, so `None` is passed to the callback if there is no
+    /// webview or the query failed.
+    ///
+    /// The callback is called on the UI thread.
+    pub fn gpu_info<F>(&self, callback: F)
+    where
+        F: FnOnce(Option<GpuInfo>) + Send + 'static,
+    {
+        let callback: GpuInfoCallback = Box::new(callback);
+
+        unsafe {
+            sys::runtime_get_gpu_info(
+                self.inner.get_raw().as_ptr(),
+                Some(gpu_info_callback),
+                Box::into_raw(Box::new(callback)) as _,
+            )
+        }
+    }
+}
+
+impl<R, W> Runtime<R, W> {
+    /// Get the live webviews
+    ///
+    /// This function is used to enumerate the webviews created by the runtime
+    /// that have not been closed or dropped yet, ordered by their id.
+    pub fn webviews(&self) -> Vec<WebView<W>> {
+        // The webviews are collected before the lock is released, but dropped
+        // after it, since dropping the last reference removes it from the map.
+        let mut webviews = self
+            .inner
+            .webviews
+            .lock()
+            .values()
+            .filter_map(|it| it.upgrade())
+            .collect::<Vec<_>>();
+
+        webviews.sort_by_key(|it| it.id);
+        webviews.into_iter().map(WebView::from_shared_ref).collect()
+    }
+
+    /// Get a live webview by its id
+    ///
+    /// `None` is returned if the webview has been closed or dropped.
+    pub fn webview(&self, id: WebViewId) -> Option<WebView<W>> {
+        let webview = self.inner.webviews.lock().get(&id)?.upgrade()?;
+
+        Some(WebView::from_shared_ref(webview))
+    }
+
+    /// Get the underlying CEF application
+    ///
+    /// This function is used to call the CEF APIs that are not wrapped by this
+    /// crate, the pointer is a `CefApp*` of the CEF C++ API, to be used by C++
+    /// code linked into the application.
+    ///
+    /// # Safety
+    ///
+    /// No reference is added to the application, the pointer is only valid
+    /// while the runtime is alive and must only be used on the UI thread. The
+    /// handlers of the application belong to this crate and must not be
+    /// replaced.
+    pub unsafe fn raw_app(&self) -> NonNull<c_void> {
+        NonNull::new(unsafe { sys::runtime_get_app(self.inner.get_raw().as_ptr()) }).unwrap()
+    }
+
+    /// Close all webviews
+    ///
+    /// This function is used to close every webview of the runtime, including
+    /// the popups. The webviews are closed asynchronously, use
+    /// **`Runtime::wait_until_idle`** to wait until they are destroyed.
+    pub fn close_all_webviews(&self) {
+        self.inner.close_browsers();
+    }
+
+    /// Wait until the runtime is idle
+    ///
+    /// This function blocks until all webviews have been destroyed and the
+    /// tasks that were pending on the UI thread have run, it returns `false`
+    /// if this does not happen within the timeout.
+    ///
+    /// If it is called on the main thread and the message loop is not
+    /// multi-threaded, the message loop is driven while waiting, otherwise the
+    /// message loop must be running on its own thread. Tasks that have been
+    /// passed to a **`Dispatcher`** are not waited for.
+    ///
+    /// It returns `false` immediately if it is called from inside a callback of
+    /// the runtime, because the message loop cannot be driven re-entrantly and
+    /// the pending tasks could not run while it waits.
+    pub fn wait_until_idle(&self, timeout: Duration) -> bool {
+        self.inner.wait_until_idle(timeout)
+    }
 }
 
 impl<R, W> GetSharedRef for Runtime<R, W> {
@@ -628,7 +1470,16 @@ impl<R, W> GetSharedRef for Runtime<R, W> {
     }
 }
 
+#[cfg(feature = "windowless")]
 impl<R> Runtime<R, WindowlessRenderWebView> {
+    /// Create a webview
+    ///
+    /// This function can be called on any thread, the webview is created on
+    /// the UI thread and the function returns once the browser exists. When it
+    /// is called on another thread, the UI thread must keep running its
+    /// message loop until the function returns, so it must not wait for the
+    /// calling thread. The calling thread waits for at most 30 seconds, an
+    /// error is returned if the browser has not been created by then.
     pub fn create_webview<T>(
         &self,
         url: &str,
@@ -653,6 +1504,14 @@ impl<R> Runtime<R, WindowlessRenderWebView> {
 }
 
 impl<R> Runtime<R, NativeWindowWebView> {
+    /// Create a webview
+    ///
+    /// This function can be called on any thread, the webview is created on
+    /// the UI thread and the function returns once the browser exists. When it
+    /// is called on another thread, the UI thread must keep running its
+    /// message loop until the function returns, so it must not wait for the
+    /// calling thread. The calling thread waits for at most 30 seconds, an
+    /// error is returned if the browser has not been created by then.
     pub fn create_webview<T>(
         &self,
         url: &str,
@@ -667,6 +1526,8 @@ impl<R> Runtime<R, NativeWindowWebView> {
             return Err(Error::RuntimeNotInitialization);
         }
 
+        attr.validate_native_window()?;
+
         WebView::new(
             self,
             url,
@@ -676,51 +1537,248 @@ impl<R> Runtime<R, NativeWindowWebView> {
     }
 }
 
-impl From<LogLevel> for sys::LogLevel {
-    fn from(val: LogLevel) -> Self {
+impl From<AutoplayPolicy> for sys::AutoplayPolicy {
+    fn from(val: AutoplayPolicy) -> Self {
         match val {
-            LogLevel::Off => sys::LogLevel::WEW_LOG_DISABLE,
-            LogLevel::Info => sys::LogLevel::WEW_LOG_INFO,
-            LogLevel::Error => sys::LogLevel::WEW_LOG_ERROR,
-            LogLevel::Warn => sys::LogLevel::WEW_LOG_WARNING,
-            LogLevel::Debug => sys::LogLevel::WEW_LOG_DEBUG,
-            LogLevel::Trace => sys::LogLevel::WEW_LOG_VERBOSE,
+            AutoplayPolicy::NoUserGestureRequired => {
+                sys::AutoplayPolicy::WEW_AUTOPLAY_NO_USER_GESTURE_REQUIRED
+            }
+            AutoplayPolicy::DocumentUserActivationRequired => {
+                sys::AutoplayPolicy::WEW_AUTOPLAY_DOCUMENT_USER_ACTIVATION_REQUIRED
+            }
+            AutoplayPolicy::UserGestureRequired => {
+                sys::AutoplayPolicy::WEW_AUTOPLAY_USER_GESTURE_REQUIRED
+            }
+        }
+    }
+}
+
+impl From<sys::LogLevel> for LogSeverity {
+    fn from(val: sys::LogLevel) -> Self {
+        match val {
+            sys::LogLevel::WEW_LOG_DISABLE => Self::Off,
+            sys::LogLevel::WEW_LOG_DEFAULT | sys::LogLevel::WEW_LOG_INFO => Self::Info,
+            sys::LogLevel::WEW_LOG_ERROR => Self::Error,
+            sys::LogLevel::WEW_LOG_WARNING => Self::Warn,
+            sys::LogLevel::WEW_LOG_VERBOSE => Self::Debug,
+            sys::LogLevel::WEW_LOG_FATAL => Self::Fatal,
+        }
+    }
+}
+
+impl From<LogSeverity> for sys::LogLevel {
+    fn from(val: LogSeverity) -> Self {
+        match val {
+            LogSeverity::Off => sys::LogLevel::WEW_LOG_DISABLE,
+            LogSeverity::Info => sys::LogLevel::WEW_LOG_INFO,
+            LogSeverity::Error => sys::LogLevel::WEW_LOG_ERROR,
+            LogSeverity::Warn => sys::LogLevel::WEW_LOG_WARNING,
+            LogSeverity::Debug => sys::LogLevel::WEW_LOG_DEBUG,
+            LogSeverity::Trace => sys::LogLevel::WEW_LOG_VERBOSE,
+            LogSeverity::Fatal => sys::LogLevel::WEW_LOG_FATAL,
         }
     }
 }
 
 struct RuntimeContext {
-    handler: MixRuntimeHnadler,
+    switches: Vec<(String, Option<String>)>,
+    handler: Arc<MixRuntimeHnadler>,
+    dispatcher: Option<Arc<dyn Dispatcher>>,
     initialized: Arc<AtomicBool>,
 }
 
+impl RuntimeContext {
+    fn dispatch<F>(&self, callback: F)
+    where
+        F: FnOnce(&MixRuntimeHnadler) + Send + 'static,
+    {
+        if let Some(dispatcher) = &self.dispatcher {
+            let handler = self.handler.clone();
+            dispatcher.dispatch(Box::new(move || {
+                catch_panic_with_name(type_name::<F>(), || callback(&handler));
+            }));
+        } else {
+            callback(&self.handler);
+        }
+    }
+}
+
 pub(crate) enum MixRuntimeHnadler {
     RuntimeHandler(Box<dyn RuntimeHandler>),
     MessagePumpRuntimeHandler(Box<dyn MessagePumpRuntimeHandler>),
 }
 
 extern "C" fn on_context_initialized_callback(context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut RuntimeContext) };
+
+        context.initialized.store(true, Ordering::Relaxed);
+
+        context.dispatch(|handler| match handler {
+            MixRuntimeHnadler::RuntimeHandler(handler) => handler.on_context_initialized(),
+            MixRuntimeHnadler::MessagePumpRuntimeHandler(handler) => {
+                handler.on_context_initialized()
+            }
+        });
+    });
+}
+
+extern "C" fn on_before_command_line_processing_callback(
+    command_line: *mut c_void,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || command_line.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut RuntimeContext) };
+        let mut command_line = CommandLine {
+            raw: command_line,
+            _p: PhantomData,
+        };
+
+        for (name, value) in &context.switches {
+            match value {
+                Some(value) => command_line.append_switch_with_value(name, value),
+                None => command_line.append_switch(name),
+            }
+        }
+
+        match &*context.handler {
+            MixRuntimeHnadler::RuntimeHandler(handler) => {
+                handler.on_before_command_line(&mut command_line)
+            }
+            MixRuntimeHnadler::MessagePumpRuntimeHandler(handler) => {
+                handler.on_before_command_line(&mut command_line)
+            }
+        }
+    });
+}
+
+type GpuInfoCallback = Box<dyn FnOnce(Option<GpuInfo>) + Send>;
+
+extern "C" fn gpu_info_callback(info: *const sys::GpuInfo, context: *mut c_void) {
     if context.is_null() {
         return;
     }
 
-    let context = unsafe { &*(context as *mut RuntimeContext) };
-
-    context.initialized.store(true, Ordering::Relaxed);
+    let callback = unsafe { Box::from_raw(context as *mut GpuInfoCallback) };
+    catch_panic(move || {
+        callback(if info.is_null() {
+            None
+        } else {
+            Some(GpuInfo::from(unsafe { &*info }))
+        })
+    });
+}
 
-    match &context.handler {
-        MixRuntimeHnadler::RuntimeHandler(handler) => handler.on_context_initialized(),
-        MixRuntimeHnadler::MessagePumpRuntimeHandler(handler) => handler.on_context_initialized(),
+impl From<&sys::GpuInfo> for GpuInfo {
+    fn from(value: &sys::GpuInfo) -> Self {
+        Self {
+            vendor: from_c_str(value.vendor),
+            device: from_c_str(value.device),
+            driver_vendor: from_c_str(value.driver_vendor),
+            driver_version: from_c_str(value.driver_version),
+            gpu_compositing: value.gpu_compositing,
+            rasterization: value.rasterization,
+            video_decode: value.video_decode,
+        }
     }
 }
 
 extern "C" fn on_schedule_message_pump_work_callback(delay: i64, context: *mut c_void) {
-    if context.is_null() {
-        return;
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut RuntimeContext) };
+        context.dispatch(move |handler| {
+            if let MixRuntimeHnadler::MessagePumpRuntimeHandler(handler) = handler {
+                handler.on_schedule_message_pump_work(delay as u64);
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use super::*;
+
+    #[test]
+    fn runtime_cannot_be_created_twice() {
+        let lifecycle = RuntimeLifecycle::new();
+
+        assert!(lifecycle.acquire().is_ok());
+        assert!(lifecycle.is_running());
+        assert!(matches!(
+            lifecycle.acquire(),
+            Err(Error::RuntimeAlreadyExists)
+        ));
+    }
+
+    #[test]
+    fn runtime_cannot_be_recreated_after_drop() {
+        let lifecycle = RuntimeLifecycle::new();
+
+        assert!(lifecycle.acquire().is_ok());
+        lifecycle.shut_down();
+
+        assert!(!lifecycle.is_running());
+        assert!(matches!(
+            lifecycle.acquire(),
+            Err(Error::RuntimeCannotBeRecreated)
+        ));
+
+        // Dropping the runtime again after a shutdown does not allow it either.
+        lifecycle.shut_down();
+        assert!(matches!(
+            lifecycle.acquire(),
+            Err(Error::RuntimeCannotBeRecreated)
+        ));
+    }
+
+    #[test]
+    fn runtime_can_be_created_after_aborted_creation() {
+        let lifecycle = RuntimeLifecycle::new();
+
+        assert!(lifecycle.acquire().is_ok());
+        lifecycle.abort();
+
+        assert!(!lifecycle.is_running());
+        assert!(lifecycle.acquire().is_ok());
     }
 
-    let context = unsafe { &*(context as *mut RuntimeContext) };
-    if let MixRuntimeHnadler::MessagePumpRuntimeHandler(handler) = &context.handler {
-        handler.on_schedule_message_pump_work(delay as u64);
+    #[test]
+    fn runtime_is_claimed_once_by_concurrent_creations() {
+        let lifecycle = Arc::new(RuntimeLifecycle::new());
+        let barrier = Arc::new(Barrier::new(8));
+
+        let handles = (0..8)
+            .map(|_| {
+                let lifecycle = lifecycle.clone();
+                let barrier = barrier.clone();
+
+                thread::spawn(move || {
+                    barrier.wait();
+                    lifecycle.acquire().is_ok()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let claimed = handles
+            .into_iter()
+            .map(|it| it.join().unwrap())
+            .filter(|it| *it)
+            .count();
+
+        assert_eq!(claimed, 1);
     }
 }
@@ -0,0 +1,261 @@
+//! This module is used to receive the frames of a web page as images.
+//!
+//! **`WebView::start_screencast`** asks the renderer to encode the frames of
+//! the page as JPEG or PNG images, it works with native window webviews as
+//! well as with windowless rendering, so a page can be viewed remotely or
+//! monitored without enabling windowless rendering:
+//!
+//! ```no_run
+//! use wew::{
+//!     NativeWindowWebView,
+//!     screencast::{ScreencastFormat, ScreencastOptions},
+//!     webview::WebView,
+//! };
+//!
+//! async fn watch(webview: &WebView<NativeWindowWebView>) {
+//!     let mut screencast = webview.start_screencast(ScreencastOptions {
+//!         format: ScreencastFormat::Jpeg,
+//!         max_width: 640,
+//!         max_height: 360,
+//!         ..Default::default()
+//!     });
+//!
+//!     while let Some(frame) = screencast.recv().await {
+//!         println!("frame: {} bytes at {:?}", frame.data.len(), frame.timestamp);
+//!     }
+//! }
+//! ```
+//!
+//! The renderer only produces the next frame after the previous one has been
+//! received, and only when the page changes. Frames are buffered without
+//! limit until they are polled, a slow consumer can lower the rate with
+//! **`ScreencastOptions::every_nth_frame`**.
+//!
+//! Only one screencast runs in a webview at a time, starting a new one ends
+//! the previous stream. The stream also ends when the screencast is stopped
+//! or the webview is closed. Dropping the stream stops the screencast when
+//! the next frame arrives.
+
+use std::{
+    collections::VecDeque,
+    ffi::c_void,
+    pin::Pin,
+    slice::from_raw_parts,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+    time::{Duration, SystemTime},
+};
+
+use futures_core::Stream;
+use parking_lot::Mutex;
+
+use crate::{sys, utils::catch_panic};
+
+/// The image format of the screencast frames
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScreencastFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl From<ScreencastFormat> for sys::ScreencastFormat {
+    fn from(value: ScreencastFormat) -> Self {
+        match value {
+            ScreencastFormat::Jpeg => sys::ScreencastFormat::WEW_SCREENCAST_FORMAT_JPEG,
+            ScreencastFormat::Png => sys::ScreencastFormat::WEW_SCREENCAST_FORMAT_PNG,
+        }
+    }
+}
+
+impl From<sys::ScreencastFormat> for ScreencastFormat {
+    fn from(value: sys::ScreencastFormat) -> Self {
+        match value {
+            sys::ScreencastFormat::WEW_SCREENCAST_FORMAT_PNG => Self::Png,
+            _ => Self::Jpeg,
+        }
+    }
+}
+
+/// Options of a screencast
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ScreencastOptions {
+    pub format: ScreencastFormat,
+    /// The compression quality from 0 to 100, only used by JPEG.
+    pub quality: u8,
+    /// The maximum width of the frames in device pixels, 0 means the width of
+    /// the view.
+    pub max_width: u32,
+    /// The maximum height of the frames in device pixels, 0 means the height
+    /// of the view.
+    pub max_height: u32,
+    /// Only send every n-th frame, 1 sends all frames.
+    pub every_nth_frame: u32,
+}
+
+impl Default for ScreencastOptions {
+    fn default() -> Self {
+        Self {
+            format: ScreencastFormat::Jpeg,
+            quality: 80,
+            max_width: 0,
+            max_height: 0,
+            every_nth_frame: 1,
+        }
+    }
+}
+
+impl From<&ScreencastOptions> for sys::ScreencastOptions {
+    fn from(value: &ScreencastOptions) -> Self {
+        Self {
+            format: value.format.into(),
+            quality: value.quality.min(100) as _,
+            max_width: value.max_width,
+            max_height: value.max_height,
+            every_nth_frame: value.every_nth_frame.max(1),
+        }
+    }
+}
+
+/// A frame of a screencast
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreencastFrame {
+    /// The encoded image
+    pub data: Vec<u8>,
+    pub format: ScreencastFormat,
+    /// The top offset of the page content in DIP
+    pub offset_top: f64,
+    /// The page scale factor, such as from pinch zoom
+    pub page_scale_factor: f64,
+    /// The width of the view in DIP
+    pub device_width: f64,
+    /// The height of the view in DIP
+    pub device_height: f64,
+    /// The horizontal scroll offset of the page in CSS pixels
+    pub scroll_offset_x: f64,
+    /// The vertical scroll offset of the page in CSS pixels
+    pub scroll_offset_y: f64,
+    /// The time the frame was presented, if it is known
+    pub timestamp: Option<SystemTime>,
+}
+
+impl From<&sys::ScreencastFrame> for ScreencastFrame {
+    fn from(value: &sys::ScreencastFrame) -> Self {
+        Self {
+            data: unsafe { from_raw_parts(value.data, value.size) }.to_vec(),
+            format: value.format.into(),
+            offset_top: value.offset_top,
+            page_scale_factor: value.page_scale_factor,
+            device_width: value.device_width,
+            device_height: value.device_height,
+            scroll_offset_x: value.scroll_offset_x,
+            scroll_offset_y: value.scroll_offset_y,
+            timestamp: if value.timestamp > 0.0 {
+                Duration::try_from_secs_f64(value.timestamp)
+                    .ok()
+                    .and_then(|it| SystemTime::UNIX_EPOCH.checked_add(it))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct FrameQueue {
+    frames: VecDeque<ScreencastFrame>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// A stream of screencast frames
+///
+/// Created by **`WebView::start_screencast`**.
+pub struct Screencast(Arc<Mutex<FrameQueue>>);
+
+impl Screencast {
+    /// The stream and the context of **`on_screencast_frame`**, the context
+    /// is released by the last call of the callback.
+    pub(crate) fn new() -> (Self, *mut c_void) {
+        let queue = Arc::new(Mutex::new(FrameQueue::default()));
+        let context = Box::into_raw(Box::new(queue.clone()));
+
+        (Self(queue), context as _)
+    }
+
+    /// Receive the next frame
+    ///
+    /// Returns `None` after the screencast has ended and all frames have been
+    /// received.
+    pub async fn recv(&mut self) -> Option<ScreencastFrame> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl Stream for Screencast {
+    type Item = ScreencastFrame;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut queue = self.0.lock();
+
+        if let Some(frame) = queue.frames.pop_front() {
+            return Poll::Ready(Some(frame));
+        }
+
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+
+        queue.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+pub(crate) extern "C" fn on_screencast_frame(
+    frame: *const sys::ScreencastFrame,
+    context: *mut c_void,
+) -> bool {
+    catch_panic(|| {
+        if context.is_null() {
+            return false;
+        }
+
+        // A null frame is the last call, the context is released.
+        if frame.is_null() {
+            let queue = unsafe { Box::from_raw(context as *mut Arc<Mutex<FrameQueue>>) };
+            let mut queue = queue.lock();
+            queue.closed = true;
+
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+
+            return false;
+        }
+
+        let queue = unsafe { &*(context as *const Arc<Mutex<FrameQueue>>) };
+
+        // The stream has been dropped, nobody receives the frames anymore.
+        if Arc::strong_count(queue) == 1 {
+            return false;
+        }
+
+        let mut queue = queue.lock();
+        queue
+            .frames
+            .push_back(ScreencastFrame::from(unsafe { &*frame }));
+
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+
+        true
+    })
+    .unwrap_or(false)
+}
@@ -0,0 +1,278 @@
+//! This module is used to receive webview events as a stream.
+//!
+//! The handler traits are called directly on the CEF threads, which is not
+//! convenient for async applications. **`WebView::events`** returns a
+//! **`WebViewEvents`** stream that carries the same events as owned values,
+//! so they can be awaited or selected over together with other futures.
+//!
+//! ```no_run
+//! use wew::stream::{WebViewEvent, WebViewEvents};
+//!
+//! async fn run(mut events: WebViewEvents) {
+//!     while let Some(event) = events.recv().await {
+//!         if let WebViewEvent::TitleChange(title) = event {
+//!             println!("title: {}", title);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! The handler is still called, the stream is an addition to it rather than a
+//! replacement. Video frames are not delivered through the stream, they are
+//! only valid during **`WindowlessRenderWebViewHandler::on_frame`**.
+//!
+//! At most 1024 events are buffered until they are polled, the events that
+//! occur while the buffer is full are dropped, so a page flooding the host
+//! with messages cannot exhaust its memory through a stream that is not
+//! polled. The stream ends after the webview is closed, even if the closing
+//! events have been dropped.
+//!
+//! Applications without an async runtime can use **`WebView::event_receiver`**
+//! instead, it delivers the same events through a `std::sync::mpsc` channel
+//! that is drained from the application's own loop:
+//!
+//! ```no_run
+//! use std::sync::mpsc::Receiver;
+//!
+//! use wew::stream::WebViewEvent;
+//!
+//! fn drain(receiver: &Receiver<WebViewEvent>) {
+//!     while let Ok(event) = receiver.try_recv() {
+//!         println!("{:?}", event);
+//!     }
+//! }
+//! ```
+//!
+//! The channel has the same limit of 1024 events, and is disconnected after
+//! the webview is closed.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        Arc, Weak,
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+use parking_lot::Mutex;
+
+use crate::{
+    Rect,
+    runtime::LogSeverity,
+    webview::{
+        CursorType, FrameInfo, HttpResponse, PageError, PdfDocument, TerminationStatus,
+        WebViewState,
+    },
+};
+
+/// The maximum number of events buffered for each stream or channel.
+const MAX_BUFFERED_EVENTS: usize = 1024;
+
+/// An event of a webview
+///
+/// Each variant corresponds to a method of **`WebViewHandler`** or
+/// **`WindowlessRenderWebViewHandler`**.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WebViewEvent {
+    /// See **`WebViewHandler::on_state_change`**
+    StateChange(WebViewState),
+    /// See **`WebViewHandler::on_cursor_change`**
+    CursorChange(CursorType),
+    /// See **`WindowlessRenderWebViewHandler::on_ime_rect`**
+    ImeRect(Rect),
+    /// See **`WindowlessRenderWebViewHandler::on_zoom_change`**
+    ZoomChange(f64),
+    /// See **`WebViewHandler::on_title_change`**
+    TitleChange(String),
+    /// See **`WebViewHandler::on_fullscreen_change`**
+    FullscreenChange(bool),
+    /// See **`WebViewHandler::on_render_process_terminated`**
+    RenderProcessTerminated {
+        status: TerminationStatus,
+        error_code: i32,
+    },
+    /// See **`WebViewHandler::on_page_error`**
+    PageError { error: PageError, frame: FrameInfo },
+    /// See **`WebViewHandler::on_console_message`**
+    ConsoleMessage {
+        level: LogSeverity,
+        message: String,
+        source: String,
+        line: u32,
+    },
+    /// See **`WebViewHandler::on_favicon_change`**
+    FaviconChange(Vec<String>),
+    /// See **`WebViewHandler::on_status_message`**
+    StatusMessage(String),
+    /// See **`WebViewHandler::on_main_frame_response`**
+    MainFrameResponse(HttpResponse),
+    /// See **`WebViewHandler::on_pdf_load`**
+    PdfLoad(PdfDocument),
+    /// See **`WebViewHandler::on_take_focus`**
+    TakeFocus(bool),
+    /// See **`WebViewHandler::on_got_focus`**
+    GotFocus,
+    /// See **`WebViewHandler::on_find_result`**
+    FindResult {
+        count: u32,
+        active_index: u32,
+        selection_rect: Rect,
+    },
+    /// See **`WebViewHandler::on_print_start`**
+    PrintStart,
+    /// See **`WebViewHandler::on_print_finished`**
+    PrintFinished,
+    /// See **`WebViewHandler::on_message`**
+    ///
+    /// Messages sent on a channel are only delivered to the channel handler.
+    Message { message: String, frame: FrameInfo },
+    /// See **`WebViewHandler::on_binary_message`**
+    BinaryMessage { message: Vec<u8>, frame: FrameInfo },
+}
+
+#[derive(Default)]
+struct EventQueue {
+    events: VecDeque<WebViewEvent>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// A stream of webview events
+///
+/// Created by **`WebView::events`**, multiple streams can be created for the
+/// same webview and each of them receives all events.
+pub struct WebViewEvents(Arc<Mutex<EventQueue>>);
+
+impl WebViewEvents {
+    /// Receive the next event
+    ///
+    /// Returns `None` after the webview is closed and all events have been
+    /// received.
+    pub async fn recv(&mut self) -> Option<WebViewEvent> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl Stream for WebViewEvents {
+    type Item = WebViewEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut queue = self.0.lock();
+
+        if let Some(event) = queue.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+
+        queue.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[derive(Default)]
+struct Subscribers {
+    queues: Vec<Weak<Mutex<EventQueue>>>,
+    senders: Vec<SyncSender<WebViewEvent>>,
+    closed: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct EventSubscribers(Mutex<Subscribers>);
+
+impl EventSubscribers {
+    pub(crate) fn subscribe(&self) -> WebViewEvents {
+        let mut subscribers = self.0.lock();
+
+        // A stream created after the webview is closed ends immediately.
+        let queue = Arc::new(Mutex::new(EventQueue {
+            closed: subscribers.closed,
+            ..Default::default()
+        }));
+
+        if !subscribers.closed {
+            subscribers.queues.push(Arc::downgrade(&queue));
+        }
+
+        WebViewEvents(queue)
+    }
+
+    pub(crate) fn subscribe_channel(&self) -> Receiver<WebViewEvent> {
+        let mut subscribers = self.0.lock();
+
+        // The sender is dropped if the webview is already closed, so that the
+        // channel is disconnected immediately.
+        let (tx, rx) = sync_channel(MAX_BUFFERED_EVENTS);
+        if !subscribers.closed {
+            subscribers.senders.push(tx);
+        }
+
+        rx
+    }
+
+    /// The event is only created if there is a stream or a channel to receive
+    /// it.
+    pub(crate) fn send<F>(&self, event: F)
+    where
+        F: FnOnce() -> WebViewEvent,
+    {
+        let mut subscribers = self.0.lock();
+        subscribers.queues.retain(|it| it.strong_count() > 0);
+
+        if subscribers.queues.is_empty() && subscribers.senders.is_empty() {
+            return;
+        }
+
+        let event = event();
+        for subscriber in subscribers.queues.iter() {
+            if let Some(queue) = subscriber.upgrade() {
+                let mut queue = queue.lock();
+                if queue.events.len() < MAX_BUFFERED_EVENTS {
+                    queue.events.push_back(event.clone());
+                }
+
+                if let Some(waker) = queue.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+
+        // The receivers that have been dropped are removed, the event is dropped
+        // for the receivers that are full.
+        subscribers.senders.retain(|it| {
+            !matches!(
+                it.try_send(event.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+
+    pub(crate) fn close(&self) {
+        let mut subscribers = self.0.lock();
+        subscribers.closed = true;
+        subscribers.senders.clear();
+
+        for subscriber in subscribers.queues.drain(..) {
+            if let Some(queue) = subscriber.upgrade() {
+                let mut queue = queue.lock();
+                queue.closed = true;
+
+                if let Some(waker) = queue.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for EventSubscribers {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
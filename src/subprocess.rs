@@ -0,0 +1,441 @@
+//! This module is used to customize the subprocess.
+//!
+//! The render process hosts the JavaScript contexts of the web pages. If you
+//! provide your own subprocess executable, you can hook into the lifecycle of
+//! these contexts, for example to inject additional bindings before any script
+//! of the page runs.
+//!
+//! ```no_run
+//! use wew::subprocess::{SubProcessHandler, V8Context};
+//!
+//! struct Bindings;
+//!
+//! impl SubProcessHandler for Bindings {
+//!     fn on_context_created(&self, context: &V8Context) {
+//!         context.execute_javascript("window.platform = 'wew';");
+//!     }
+//! }
+//!
+//! fn main() {
+//!     if wew::is_subprocess() {
+//!         wew::subprocess::execute_subprocess_with_handler(Bindings);
+//!
+//!         return;
+//!     }
+//! }
+//! ```
+//!
+//! ## V8 Extensions
+//!
+//! Performance-critical page APIs can be implemented as native functions of a
+//! V8 extension, the arguments and the return value are passed as
+//! **`V8Value`** without any serialization.
+//!
+//! ```no_run
+//! use wew::subprocess::{SubProcessHandler, V8Extension, V8Value};
+//!
+//! struct Extensions;
+//!
+//! impl SubProcessHandler for Extensions {
+//!     fn extensions(&self) -> Vec<V8Extension> {
+//!         vec![
+//!             V8Extension::new(
+//!                 "v8/math",
+//!                 "var math = { add: function(a, b) { native function add(); return add(a, b); } };",
+//!             )
+//!             .with_function("add", |arguments| match arguments {
+//!                 [V8Value::Int(a), V8Value::Int(b)] => Ok(V8Value::Int(a + b)),
+//!                 _ => Err("invalid arguments".to_string()),
+//!             }),
+//!         ]
+//!     }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString, c_char, c_void},
+    marker::PhantomData,
+    ptr::null,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    SubProcessExit, sys,
+    utils::{self, AnyStringCast, catch_panic},
+    webview::FrameInfo,
+};
+
+/// Represents the JavaScript context of a frame
+///
+/// The context is only valid during the **`SubProcessHandler`** callback it
+/// is passed to.
+pub struct V8Context<'a> {
+    raw: *mut c_void,
+    frame: FrameInfo,
+    _p: PhantomData<&'a ()>,
+}
+
+impl V8Context<'_> {
+    /// Get the frame that owns the context
+    pub fn frame(&self) -> &FrameInfo {
+        &self.frame
+    }
+
+    /// Execute JavaScript
+    ///
+    /// This function is used to execute a string of JavaScript code in the
+    /// context, returns `false` if an exception was thrown.
+    pub fn execute_javascript(&self, script: &str) -> bool {
+        let script = CString::new(script).unwrap();
+
+        unsafe { sys::v8_context_execute_javascript(self.raw, script.as_raw()) }
+    }
+}
+
+impl std::fmt::Debug for V8Context<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("V8Context")
+            .field("frame", &self.frame)
+            .finish()
+    }
+}
+
+/// Represents a JavaScript value
+///
+/// Values of other types, such as objects and functions, are passed to native
+/// functions as `Undefined`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum V8Value {
+    Undefined,
+    Null,
+    Bool(bool),
+    Int(i32),
+    Double(f64),
+    String(String),
+    ArrayBuffer(Vec<u8>),
+}
+
+type NativeFunction = Box<dyn Fn(&[V8Value]) -> Result<V8Value, String> + Send + Sync>;
+
+/// Represents a V8 extension
+///
+/// An extension is a piece of JavaScript code that is loaded in every
+/// JavaScript context, the code declares the native functions with
+/// `native function name();`, and calls to them are routed to the functions
+/// registered with **`V8Extension::with_function`**.
+pub struct V8Extension {
+    name: CString,
+    code: CString,
+    functions: HashMap<String, NativeFunction>,
+}
+
+impl V8Extension {
+    /// Create a V8 extension
+    ///
+    /// This function is used to create an extension from its name and its
+    /// JavaScript code.
+    pub fn new(name: &str, code: &str) -> Self {
+        Self {
+            name: CString::new(name).unwrap(),
+            code: CString::new(code).unwrap(),
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Set a native function
+    ///
+    /// This function is used to implement a native function declared in the
+    /// code of the extension, returning an error throws an exception to the
+    /// web page.
+    pub fn with_function<F>(mut self, name: &str, function: F) -> Self
+    where
+        F: Fn(&[V8Value]) -> Result<V8Value, String> + Send + Sync + 'static,
+    {
+        self.functions.insert(name.to_string(), Box::new(function));
+        self
+    }
+}
+
+impl std::fmt::Debug for V8Extension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("V8Extension")
+            .field("name", &self.name)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Subprocess handler
+///
+/// This trait is used to handle the events of the render process.
+#[allow(unused)]
+pub trait SubProcessHandler: Send + Sync {
+    /// Get the V8 extensions
+    ///
+    /// This function is called once when the render process starts, the
+    /// returned extensions are loaded in every JavaScript context.
+    fn extensions(&self) -> Vec<V8Extension> {
+        Vec::new()
+    }
+
+    /// Called after the JavaScript context of a frame has been created
+    ///
+    /// The objects injected by this library, such as **`MessageTransport`**,
+    /// are already available in the context.
+    fn on_context_created(&self, context: &V8Context) {}
+
+    /// Called before the JavaScript context of a frame is released
+    fn on_context_released(&self, context: &V8Context) {}
+}
+
+/// Execute subprocess with a handler
+///
+/// This method is the same as **`wew::execute_subprocess`**, except that the
+/// handler is called for the events of the render process.
+///
+/// #### Please be careful!
+///
+/// Do not call this function in an asynchronous runtime, such as tokio,
+/// which can lead to unexpected crashes!
+pub fn execute_subprocess_with_handler<T>(handler: T) -> SubProcessExit
+where
+    T: SubProcessHandler + 'static,
+{
+    SubProcessExit::new(execute(handler))
+}
+
+/// Run the subprocess with a handler
+///
+/// This function is the same as **`execute_subprocess_with_handler`**, except
+/// that the process exits with the exit code of the subprocess once it is
+/// finished, so it can be the whole body of the main function of a helper
+/// executable.
+///
+/// ```no_run
+/// use wew::subprocess::{SubProcessHandler, V8Context};
+///
+/// struct Bindings;
+///
+/// impl SubProcessHandler for Bindings {
+///     fn on_context_created(&self, context: &V8Context) {
+///         context.execute_javascript("window.platform = 'wew';");
+///     }
+/// }
+///
+/// fn main() {
+///     wew::subprocess::run(Bindings);
+/// }
+/// ```
+pub fn run<T>(handler: T) -> !
+where
+    T: SubProcessHandler + 'static,
+{
+    std::process::exit(execute(handler))
+}
+
+fn execute<T>(handler: T) -> i32
+where
+    T: SubProcessHandler + 'static,
+{
+    if !utils::is_main_thread() {
+        panic!("this operation is not allowed in non-main threads!");
+    }
+
+    // The subprocess blocks until it exits, the context outlives every
+    // callback.
+    let context = SubProcessContext {
+        handler: Box::new(handler),
+        extensions: Mutex::new(Vec::new()),
+    };
+
+    let raw_handler = sys::SubProcessHandler {
+        on_webkit_initialized: Some(on_webkit_initialized_callback),
+        on_context_created: Some(on_context_created_callback),
+        on_context_released: Some(on_context_released_callback),
+        context: &context as *const _ as _,
+    };
+
+    let args = utils::Args::default();
+    (unsafe { sys::execute_subprocess(args.size() as _, args.as_ptr() as _, &raw_handler) }) as _
+}
+
+struct SubProcessContext {
+    handler: Box<dyn SubProcessHandler>,
+    // The extensions are boxed, their addresses are passed to the native side
+    // and must not change.
+    #[allow(clippy::vec_box)]
+    extensions: Mutex<Vec<Box<V8Extension>>>,
+}
+
+extern "C" fn on_webkit_initialized_callback(context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *const SubProcessContext) };
+        let mut extensions = context.extensions.lock();
+
+        for extension in context.handler.extensions() {
+            let extension = Box::new(extension);
+
+            let registered = unsafe {
+                sys::register_v8_extension(&sys::V8Extension {
+                    name: extension.name.as_raw(),
+                    code: extension.code.as_raw(),
+                    execute: Some(on_extension_execute_callback),
+                    context: &*extension as *const _ as _,
+                })
+            };
+
+            if registered {
+                extensions.push(extension);
+            }
+        }
+    });
+}
+
+extern "C" fn on_extension_execute_callback(
+    function: *const c_char,
+    arguments: *const sys::V8Value,
+    size: usize,
+    result: *mut c_void,
+    context: *mut c_void,
+) {
+    if context.is_null() || function.is_null() || result.is_null() {
+        return;
+    }
+
+    let extension = unsafe { &*(context as *const V8Extension) };
+    let Some(function) = unsafe { CStr::from_ptr(function) }
+        .to_str()
+        .ok()
+        .and_then(|it| extension.functions.get(it))
+    else {
+        return;
+    };
+
+    let arguments = if arguments.is_null() {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(arguments, size) }
+            .iter()
+            .map(V8Value::from)
+            .collect::<Vec<_>>()
+    };
+
+    // A panic in the function is thrown to the script as an exception.
+    let value = catch_panic(|| function(&arguments))
+        .unwrap_or_else(|| Err("the native function panicked".to_string()));
+
+    match value {
+        Ok(value) => {
+            let mut raw = sys::V8Value {
+                type_: sys::V8ValueType::WEW_V8_VALUE_UNDEFINED,
+                bool_value: false,
+                int_value: 0,
+                double_value: 0.0,
+                data: null(),
+                size: 0,
+            };
+
+            match &value {
+                V8Value::Undefined => (),
+                V8Value::Null => raw.type_ = sys::V8ValueType::WEW_V8_VALUE_NULL,
+                V8Value::Bool(it) => {
+                    raw.type_ = sys::V8ValueType::WEW_V8_VALUE_BOOL;
+                    raw.bool_value = *it;
+                }
+                V8Value::Int(it) => {
+                    raw.type_ = sys::V8ValueType::WEW_V8_VALUE_INT;
+                    raw.int_value = *it;
+                }
+                V8Value::Double(it) => {
+                    raw.type_ = sys::V8ValueType::WEW_V8_VALUE_DOUBLE;
+                    raw.double_value = *it;
+                }
+                V8Value::String(it) => {
+                    raw.type_ = sys::V8ValueType::WEW_V8_VALUE_STRING;
+                    raw.data = it.as_ptr();
+                    raw.size = it.len();
+                }
+                V8Value::ArrayBuffer(it) => {
+                    raw.type_ = sys::V8ValueType::WEW_V8_VALUE_ARRAY_BUFFER;
+                    raw.data = it.as_ptr();
+                    raw.size = it.len();
+                }
+            }
+
+            unsafe { sys::v8_extension_set_result(result, raw) }
+        }
+        Err(message) => {
+            let message = CString::new(message).unwrap_or_default();
+
+            unsafe { sys::v8_extension_set_exception(result, message.as_raw()) }
+        }
+    }
+}
+
+impl From<&sys::V8Value> for V8Value {
+    fn from(value: &sys::V8Value) -> Self {
+        let data = || {
+            if value.data.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(value.data, value.size) }
+            }
+        };
+
+        match value.type_ {
+            sys::V8ValueType::WEW_V8_VALUE_NULL => Self::Null,
+            sys::V8ValueType::WEW_V8_VALUE_BOOL => Self::Bool(value.bool_value),
+            sys::V8ValueType::WEW_V8_VALUE_INT => Self::Int(value.int_value),
+            sys::V8ValueType::WEW_V8_VALUE_DOUBLE => Self::Double(value.double_value),
+            sys::V8ValueType::WEW_V8_VALUE_STRING => {
+                Self::String(String::from_utf8_lossy(data()).to_string())
+            }
+            sys::V8ValueType::WEW_V8_VALUE_ARRAY_BUFFER => Self::ArrayBuffer(data().to_vec()),
+            _ => Self::Undefined,
+        }
+    }
+}
+
+extern "C" fn on_context_created_callback(
+    v8_context: *mut c_void,
+    frame: sys::FrameInfo,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || v8_context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *const SubProcessContext) };
+        context.handler.on_context_created(&V8Context {
+            raw: v8_context,
+            frame: FrameInfo::from(&frame),
+            _p: PhantomData,
+        });
+    });
+}
+
+extern "C" fn on_context_released_callback(
+    v8_context: *mut c_void,
+    frame: sys::FrameInfo,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || v8_context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *const SubProcessContext) };
+        context.handler.on_context_released(&V8Context {
+            raw: v8_context,
+            frame: FrameInfo::from(&frame),
+            _p: PhantomData,
+        });
+    });
+}
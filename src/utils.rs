@@ -32,16 +32,27 @@
 //! ```
 
 use std::{
+    any::Any,
     cell::Cell,
     ffi::{CString, c_char, c_void},
+    panic::AssertUnwindSafe,
     ptr::{NonNull, null},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
 
-#[cfg(target_os = "macos")]
-use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::RwLock;
 
 #[cfg(target_os = "windows")]
-use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::{
+    Foundation::{CloseHandle, FILETIME},
+    System::{
+        ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+        Threading::{
+            GetCurrentThreadId, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        },
+    },
+};
 
 #[cfg(target_os = "macos")]
 use objc2::{
@@ -52,7 +63,35 @@ use objc2::{
 };
 
 #[cfg(target_os = "linux")]
-use libc::{SYS_gettid, c_long, getpid, syscall};
+use libc::{_SC_CLK_TCK, _SC_PAGESIZE, SYS_gettid, c_long, getpid, syscall, sysconf};
+
+/// The optional strings of the attributes are stored as `CString`, which serde
+/// serializes as bytes, so they are serialized as strings instead.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_c_string {
+    use std::ffi::CString;
+
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S>(value: &Option<CString>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(it) => serializer.serialize_some(&it.to_string_lossy()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<CString>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|it| CString::new(it).map_err(D::Error::custom))
+            .transpose()
+    }
+}
 
 /// A pointer type that is assumed to be thread-safe.
 ///
@@ -264,6 +303,156 @@ pub(crate) trait GetSharedRef {
     fn get_shared_ref(&self) -> Self::Ref;
 }
 
+/// What to do when a handler panics
+///
+/// The handlers are called from CEF, a panic cannot unwind through it, so it
+/// is caught when the handler returns. The panic is reported by the panic hook
+/// of the process in both cases, and passed to the handler set with
+/// **`set_panic_handler`**, which can forward it to an error reporter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Continue as if the handler returned a default value, the request is
+    /// failed, the message is ignored, etc.
+    #[default]
+    Continue,
+    /// Abort the process.
+    Abort,
+}
+
+static PANIC_ABORT: AtomicBool = AtomicBool::new(false);
+
+/// Set what to do when a handler panics.
+///
+/// The policy applies to all handlers of the current process, the default is
+/// `PanicPolicy::Continue`.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    PANIC_ABORT.store(policy == PanicPolicy::Abort, Ordering::Relaxed);
+}
+
+/// A panic caught in a handler
+pub struct HandlerPanic<'a> {
+    /// The name of the callback that panicked, such as
+    /// `wew::webview::on_state_change_callback`.
+    pub callback: &'a str,
+    /// The payload of the panic, as passed to `std::panic::panic_any`.
+    pub payload: &'a (dyn Any + Send),
+}
+
+impl HandlerPanic<'_> {
+    /// Get the message of the panic
+    ///
+    /// Returns `None` if the payload is not a string, which is the case for
+    /// the panics not raised by `panic!`.
+    pub fn message(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| self.payload.downcast_ref::<String>().map(|it| it.as_str()))
+    }
+}
+
+type PanicHandler = Box<dyn Fn(&HandlerPanic) + Send + Sync>;
+
+static PANIC_HANDLER: RwLock<Option<PanicHandler>> = RwLock::new(None);
+
+/// Set the function called when a handler panics.
+///
+/// The function receives the payload of the panic and the name of the
+/// callback, it is called before the panic policy is applied, so it can also
+/// report the panics that abort the process. It is called on the thread of the
+/// handler and a panic in it is ignored.
+pub fn set_panic_handler<F>(handler: F)
+where
+    F: Fn(&HandlerPanic) + Send + Sync + 'static,
+{
+    PANIC_HANDLER.write().replace(Box::new(handler));
+}
+
+/// Call a handler, catching the panic according to the panic policy.
+pub(crate) fn catch_panic<T, F>(func: F) -> Option<T>
+where
+    F: FnOnce() -> T,
+{
+    catch_panic_with_name(std::any::type_name::<F>(), func)
+}
+
+/// Call a handler, the name is the type name of the callback that is reported
+/// if the handler panics.
+pub(crate) fn catch_panic_with_name<T, F>(name: &str, func: F) -> Option<T>
+where
+    F: FnOnce() -> T,
+{
+    match std::panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            if let Some(handler) = PANIC_HANDLER.read().as_ref() {
+                // The callbacks are closures, the name of the closure type is the
+                // path of the function that created it.
+                let mut callback = name;
+                while let Some(it) = callback.strip_suffix("::{{closure}}") {
+                    callback = it;
+                }
+
+                let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    handler(&HandlerPanic {
+                        callback,
+                        payload: payload.as_ref(),
+                    })
+                }));
+            }
+
+            if PANIC_ABORT.load(Ordering::Relaxed) {
+                std::process::abort();
+            }
+
+            None
+        }
+    }
+}
+
+/// Get the sandbox information of the process.
+///
+/// On Windows, the sandbox information is created once in the executable and
+/// passed to both the runtime and **`wew::execute_subprocess`**, so a single
+/// executable can be used for the browser process and the sandboxed
+/// subprocesses. This function returns the same information, for example to
+/// start a CEF process by other means.
+///
+/// `None` is returned if the `sandbox` feature is disabled or the platform is
+/// not Windows.
+pub fn get_sandbox_info() -> Option<NonNull<c_void>> {
+    NonNull::new(unsafe { crate::sys::get_sandbox_info() })
+}
+
+thread_local! {
+    static IN_MESSAGE_LOOP: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Drive the message loop on the current thread.
+///
+/// The callbacks of the runtime are called from inside the message loop, so
+/// the message loop cannot be driven again from a callback. Returns `false`
+/// without calling the function if the current thread is already driving it.
+pub(crate) fn drive_message_loop<T>(func: T) -> bool
+where
+    T: FnOnce(),
+{
+    if IN_MESSAGE_LOOP.replace(true) {
+        return false;
+    }
+
+    func();
+
+    IN_MESSAGE_LOOP.set(false);
+    true
+}
+
+/// Check if the current thread is driving the message loop, such as from
+/// inside a callback of the runtime.
+pub(crate) fn is_in_message_loop() -> bool {
+    IN_MESSAGE_LOOP.get()
+}
+
 /// Post a task to the main thread for execution.
 ///
 /// Please note that you should not post blocking tasks, as this will severely
@@ -277,7 +466,9 @@ where
             return;
         }
 
-        (unsafe { Box::from_raw(context as *mut Box<dyn FnOnce() + Send + Sync + 'static>) })();
+        let task =
+            unsafe { Box::from_raw(context as *mut Box<dyn FnOnce() + Send + Sync + 'static>) };
+        catch_panic(task);
     }
 
     unsafe {
@@ -287,3 +478,105 @@ where
         )
     }
 }
+
+/// Post a task to the main thread that runs after the delay.
+///
+/// The task is dropped without running if it cannot be posted, such as when
+/// the runtime has been shut down.
+pub(crate) fn post_main_delayed<T>(delay: Duration, task: T) -> bool
+where
+    T: FnOnce() + Send + 'static,
+{
+    extern "C" fn post_main_delayed_callback(context: *mut c_void) {
+        if context.is_null() {
+            return;
+        }
+
+        let task = unsafe { Box::from_raw(context as *mut Box<dyn FnOnce() + Send + 'static>) };
+        catch_panic(task);
+    }
+
+    let context = Box::into_raw(Box::new(Box::new(task) as Box<dyn FnOnce() + Send>));
+    let posted = unsafe {
+        crate::sys::post_delayed_task_with_main_thread(
+            Some(post_main_delayed_callback),
+            context as _,
+            delay.as_millis() as _,
+        )
+    };
+
+    if !posted {
+        drop(unsafe { Box::from_raw(context) });
+    }
+
+    posted
+}
+
+/// Get the memory and CPU usage of a process.
+///
+/// Returns the resident memory in bytes and the CPU time spent in user and
+/// kernel mode, `None` is returned if the process does not exist or the
+/// platform is not supported.
+#[allow(unused_variables)]
+pub(crate) fn get_process_usage(pid: u32) -> Option<(u64, Duration)> {
+    #[cfg(target_os = "linux")]
+    {
+        let statm = std::fs::read_to_string(format!("/proc/{}/statm", pid)).ok()?;
+        let pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+
+        // The name of the executable is enclosed in parentheses and may contain
+        // spaces, the fields are counted from the closing parenthesis.
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let mut fields = stat.get(stat.rfind(')')? + 1..)?.split_whitespace();
+        let utime = fields.nth(11)?.parse::<u64>().ok()?;
+        let stime = fields.next()?.parse::<u64>().ok()?;
+
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) }.max(0) as u64;
+        let ticks = unsafe { sysconf(_SC_CLK_TCK) }.max(1) as u64;
+
+        Some((
+            pages * page_size,
+            Duration::from_secs_f64((utime + stime) as f64 / ticks as f64),
+        ))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+        let memory = unsafe {
+            K32GetProcessMemoryInfo(
+                handle,
+                &mut counters,
+                size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+            )
+        }
+        .as_bool()
+        .then_some(counters.WorkingSetSize as u64);
+
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        let times =
+            unsafe { GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user) };
+
+        let _ = unsafe { CloseHandle(handle) };
+
+        // The times are in 100 nanosecond units.
+        let to_nanos =
+            |it: FILETIME| ((it.dwHighDateTime as u64) << 32 | it.dwLowDateTime as u64) * 100;
+
+        times.ok()?;
+        Some((
+            memory?,
+            Duration::from_nanos(to_nanos(kernel) + to_nanos(user)),
+        ))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        None
+    }
+}
@@ -9,8 +9,13 @@
 //! declare global {
 //!     interface Window {
 //!         MessageTransport: {
-//!             on: (handle: (message: string) => void) => void;
-//!             send: (message: string) => void;
+//!             on: (handle: (message: string | ArrayBuffer) => void) => void;
+//!             send: (message: string | ArrayBuffer) => boolean;
+//!             channel: (name: string) => {
+//!                 name: string;
+//!                 on: (handle: (message: string | ArrayBuffer) => void) => void;
+//!                 send: (message: string | ArrayBuffer) => boolean;
+//!             };
 //!         };
 //!     }
 //! }
@@ -31,6 +36,81 @@
 //! receive messages sent by **`WebView::send_message`**. Sending and receiving
 //! messages are full-duplex and asynchronous.
 //!
+//! Every message received by the host comes with the **`FrameInfo`** of the
+//! frame that sent it, so messages from untrusted third-party iframes can be
+//! rejected by checking **`FrameInfo::origin`**.
+//!
+//! Messages are not lost because of the page still loading: the handler is
+//! installed when the webview is created, before any script runs, and messages
+//! sent to the page before it has called **`MessageTransport.on`** are kept
+//! and delivered in order once the listener is registered. By default at most
+//! 256 messages are kept for each channel of a frame, see
+//! **`WebViewAttributes::max_pending_messages`**, when more arrive before the
+//! listener is registered the oldest ones are dropped.
+//!
+//! Binary payloads can be sent as `ArrayBuffer`, they are received by
+//! **`WebViewHandler::on_binary_message`**, and
+//! **`WebView::send_binary_message`** delivers an `ArrayBuffer` to the web
+//! page, no encoding is performed in either direction.
+//!
+//! To protect the host from a page flooding it with messages, the number of
+//! messages that have not yet been handled can be limited with
+//! **`WebViewAttributes::max_in_flight_messages`**. A message is handled once
+//! the handler has returned, including when it runs on a dispatcher, the
+//! **`MessageOverflowPolicy`** decides whether the overflowing messages are
+//! dropped (`send` returns `false`) or an exception is thrown to the page.
+//!
+//! ## Channels
+//!
+//! Large applications can split their traffic into named channels instead of
+//! routing everything through one callback. **`WebView::channel`** registers a
+//! **`ChannelHandler`** for a channel and returns a **`Channel`** used to send
+//! messages on it, the page side counterpart is
+//! **`MessageTransport.channel(name)`**.
+//!
+//! ```typescript
+//! const state = window.MessageTransport.channel("state");
+//!
+//! state.on((message) => {
+//!     console.log("Received state from Rust:", message);
+//! });
+//!
+//! state.send("Send state to Rust");
+//! ```
+//!
+//! ## Events
+//!
+//! In addition to raw messages, Rust can push named events to the web page
+//! through **`WebView::emit`**, the payload is serialized as JSON and decoded
+//! before being passed to the listeners.
+//!
+//! ```typescript
+//! declare global {
+//!     interface Window {
+//!         wew: {
+//!             version: string;
+//!             ready: Promise<void>;
+//!             postMessage: (message: string | ArrayBuffer) => boolean;
+//!             on: (event: string, handle: (payload: any) => void) => void;
+//!         };
+//!     }
+//! }
+//! ```
+//!
+//! Usage example:
+//!
+//! ```typescript
+//! window.wew.on("state", (payload) => {
+//!     console.log("Received state from Rust:", payload);
+//! });
+//! ```
+//!
+//! **`window.wew`** is the versioned entry point for page authors:
+//! `version` is the version of this library, `ready` is resolved once the
+//! bridge is available, and `postMessage` is the same as
+//! **`MessageTransport.send`**. Pages that also run outside of this library
+//! can detect the host with `await window.wew?.ready`.
+//!
 //! ## WebView Types
 //!
 //! There are two types of runtime:
@@ -60,31 +140,61 @@
 //! Chromium-style window.
 
 use std::{
+    any::type_name,
+    collections::HashMap,
     ffi::{CStr, CString, c_char, c_int, c_void},
     marker::PhantomData,
-    mem::MaybeUninit,
     ops::Deref,
-    ptr::null,
-    sync::Arc,
+    path::{Path, PathBuf},
+    ptr::{NonNull, null},
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        mpsc::Receiver,
+    },
+    time::Duration,
 };
 
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use raw_window_handle::RawWindowHandle;
+use serde::Serialize;
 
 use crate::{
-    Error, Rect, WindowlessRenderWebView,
+    Error, NativeWindowWebView, Operation, Rect,
+    dispatcher::Dispatcher,
+    dom::{self, DOM_CHANNEL, DomQueries, DomQuery, ElementInfo},
     events::{
-        IMEAction, KeyboardEvent, KeyboardEventType, KeyboardModifiers, MouseButton, MouseEvent,
+        DragData, DragOperations, KeyboardEvent, KeyboardEventType, KeyboardModifiers, MouseButton,
+        PointerType, Position, TouchEventType,
     },
-    request::{CustomRequestHandlerFactory, ICustomRequestHandlerFactory},
-    runtime::{IRuntime, Runtime},
+    metrics::{Metrics, MetricsCollector},
+    runtime::{IRuntime, LogSeverity, Runtime},
+    screencast::{Screencast, ScreencastOptions, on_screencast_frame},
+    stream::{EventSubscribers, WebViewEvent, WebViewEvents},
     sys,
-    utils::{AnyStringCast, GetSharedRef, ThreadSafePointer},
+    utils::{
+        AnyStringCast, GetSharedRef, ThreadSafePointer, catch_panic, catch_panic_with_name,
+        get_process_usage,
+    },
+};
+
+#[cfg(feature = "windowless")]
+use crate::{
+    WindowlessRenderWebView,
+    events::{IMEAction, InputEvent, InputRecorder, MouseEvent, TouchEvent},
+    replay::Recording,
+    utils::post_main,
+};
+
+#[cfg(feature = "request")]
+use crate::request::{
+    CustomRequestHandlerFactory, RouteHandler, RoutedRequestHandlerFactory, Router,
 };
 
 /// Represents the type of cursor
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorType {
     Pointer = 0,
     Cross = 1,
@@ -162,6 +272,63 @@ pub struct Frame<'a> {
     pub height: u32,
 }
 
+/// Represents a rectangular area of the screen
+///
+/// The position is relative to the primary monitor, so it can be negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScreenRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Represents the monitor the webview is displayed on
+///
+/// This is used in windowless rendering mode, see
+/// **`WindowlessRenderWebViewHandler::on_screen_info`**.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenInfo {
+    /// The ratio between physical pixels and DIPs, 1.0 is 96 DPI.
+    pub device_scale_factor: f32,
+    /// The color depth in bits per pixel.
+    pub depth: u32,
+    /// The color depth in bits per color component.
+    pub depth_per_component: u32,
+    /// Whether the monitor is monochrome.
+    pub is_monochrome: bool,
+    /// The rectangle of the monitor in screen DIP coordinates, the rectangle
+    /// of the view is used if it is empty.
+    pub rect: ScreenRect,
+    /// The area of the monitor available to windows, excluding the task bar
+    /// and the docks, the rectangle of the view is used if it is empty.
+    pub available_rect: ScreenRect,
+}
+
+impl Default for ScreenInfo {
+    fn default() -> Self {
+        Self {
+            device_scale_factor: 1.0,
+            depth: 24,
+            depth_per_component: 8,
+            is_monochrome: false,
+            rect: ScreenRect::default(),
+            available_rect: ScreenRect::default(),
+        }
+    }
+}
+
+impl From<ScreenRect> for sys::Rect {
+    fn from(value: ScreenRect) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            width: value.width as _,
+            height: value.height as _,
+        }
+    }
+}
+
 impl std::fmt::Debug for Frame<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Frame")
@@ -174,6 +341,29 @@ impl std::fmt::Debug for Frame<'_> {
     }
 }
 
+/// Represents a frame in the frame tree of a web page
+///
+/// A web page has one main frame, and an additional frame for every iframe
+/// embedded in it.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameInfo {
+    /// The globally unique identifier of the frame
+    pub id: String,
+    /// The name of the frame, empty for the main frame
+    pub name: String,
+    /// The URL currently loaded in the frame
+    pub url: String,
+    /// The origin of the URL loaded in the frame, such as
+    /// `https://example.com`, an opaque origin is serialized as `null`
+    ///
+    /// The URL and the origin are taken from the browser process, the page
+    /// cannot claim another one.
+    pub origin: String,
+    /// Whether the frame is the main frame
+    pub is_main: bool,
+}
+
 /// Represents the state of a web page
 ///
 /// The order of events is as follows:
@@ -186,47 +376,560 @@ impl std::fmt::Debug for Frame<'_> {
 /// Regardless of whether the loading exists an error, the `Loaded` event is
 /// triggered, the difference is that if the loading error occurs, the
 /// `LoadError` event is triggered first.
-#[repr(u32)]
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+///
+/// The loading states are reported for every frame, `is_main_frame` is
+/// `true` for the main frame.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WebViewState {
     /// The web page is before loading
-    BeforeLoad = 1,
+    BeforeLoad { url: String, is_main_frame: bool },
     /// The web page is loaded
-    Loaded = 2,
+    ///
+    /// `http_status` is the HTTP status code of the response, `0` if the page
+    /// was not loaded over HTTP.
+    Loaded {
+        url: String,
+        is_main_frame: bool,
+        http_status: i32,
+    },
     /// The web page is loading error
-    LoadError = 3,
+    ///
+    /// `url` is the URL that failed to load and `code` is the network error
+    /// code of Chromium, such as `-105` for a name that cannot be resolved,
+    /// `text` describes it.
+    LoadError {
+        url: String,
+        is_main_frame: bool,
+        code: i32,
+        text: String,
+    },
     /// The web page is requesting to close
-    RequestClose = 4,
+    RequestClose,
     /// The web page is closed
-    Close = 5,
+    Close,
+}
+
+/// Represents an uncaught exception of a web page
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageError {
+    /// The exception message
+    pub message: String,
+    /// The URL of the script that threw the exception
+    pub source: String,
+    /// The line number where the exception was thrown, 1-based
+    pub line: u32,
+    /// The column number where the exception was thrown, 1-based
+    pub column: u32,
+    /// The stack trace, one frame per line
+    pub stack: String,
+}
+
+/// Represents an item of the outline of a PDF document
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PdfOutlineItem {
+    pub title: String,
+    /// The target page starting from 0, if the item points into the document
+    pub page: Option<u32>,
+    /// The target URI, if the item is a link
+    pub uri: Option<String>,
+    pub children: Vec<PdfOutlineItem>,
+}
+
+impl PdfOutlineItem {
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            title: value.get("title")?.as_str()?.to_string(),
+            page: value
+                .get("page")
+                .and_then(serde_json::Value::as_u64)
+                .map(|it| it as u32),
+            uri: value
+                .get("uri")
+                .and_then(serde_json::Value::as_str)
+                .map(|it| it.to_string()),
+            children: Self::from_array(value.get("children")),
+        })
+    }
+
+    fn from_array(value: Option<&serde_json::Value>) -> Vec<Self> {
+        value
+            .and_then(serde_json::Value::as_array)
+            .map(|it| it.iter().filter_map(Self::from_value).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Represents a document loaded by the built-in PDF viewer
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PdfDocument {
+    /// The URL of the document
+    pub url: String,
+    pub page_count: u32,
+    /// The outline of the document, empty if it has no outline
+    pub outline: Vec<PdfOutlineItem>,
+}
+
+/// Represents the HTTP response of a page
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpResponse {
+    /// The URL of the response, after all redirects
+    pub url: String,
+    /// The HTTP status code, 0 if the request failed before a response was
+    /// received
+    pub status: u16,
+    pub status_text: String,
+    pub mime_type: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Represents a decoded image
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels in RGBA format
+    pub data: Vec<u8>,
+}
+
+/// Represents the layout of the main frame of a web page
+///
+/// All values are in CSS pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayoutMetrics {
+    /// The width of the content, including the parts scrolled out of view
+    pub content_width: f64,
+    /// The height of the content, including the parts scrolled out of view
+    pub content_height: f64,
+    /// The width of the viewport
+    pub viewport_width: f64,
+    /// The height of the viewport
+    pub viewport_height: f64,
+    /// The horizontal scroll offset of the viewport
+    pub scroll_x: f64,
+    /// The vertical scroll offset of the viewport
+    pub scroll_y: f64,
+}
+
+/// Represents the render process of a web page
+///
+/// Pages of the same site may share a render process, in which case the
+/// memory and CPU usage include all of them.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct ProcessInfo {
+    /// The process id of the render process
+    pub pid: u32,
+    /// The resident memory in bytes, this is only available on Windows and
+    /// Linux.
+    pub memory: Option<u64>,
+    /// The CPU time spent in user and kernel mode since the process was
+    /// started, this is only available on Windows and Linux.
+    ///
+    /// The CPU usage is the difference between two samples divided by the
+    /// time elapsed between them.
+    pub cpu_time: Option<Duration>,
+}
+
+/// Represents the reason the render process terminated
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TerminationStatus {
+    /// Non-zero exit status.
+    AbnormalTermination,
+    /// SIGKILL or task manager kill.
+    ProcessWasKilled,
+    /// Segmentation fault.
+    ProcessCrashed,
+    /// Out of memory.
+    ProcessOom,
+    /// Failed to launch the process.
+    LaunchFailed,
+    /// The code integrity check failed on Windows.
+    IntegrityFailure,
+}
+
+/// The reason the web page takes the focus
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FocusSource {
+    /// The page focused an element, such as by calling `focus()`.
+    Navigation,
+    /// The focus was requested by the system, such as a click in the page.
+    System,
+}
+
+/// The forced colors mode of the web page
+///
+/// In forced colors mode, such as with a high contrast theme of Windows, the
+/// colors of the page are replaced with a limited palette and the selection
+/// uses the highlight colors of the palette.
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForcedColors {
+    /// Follow the high contrast setting of the system.
+    #[default]
+    System,
+    /// The page is rendered in forced colors mode, the `forced-colors` media
+    /// feature is `active` and `prefers-contrast` is `more`.
+    Active,
+    /// The page is never rendered in forced colors mode.
+    None,
+}
+
+/// An access of the web page to the clipboard
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipboardAccess {
+    /// The page reads the clipboard with `navigator.clipboard.read` or
+    /// `readText`.
+    Read,
+    /// The page writes the clipboard with `navigator.clipboard.write` or
+    /// `writeText`.
+    Write,
+}
+
+/// The decision for an access of the web page to the clipboard
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipboardPolicy {
+    /// The access is allowed.
+    Allow,
+    /// The promise of the page is rejected with a `NotAllowedError`.
+    Deny,
+    /// The access is allowed, but the HTML content is replaced with its plain
+    /// text.
+    SanitizeHtml,
+}
+
+/// Options of a search in the web page
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FindOptions {
+    /// Search forward, or backward if `false`.
+    pub forward: bool,
+    /// Whether the search is case sensitive.
+    pub match_case: bool,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self {
+            forward: true,
+            match_case: false,
+        }
+    }
+}
+
+/// Message overflow policy
+///
+/// Decide what to do when the web page sends messages faster than the host
+/// handles them.
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MessageOverflowPolicy {
+    /// The message is discarded and `MessageTransport.send` returns `false`.
+    #[default]
+    Drop,
+    /// An exception is thrown to the web page.
+    Throw,
 }
 
 /// WebView handler
 ///
-/// This trait is used to handle web view events.
+/// This trait is used to handle web view events. Every callback receives the
+/// **`WebViewId`** of the webview, so that one handler can be shared by
+/// multiple webviews and still tell them apart.
 #[allow(unused)]
 pub trait WebViewHandler: Send + Sync {
     /// Called when the cursor changes
     ///
     /// When the web page wants to change the mouse pointer style, it will be
     /// triggered, such as moving to a link.
-    fn on_cursor_change(&self, ty: CursorType) {}
+    fn on_cursor_change(&self, id: WebViewId, ty: CursorType) {}
     /// Called when the web page state changes
     ///
     /// You need to pay attention to status changes, determine whether loading
     /// was successful, and monitor events related to the page closing.
-    fn on_state_change(&self, state: WebViewState) {}
+    fn on_state_change(&self, id: WebViewId, state: WebViewState) {}
+
+    /// Called after the webview is closed
+    ///
+    /// This is called after **`WebViewState::Close`**, such as when the web
+    /// page closes itself by calling `window.close()`. No other callback is
+    /// called after this one, and the input methods of the webview return
+    /// **`Error::WebViewClosed`**.
+    fn on_closed(&self, id: WebViewId) {}
 
     /// Called when the title changes
-    fn on_title_change(&self, title: &str) {}
+    fn on_title_change(&self, id: WebViewId, title: &str) {}
 
     /// Called when the fullscreen state changes
-    fn on_fullscreen_change(&self, fullscreen: bool) {}
+    fn on_fullscreen_change(&self, id: WebViewId, fullscreen: bool) {}
+
+    /// Called when the render process terminated unexpectedly
+    ///
+    /// The web page stops responding until it is reloaded, see
+    /// **`WebViewAttributes::auto_reload_max_attempts`** for reloading it
+    /// automatically.
+    fn on_render_process_terminated(
+        &self,
+        id: WebViewId,
+        status: TerminationStatus,
+        error_code: i32,
+    ) {
+    }
+
+    /// Called when an exception is not caught by the web page
+    ///
+    /// This callback is called for every uncaught exception of every frame,
+    /// which makes it suitable for capturing frontend crashes.
+    fn on_page_error(&self, id: WebViewId, error: &PageError, frame: &FrameInfo) {}
+
+    /// Called when the web page writes to the console
+    ///
+    /// This callback is called for `console.log`, `console.error` and the other
+    /// console methods, which makes it possible to forward the output of the
+    /// web page to the logging of the application. `source` is the URL of the
+    /// script and `line` is the line number in it.
+    fn on_console_message(
+        &self,
+        id: WebViewId,
+        level: LogSeverity,
+        message: &str,
+        source: &str,
+        line: u32,
+    ) {
+    }
+
+    /// Called when the favicon changes
+    ///
+    /// `urls` are the URLs of the icons declared by the web page, use
+    /// **`WebView::download_favicon`** to get the image of an icon.
+    fn on_favicon_change(&self, id: WebViewId, urls: &[String]) {}
+
+    /// Called when the status message changes
+    ///
+    /// The status message is usually the target of the hovered link, it is
+    /// empty when the mouse leaves the link.
+    fn on_status_message(&self, id: WebViewId, message: &str) {}
+
+    /// Called when the main document has been loaded
+    ///
+    /// This callback provides the HTTP status and the headers of the main
+    /// document, so that error responses such as 401 or 500 can be detected,
+    /// they are reported as successful loads by **`on_state_change`**.
+    ///
+    /// Note that this callback is called on the IO thread.
+    fn on_main_frame_response(&self, id: WebViewId, response: &HttpResponse) {}
+
+    /// Called when the built-in PDF viewer has loaded a document
+    ///
+    /// This callback provides the number of pages and the outline of the
+    /// document, so that the host can show its own toolbar or table of
+    /// contents around the viewer. It is called for the documents shown in
+    /// the whole page as well as for the embedded ones.
+    ///
+    /// It is not called if **`WebViewAttributes::pdf_viewer`** is disabled.
+    /// The information is read from the internals of the PDF viewer of the
+    /// bundled CEF 137, it is not called with other versions that have changed
+    /// them, or if the document has not loaded within 30 seconds.
+    fn on_pdf_load(&self, id: WebViewId, document: &PdfDocument) {}
+
+    /// Called when the focus leaves the web page
+    ///
+    /// `next` is `true` when the user tabs forward out of the last element of
+    /// the page, and `false` when tabbing backward out of the first one. The
+    /// host should move the focus to the next or previous control of its UI.
+    fn on_take_focus(&self, id: WebViewId, next: bool) {}
+
+    /// Called when the web page is about to take the focus
+    ///
+    /// Return `true` to cancel it, such as to keep the focus on a control of
+    /// the host UI while the page is loading.
+    ///
+    /// This function is called synchronously on the UI thread, even if a
+    /// dispatcher is set.
+    fn on_set_focus(&self, id: WebViewId, source: FocusSource) -> bool {
+        false
+    }
+
+    /// Called when the web page has received the focus
+    fn on_got_focus(&self, id: WebViewId) {}
+
+    /// Called with the results of **`WebView::find`**
+    ///
+    /// `active_index` is the position of the active match starting from 1, it
+    /// is 0 if there is no match. The selection rectangle is the position of
+    /// the active match in the view, so that a find bar does not cover it.
+    ///
+    /// This callback is called multiple times while the search progresses,
+    /// the count may grow until the search is complete.
+    fn on_find_result(&self, id: WebViewId, count: u32, active_index: u32, selection_rect: Rect) {}
+
+    /// Called when the web page is about to be printed
+    ///
+    /// This is called before the print dialog is shown, both for
+    /// **`WebView::print`** and for `window.print()`.
+    fn on_print_start(&self, id: WebViewId) {}
+
+    /// Called when the printing is finished
+    ///
+    /// This is called when the print dialog is closed, whether the page was
+    /// printed or the printing was cancelled.
+    fn on_print_finished(&self, id: WebViewId) {}
+
+    /// Called to show the print dialog
+    ///
+    /// This is only called on Linux, where the print dialog is provided by the
+    /// application. Return `false` to cancel the printing, the page is printed
+    /// with the default settings otherwise.
+    fn on_print_dialog(&self, id: WebViewId, has_selection: bool) -> bool {
+        true
+    }
+
+    /// Called with the document to print
+    ///
+    /// This is only called on Linux, the page is printed to a PDF file that is
+    /// deleted after this call, it should be copied to be kept. Return `true`
+    /// if the document has been handled, otherwise it is sent to the default
+    /// printer of the system with `lp`.
+    fn on_print_job(&self, id: WebViewId, document_name: &str, path: &Path) -> bool {
+        false
+    }
+
+    /// Called when a frame accesses the clipboard
+    ///
+    /// This is only called if **`WebViewAttributes::clipboard_policy`** is
+    /// enabled, for each call of the async clipboard API, `origin` is the
+    /// origin of the frame. The access is still subject to the permissions of
+    /// the browser once it is allowed.
+    ///
+    /// The decision is advisory, it is applied by the page, see
+    /// **`WebViewAttributesBuilder::with_clipboard_policy`**.
+    ///
+    /// This function is called synchronously on the UI thread, even if a
+    /// dispatcher is set.
+    fn on_clipboard_access(
+        &self,
+        id: WebViewId,
+        origin: &str,
+        access: ClipboardAccess,
+    ) -> ClipboardPolicy {
+        ClipboardPolicy::Allow
+    }
 
     /// Called when a message is received
     ///
     /// This callback is called when a message is received from the web page.
-    fn on_message(&self, message: &str) {}
+    ///
+    /// The frame is the sender of the message, its URL is the URL of the
+    /// frame known to the browser process when the message is handled, use
+    /// **`FrameInfo::origin`** to reject messages from untrusted iframes.
+    fn on_message(&self, id: WebViewId, message: &str, frame: &FrameInfo) {}
+
+    /// Called when a binary message is received
+    ///
+    /// This callback is called when an `ArrayBuffer` is sent from the web
+    /// page.
+    fn on_binary_message(&self, id: WebViewId, message: &[u8], frame: &FrameInfo) {}
+}
+
+/// Channel handler
+///
+/// This trait is used to handle the messages of a named channel, see
+/// **`WebView::channel`**.
+#[allow(unused)]
+pub trait ChannelHandler: Send + Sync {
+    /// Called when a message is received on the channel
+    fn on_message(&self, message: &str, frame: &FrameInfo) {}
+
+    /// Called when a binary message is received on the channel
+    fn on_binary_message(&self, message: &[u8], frame: &FrameInfo) {}
+}
+
+/// Represents a named message channel
+///
+/// Created by **`WebView::channel`**, dropping the channel unregisters its
+/// handler, and the messages received on it afterwards are discarded.
+pub struct Channel {
+    name: String,
+    handler: Arc<dyn ChannelHandler>,
+    webview: Weak<IWebView>,
+}
+
+impl Channel {
+    /// Get the channel name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Send a message
+    ///
+    /// This function is used to send a message to the listener registered with
+    /// **`MessageTransport.channel(name).on`** in the main frame of the web
+    /// page. Until the listener is registered, only the last messages of the
+    /// channel are kept for it, see
+    /// **`WebViewAttributes::max_pending_messages`**.
+    pub fn send(&self, message: &str) {
+        if let Some(webview) = self.webview.upgrade() {
+            let name = CString::new(self.name.as_str()).unwrap();
+            let message = CString::new(message).unwrap();
+
+            unsafe {
+                sys::webview_send_message(
+                    webview.raw.lock().as_ptr(),
+                    null(),
+                    name.as_raw(),
+                    message.as_raw(),
+                );
+            }
+        }
+    }
+
+    /// Send a binary message
+    ///
+    /// This function is used to send a binary message on the channel, the web
+    /// page receives it as an `ArrayBuffer`.
+    pub fn send_binary(&self, message: &[u8]) {
+        if let Some(webview) = self.webview.upgrade() {
+            let name = CString::new(self.name.as_str()).unwrap();
+
+            unsafe {
+                sys::webview_send_binary_message(
+                    webview.raw.lock().as_ptr(),
+                    null(),
+                    name.as_raw(),
+                    message.as_ptr(),
+                    message.len(),
+                );
+            }
+        }
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        if let Some(webview) = self.webview.upgrade() {
+            let mut channels = webview.context().channels.write();
+
+            // The channel may have been registered again with a new handler,
+            // which must not be removed.
+            if channels
+                .get(&self.name)
+                .is_some_and(|it| Arc::ptr_eq(it, &self.handler))
+            {
+                channels.remove(&self.name);
+            }
+        }
+    }
 }
 
 /// Windowless render web view handler
@@ -237,7 +940,13 @@ pub trait WindowlessRenderWebViewHandler: WebViewHandler {
     /// Called when the IME composition rectangle changes
     ///
     /// When the IME region changes, you should notify the external window.
-    fn on_ime_rect(&self, rect: Rect) {}
+    fn on_ime_rect(&self, id: WebViewId, rect: Rect) {}
+
+    /// Called when the zoom level changes with ctrl and the mouse wheel
+    ///
+    /// This is only called if **`WebViewAttributes::ctrl_wheel_zoom`** is
+    /// enabled, see **`WebView::set_zoom_level`** for the unit of the level.
+    fn on_zoom_change(&self, id: WebViewId, level: f64) {}
 
     /// Push a new frame when rendering changes
     ///
@@ -249,17 +958,197 @@ pub trait WindowlessRenderWebViewHandler: WebViewHandler {
     ///
     /// It should be noted that if the webview is resized, the width and height
     /// of the texture will also change.
-    fn on_frame(&self, frame: &Frame) {}
+    fn on_frame(&self, id: WebViewId, frame: &Frame) {}
+
+    /// Get the screen info of the monitor the webview is displayed on
+    ///
+    /// Without a screen info, CEF uses the device scale factor of the
+    /// attributes and guesses the monitor from the window handle, which places
+    /// popups and context menus wrongly when the monitors have different
+    /// scales. Return `None` to keep the default behavior.
+    ///
+    /// This function is called synchronously on the UI thread, even if a
+    /// dispatcher is set. Call **`WebView::notify_screen_info_changed`** when
+    /// the returned value changes.
+    fn on_screen_info(&self, id: WebViewId) -> Option<ScreenInfo> {
+        None
+    }
+
+    /// Called when the page starts a drag
+    ///
+    /// The page drags an image, a selected text or a draggable element,
+    /// `operations` are the operations allowed by the page. Return `true` to
+    /// run the drag loop of the operating system with the data, then call
+    /// **`WebView::drag_source_ended`** once it is completed. Return `false` to
+    /// cancel the drag.
+    ///
+    /// This function is called synchronously on the UI thread, even if a
+    /// dispatcher is set.
+    fn on_start_dragging(
+        &self,
+        id: WebViewId,
+        data: &DragData,
+        operations: DragOperations,
+        pos: Position,
+    ) -> bool {
+        false
+    }
+
+    /// Called when the operation accepted by the page for the drag changes
+    ///
+    /// The cursor of the drag loop should be updated to show the operation,
+    /// **`WebViewHandler::on_cursor_change`** is also called with the matching
+    /// drag cursor.
+    fn on_update_drag_cursor(&self, id: WebViewId, operation: DragOperations) {}
+}
+
+/// Default font families
+///
+/// The font family of each generic family, such as `serif` in CSS, the
+/// platform default is used if not set.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct FontFamilies {
+    /// The font family used when the page does not specify one.
+    pub standard: Option<String>,
+    /// The `monospace` font family.
+    pub fixed: Option<String>,
+    /// The `serif` font family.
+    pub serif: Option<String>,
+    /// The `sans-serif` font family.
+    pub sans_serif: Option<String>,
+    /// The `cursive` font family.
+    pub cursive: Option<String>,
+    /// The `fantasy` font family.
+    pub fantasy: Option<String>,
+}
+
+/// User agent client hints
+///
+/// These values are exposed to the web page through `navigator.userAgentData`
+/// and the `Sec-CH-UA-*` request headers.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct UserAgentMetadata {
+    /// The brands and their versions, such as `("Kyle", "1")`.
+    pub brands: Vec<(String, String)>,
+    /// The platform, such as `Windows`.
+    pub platform: String,
+    /// The platform version, such as `10.0.0`.
+    pub platform_version: String,
+    /// The CPU architecture, such as `x86`.
+    pub architecture: String,
+    /// The device model, usually empty on desktop platforms.
+    pub model: String,
+    /// Whether the device is a mobile device.
+    pub mobile: bool,
+}
+
+impl UserAgentMetadata {
+    fn to_json(&self) -> String {
+        serde_json::json!({
+            "brands": self
+                .brands
+                .iter()
+                .map(|(brand, version)| serde_json::json!({ "brand": brand, "version": version }))
+                .collect::<Vec<_>>(),
+            "platform": self.platform,
+            "platformVersion": self.platform_version,
+            "architecture": self.architecture,
+            "model": self.model,
+            "mobile": self.mobile,
+        })
+        .to_string()
+    }
+}
+
+/// Kiosk policy
+///
+/// The restrictions of a webview used for kiosks and digital signage, they are
+/// applied together with **`WebViewAttributesBuilder::with_kiosk_policy`**.
+/// The default policy enables all restrictions and allows all origins.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct KioskPolicy {
+    /// Discard popups instead of loading them in the webview.
+    pub block_popups: bool,
+    /// Cancel downloads.
+    pub block_downloads: bool,
+    /// Prevent the developer tools from being opened, both by
+    /// **`WebView::devtools_enabled`** and by the keyboard shortcuts.
+    pub block_devtools: bool,
+    /// Never show the context menu, not even for selections and editable
+    /// fields.
+    pub disable_context_menu: bool,
+    /// Swallow the shortcuts that switch or close applications, such as
+    /// `Alt+Tab`, `Alt+F4` and `Cmd+Q`.
+    ///
+    /// Only the shortcuts that reach the browser can be swallowed, the
+    /// shortcuts that the operating system handles itself must be disabled in
+    /// the system settings.
+    pub block_app_switch_shortcuts: bool,
+    /// The origins the page can navigate to, such as `https://example.com`,
+    /// navigations of the main frame to other origins are cancelled. All
+    /// origins are allowed if it is empty.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for KioskPolicy {
+    fn default() -> Self {
+        Self {
+            block_popups: true,
+            block_downloads: true,
+            block_devtools: true,
+            disable_context_menu: true,
+            block_app_switch_shortcuts: true,
+            allowed_origins: Vec::new(),
+        }
+    }
 }
 
 /// WebView configuration attributes
+///
+/// With the `serde` feature, the attributes can be loaded from a configuration
+/// file, the missing fields keep their default values. The request handler
+/// factory and the window handle are not serialized.
+///
+/// The autofill and the password manager cannot be disabled per webview, they
+/// are preferences of the browser profile shared by all webviews, see
+/// **`RuntimeAttributesBuilder::with_disable_autofill`** and
+/// **`RuntimeAttributesBuilder::with_disable_password_manager`**.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct WebViewAttributes {
     /// Request handler factory.
+    #[cfg(feature = "request")]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub request_handler_factory: Option<CustomRequestHandlerFactory>,
     /// External native window handle.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub window_handle: Option<RawWindowHandle>,
     /// The maximum rate in frames per second (fps).
     pub windowless_frame_rate: u32,
+    /// Whether to merge bursts of mouse moves into the latest position in
+    /// windowless rendering mode.
+    pub coalesce_mouse_moves: bool,
+    /// Whether to zoom the page with the mouse wheel while ctrl is held in
+    /// windowless rendering mode.
+    pub ctrl_wheel_zoom: bool,
     /// window size width.
     pub width: u32,
     /// window size height.
@@ -274,7 +1163,17 @@ pub struct WebViewAttributes {
     pub minimum_font_size: u32,
     /// The minimum logical font size.
     pub minimum_logical_font_size: u32,
+    /// The default font families.
+    pub font_families: FontFamilies,
+    /// The default text encoding.
+    pub default_encoding: Option<String>,
     /// Controls whether WebGL is enabled.
+    ///
+    /// There are no per-webview switches for the 2D canvas acceleration and
+    /// the hardware video decoding, Chromium can only disable them for the
+    /// whole process, see
+    /// **`RuntimeAttributesBuilder::with_disable_accelerated_2d_canvas`** and
+    /// **`RuntimeAttributesBuilder::with_disable_hardware_video_decode`**.
     pub webgl: bool,
     /// Controls whether databases are enabled.
     pub databases: bool,
@@ -290,46 +1189,218 @@ pub struct WebViewAttributes {
     pub javascript_dom_paste: bool,
     /// Controls whether local storage can be used.
     pub local_storage: bool,
+    /// Controls whether images are loaded.
+    pub images: bool,
     /// END values that map to WebPreferences settings.
-    pub background_color: u32,
+    ///
+    /// The background color in ARGB format, the runtime's background color is
+    /// used if not set.
+    pub background_color: Option<u32>,
+    /// The maximum number of messages sent by the web page that have not yet
+    /// been handled by the host, 0 means unlimited.
+    pub max_in_flight_messages: u32,
+    /// What to do when the number of in-flight messages reaches the limit.
+    pub message_overflow_policy: MessageOverflowPolicy,
+    /// The maximum number of messages kept for each channel of a frame until
+    /// the web page has registered a listener, 0 means no message is kept.
+    pub max_pending_messages: u32,
+    /// The maximum number of times the page is reloaded after the render
+    /// process terminated, 0 disables the automatic reload.
+    pub auto_reload_max_attempts: u32,
+    /// The delay before the first automatic reload, doubled for every
+    /// following attempt.
+    pub auto_reload_delay: Duration,
+    /// The user agent, the runtime's user agent is used if not set.
+    pub user_agent: Option<String>,
+    /// The user agent client hints, the runtime's client hints are used if not
+    /// set.
+    pub user_agent_metadata: Option<UserAgentMetadata>,
+    /// Whether to collect the rendering metrics, see **`WebView::metrics`**.
+    pub metrics: bool,
+    /// The restrictions of a kiosk, no restriction is applied if not set.
+    pub kiosk_policy: Option<KioskPolicy>,
+    /// Whether the accesses to the async clipboard API are checked with
+    /// **`WebViewHandler::on_clipboard_access`**, the check is advisory.
+    pub clipboard_policy: bool,
+    /// The forced colors mode, the system setting is followed by default.
+    pub forced_colors: ForcedColors,
+    /// Whether PDF documents are shown in the built-in PDF viewer.
+    pub pdf_viewer: bool,
 }
 
 unsafe impl Send for WebViewAttributes {}
 unsafe impl Sync for WebViewAttributes {}
 
-impl Default for WebViewAttributes {
-    fn default() -> Self {
-        Self {
-            width: 800,
-            height: 600,
-            window_handle: None,
-            device_scale_factor: 1.0,
-            windowless_frame_rate: 30,
-            default_font_size: 12,
-            default_fixed_font_size: 12,
-            javascript: true,
-            local_storage: true,
-            javascript_access_clipboard: false,
-            request_handler_factory: None,
-            webgl: false,
-            databases: false,
-            javascript_close_windows: false,
-            javascript_dom_paste: false,
-            background_color: 0xFFFFFFFF,
+impl WebViewAttributes {
+    /// Validate the attributes
+    ///
+    /// This function is used to reject the values that CEF does not accept,
+    /// it is called by **`WebViewAttributesBuilder::build`** and again when
+    /// the webview is created, since the fields can also be set directly.
+    ///
+    /// The attributes do not know the rendering mode of the runtime, the
+    /// options that only apply to the windowless rendering mode are checked
+    /// by **`WebViewAttributes::validate_native_window`** when a webview with
+    /// a native window is created.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |name, reason| Err(Error::InvalidAttribute { name, reason });
+        let has_nul = |value: &Option<String>| value.as_ref().is_some_and(|it| it.contains('\0'));
+
+        if self.width == 0 {
+            return invalid("width", "must not be zero");
+        }
+
+        if self.height == 0 {
+            return invalid("height", "must not be zero");
+        }
+
+        if !(self.device_scale_factor.is_finite() && self.device_scale_factor > 0.0) {
+            return invalid("device_scale_factor", "must be a positive number");
+        }
+
+        if !(1..=240).contains(&self.windowless_frame_rate) {
+            return invalid("windowless_frame_rate", "must be between 1 and 240");
+        }
+
+        if let Some(handle) = &self.window_handle
+            && !is_supported_window_handle(handle)
+        {
+            return invalid(
+                "window_handle",
+                "the handle type is not supported on this platform",
+            );
+        }
+
+        let fonts = &self.font_families;
+        if [
+            &fonts.standard,
+            &fonts.fixed,
+            &fonts.serif,
+            &fonts.sans_serif,
+            &fonts.cursive,
+            &fonts.fantasy,
+        ]
+        .into_iter()
+        .any(has_nul)
+        {
+            return invalid("font_families", "must not contain a NUL character");
+        }
+
+        if has_nul(&self.default_encoding) {
+            return invalid("default_encoding", "must not contain a NUL character");
+        }
+
+        if has_nul(&self.user_agent) {
+            return invalid("user_agent", "must not contain a NUL character");
+        }
+
+        if let Some(policy) = &self.kiosk_policy
+            && policy.allowed_origins.iter().any(|it| it.contains('\0'))
+        {
+            return invalid(
+                "kiosk_policy",
+                "the allowed origins must not contain a NUL character",
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validate the attributes for a native window
+    ///
+    /// The input of a native window is handled by the window itself, this
+    /// function rejects the options that only apply to the windowless
+    /// rendering mode.
+    pub fn validate_native_window(&self) -> Result<(), Error> {
+        let invalid = |name, reason| Err(Error::InvalidAttribute { name, reason });
+
+        if self.coalesce_mouse_moves {
+            return invalid(
+                "coalesce_mouse_moves",
+                "only supported in windowless rendering mode",
+            );
+        }
+
+        if self.ctrl_wheel_zoom {
+            return invalid(
+                "ctrl_wheel_zoom",
+                "only supported in windowless rendering mode",
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn is_supported_window_handle(handle: &RawWindowHandle) -> bool {
+    match handle {
+        #[cfg(target_os = "linux")]
+        RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => true,
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Win32(_) => true,
+        #[cfg(target_os = "macos")]
+        RawWindowHandle::AppKit(_) => true,
+        _ => false,
+    }
+}
+
+impl Default for WebViewAttributes {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            window_handle: None,
+            device_scale_factor: 1.0,
+            windowless_frame_rate: 30,
+            coalesce_mouse_moves: false,
+            ctrl_wheel_zoom: false,
+            default_font_size: 12,
+            default_fixed_font_size: 12,
+            javascript: true,
+            local_storage: true,
+            images: true,
+            javascript_access_clipboard: false,
+            #[cfg(feature = "request")]
+            request_handler_factory: None,
+            webgl: false,
+            databases: false,
+            javascript_close_windows: false,
+            javascript_dom_paste: false,
+            background_color: None,
             minimum_font_size: 12,
             minimum_logical_font_size: 12,
+            font_families: FontFamilies::default(),
+            default_encoding: None,
+            max_in_flight_messages: 0,
+            message_overflow_policy: MessageOverflowPolicy::Drop,
+            max_pending_messages: 256,
+            auto_reload_max_attempts: 0,
+            auto_reload_delay: Duration::from_secs(1),
+            user_agent: None,
+            user_agent_metadata: None,
+            metrics: false,
+            kiosk_policy: None,
+            clipboard_policy: false,
+            forced_colors: ForcedColors::System,
+            pdf_viewer: true,
         }
     }
 }
 
 /// WebView configuration attributes builder
 #[derive(Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct WebViewAttributesBuilder(WebViewAttributes);
 
 impl WebViewAttributesBuilder {
     /// Set the request handler factory
     ///
     /// This function is used to set the request handler factory.
+    #[cfg(feature = "request")]
     pub fn with_request_handler_factory(mut self, value: CustomRequestHandlerFactory) -> Self {
         self.0.request_handler_factory = Some(value);
         self
@@ -345,6 +1416,9 @@ impl WebViewAttributesBuilder {
     /// menus, and other elements. If not provided, the main screen monitor will
     /// be used, and some features that require a parent view may not work
     /// properly.
+    ///
+    /// On Linux, both Xlib and XCB window handles are supported, the window is
+    /// used as the parent X11 window of the webview.
     pub fn with_window_handle(mut self, value: RawWindowHandle) -> Self {
         self.0.window_handle = Some(value);
         self
@@ -361,6 +1435,34 @@ impl WebViewAttributesBuilder {
         self
     }
 
+    /// Set whether to coalesce mouse moves
+    ///
+    /// This function is used to reduce the pressure on the input queue during
+    /// fast drags, the mouse moves sent by **`WebView::mouse`** are merged
+    /// into the latest position until the UI thread forwards it to the page.
+    /// Clicks and wheel events are not merged, a pending move is forwarded
+    /// before them.
+    ///
+    /// Note that this parameter only works in windowless rendering mode.
+    pub fn with_coalesce_mouse_moves(mut self, value: bool) -> Self {
+        self.0.coalesce_mouse_moves = value;
+        self
+    }
+
+    /// Set whether to zoom with ctrl and the mouse wheel
+    ///
+    /// This function is used to intercept the mouse wheel while ctrl, or
+    /// command on macOS, is held, and change the zoom level of the page
+    /// instead of scrolling it, like browsers do. The modifiers are taken from
+    /// the events sent by **`WebView::keyboard`**, and the new zoom level is
+    /// reported by **`WindowlessRenderWebViewHandler::on_zoom_change`**.
+    ///
+    /// Note that this parameter only works in windowless rendering mode.
+    pub fn with_ctrl_wheel_zoom(mut self, value: bool) -> Self {
+        self.0.ctrl_wheel_zoom = value;
+        self
+    }
+
     /// Set the window width
     ///
     /// This function is used to set the window width.
@@ -421,6 +1523,24 @@ impl WebViewAttributesBuilder {
         self
     }
 
+    /// Set the default font families
+    ///
+    /// This function is used to override the font families that are used when
+    /// the page does not specify a font or uses a generic family.
+    pub fn with_font_families(mut self, value: FontFamilies) -> Self {
+        self.0.font_families = value;
+        self
+    }
+
+    /// Set the default text encoding
+    ///
+    /// This function is used to set the encoding of pages that do not declare
+    /// a charset, such as `GBK` or `Shift_JIS`, the default is `ISO-8859-1`.
+    pub fn with_default_encoding(mut self, value: &str) -> Self {
+        self.0.default_encoding = Some(value.to_string());
+        self
+    }
+
     /// Set whether local storage is enabled
     ///
     /// This function is used to set whether local storage is enabled.
@@ -429,9 +1549,23 @@ impl WebViewAttributesBuilder {
         self
     }
 
+    /// Set whether images are enabled
+    ///
+    /// This function is used to set whether images are loaded, disabling them
+    /// saves bandwidth for pages that only need the text content. Images that
+    /// are not loaded are not replaced by placeholders.
+    pub fn with_images_enabled(mut self, value: bool) -> Self {
+        self.0.images = value;
+        self
+    }
+
     /// Set whether WebGL is enabled
     ///
-    /// This function is used to set whether WebGL is enabled.
+    /// This function is used to set whether WebGL is enabled. Unlike WebGL,
+    /// the acceleration of the 2D canvas and the hardware video decoding
+    /// cannot be disabled per webview: Chromium only has command line switches
+    /// for them, which apply to the GPU process shared by all webviews. They
+    /// are options of **`RuntimeAttributesBuilder`** instead.
     pub fn with_webgl(mut self, value: bool) -> Self {
         self.0.webgl = value;
         self
@@ -486,12 +1620,165 @@ impl WebViewAttributesBuilder {
     ///
     /// This function is used to set the background color.
     pub fn with_background_color(mut self, value: u32) -> Self {
-        self.0.background_color = value;
+        self.0.background_color = Some(value);
         self
     }
 
-    pub fn build(self) -> WebViewAttributes {
-        self.0
+    /// Set the maximum number of in-flight messages
+    ///
+    /// A message sent by the web page is in flight until the host has handled
+    /// it, once the limit is reached, the overflow policy is applied to new
+    /// messages. This prevents a misbehaving page from flooding the host.
+    ///
+    /// 0 means unlimited.
+    pub fn with_max_in_flight_messages(mut self, value: u32) -> Self {
+        self.0.max_in_flight_messages = value;
+        self
+    }
+
+    /// Set the message overflow policy
+    ///
+    /// This function is used to set what to do when the number of in-flight
+    /// messages reaches the limit.
+    pub fn with_message_overflow_policy(mut self, value: MessageOverflowPolicy) -> Self {
+        self.0.message_overflow_policy = value;
+        self
+    }
+
+    /// Set the maximum number of pending messages
+    ///
+    /// Messages sent to the web page before it has registered a listener are
+    /// kept until the listener is registered, once the limit is reached, the
+    /// oldest message of the channel is dropped.
+    ///
+    /// The default is 256, 0 means no message is kept.
+    pub fn with_max_pending_messages(mut self, value: u32) -> Self {
+        self.0.max_pending_messages = value;
+        self
+    }
+
+    /// Set the maximum number of automatic reloads
+    ///
+    /// This function is used to set how many times the page is reloaded after
+    /// the render process terminated, the count is reset once the page has
+    /// loaded.
+    pub fn with_auto_reload_max_attempts(mut self, value: u32) -> Self {
+        self.0.auto_reload_max_attempts = value;
+        self
+    }
+
+    /// Set the automatic reload delay
+    ///
+    /// This function is used to set the delay before the first automatic
+    /// reload, the delay is doubled for every following attempt.
+    pub fn with_auto_reload_delay(mut self, value: Duration) -> Self {
+        self.0.auto_reload_delay = value;
+        self
+    }
+
+    /// Set the user agent
+    ///
+    /// This function is used to override the user agent of this webview, the
+    /// runtime's user agent is used by default.
+    pub fn with_user_agent(mut self, value: &str) -> Self {
+        self.0.user_agent = Some(value.to_string());
+        self
+    }
+
+    /// Set the user agent client hints
+    ///
+    /// This function is used to override the brands and the platform reported
+    /// through the client hints, so that the embedding does not leak. The
+    /// client hints are only overridden if a user agent is set for the webview
+    /// or the runtime.
+    pub fn with_user_agent_metadata(mut self, value: UserAgentMetadata) -> Self {
+        self.0.user_agent_metadata = Some(value);
+        self
+    }
+
+    /// Set whether to collect metrics
+    ///
+    /// This function is used to enable the collection of the frame and message
+    /// callback metrics, which can be queried with **`WebView::metrics`**.
+    /// Collecting the metrics adds a small overhead to every callback.
+    pub fn with_metrics(mut self, value: bool) -> Self {
+        self.0.metrics = value;
+        self
+    }
+
+    /// Set whether the clipboard accesses are checked
+    ///
+    /// When enabled, each call of `navigator.clipboard.read`, `readText`,
+    /// `write` and `writeText` in any frame is passed to
+    /// **`WebViewHandler::on_clipboard_access`** with the origin of the frame,
+    /// so that the host can allow, deny or sanitize it, and audit the
+    /// clipboard flows of the embedded pages.
+    ///
+    /// The policy is advisory, not a security boundary. It is enforced by a
+    /// script that wraps the async clipboard API in the render process, the
+    /// browser process does not block the accesses itself. A page can bypass
+    /// it, such as with `document.execCommand`, with the `clipboardData` of
+    /// the `copy`, `cut` and `paste` events, or by calling the original
+    /// methods before they are wrapped. `document.execCommand` is controlled
+    /// by **`WebViewAttributes::javascript_access_clipboard`**, untrusted
+    /// pages should not be relied on to honor the policy.
+    pub fn with_clipboard_policy(mut self, value: bool) -> Self {
+        self.0.clipboard_policy = value;
+        self
+    }
+
+    /// Set whether the built-in PDF viewer is enabled
+    ///
+    /// When disabled, the viewer is not loaded and PDF documents are left
+    /// blank in the frame that shows them, such as for an application that
+    /// renders the documents itself. The other webviews are not affected.
+    pub fn with_pdf_viewer(mut self, value: bool) -> Self {
+        self.0.pdf_viewer = value;
+        self
+    }
+
+    /// Set the forced colors mode
+    ///
+    /// This function is used to override the high contrast setting of the
+    /// system for the page, see **`WebView::set_forced_colors`**.
+    pub fn with_forced_colors(mut self, value: ForcedColors) -> Self {
+        self.0.forced_colors = value;
+        self
+    }
+
+    /// Set the kiosk policy
+    ///
+    /// This function is used to lock down the webview for kiosks and digital
+    /// signage, popups, downloads, the developer tools, the context menu and
+    /// the application switch shortcuts are blocked in one call. Start from
+    /// **`KioskPolicy::default`** and relax the restrictions that are not
+    /// needed:
+    ///
+    /// ```no_run
+    /// use wew::webview::{KioskPolicy, WebViewAttributesBuilder};
+    ///
+    /// let attr = WebViewAttributesBuilder::default()
+    ///     .with_kiosk_policy(KioskPolicy {
+    ///         allowed_origins: vec!["https://example.com".to_string()],
+    ///         ..Default::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// Note that the origin of the initial URL must also be allowed.
+    pub fn with_kiosk_policy(mut self, value: KioskPolicy) -> Self {
+        self.0.kiosk_policy = Some(value);
+        self
+    }
+
+    /// Build the attributes
+    ///
+    /// The attributes are validated with **`WebViewAttributes::validate`**.
+    pub fn build(self) -> Result<WebViewAttributes, Error> {
+        self.0.validate()?;
+
+        Ok(self.0)
     }
 }
 
@@ -503,12 +1790,92 @@ impl Deref for WebViewAttributesBuilder {
     }
 }
 
+/// The settings that can be changed after the webview is created
+///
+/// The settings that are `None` are not changed, see
+/// **`WebView::update_settings`**.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebViewSettingsDelta {
+    /// The maximum rate in frames per second (fps), only used in windowless
+    /// rendering mode.
+    pub windowless_frame_rate: Option<u32>,
+    /// The zoom level, see **`WebView::set_zoom_level`**.
+    pub zoom_level: Option<f64>,
+    /// Whether the audio of the page is muted.
+    pub muted: Option<bool>,
+    /// The background color in ARGB format, it is shown where the page does
+    /// not paint its own background.
+    pub background_color: Option<u32>,
+    /// The user agent, it is used for the following requests.
+    pub user_agent: Option<String>,
+    /// The user agent client hints, only used together with `user_agent`.
+    pub user_agent_metadata: Option<UserAgentMetadata>,
+}
+
+/// The identifier of a webview
+///
+/// The identifier is assigned when the webview is created and is unique in the
+/// process, it is never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WebViewId(u64);
+
+impl WebViewId {
+    fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Get the value of the identifier
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for WebViewId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// The zoom levels of 25% and 500%, the limits of the browsers.
+const MIN_ZOOM_LEVEL: f64 = -7.6;
+const MAX_ZOOM_LEVEL: f64 = 8.8;
+
+// The zoom level change of one notch of the mouse wheel.
+#[cfg(feature = "windowless")]
+const ZOOM_LEVEL_STEP: f64 = 0.5;
+
+const VK_CONTROL: u32 = 0x11;
+#[cfg(target_os = "macos")]
+const VK_LWIN: u32 = 0x5B;
+#[cfg(target_os = "macos")]
+const VK_RWIN: u32 = 0x5C;
+
 pub(crate) struct IWebView {
+    pub(crate) id: WebViewId,
+    #[cfg(feature = "windowless")]
     mouse_event: Mutex<sys::MouseEvent>,
-    // The runtime may use a custom request interceptor; a reference is kept here to ensure correct
-    // lifetime management.
-    #[allow(unused)]
-    request_handler_factory: Option<Arc<ICustomRequestHandlerFactory>>,
+    #[cfg(feature = "windowless")]
+    coalesce_mouse_moves: bool,
+    #[cfg(feature = "windowless")]
+    mouse_move_pending: AtomicBool,
+    ctrl_wheel_zoom: bool,
+    find_text: Mutex<Option<String>>,
+    #[cfg(feature = "windowless")]
+    recorder: Mutex<Option<InputRecorder>>,
+    keyboard_modifiers: Mutex<KeyboardModifiers>,
+    zoom_level: Mutex<f64>,
+    // The factory of the routes, it also keeps the request handler factory of the attributes alive
+    // while the webview uses it.
+    #[cfg(feature = "request")]
+    request_handler_factory: CustomRequestHandlerFactory,
+    #[cfg(feature = "request")]
+    router: Router,
+    block_devtools: bool,
+    // The DOM queries cannot run without JavaScript.
+    javascript: bool,
     context: ThreadSafePointer<WebViewContext>,
     raw: Mutex<ThreadSafePointer<c_void>>,
 }
@@ -520,22 +1887,93 @@ impl IWebView {
         attr: WebViewAttributes,
         handler: MixWebviewHnadler,
     ) -> Result<Self, Error> {
+        attr.validate()?;
+
         let runtime = runtime.get_shared_ref();
         let raw_runtime = runtime.get_raw();
 
+        let user_agent = attr
+            .user_agent
+            .as_ref()
+            .or(runtime.user_agent.as_ref())
+            .map(|it| CString::new(it.as_str()).unwrap());
+
+        let to_c_string =
+            |value: &Option<String>| value.as_ref().map(|it| CString::new(it.as_str()).unwrap());
+
+        let standard_font_family = to_c_string(&attr.font_families.standard);
+        let fixed_font_family = to_c_string(&attr.font_families.fixed);
+        let serif_font_family = to_c_string(&attr.font_families.serif);
+        let sans_serif_font_family = to_c_string(&attr.font_families.sans_serif);
+        let cursive_font_family = to_c_string(&attr.font_families.cursive);
+        let fantasy_font_family = to_c_string(&attr.font_families.fantasy);
+        let default_encoding = to_c_string(&attr.default_encoding);
+
+        let user_agent_metadata = attr
+            .user_agent_metadata
+            .as_ref()
+            .or(runtime.user_agent_metadata.as_ref())
+            .map(|it| CString::new(it.to_json()).unwrap());
+
+        // The routes of the webview are checked before the request handler factory
+        // of the attributes. The routed factory replaces it only once a route is added
+        // or recording is enabled, the other webviews do not pay for the router.
+        #[cfg(feature = "request")]
+        let router = Router::default();
+
+        #[cfg(feature = "request")]
+        let request_handler_factory =
+            CustomRequestHandlerFactory::new(RoutedRequestHandlerFactory {
+                router: router.clone(),
+                factory: attr.request_handler_factory.clone(),
+            });
+
+        let allowed_origins = attr
+            .kiosk_policy
+            .iter()
+            .flat_map(|it| it.allowed_origins.iter())
+            .map(|it| CString::new(it.as_str()).unwrap())
+            .collect::<Vec<_>>();
+
+        let allowed_origin_ptrs = allowed_origins
+            .iter()
+            .map(|it| it.as_ptr())
+            .collect::<Vec<_>>();
+
+        let kiosk_policy = attr.kiosk_policy.as_ref().map(|policy| sys::KioskPolicy {
+            block_popups: policy.block_popups,
+            block_downloads: policy.block_downloads,
+            block_devtools: policy.block_devtools,
+            disable_context_menu: policy.disable_context_menu,
+            block_app_switch_shortcuts: policy.block_app_switch_shortcuts,
+            allowed_origins: allowed_origin_ptrs.as_ptr() as _,
+            allowed_origins_size: allowed_origin_ptrs.len(),
+        });
+
         let options = sys::WebViewSettings {
             width: attr.width,
             height: attr.height,
             webgl: attr.webgl,
             databases: attr.databases,
             local_storage: attr.local_storage,
-            background_color: attr.background_color,
+            images: attr.images,
+            background_color: attr
+                .background_color
+                .or(runtime.background_color)
+                .unwrap_or(0xFFFFFFFF),
             javascript: attr.javascript,
             javascript_access_clipboard: attr.javascript_access_clipboard,
             javascript_close_windows: attr.javascript_close_windows,
             javascript_dom_paste: attr.javascript_dom_paste,
             minimum_font_size: attr.minimum_font_size as _,
             minimum_logical_font_size: attr.minimum_logical_font_size as _,
+            standard_font_family: standard_font_family.as_raw(),
+            fixed_font_family: fixed_font_family.as_raw(),
+            serif_font_family: serif_font_family.as_raw(),
+            sans_serif_font_family: sans_serif_font_family.as_raw(),
+            cursive_font_family: cursive_font_family.as_raw(),
+            fantasy_font_family: fantasy_font_family.as_raw(),
+            default_encoding: default_encoding.as_raw(),
             device_scale_factor: attr.device_scale_factor,
             windowless_frame_rate: attr.windowless_frame_rate,
             default_fixed_font_size: attr.default_fixed_font_size as _,
@@ -551,26 +1989,61 @@ impl IWebView {
                     value = match it {
                         #[cfg(target_os = "linux")]
                         RawWindowHandle::Xlib(it) => it.window,
+                        // The XCB window is the same X11 window id as the Xlib one.
+                        #[cfg(target_os = "linux")]
+                        RawWindowHandle::Xcb(it) => it.window.get() as _,
                         #[cfg(target_os = "windows")]
                         RawWindowHandle::Win32(it) => it.hwnd.get() as _,
                         #[cfg(target_os = "macos")]
                         RawWindowHandle::AppKit(it) => it.ns_view.as_ptr() as _,
-                        _ => unimplemented!("Unsupported window handle type: {:?}", it),
+                        _ => unreachable!("checked by WebViewAttributes::validate"),
                     };
                 }
 
                 value
             },
-            request_handler_factory: if let Some(it) = &attr.request_handler_factory {
-                it.as_raw().as_ptr() as _
-            } else {
-                null()
-            },
+            #[cfg(feature = "request")]
+            request_handler_factory: attr
+                .request_handler_factory
+                .as_ref()
+                .map(|it| it.as_raw().as_ptr() as _)
+                .unwrap_or(null()),
+            #[cfg(not(feature = "request"))]
+            request_handler_factory: null(),
+            max_in_flight_messages: attr.max_in_flight_messages,
+            message_overflow_policy: attr.message_overflow_policy.into(),
+            max_pending_messages: attr.max_pending_messages,
+            auto_reload_max_attempts: attr.auto_reload_max_attempts,
+            auto_reload_delay: attr.auto_reload_delay.as_millis() as _,
+            user_agent: user_agent.as_raw(),
+            user_agent_metadata: user_agent_metadata.as_raw(),
+            kiosk_policy: kiosk_policy
+                .as_ref()
+                .map(|it| it as *const _)
+                .unwrap_or_else(null),
+            clipboard_policy: attr.clipboard_policy,
+            forced_colors: attr.forced_colors.into(),
+            pdf_viewer: attr.pdf_viewer,
         };
 
+        let id = WebViewId::next();
+        let dom: Arc<DomQueries> = Default::default();
         let context: *mut WebViewContext = Box::into_raw(Box::new(WebViewContext {
-            runtime: Some(runtime),
-            handler,
+            id,
+            closed: AtomicBool::new(false),
+            dispatcher: runtime.dispatcher.clone(),
+            runtime: Mutex::new(Some(runtime)),
+            channels: RwLock::new(HashMap::from([(
+                DOM_CHANNEL.to_string(),
+                dom.clone() as Arc<dyn ChannelHandler>,
+            )])),
+            dom,
+            events: EventSubscribers::default(),
+            metrics: attr
+                .metrics
+                .then(|| Arc::new(MetricsCollector::new(attr.windowless_frame_rate))),
+            handler: Arc::new(handler),
+            drag_operation: AtomicU32::new(0),
         }));
 
         let url = CString::new(url).unwrap();
@@ -587,31 +2060,147 @@ impl IWebView {
                     on_title_change: Some(on_title_change_callback),
                     on_fullscreen_change: Some(on_fullscreen_change_callback),
                     on_message: Some(on_message_callback),
+                    on_binary_message: Some(on_binary_message_callback),
+                    on_render_process_terminated: Some(on_render_process_terminated_callback),
+                    on_page_error: Some(on_page_error_callback),
+                    on_console_message: Some(on_console_message_callback),
+                    on_favicon_change: Some(on_favicon_change_callback),
+                    on_status_message: Some(on_status_message_callback),
+                    on_main_frame_response: Some(on_main_frame_response_callback),
+                    on_screen_info: Some(on_screen_info_callback),
+                    on_take_focus: Some(on_take_focus_callback),
+                    on_set_focus: Some(on_set_focus_callback),
+                    on_got_focus: Some(on_got_focus_callback),
+                    on_find_result: Some(on_find_result_callback),
+                    on_print_start: Some(on_print_start_callback),
+                    on_print_finished: Some(on_print_finished_callback),
+                    on_print_dialog: Some(on_print_dialog_callback),
+                    on_print_job: Some(on_print_job_callback),
+                    on_start_dragging: Some(on_start_dragging_callback),
+                    on_update_drag_cursor: Some(on_update_drag_cursor_callback),
+                    on_clipboard_access: Some(on_clipboard_access_callback),
+                    on_pdf_load: Some(on_pdf_load_callback),
                     context: context as _,
                 },
             )
         };
 
         let raw = if ptr.is_null() {
-            return Err(Error::FailedToCreateWebView);
+            // No callback is called if the browser was not created.
+            drop(unsafe { Box::from_raw(context) });
+
+            return Err(Error::Failed {
+                operation: Operation::CreateWebView,
+                code: None,
+                os_error: None,
+            });
         } else {
             ThreadSafePointer::new(ptr)
         };
 
         Ok(Self {
+            id,
             raw: Mutex::new(raw),
             context: ThreadSafePointer::new(context),
+            #[cfg(feature = "windowless")]
             mouse_event: Mutex::new(unsafe { std::mem::zeroed() }),
-            request_handler_factory: attr
-                .request_handler_factory
+            #[cfg(feature = "windowless")]
+            coalesce_mouse_moves: attr.coalesce_mouse_moves,
+            #[cfg(feature = "windowless")]
+            mouse_move_pending: AtomicBool::new(false),
+            ctrl_wheel_zoom: attr.ctrl_wheel_zoom,
+            find_text: Mutex::new(None),
+            #[cfg(feature = "windowless")]
+            recorder: Mutex::new(None),
+            keyboard_modifiers: Mutex::new(KeyboardModifiers::None),
+            zoom_level: Mutex::new(0.0),
+            #[cfg(feature = "request")]
+            request_handler_factory,
+            #[cfg(feature = "request")]
+            router,
+            block_devtools: attr
+                .kiosk_policy
                 .as_ref()
-                .map(|it| it.get_shared_ref()),
+                .map(|it| it.block_devtools)
+                .unwrap_or(false),
+            javascript: attr.javascript,
         })
     }
 }
 
+impl IWebView {
+    fn context(&self) -> &WebViewContext {
+        unsafe { &*self.context.as_ptr() }
+    }
+
+    // The event is only created while the input is being recorded.
+    #[cfg(feature = "windowless")]
+    fn record<F>(&self, event: F)
+    where
+        F: FnOnce() -> InputEvent,
+    {
+        if let Some(recorder) = self.recorder.lock().as_mut() {
+            recorder.record(event());
+        }
+    }
+
+    // Forward the latest position of the coalesced mouse moves, if it has not
+    // been forwarded yet.
+    #[cfg(feature = "windowless")]
+    fn flush_mouse_move(&self, event: &sys::MouseEvent) {
+        if self.mouse_move_pending.swap(false, Ordering::AcqRel) {
+            unsafe { sys::webview_mouse_move(self.raw.lock().as_ptr(), *event) }
+        }
+    }
+
+    fn set_zoom_level(&self, level: f64) -> f64 {
+        let level = level.clamp(MIN_ZOOM_LEVEL, MAX_ZOOM_LEVEL);
+        *self.zoom_level.lock() = level;
+
+        unsafe { sys::webview_set_zoom_level(self.raw.lock().as_ptr(), level) }
+
+        level
+    }
+
+    fn update_keyboard_modifiers(&self, event: &KeyboardEvent) {
+        // The event of a modifier key does not always include the key itself in
+        // its modifiers.
+        let key = match event.windows_key_code {
+            VK_CONTROL => KeyboardModifiers::Ctrl,
+            #[cfg(target_os = "macos")]
+            VK_LWIN | VK_RWIN => KeyboardModifiers::Command,
+            _ => KeyboardModifiers::None,
+        };
+
+        let mut modifiers = event.modifiers;
+        match event.ty {
+            KeyboardEventType::KeyDown => modifiers.insert(key),
+            KeyboardEventType::KeyUp => modifiers.remove(key),
+            KeyboardEventType::Char => return,
+        }
+
+        *self.keyboard_modifiers.lock() = modifiers;
+    }
+
+    fn edit_command(&self, command: sys::EditCommand) {
+        unsafe { sys::webview_edit_command(self.raw.lock().as_ptr(), command) }
+    }
+
+    #[cfg(feature = "windowless")]
+    fn is_zoom_modifier_pressed(&self) -> bool {
+        self.keyboard_modifiers
+            .lock()
+            .intersects(KeyboardModifiers::Ctrl | KeyboardModifiers::Command)
+    }
+}
+
 impl Drop for IWebView {
     fn drop(&mut self) {
+        let runtime = self.context().runtime.lock().clone();
+        if let Some(runtime) = &runtime {
+            runtime.webviews.lock().remove(&self.id);
+        }
+
         unsafe {
             sys::close_webview(self.raw.lock().as_ptr());
         }
@@ -642,395 +2231,2780 @@ impl<W> WebView<W> {
         attr: WebViewAttributes,
         handler: MixWebviewHnadler,
     ) -> Result<Self, Error> {
-        Ok(Self {
+        let inner = Arc::new(IWebView::new(runtime, url, attr, handler)?);
+
+        #[cfg(feature = "request")]
+        {
+            let weak = Arc::downgrade(&inner);
+            inner.router.on_enable(move || {
+                if let Some(inner) = weak.upgrade() {
+                    unsafe {
+                        sys::webview_set_request_handler_factory(
+                            inner.raw.lock().as_ptr(),
+                            inner.request_handler_factory.as_raw().as_ptr(),
+                        )
+                    }
+                }
+            });
+        }
+
+        runtime
+            .get_shared_ref()
+            .webviews
+            .lock()
+            .insert(inner.id, Arc::downgrade(&inner));
+
+        Ok(Self::from_shared_ref(inner))
+    }
+
+    pub(crate) fn from_shared_ref(inner: Arc<IWebView>) -> Self {
+        Self {
             _w: PhantomData,
-            inner: Arc::new(IWebView::new(runtime, url, attr, handler)?),
-        })
+            inner,
+        }
+    }
+
+    /// Whether the webview is closed
+    ///
+    /// The webview is closed when the web page closes itself, such as by
+    /// calling `window.close()`, or when the runtime closes it. A closed
+    /// webview can no longer be used, but it still has to be dropped.
+    pub fn is_closed(&self) -> bool {
+        self.inner.context().closed.load(Ordering::Acquire)
+    }
+
+    /// Get the id of the webview
+    ///
+    /// The id is stable for the lifetime of the webview, it is passed to every
+    /// callback of the handler and can be used to find the webview with
+    /// **`Runtime::webview`**.
+    pub fn id(&self) -> WebViewId {
+        self.inner.id
     }
 
     /// Get the window handle
     ///
     /// This function is used to get the window handle.
-    pub fn window_handle(&self) -> Option<RawWindowHandle> {
-        let handle = unsafe { sys::webview_get_window_handle(self.inner.raw.lock().as_ptr()) };
+    ///
+    /// The browser is created before the webview is returned,
+    /// **`Error::WebViewClosed`** is returned after the webview is closed.
+    ///
+    /// On Linux, the handle is always returned as an Xlib window, even if the
+    /// webview was created with an XCB window handle. On a Wayland session,
+    /// Chromium uses X11 through XWayland by default, so the Xlib window is
+    /// still returned. If Chromium runs on the Wayland platform instead, it
+    /// does not expose its surfaces and **`Error::NoNativeWindow`** is returned.
+    pub fn window_handle(&self) -> Result<RawWindowHandle, Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
 
-        let mut value = MaybeUninit::<RawWindowHandle>::uninit();
+        let mut handle = unsafe { std::mem::zeroed() };
+        if !unsafe { sys::webview_get_window_handle(self.inner.raw.lock().as_ptr(), &mut handle) } {
+            return Err(Error::WindowNotCreated);
+        }
 
         #[cfg(target_os = "linux")]
-        if handle == 0 {
-            return None;
-        } else {
-            unsafe {
-                value.as_mut_ptr().write(RawWindowHandle::Xlib(
-                    raw_window_handle::XlibWindowHandle::new(handle),
-                ));
+        {
+            if handle == 0 {
+                return Err(Error::NoNativeWindow);
             }
-        }
 
-        #[cfg(not(target_os = "linux"))]
-        if handle.is_null() {
-            return None;
-        } else {
-            #[cfg(target_os = "windows")]
-            unsafe {
-                value.as_mut_ptr().write(RawWindowHandle::Win32(
-                    raw_window_handle::Win32WindowHandle::new(
-                        std::num::NonZeroIsize::new(handle as _).unwrap(),
-                    ),
-                ));
-            }
+            Ok(RawWindowHandle::Xlib(
+                raw_window_handle::XlibWindowHandle::new(handle),
+            ))
+        }
 
-            #[cfg(target_os = "macos")]
-            unsafe {
-                value.as_mut_ptr().write(RawWindowHandle::AppKit(
-                    raw_window_handle::AppKitWindowHandle::new(std::ptr::NonNull::new_unchecked(
-                        handle as _,
-                    )),
-                ));
-            }
+        #[cfg(target_os = "windows")]
+        {
+            std::num::NonZeroIsize::new(handle as _)
+                .map(|it| RawWindowHandle::Win32(raw_window_handle::Win32WindowHandle::new(it)))
+                .ok_or(Error::NoNativeWindow)
         }
 
-        Some(unsafe { value.assume_init() })
+        #[cfg(target_os = "macos")]
+        {
+            std::ptr::NonNull::new(handle as *mut _)
+                .map(|it| RawWindowHandle::AppKit(raw_window_handle::AppKitWindowHandle::new(it)))
+                .ok_or(Error::NoNativeWindow)
+        }
     }
 
-    /// Send a message
+    /// Get the underlying CEF browser
     ///
-    /// This function is used to send a message to the web page.
+    /// This function is used to call the CEF APIs that are not wrapped by this
+    /// crate, the pointer is a `CefBrowser*` of the CEF C++ API, to be used by
+    /// C++ code linked into the application. `None` is returned if the browser
+    /// has not been created yet or has been closed.
     ///
-    /// Messages sent from the web page are received through the
-    /// **`WebViewHandler::on_message`** callback.
-    pub fn send_message(&self, message: &str) {
-        let message = CString::new(message).unwrap();
+    /// # Safety
+    ///
+    /// No reference is added to the browser, the pointer must only be used on
+    /// the UI thread, such as in a task posted with **`utils::post_main`**
+    /// when the message loop is not multi-threaded, and it is invalid once the
+    /// webview is closed. The state that this crate keeps for the browser must
+    /// not be changed, such as by replacing its handlers or closing it
+    /// directly.
+    pub unsafe fn raw_browser(&self) -> Option<NonNull<c_void>> {
+        NonNull::new(unsafe { sys::webview_get_browser(self.inner.raw.lock().as_ptr()) })
+    }
 
-        unsafe {
-            sys::webview_send_message(self.inner.raw.lock().as_ptr(), message.as_raw());
-        }
+    /// Get a stream of the webview events
+    ///
+    /// This function is used to receive the events of the handler as owned
+    /// values in async code, see the **`stream`** module. The stream only
+    /// receives the events that occur after it is created.
+    pub fn events(&self) -> WebViewEvents {
+        self.inner.context().events.subscribe()
     }
 
-    /// Set whether developer tools are enabled
+    /// Get a channel of the webview events
     ///
-    /// This function is used to set whether developer tools are enabled.
-    pub fn devtools_enabled(&self, enable: bool) {
-        unsafe { sys::webview_set_devtools_state(self.inner.raw.lock().as_ptr(), enable) }
+    /// This function is used to receive the events of the handler from the
+    /// application's own loop without an async runtime, see the **`stream`**
+    /// module. The channel only receives the events that occur after it is
+    /// created.
+    pub fn event_receiver(&self) -> Receiver<WebViewEvent> {
+        self.inner.context().events.subscribe_channel()
     }
-}
 
-impl WebView<WindowlessRenderWebView> {
-    /// Send a mouse event
+    /// Add a route
     ///
-    /// This function is used to send mouse events.
+    /// This function is used to serve the requests whose URL matches the
+    /// pattern with a canned response instead of loading them, such as to test
+    /// the frontend of an application without a server. See the **`request`**
+    /// module for details.
     ///
-    /// Note that this function only works in windowless rendering mode.
-    pub fn mouse(&self, action: &MouseEvent) {
-        let mut event = self.inner.mouse_event.lock();
+    /// The route applies to the requests made after it is added.
+    #[cfg(feature = "request")]
+    pub fn route<T>(&self, pattern: &str, handler: T)
+    where
+        T: RouteHandler + 'static,
+    {
+        self.inner.router.route(pattern, handler);
+    }
 
-        match action {
-            MouseEvent::Move(pos) => unsafe {
-                event.x = pos.x;
-                event.y = pos.y;
+    /// Get the request router
+    ///
+    /// The router manages the routes of the webview and records its requests
+    /// once recording is enabled.
+    #[cfg(feature = "request")]
+    pub fn router(&self) -> &Router {
+        &self.inner.router
+    }
 
-                sys::webview_mouse_move(self.inner.raw.lock().as_ptr(), *event)
-            },
-            MouseEvent::Wheel(pos) => unsafe {
-                sys::webview_mouse_wheel(self.inner.raw.lock().as_ptr(), *event, pos.x, pos.y)
-            },
-            MouseEvent::Click(button, is_pressed, pos) => {
-                if let Some(pos) = pos {
-                    event.x = pos.x;
-                    event.y = pos.y;
-                }
+    /// Get the metrics of the webview
+    ///
+    /// This function is used to get a snapshot of the metrics collected since
+    /// the webview was created, `None` is returned if
+    /// **`WebViewAttributes::metrics`** is not enabled.
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.inner
+            .context()
+            .metrics
+            .as_ref()
+            .map(|it| it.snapshot())
+    }
 
-                if *is_pressed {
-                    event.modifiers |= match button {
-                        MouseButton::Left => sys::EventFlags::WEW_EVENTFLAG_LEFT_MOUSE_BUTTON,
-                        MouseButton::Right => sys::EventFlags::WEW_EVENTFLAG_RIGHT_MOUSE_BUTTON,
-                        MouseButton::Middle => sys::EventFlags::WEW_EVENTFLAG_MIDDLE_MOUSE_BUTTON,
-                    } as u32;
-                } else {
-                    event.modifiers = 0;
-                };
+    /// Get the render process information
+    ///
+    /// This function is used to get the process that renders the main frame
+    /// of the web page and its resource usage, `None` is returned if the page
+    /// has not been rendered yet. The process may change after a navigation.
+    pub fn process_info(&self) -> Option<ProcessInfo> {
+        let pid =
+            unsafe { sys::webview_get_render_process_id(self.inner.raw.lock().as_ptr()) } as u32;
+        if pid == 0 {
+            return None;
+        }
 
-                unsafe {
-                    sys::webview_mouse_click(
-                        self.inner.raw.lock().as_ptr(),
-                        *event,
-                        (*button).into(),
-                        *is_pressed,
-                    )
-                }
-            }
+        let usage = get_process_usage(pid);
+        Some(ProcessInfo {
+            pid,
+            memory: usage.map(|it| it.0),
+            cpu_time: usage.map(|it| it.1),
+        })
+    }
+
+    /// Get the frames of the web page
+    ///
+    /// This function is used to get the main frame and all child frames of the
+    /// web page, an empty list is returned if the page has not been created
+    /// yet.
+    pub fn frames(&self) -> Vec<FrameInfo> {
+        let mut frames: Vec<FrameInfo> = Vec::new();
+
+        unsafe {
+            sys::webview_get_frames(
+                self.inner.raw.lock().as_ptr(),
+                Some(get_frames_callback),
+                &mut frames as *mut _ as _,
+            );
         }
+
+        frames
     }
 
-    /// Send a keyboard event
+    /// Download a favicon
     ///
-    /// This function is used to send keyboard events.
+    /// This function is used to download an icon reported by
+    /// **`WebViewHandler::on_favicon_change`** and decode it to RGBA. If the
+    /// icon contains multiple sizes, the largest one that fits `max_size` is
+    /// used, 0 means unlimited.
     ///
-    /// Note that this function only works in windowless rendering mode.
-    pub fn keyboard(&self, event: &KeyboardEvent) {
-        let mut modifiers = sys::EventFlags::WEW_EVENTFLAG_NONE as u32;
-        for it in KeyboardModifiers::all() {
-            if event.modifiers.contains(it) {
-                let flag: sys::EventFlags = it.into();
-                modifiers |= flag as u32;
-            }
-        }
+    /// The callback is called once on the main thread, with `None` if the icon
+    /// could not be downloaded or decoded.
+    pub fn download_favicon<F>(&self, url: &str, max_size: u32, callback: F)
+    where
+        F: FnOnce(Option<Image>) + Send + 'static,
+    {
+        let url = CString::new(url).unwrap();
+        let callback: Box<DownloadImageCallback> = Box::new(Box::new(callback));
 
         unsafe {
-            sys::webview_keyboard(
+            sys::webview_download_image(
                 self.inner.raw.lock().as_ptr(),
-                sys::KeyEvent {
-                    modifiers,
-                    character: event.character,
-                    unmodified_character: event.unmodified_character,
-                    windows_key_code: event.windows_key_code as i32,
-                    native_key_code: event.native_key_code as i32,
-                    is_system_key: event.is_system_key as i32,
-                    focus_on_editable_field: event.focus_on_editable_field as i32,
-                    type_: event.ty.into(),
-                },
-            )
+                url.as_raw(),
+                true,
+                max_size,
+                Some(download_image_callback),
+                Box::into_raw(callback) as _,
+            );
         }
     }
 
-    /// Send an IME event
+    /// Query the layout of the web page
     ///
-    /// This function is used to send IME events.
+    /// This function is used to get the content size and the scroll offset of
+    /// the main frame, such as to resize a panel to fit the content of the
+    /// page. The values are measured after the layout, so they reflect the
+    /// changes made by scripts before the query.
     ///
-    /// Note that this function only works in windowless rendering mode.
-    pub fn ime(&self, action: &IMEAction) {
-        let input = match action {
-            IMEAction::Composition(it) | IMEAction::Pre(it, _, _) => CString::new(*it).unwrap(),
-        };
+    /// The future can be awaited by any executor, it resolves to `None` if the
+    /// webview is closed or the query failed.
+    pub fn layout_metrics(&self) -> DomQuery<Option<LayoutMetrics>> {
+        let (query, resolve) = DomQuery::pending();
+        let callback: Box<LayoutMetricsCallback> = Box::new(Box::new(resolve));
 
-        match action {
-            IMEAction::Composition(_) => unsafe {
-                sys::webview_ime_composition(self.inner.raw.lock().as_ptr(), input.as_raw())
-            },
-            IMEAction::Pre(_, x, y) => unsafe {
-                sys::webview_ime_set_composition(
-                    self.inner.raw.lock().as_ptr(),
-                    input.as_raw(),
-                    *x,
-                    *y,
-                )
-            },
+        unsafe {
+            sys::webview_get_layout_metrics(
+                self.inner.raw.lock().as_ptr(),
+                Some(layout_metrics_callback),
+                Box::into_raw(callback) as _,
+            );
         }
+
+        query
     }
 
-    /// Resize the window
+    /// Capture a thumbnail of the web page
     ///
-    /// This function is used to resize the window.
+    /// This function is used to take a reduced snapshot of the visible part of
+    /// the page, such as for a tab switcher. The page is scaled down by the
+    /// renderer to fit within `max_width` and `max_height` while keeping its
+    /// aspect ratio, so the full resolution frame is never copied. The page is
+    /// never scaled up.
     ///
-    /// Note that this function only works in windowless rendering mode.
-    pub fn resize(&self, width: u32, height: u32) {
+    /// The callback is called once on the main thread, with `None` if the web
+    /// page has not been created yet or the capture failed.
+    pub fn capture_thumbnail<F>(&self, max_width: u32, max_height: u32, callback: F)
+    where
+        F: FnOnce(Option<Image>) + Send + 'static,
+    {
+        let callback: Box<DownloadImageCallback> = Box::new(Box::new(callback));
+
         unsafe {
-            sys::webview_resize(
+            sys::webview_capture_thumbnail(
                 self.inner.raw.lock().as_ptr(),
-                width as c_int,
-                height as c_int,
-            )
+                max_width,
+                max_height,
+                Some(download_image_callback),
+                Box::into_raw(callback) as _,
+            );
         }
     }
 
-    /// Set the focus state
+    /// Start a screencast
     ///
-    /// This function is used to set the focus state.
+    /// This function is used to receive the frames of the page as encoded
+    /// images, see the **`screencast`** module. It does not require windowless
+    /// rendering, so it can be used to view or monitor a native window webview
+    /// remotely.
     ///
-    /// Note that this function only works in windowless rendering mode.
-    pub fn focus(&self, state: bool) {
-        unsafe { sys::webview_set_focus(self.inner.raw.lock().as_ptr(), state) }
-    }
-}
+    /// A webview only runs one screencast, starting a new one ends the stream
+    /// of the previous one.
+    pub fn start_screencast(&self, options: ScreencastOptions) -> Screencast {
+        let options = sys::ScreencastOptions::from(&options);
+        let (screencast, context) = Screencast::new();
 
-impl From<sys::WebViewState> for WebViewState {
-    fn from(value: sys::WebViewState) -> Self {
-        match value {
-            sys::WebViewState::WEW_BEFORE_LOAD => Self::BeforeLoad,
-            sys::WebViewState::WEW_LOADED => Self::Loaded,
-            sys::WebViewState::WEW_LOAD_ERROR => Self::LoadError,
-            sys::WebViewState::WEW_REQUEST_CLOSE => Self::RequestClose,
-            sys::WebViewState::WEW_CLOSE => Self::Close,
+        unsafe {
+            sys::webview_start_screencast(
+                self.inner.raw.lock().as_ptr(),
+                &options,
+                Some(on_screencast_frame),
+                context,
+            );
         }
+
+        screencast
     }
-}
 
-impl From<KeyboardEventType> for sys::KeyEventType {
-    fn from(val: KeyboardEventType) -> Self {
-        match val {
-            KeyboardEventType::KeyDown => sys::KeyEventType::WEW_KEYEVENT_KEYDOWN,
-            KeyboardEventType::KeyUp => sys::KeyEventType::WEW_KEYEVENT_KEYUP,
-            KeyboardEventType::Char => sys::KeyEventType::WEW_KEYEVENT_CHAR,
+    /// Stop the screencast
+    ///
+    /// This function is used to stop the running screencast, its stream ends
+    /// after the frames that have already been received.
+    pub fn stop_screencast(&self) {
+        unsafe {
+            sys::webview_stop_screencast(self.inner.raw.lock().as_ptr());
         }
     }
-}
 
-impl From<KeyboardModifiers> for sys::EventFlags {
-    fn from(val: KeyboardModifiers) -> Self {
-        match val {
-            KeyboardModifiers::None => sys::EventFlags::WEW_EVENTFLAG_NONE,
-            KeyboardModifiers::Win => sys::EventFlags::WEW_EVENTFLAG_COMMAND_DOWN,
-            KeyboardModifiers::Shift => sys::EventFlags::WEW_EVENTFLAG_SHIFT_DOWN,
-            KeyboardModifiers::Ctrl => sys::EventFlags::WEW_EVENTFLAG_CONTROL_DOWN,
-            KeyboardModifiers::Alt => sys::EventFlags::WEW_EVENTFLAG_ALT_DOWN,
-            KeyboardModifiers::Command => sys::EventFlags::WEW_EVENTFLAG_COMMAND_DOWN,
-            KeyboardModifiers::CapsLock => sys::EventFlags::WEW_EVENTFLAG_CAPS_LOCK_ON,
-            _ => sys::EventFlags::WEW_EVENTFLAG_NONE,
+    /// Send a message
+    ///
+    /// This function is used to send a message to the web page.
+    ///
+    /// Messages sent from the web page are received through the
+    /// **`WebViewHandler::on_message`** callback.
+    pub fn send_message(&self, message: &str) {
+        let message = CString::new(message).unwrap();
+
+        unsafe {
+            sys::webview_send_message(
+                self.inner.raw.lock().as_ptr(),
+                null(),
+                null(),
+                message.as_raw(),
+            );
         }
     }
-}
 
-impl From<MouseButton> for sys::MouseButton {
-    fn from(val: MouseButton) -> Self {
-        match val {
-            MouseButton::Left => sys::MouseButton::WEW_MBT_LEFT,
-            MouseButton::Middle => sys::MouseButton::WEW_MBT_MIDDLE,
-            MouseButton::Right => sys::MouseButton::WEW_MBT_RIGHT,
+    /// Send a message to a frame
+    ///
+    /// This function is used to send a message to a frame of the web page, the
+    /// frame is identified by **`FrameInfo::id`**.
+    ///
+    /// The message is discarded if the frame does not exist.
+    pub fn send_message_to_frame(&self, frame: &str, message: &str) {
+        let frame = CString::new(frame).unwrap();
+        let message = CString::new(message).unwrap();
+
+        unsafe {
+            sys::webview_send_message(
+                self.inner.raw.lock().as_ptr(),
+                frame.as_raw(),
+                null(),
+                message.as_raw(),
+            );
         }
     }
-}
 
-struct WebViewContext {
-    runtime: Option<Arc<IRuntime>>,
-    handler: MixWebviewHnadler,
-}
+    /// Send a binary message
+    ///
+    /// This function is used to send a binary message to the web page, the
+    /// web page receives it as an `ArrayBuffer`.
+    ///
+    /// Binary messages sent from the web page are received through the
+    /// **`WebViewHandler::on_binary_message`** callback.
+    pub fn send_binary_message(&self, message: &[u8]) {
+        unsafe {
+            sys::webview_send_binary_message(
+                self.inner.raw.lock().as_ptr(),
+                null(),
+                null(),
+                message.as_ptr(),
+                message.len(),
+            );
+        }
+    }
 
-pub(crate) enum MixWebviewHnadler {
-    WebViewHandler(Box<dyn WebViewHandler>),
-    WindowlessRenderWebViewHandler(Box<dyn WindowlessRenderWebViewHandler>),
-}
+    /// Send a binary message to a frame
+    ///
+    /// This function is used to send a binary message to a frame of the web
+    /// page, the frame is identified by **`FrameInfo::id`**.
+    ///
+    /// The message is discarded if the frame does not exist.
+    pub fn send_binary_message_to_frame(&self, frame: &str, message: &[u8]) {
+        let frame = CString::new(frame).unwrap();
 
-extern "C" fn on_state_change_callback(state: sys::WebViewState, context: *mut c_void) {
-    if context.is_null() {
-        return;
+        unsafe {
+            sys::webview_send_binary_message(
+                self.inner.raw.lock().as_ptr(),
+                frame.as_raw(),
+                null(),
+                message.as_ptr(),
+                message.len(),
+            );
+        }
     }
 
-    let state = WebViewState::from(state);
-    let context = unsafe { &mut *(context as *mut WebViewContext) };
+    /// Execute JavaScript
+    ///
+    /// This function is used to execute a string of JavaScript code in the
+    /// main frame of the web page.
+    ///
+    /// The code is discarded if the web page has not been created yet.
+    pub fn execute_javascript(&self, script: &str) {
+        let script = CString::new(script).unwrap();
 
-    // Only after all webviews are closed can the runtime be closed. Here, we clear
-    // the reference held by the current webview.
-    //
-    // If all webviews are closed, the runtime reference will be cleared,
-    // and only then will the runtime's Drop be triggered.
-    if state == WebViewState::Close {
-        drop(context.runtime.take());
+        unsafe {
+            sys::webview_execute_javascript(
+                self.inner.raw.lock().as_ptr(),
+                null(),
+                script.as_raw(),
+            );
+        }
     }
 
-    match &context.handler {
-        MixWebviewHnadler::WebViewHandler(handler) => handler.on_state_change(state),
-        MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
-            handler.on_state_change(state)
+    /// Query the first element matching a CSS selector
+    ///
+    /// This function is used to describe the first element of the main frame
+    /// that matches the selector, see the **`dom`** module. The future resolves
+    /// to `None` if no element matches or the selector is invalid.
+    ///
+    /// The future also resolves to `None` if JavaScript is disabled, or if the
+    /// page has not answered within 10 seconds.
+    pub fn query_selector(&self, selector: &str) -> DomQuery<Option<ElementInfo>> {
+        if !self.inner.javascript {
+            return DomQuery::resolved(None);
         }
-    }
-}
 
-extern "C" fn on_ime_rect_callback(rect: sys::Rect, context: *mut c_void) {
-    if context.is_null() {
-        return;
-    }
+        let (query, script) = self
+            .inner
+            .context()
+            .dom
+            .query(selector, false, dom::parse_element);
 
-    let context = unsafe { &*(context as *mut WebViewContext) };
+        if let Some(script) = script {
+            self.execute_javascript(&script);
+        }
 
-    if let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = &context.handler {
-        handler.on_ime_rect(Rect {
-            x: rect.x as u32,
-            y: rect.y as u32,
-            width: rect.width as u32,
-            height: rect.height as u32,
-        })
+        query
     }
-}
 
-extern "C" fn on_frame_callback(frame: *const sys::Frame, context: *mut c_void) {
-    if context.is_null() || frame.is_null() {
-        return;
-    }
-
-    let raw_frame = unsafe { &*frame };
-    let context = unsafe { &*(context as *mut WebViewContext) };
-
-    let frame = Frame {
-        x: raw_frame.x,
-        y: raw_frame.y,
-        width: raw_frame.width,
-        height: raw_frame.height,
-        buffer: unsafe {
-            std::slice::from_raw_parts(
-                raw_frame.buffer as *const u8,
-                raw_frame.width as usize * raw_frame.height as usize * 4,
-            )
-        },
-        ty: if raw_frame.is_popup {
-            FrameType::Popup
-        } else {
-            FrameType::View
-        },
-    };
+    /// Query all elements matching a CSS selector
+    ///
+    /// This function is the same as **`WebView::query_selector`**, except that
+    /// all matching elements are described, in document order.
+    pub fn query_selector_all(&self, selector: &str) -> DomQuery<Vec<ElementInfo>> {
+        if !self.inner.javascript {
+            return DomQuery::resolved(Vec::new());
+        }
 
-    if let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = &context.handler {
-        handler.on_frame(&frame);
-    }
-}
+        let (query, script) = self
+            .inner
+            .context()
+            .dom
+            .query(selector, true, dom::parse_elements);
 
-extern "C" fn on_title_change_callback(title: *const c_char, context: *mut c_void) {
-    if context.is_null() || title.is_null() {
-        return;
+        if let Some(script) = script {
+            self.execute_javascript(&script);
+        }
+
+        query
     }
 
-    let context = unsafe { &*(context as *mut WebViewContext) };
+    /// Execute JavaScript in a frame
+    ///
+    /// This function is used to execute a string of JavaScript code in a frame
+    /// of the web page, the frame is identified by **`FrameInfo::id`**.
+    ///
+    /// The code is discarded if the frame does not exist.
+    pub fn execute_javascript_in_frame(&self, frame: &str, script: &str) {
+        let frame = CString::new(frame).unwrap();
+        let script = CString::new(script).unwrap();
 
-    if let Ok(title) = unsafe { CStr::from_ptr(title) }.to_str() {
-        match &context.handler {
-            MixWebviewHnadler::WebViewHandler(handler) => handler.on_title_change(title),
-            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
-                handler.on_title_change(title)
-            }
+        unsafe {
+            sys::webview_execute_javascript(
+                self.inner.raw.lock().as_ptr(),
+                frame.as_raw(),
+                script.as_raw(),
+            );
         }
     }
-}
-extern "C" fn on_fullscreen_change_callback(fullscreen: bool, context: *mut c_void) {
-    if context.is_null() {
-        return;
+
+    /// Scroll to a position
+    ///
+    /// This function is used to scroll the main frame of the web page to a
+    /// position in CSS pixels, such as to restore the reading position. The
+    /// page is scrolled directly instead of receiving wheel events, and
+    /// smooth scrolling is not applied.
+    pub fn scroll_to(&self, x: i32, y: i32) {
+        self.execute_javascript(&format!(
+            "window.scrollTo({{ left: {}, top: {}, behavior: 'instant' }});",
+            x, y
+        ));
     }
 
-    let context = unsafe { &*(context as *mut WebViewContext) };
+    /// Scroll by an offset
+    ///
+    /// This function is used to scroll the main frame of the web page by an
+    /// offset in CSS pixels, see **`WebView::scroll_to`**.
+    pub fn scroll_by(&self, x: i32, y: i32) {
+        self.execute_javascript(&format!(
+            "window.scrollBy({{ left: {}, top: {}, behavior: 'instant' }});",
+            x, y
+        ));
+    }
+
+    /// Emit an event
+    ///
+    /// This function is used to emit a named event to the web page, the
+    /// payload is serialized as JSON.
+    ///
+    /// Events are received by the main frame of the web page through
+    /// **`window.wew.on`**, if no listener is registered for the event, the
+    /// event is discarded. An error is returned if the payload cannot be
+    /// serialized or the event name contains a NUL character.
+    pub fn emit<T>(&self, event: &str, payload: T) -> Result<(), serde_json::Error>
+    where
+        T: Serialize,
+    {
+        // The event name is passed as a C string, a NUL in it cannot be sent.
+        let event =
+            CString::new(event).map_err(<serde_json::Error as serde::ser::Error>::custom)?;
+        let payload = CString::new(serde_json::to_string(&payload)?).unwrap();
 
-    match &context.handler {
-        MixWebviewHnadler::WebViewHandler(handler) => handler.on_fullscreen_change(fullscreen),
-        MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
-            handler.on_fullscreen_change(fullscreen)
+        unsafe {
+            sys::webview_emit(
+                self.inner.raw.lock().as_ptr(),
+                event.as_raw(),
+                payload.as_raw(),
+            );
         }
+
+        Ok(())
     }
-}
 
-extern "C" fn on_message_callback(message: *const c_char, context: *mut c_void) {
-    if context.is_null() || message.is_null() {
-        return;
+    /// Open a named channel
+    ///
+    /// This function is used to register a handler for the messages sent by
+    /// the web page on a named channel, so that different subsystems do not
+    /// have to share the **`WebViewHandler::on_message`** callback.
+    ///
+    /// The page side counterpart is **`MessageTransport.channel(name)`**,
+    /// which has its own `send` and `on`. Opening a channel that is already
+    /// open replaces its handler, messages received on a channel without
+    /// handler are discarded, so channels should be opened right after the
+    /// webview is created.
+    ///
+    /// #### Panics
+    ///
+    /// Panics if the name is empty, the empty name is reserved for the
+    /// default channel. The `__wew_dom` name is reserved for the helper of the
+    /// **`dom`** module.
+    pub fn channel<T>(&self, name: &str, handler: T) -> Channel
+    where
+        T: ChannelHandler + 'static,
+    {
+        assert!(!name.is_empty(), "channel name must not be empty");
+        assert!(name != DOM_CHANNEL, "channel name is reserved");
+
+        let handler: Arc<dyn ChannelHandler> = Arc::new(handler);
+        self.inner
+            .context()
+            .channels
+            .write()
+            .insert(name.to_string(), handler.clone());
+
+        Channel {
+            name: name.to_string(),
+            webview: Arc::downgrade(&self.inner),
+            handler,
+        }
     }
 
-    let context = unsafe { &*(context as *mut WebViewContext) };
+    /// Set whether developer tools are enabled
+    ///
+    /// This function is used to set whether developer tools are enabled.
+    pub fn devtools_enabled(&self, enable: bool) {
+        unsafe { sys::webview_set_devtools_state(self.inner.raw.lock().as_ptr(), enable) }
+    }
 
-    if let Ok(message) = unsafe { CStr::from_ptr(message) }.to_str() {
-        match &context.handler {
-            MixWebviewHnadler::WebViewHandler(handler) => handler.on_message(message),
-            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
-                handler.on_message(message)
-            }
+    /// Get the URL that shows the source of the web page
+    ///
+    /// This is the `view-source:` URL of the main frame, it is loaded in a
+    /// separate webview so that the page of this webview is kept.
+    fn view_source_url(&self) -> Result<String, Error> {
+        if self.inner.block_devtools {
+            return Err(Error::NotAllowed {
+                reason: "the developer tools are blocked by the kiosk policy",
+            });
         }
+
+        let frame = self
+            .frames()
+            .into_iter()
+            .find(|it| it.is_main)
+            .ok_or(Error::WindowNotCreated)?;
+
+        Ok(format!("view-source:{}", frame.url))
     }
-}
 
-extern "C" fn on_cursor_callback(ty: sys::CursorType, context: *mut c_void) {
-    if context.is_null() {
-        return;
+    /// Set the focus state
+    ///
+    /// This function is used to give the focus to the web page or remove it,
+    /// such as when the user tabs from the host UI into the webview. The page
+    /// gives the focus back with **`WebViewHandler::on_take_focus`**.
+    pub fn focus(&self, state: bool) {
+        unsafe { sys::webview_set_focus(self.inner.raw.lock().as_ptr(), state) }
     }
 
-    let ty = unsafe { std::mem::transmute::<sys::CursorType, CursorType>(ty) };
+    /// Send a keyboard event
+    ///
+    /// This function is used to send keyboard events.
+    ///
+    /// In native window mode the event is sent to the browser as if it came
+    /// from the window, which can be used to inject shortcuts or simulate
+    /// typing. **`Error::WebViewClosed`** is returned once the webview is
+    /// closed.
+    pub fn keyboard(&self, event: &KeyboardEvent) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        #[cfg(feature = "windowless")]
+        self.inner.record(|| InputEvent::Keyboard(*event));
+
+        if self.inner.ctrl_wheel_zoom {
+            self.inner.update_keyboard_modifiers(event);
+        }
+
+        unsafe {
+            sys::webview_keyboard(
+                self.inner.raw.lock().as_ptr(),
+                sys::KeyEvent {
+                    modifiers: event_flags(event.modifiers),
+                    character: event.character,
+                    unmodified_character: event.unmodified_character,
+                    windows_key_code: event.windows_key_code as i32,
+                    native_key_code: event.native_key_code as i32,
+                    is_system_key: event.is_system_key as i32,
+                    focus_on_editable_field: event.focus_on_editable_field as i32,
+                    type_: event.ty.into(),
+                },
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Send a shortcut
+    ///
+    /// This function is used to press and release a key with the modifiers
+    /// held, such as `Ctrl+A`, the key is a Windows virtual key code.
+    pub fn shortcut(
+        &self,
+        windows_key_code: u32,
+        modifiers: KeyboardModifiers,
+    ) -> Result<(), Error> {
+        for ty in [KeyboardEventType::KeyDown, KeyboardEventType::KeyUp] {
+            self.keyboard(&KeyboardEvent {
+                ty,
+                modifiers,
+                windows_key_code,
+                ..Default::default()
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Type a text
+    ///
+    /// This function is used to send a character event for each character of
+    /// the text, which inserts the text into the focused editable element.
+    pub fn type_text(&self, text: &str) -> Result<(), Error> {
+        for character in text.encode_utf16() {
+            self.keyboard(&KeyboardEvent {
+                ty: KeyboardEventType::Char,
+                windows_key_code: character as u32,
+                character,
+                unmodified_character: character,
+                ..Default::default()
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the zoom level
+    ///
+    /// This function is used to get the zoom level set with
+    /// **`WebView::set_zoom_level`** or with ctrl and the mouse wheel.
+    pub fn zoom_level(&self) -> f64 {
+        *self.inner.zoom_level.lock()
+    }
+
+    /// Set the zoom level
+    ///
+    /// This function is used to zoom the page, `0.0` is the default level and
+    /// each step up or down scales the page by 20 percent, so `1.0` is 120%
+    /// and `-1.0` is about 83%. The level is limited to the range of 25% to
+    /// 500%, the level that is actually set is returned.
+    pub fn set_zoom_level(&self, level: f64) -> f64 {
+        self.inner.set_zoom_level(level)
+    }
+
+    /// Print the web page
+    ///
+    /// This function is used to show the print dialog of the system for the
+    /// web page, **`WebViewHandler::on_print_start`** and
+    /// **`WebViewHandler::on_print_finished`** are called around it.
+    pub fn print(&self) {
+        unsafe { sys::webview_print(self.inner.raw.lock().as_ptr()) }
+    }
+
+    /// Find a text in the web page
+    ///
+    /// This function is used to highlight the matches of the text and select
+    /// the next one, calling it again with the same text moves to the next
+    /// match, like the find bar of browsers. The results are reported by
+    /// **`WebViewHandler::on_find_result`**.
+    pub fn find(&self, text: &str, options: &FindOptions) {
+        // An empty text would only clear the highlighted matches.
+        if text.is_empty() {
+            self.stop_finding(true);
+
+            return;
+        }
+
+        let find_next = {
+            let mut find_text = self.inner.find_text.lock();
+            let find_next = find_text.as_deref() == Some(text);
+
+            *find_text = Some(text.to_string());
+            find_next
+        };
+
+        let text = CString::new(text).unwrap();
+
+        unsafe {
+            sys::webview_find(
+                self.inner.raw.lock().as_ptr(),
+                text.as_raw(),
+                options.forward,
+                options.match_case,
+                find_next,
+            )
+        }
+    }
+
+    /// Stop finding
+    ///
+    /// This function is used to cancel the search and remove the highlighted
+    /// matches. The active match stays selected if `clear_selection` is
+    /// `false`.
+    pub fn stop_finding(&self, clear_selection: bool) {
+        self.inner.find_text.lock().take();
+
+        unsafe { sys::webview_stop_finding(self.inner.raw.lock().as_ptr(), clear_selection) }
+    }
+
+    /// Exit fullscreen
+    ///
+    /// This function is used to force the web page out of the fullscreen mode
+    /// it entered with the JavaScript fullscreen API, such as when the host
+    /// handles the escape key itself or does not allow fullscreen.
+    /// **`WebViewHandler::on_fullscreen_change`** is called with `false` once
+    /// the page has left fullscreen.
+    pub fn exit_fullscreen(&self) {
+        unsafe { sys::webview_exit_fullscreen(self.inner.raw.lock().as_ptr()) }
+    }
+
+    /// Set the forced colors mode
+    ///
+    /// This function is used to keep the page legible for the users of a high
+    /// contrast theme, such as when the host has its own high contrast setting.
+    /// The `forced-colors` and `prefers-contrast` media features and the colors
+    /// of the page are updated immediately, **`ForcedColors::System`** removes
+    /// the override.
+    pub fn set_forced_colors(&self, value: ForcedColors) {
+        unsafe { sys::webview_set_forced_colors(self.inner.raw.lock().as_ptr(), value.into()) }
+    }
+
+    /// Update the settings
+    ///
+    /// This function is used to change the settings that CEF supports after
+    /// the webview is created, without recreating the webview. The other
+    /// attributes can only be set with **`WebViewAttributes`**.
+    ///
+    /// The settings are validated like the attributes, nothing is changed if
+    /// one of them is invalid. The settings are not applied before the
+    /// browser has been created, except for the zoom level.
+    pub fn update_settings(&self, delta: WebViewSettingsDelta) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        if let Some(frame_rate) = delta.windowless_frame_rate
+            && !(1..=240).contains(&frame_rate)
+        {
+            return Err(Error::InvalidAttribute {
+                name: "windowless_frame_rate",
+                reason: "must be between 1 and 240",
+            });
+        }
+
+        if let Some(level) = delta.zoom_level {
+            self.inner.set_zoom_level(level);
+        }
+
+        if let (Some(frame_rate), Some(metrics)) =
+            (delta.windowless_frame_rate, &self.inner.context().metrics)
+        {
+            metrics.set_frame_rate(frame_rate);
+        }
+
+        let user_agent = delta
+            .user_agent
+            .as_ref()
+            .map(|it| CString::new(it.as_str()).unwrap());
+
+        let user_agent_metadata = delta
+            .user_agent_metadata
+            .as_ref()
+            .map(|it| CString::new(it.to_json()).unwrap());
+
+        unsafe {
+            sys::webview_update_settings(
+                self.inner.raw.lock().as_ptr(),
+                &sys::WebViewSettingsDelta {
+                    windowless_frame_rate: delta.windowless_frame_rate.unwrap_or(0),
+                    set_muted: delta.muted.is_some(),
+                    muted: delta.muted.unwrap_or(false),
+                    set_background_color: delta.background_color.is_some(),
+                    background_color: delta.background_color.unwrap_or(0),
+                    user_agent: user_agent.as_raw(),
+                    user_agent_metadata: user_agent_metadata.as_raw(),
+                },
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Cut the selection
+    ///
+    /// This function is used to cut the selection of the focused frame to the
+    /// clipboard, such as for the cut item of a native edit menu.
+    pub fn cut(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_CUT);
+    }
+
+    /// Copy the selection
+    ///
+    /// This function is used to copy the selection of the focused frame to the
+    /// clipboard.
+    pub fn copy(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_COPY);
+    }
+
+    /// Paste the clipboard
+    ///
+    /// This function is used to paste the content of the clipboard into the
+    /// focused element of the focused frame.
+    pub fn paste(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_PASTE);
+    }
+
+    /// Paste the clipboard as plain text
+    ///
+    /// This function is used to paste the content of the clipboard without its
+    /// formatting, the pasted text matches the style of the surrounding text.
+    pub fn paste_as_plain_text(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_PASTE_AS_PLAIN_TEXT);
+    }
+
+    /// Select all
+    ///
+    /// This function is used to select all the content of the focused element,
+    /// or of the focused frame if no editable element is focused.
+    pub fn select_all(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_SELECT_ALL);
+    }
+
+    /// Undo the last edit
+    ///
+    /// This function is used to undo the last edit of the focused frame, such
+    /// as for the undo item of a native edit menu.
+    pub fn undo(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_UNDO);
+    }
+
+    /// Redo the last undone edit
+    ///
+    /// This function is used to redo the last edit of the focused frame that
+    /// was undone with **`WebView::undo`**.
+    pub fn redo(&self) {
+        self.inner
+            .edit_command(sys::EditCommand::WEW_EDIT_COMMAND_REDO);
+    }
+}
+
+impl WebView<NativeWindowWebView> {
+    /// View the source of the web page
+    ///
+    /// This function is used to create a separate webview that shows the HTML
+    /// source of the main frame, such as for developer tools built on the
+    /// webview. The page of this webview is kept, the returned webview is
+    /// created with the attributes and the handler like
+    /// **`Runtime::create_webview`**.
+    ///
+    /// **`Error::WindowNotCreated`** is returned if the page does not exist,
+    /// and **`Error::NotAllowed`** if the developer tools are blocked by the
+    /// **`KioskPolicy`** of this webview.
+    pub fn view_source<R, T>(
+        &self,
+        runtime: &Runtime<R, NativeWindowWebView>,
+        attr: WebViewAttributes,
+        handler: T,
+    ) -> Result<Self, Error>
+    where
+        T: WebViewHandler + 'static,
+        R: Clone,
+    {
+        runtime.create_webview(&self.view_source_url()?, attr, handler)
+    }
+}
+
+#[cfg(feature = "windowless")]
+impl WebView<WindowlessRenderWebView> {
+    /// View the source of the web page
+    ///
+    /// This function is the same as the one of the native window webviews,
+    /// the source is shown in a separate windowless webview.
+    pub fn view_source<R, T>(
+        &self,
+        runtime: &Runtime<R, WindowlessRenderWebView>,
+        attr: WebViewAttributes,
+        handler: T,
+    ) -> Result<Self, Error>
+    where
+        T: WindowlessRenderWebViewHandler + 'static,
+        R: Clone,
+    {
+        runtime.create_webview(&self.view_source_url()?, attr, handler)
+    }
+
+    /// Send a mouse event
+    ///
+    /// This function is used to send mouse events.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn mouse(&self, action: &MouseEvent) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::Mouse(action.clone()));
+
+        let mut event = self.inner.mouse_event.lock();
+
+        match action {
+            MouseEvent::Move(pos) => {
+                event.x = pos.x;
+                event.y = pos.y;
+
+                if !self.inner.coalesce_mouse_moves {
+                    unsafe { sys::webview_mouse_move(self.inner.raw.lock().as_ptr(), *event) }
+
+                    return Ok(());
+                }
+
+                // Only the first move of a burst posts a task, the following moves
+                // only update the position that the task will forward.
+                if !self.inner.mouse_move_pending.swap(true, Ordering::AcqRel) {
+                    let inner = Arc::downgrade(&self.inner);
+                    let posted = post_main(move || {
+                        if let Some(inner) = inner.upgrade() {
+                            inner.flush_mouse_move(&inner.mouse_event.lock());
+                        }
+                    });
+
+                    if !posted {
+                        self.inner.flush_mouse_move(&event);
+                    }
+                }
+            }
+            MouseEvent::Wheel(pos)
+                if self.inner.ctrl_wheel_zoom
+                    && pos.y != 0
+                    && self.inner.is_zoom_modifier_pressed() =>
+            {
+                self.inner.flush_mouse_move(&event);
+
+                let level = *self.inner.zoom_level.lock() + ZOOM_LEVEL_STEP * pos.y.signum() as f64;
+                let level = self.inner.set_zoom_level(level);
+
+                let context = self.inner.context();
+                context.events.send(|| WebViewEvent::ZoomChange(level));
+                context.dispatch(move |id, handler| {
+                    if let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = handler {
+                        handler.on_zoom_change(id, level)
+                    }
+                });
+            }
+            MouseEvent::Wheel(pos) => unsafe {
+                self.inner.flush_mouse_move(&event);
+
+                sys::webview_mouse_wheel(self.inner.raw.lock().as_ptr(), *event, pos.x, pos.y)
+            },
+            MouseEvent::Click(button, is_pressed, pos) => {
+                self.inner.flush_mouse_move(&event);
+
+                if let Some(pos) = pos {
+                    event.x = pos.x;
+                    event.y = pos.y;
+                }
+
+                if *is_pressed {
+                    event.modifiers |= match button {
+                        MouseButton::Left => sys::EventFlags::WEW_EVENTFLAG_LEFT_MOUSE_BUTTON,
+                        MouseButton::Right => sys::EventFlags::WEW_EVENTFLAG_RIGHT_MOUSE_BUTTON,
+                        MouseButton::Middle => sys::EventFlags::WEW_EVENTFLAG_MIDDLE_MOUSE_BUTTON,
+                    } as u32;
+                } else {
+                    event.modifiers = 0;
+                };
+
+                unsafe {
+                    sys::webview_mouse_click(
+                        self.inner.raw.lock().as_ptr(),
+                        *event,
+                        (*button).into(),
+                        *is_pressed,
+                    )
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a drag enter event
+    ///
+    /// This function is used to forward a drag of the operating system that
+    /// entered the view, so that the page receives the HTML5 drag and drop
+    /// events. `operations` are the operations allowed by the drag source.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn drag_enter(
+        &self,
+        data: &DragData,
+        pos: Position,
+        operations: DragOperations,
+    ) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner
+            .record(|| InputEvent::DragEnter(data.clone(), pos, operations));
+
+        let files = data
+            .files
+            .iter()
+            .filter_map(|it| CString::new(it.to_string_lossy().as_ref()).ok())
+            .collect::<Vec<_>>();
+
+        let files_ptr = files.iter().map(|it| it.as_raw()).collect::<Vec<_>>();
+        let text = data.text.as_deref().and_then(|it| CString::new(it).ok());
+        let url = data.url.as_deref().and_then(|it| CString::new(it).ok());
+
+        let data = sys::DragData {
+            files: files_ptr.as_ptr() as _,
+            files_size: files_ptr.len(),
+            text: text.as_raw(),
+            url: url.as_raw(),
+        };
+
+        let mut event = self.inner.mouse_event.lock();
+        self.inner.flush_mouse_move(&event);
+
+        event.x = pos.x;
+        event.y = pos.y;
+
+        // Nothing is accepted until the page has seen the drag.
+        self.inner
+            .context()
+            .drag_operation
+            .store(0, Ordering::Release);
+
+        unsafe {
+            sys::webview_drag_enter(
+                self.inner.raw.lock().as_ptr(),
+                &data,
+                *event,
+                operations.bits(),
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Send a drag over event
+    ///
+    /// This function is used to forward the moves of the cursor during a drag,
+    /// after **`WebView::drag_enter`**. `operations` are the operations allowed
+    /// by the drag source at this position, such as when a modifier key
+    /// restricts the drag to a copy.
+    ///
+    /// The operation accepted by the drop target of the page is returned, it
+    /// should be reported to the drag loop of the operating system. The page
+    /// handles the event asynchronously, so the operation is the one accepted
+    /// at the previous position.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn drag_over(
+        &self,
+        pos: Position,
+        operations: DragOperations,
+    ) -> Result<DragOperations, Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::DragOver(pos, operations));
+
+        let mut event = self.inner.mouse_event.lock();
+        event.x = pos.x;
+        event.y = pos.y;
+
+        unsafe { sys::webview_drag_over(self.inner.raw.lock().as_ptr(), *event, operations.bits()) }
+
+        let accepted = self.inner.context().drag_operation.load(Ordering::Acquire);
+        Ok(DragOperations::from_bits_truncate(accepted) & operations)
+    }
+
+    /// Update the cursor of a drag
+    ///
+    /// This function is used to show the badge of an operation on the cursor,
+    /// **`WebViewHandler::on_cursor_change`** is called with the matching drag
+    /// cursor. It is called automatically when the drop target of the page
+    /// changes the accepted operation, and can be called when the host runs
+    /// the drag loop of a drag started by the page, to show the operation of
+    /// the drop target of the operating system.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn update_drag_cursor(&self, operation: DragOperations) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.context().update_drag_cursor(operation);
+
+        Ok(())
+    }
+
+    /// Send a drop event
+    ///
+    /// This function is used to complete a drag, the page receives the dropped
+    /// data at the position.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn drop(&self, pos: Position) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::Drop(pos));
+
+        let mut event = self.inner.mouse_event.lock();
+        event.x = pos.x;
+        event.y = pos.y;
+
+        unsafe { sys::webview_drop(self.inner.raw.lock().as_ptr(), *event) }
+
+        Ok(())
+    }
+
+    /// Send a drag leave event
+    ///
+    /// This function is used to cancel a drag, such as when the cursor left
+    /// the view.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn drag_leave(&self) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::DragLeave);
+
+        unsafe { sys::webview_drag_leave(self.inner.raw.lock().as_ptr()) }
+
+        Ok(())
+    }
+
+    /// Complete a drag started by the page
+    ///
+    /// This function is used to report the end of the drag loop started for
+    /// **`WindowlessRenderWebViewHandler::on_start_dragging`**, `operation` is
+    /// the operation performed by the drop target, or
+    /// **`DragOperations::None`** if the drag was cancelled.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn drag_source_ended(&self, pos: Position, operation: DragOperations) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        unsafe {
+            sys::webview_drag_source_ended(
+                self.inner.raw.lock().as_ptr(),
+                pos.x,
+                pos.y,
+                operation.bits(),
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Send a touch event
+    ///
+    /// This function is used to send the touch points of a touch screen or a
+    /// pen, the web page receives them as touch and pointer events.
+    ///
+    /// Note that this function only works in windowless rendering mode, and
+    /// **`Error::WebViewClosed`** is returned once the webview is closed.
+    pub fn touch(&self, event: &TouchEvent) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::Touch(*event));
+
+        unsafe {
+            sys::webview_touch(
+                self.inner.raw.lock().as_ptr(),
+                sys::TouchEvent {
+                    id: event.id,
+                    x: event.x,
+                    y: event.y,
+                    radius_x: event.radius_x,
+                    radius_y: event.radius_y,
+                    rotation_angle: event.rotation_angle,
+                    pressure: event.pressure,
+                    type_: event.ty.into(),
+                    modifiers: event_flags(event.modifiers),
+                    pointer_type: event.pointer_type.into(),
+                },
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Send an IME event
+    ///
+    /// This function is used to send IME events.
+    ///
+    /// Note that this function only works in windowless rendering mode, and
+    /// **`Error::WebViewClosed`** is returned once the webview is closed.
+    pub fn ime(&self, action: &IMEAction) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::WebViewClosed);
+        }
+
+        self.inner.record(|| InputEvent::from(*action));
+
+        let input = match action {
+            IMEAction::Composition(it) | IMEAction::Pre(it, _, _) => CString::new(*it).unwrap(),
+        };
+
+        match action {
+            IMEAction::Composition(_) => unsafe {
+                sys::webview_ime_composition(self.inner.raw.lock().as_ptr(), input.as_raw())
+            },
+            IMEAction::Pre(_, x, y) => unsafe {
+                sys::webview_ime_set_composition(
+                    self.inner.raw.lock().as_ptr(),
+                    input.as_raw(),
+                    *x,
+                    *y,
+                )
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Send an input event
+    ///
+    /// This function is used to replay the events recorded with
+    /// **`InputRecorder`**, each event is sent with the corresponding input
+    /// function, such as **`WebView::mouse`** for a mouse event.
+    ///
+    /// Note that this function only works in windowless rendering mode, and
+    /// **`Error::WebViewClosed`** is returned once the webview is closed.
+    pub fn input(&self, event: &InputEvent) -> Result<(), Error> {
+        match event {
+            InputEvent::Mouse(it) => self.mouse(it),
+            InputEvent::Keyboard(it) => self.keyboard(it),
+            InputEvent::Touch(it) => self.touch(it),
+            InputEvent::ImeComposition(it) => self.ime(&IMEAction::Composition(it)),
+            InputEvent::ImePre(it, start, end) => self.ime(&IMEAction::Pre(it, *start, *end)),
+            InputEvent::DragEnter(data, pos, operations) => {
+                self.drag_enter(data, *pos, *operations)
+            }
+            InputEvent::DragOver(pos, operations) => self.drag_over(*pos, *operations).map(|_| ()),
+            InputEvent::DragLeave => self.drag_leave(),
+            InputEvent::Drop(pos) => self.drop(*pos),
+        }
+    }
+
+    /// Start recording the input
+    ///
+    /// This function is used to record all input sent to the webview from now
+    /// on, a recording that is already in progress is restarted.
+    ///
+    /// See the **`replay`** module for details.
+    pub fn start_recording(&self) {
+        *self.inner.recorder.lock() = Some(InputRecorder::new());
+    }
+
+    /// Stop recording the input
+    ///
+    /// Returns the recorded input, or `None` if the input is not being
+    /// recorded.
+    pub fn stop_recording(&self) -> Option<Recording> {
+        self.inner.recorder.lock().take().map(Recording::from)
+    }
+
+    /// Resize the window
+    ///
+    /// This function is used to resize the window.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn resize(&self, width: u32, height: u32) {
+        unsafe {
+            sys::webview_resize(
+                self.inner.raw.lock().as_ptr(),
+                width as c_int,
+                height as c_int,
+            )
+        }
+    }
+
+    /// Notify that the screen info has changed
+    ///
+    /// This function is used to request the screen info again from
+    /// **`WindowlessRenderWebViewHandler::on_screen_info`**, such as when the
+    /// window is moved to another monitor or the scale of the monitor changes.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn notify_screen_info_changed(&self) {
+        unsafe { sys::webview_notify_screen_info_changed(self.inner.raw.lock().as_ptr()) }
+    }
+
+    /// Set the device scale factor
+    ///
+    /// This function is used to change **`WebViewAttributes::device_scale_factor`**
+    /// after the webview is created, such as when the window is moved to a
+    /// monitor with a different DPI. The value is ignored if
+    /// **`WindowlessRenderWebViewHandler::on_screen_info`** returns a screen
+    /// info.
+    ///
+    /// Note that this function only works in windowless rendering mode.
+    pub fn set_device_scale_factor(&self, value: f32) {
+        unsafe { sys::webview_set_device_scale_factor(self.inner.raw.lock().as_ptr(), value) }
+    }
+}
+
+impl From<&sys::WebViewStateChange> for WebViewState {
+    fn from(value: &sys::WebViewStateChange) -> Self {
+        match value.state {
+            sys::WebViewState::WEW_BEFORE_LOAD => Self::BeforeLoad {
+                url: from_c_str(value.url),
+                is_main_frame: value.is_main_frame,
+            },
+            sys::WebViewState::WEW_LOADED => Self::Loaded {
+                url: from_c_str(value.url),
+                is_main_frame: value.is_main_frame,
+                http_status: value.http_status,
+            },
+            sys::WebViewState::WEW_LOAD_ERROR => Self::LoadError {
+                url: from_c_str(value.url),
+                is_main_frame: value.is_main_frame,
+                code: value.error_code,
+                text: from_c_str(value.error_text),
+            },
+            sys::WebViewState::WEW_REQUEST_CLOSE => Self::RequestClose,
+            sys::WebViewState::WEW_CLOSE => Self::Close,
+        }
+    }
+}
+
+impl From<sys::FocusSource> for FocusSource {
+    fn from(value: sys::FocusSource) -> Self {
+        match value {
+            sys::FocusSource::WEW_FOCUS_SOURCE_NAVIGATION => Self::Navigation,
+            sys::FocusSource::WEW_FOCUS_SOURCE_SYSTEM => Self::System,
+        }
+    }
+}
+
+impl From<ForcedColors> for sys::ForcedColors {
+    fn from(val: ForcedColors) -> Self {
+        match val {
+            ForcedColors::System => sys::ForcedColors::WEW_FORCED_COLORS_SYSTEM,
+            ForcedColors::Active => sys::ForcedColors::WEW_FORCED_COLORS_ACTIVE,
+            ForcedColors::None => sys::ForcedColors::WEW_FORCED_COLORS_NONE,
+        }
+    }
+}
+
+impl From<sys::ClipboardAccess> for ClipboardAccess {
+    fn from(value: sys::ClipboardAccess) -> Self {
+        match value {
+            sys::ClipboardAccess::WEW_CLIPBOARD_ACCESS_READ => Self::Read,
+            sys::ClipboardAccess::WEW_CLIPBOARD_ACCESS_WRITE => Self::Write,
+        }
+    }
+}
+
+impl From<ClipboardPolicy> for sys::ClipboardPolicy {
+    fn from(val: ClipboardPolicy) -> Self {
+        match val {
+            ClipboardPolicy::Allow => sys::ClipboardPolicy::WEW_CLIPBOARD_POLICY_ALLOW,
+            ClipboardPolicy::Deny => sys::ClipboardPolicy::WEW_CLIPBOARD_POLICY_DENY,
+            ClipboardPolicy::SanitizeHtml => {
+                sys::ClipboardPolicy::WEW_CLIPBOARD_POLICY_SANITIZE_HTML
+            }
+        }
+    }
+}
+
+impl From<sys::RenderProcessTerminationStatus> for TerminationStatus {
+    fn from(value: sys::RenderProcessTerminationStatus) -> Self {
+        match value {
+            sys::RenderProcessTerminationStatus::WEW_TS_ABNORMAL_TERMINATION => {
+                Self::AbnormalTermination
+            }
+            sys::RenderProcessTerminationStatus::WEW_TS_PROCESS_WAS_KILLED => {
+                Self::ProcessWasKilled
+            }
+            sys::RenderProcessTerminationStatus::WEW_TS_PROCESS_CRASHED => Self::ProcessCrashed,
+            sys::RenderProcessTerminationStatus::WEW_TS_PROCESS_OOM => Self::ProcessOom,
+            sys::RenderProcessTerminationStatus::WEW_TS_LAUNCH_FAILED => Self::LaunchFailed,
+            sys::RenderProcessTerminationStatus::WEW_TS_INTEGRITY_FAILURE => Self::IntegrityFailure,
+        }
+    }
+}
+
+pub(crate) fn from_c_str(value: *const c_char) -> String {
+    if value.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(value) }
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+impl From<&sys::HttpResponse> for HttpResponse {
+    fn from(value: &sys::HttpResponse) -> Self {
+        Self {
+            url: from_c_str(value.url),
+            status: value.status.clamp(0, u16::MAX as _) as u16,
+            status_text: from_c_str(value.status_text),
+            mime_type: from_c_str(value.mime_type),
+            headers: if value.headers.is_null() {
+                Vec::new()
+            } else {
+                unsafe { std::slice::from_raw_parts(value.headers, value.headers_size) }
+                    .iter()
+                    .map(|it| (from_c_str(it.name), from_c_str(it.value)))
+                    .collect()
+            },
+        }
+    }
+}
+
+impl From<&sys::Image> for Image {
+    fn from(value: &sys::Image) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            data: if value.data.is_null() {
+                Vec::new()
+            } else {
+                unsafe { std::slice::from_raw_parts(value.data, value.size) }.to_vec()
+            },
+        }
+    }
+}
+
+impl From<&sys::LayoutMetrics> for LayoutMetrics {
+    fn from(value: &sys::LayoutMetrics) -> Self {
+        Self {
+            content_width: value.content_width,
+            content_height: value.content_height,
+            viewport_width: value.viewport_width,
+            viewport_height: value.viewport_height,
+            scroll_x: value.scroll_x,
+            scroll_y: value.scroll_y,
+        }
+    }
+}
+
+impl From<&sys::PageError> for PageError {
+    fn from(value: &sys::PageError) -> Self {
+        Self {
+            message: from_c_str(value.message),
+            source: from_c_str(value.source),
+            line: value.line.max(0) as u32,
+            column: value.column.max(0) as u32,
+            stack: from_c_str(value.stack),
+        }
+    }
+}
+
+impl From<&sys::FrameInfo> for FrameInfo {
+    fn from(value: &sys::FrameInfo) -> Self {
+        Self {
+            id: from_c_str(value.identifier),
+            name: from_c_str(value.name),
+            url: from_c_str(value.url),
+            origin: from_c_str(value.origin),
+            is_main: value.is_main,
+        }
+    }
+}
+
+impl From<MessageOverflowPolicy> for sys::MessageOverflowPolicy {
+    fn from(val: MessageOverflowPolicy) -> Self {
+        match val {
+            MessageOverflowPolicy::Drop => sys::MessageOverflowPolicy::WEW_MESSAGE_OVERFLOW_DROP,
+            MessageOverflowPolicy::Throw => sys::MessageOverflowPolicy::WEW_MESSAGE_OVERFLOW_THROW,
+        }
+    }
+}
+
+impl From<KeyboardEventType> for sys::KeyEventType {
+    fn from(val: KeyboardEventType) -> Self {
+        match val {
+            KeyboardEventType::KeyDown => sys::KeyEventType::WEW_KEYEVENT_KEYDOWN,
+            KeyboardEventType::KeyUp => sys::KeyEventType::WEW_KEYEVENT_KEYUP,
+            KeyboardEventType::Char => sys::KeyEventType::WEW_KEYEVENT_CHAR,
+        }
+    }
+}
+
+fn event_flags(modifiers: KeyboardModifiers) -> u32 {
+    let mut flags = sys::EventFlags::WEW_EVENTFLAG_NONE as u32;
+    for it in KeyboardModifiers::all() {
+        if modifiers.contains(it) {
+            let flag: sys::EventFlags = it.into();
+            flags |= flag as u32;
+        }
+    }
+
+    flags
+}
+
+impl From<TouchEventType> for sys::TouchEventType {
+    fn from(val: TouchEventType) -> Self {
+        match val {
+            TouchEventType::Pressed => sys::TouchEventType::WEW_TET_PRESSED,
+            TouchEventType::Moved => sys::TouchEventType::WEW_TET_MOVED,
+            TouchEventType::Released => sys::TouchEventType::WEW_TET_RELEASED,
+            TouchEventType::Cancelled => sys::TouchEventType::WEW_TET_CANCELLED,
+        }
+    }
+}
+
+impl From<PointerType> for sys::PointerType {
+    fn from(val: PointerType) -> Self {
+        match val {
+            PointerType::Touch => sys::PointerType::WEW_POINTER_TYPE_TOUCH,
+            PointerType::Mouse => sys::PointerType::WEW_POINTER_TYPE_MOUSE,
+            PointerType::Pen => sys::PointerType::WEW_POINTER_TYPE_PEN,
+            PointerType::Eraser => sys::PointerType::WEW_POINTER_TYPE_ERASER,
+            PointerType::Unknown => sys::PointerType::WEW_POINTER_TYPE_UNKNOWN,
+        }
+    }
+}
+
+impl From<KeyboardModifiers> for sys::EventFlags {
+    fn from(val: KeyboardModifiers) -> Self {
+        match val {
+            KeyboardModifiers::None => sys::EventFlags::WEW_EVENTFLAG_NONE,
+            KeyboardModifiers::Win => sys::EventFlags::WEW_EVENTFLAG_COMMAND_DOWN,
+            KeyboardModifiers::Shift => sys::EventFlags::WEW_EVENTFLAG_SHIFT_DOWN,
+            KeyboardModifiers::Ctrl => sys::EventFlags::WEW_EVENTFLAG_CONTROL_DOWN,
+            KeyboardModifiers::Alt => sys::EventFlags::WEW_EVENTFLAG_ALT_DOWN,
+            KeyboardModifiers::Command => sys::EventFlags::WEW_EVENTFLAG_COMMAND_DOWN,
+            KeyboardModifiers::CapsLock => sys::EventFlags::WEW_EVENTFLAG_CAPS_LOCK_ON,
+            _ => sys::EventFlags::WEW_EVENTFLAG_NONE,
+        }
+    }
+}
+
+impl From<MouseButton> for sys::MouseButton {
+    fn from(val: MouseButton) -> Self {
+        match val {
+            MouseButton::Left => sys::MouseButton::WEW_MBT_LEFT,
+            MouseButton::Middle => sys::MouseButton::WEW_MBT_MIDDLE,
+            MouseButton::Right => sys::MouseButton::WEW_MBT_RIGHT,
+        }
+    }
+}
+
+/// Acknowledges a message sent by the web page when it is dropped
+///
+/// The render process counts the messages that have not been handled yet, the
+/// acknowledgement is moved into the task that calls the handler, so that it
+/// is sent once the handler has returned or the task has been dropped.
+struct MessageAck {
+    id: WebViewId,
+    runtime: Option<Arc<IRuntime>>,
+    frame: Option<CString>,
+}
+
+impl Drop for MessageAck {
+    fn drop(&mut self) {
+        let webview = self
+            .runtime
+            .as_ref()
+            .and_then(|it| it.webviews.lock().get(&self.id).and_then(Weak::upgrade));
+
+        if let (Some(webview), Some(frame)) = (webview, &self.frame) {
+            unsafe {
+                sys::webview_ack_message(webview.raw.lock().as_ptr(), frame.as_raw());
+            }
+        }
+    }
+}
+
+struct WebViewContext {
+    id: WebViewId,
+    closed: AtomicBool,
+    // Taken on the UI thread when the webview closes, and read by the thread
+    // that drops the webview.
+    runtime: Mutex<Option<Arc<IRuntime>>>,
+    channels: RwLock<HashMap<String, Arc<dyn ChannelHandler>>>,
+    dom: Arc<DomQueries>,
+    events: EventSubscribers,
+    metrics: Option<Arc<MetricsCollector>>,
+    dispatcher: Option<Arc<dyn Dispatcher>>,
+    handler: Arc<MixWebviewHnadler>,
+    drag_operation: AtomicU32,
+}
+
+impl WebViewContext {
+    fn acknowledge(&self, channel: *const c_char, frame: &FrameInfo) -> MessageAck {
+        // The answers of the DOM queries are not counted by the render process.
+        let reserved = !channel.is_null()
+            && unsafe { CStr::from_ptr(channel) }.to_bytes() == DOM_CHANNEL.as_bytes();
+
+        MessageAck {
+            id: self.id,
+            runtime: self.runtime.lock().clone(),
+            frame: CString::new(frame.id.as_str()).ok().filter(|_| !reserved),
+        }
+    }
+
+    fn channel(&self, name: *const c_char) -> Option<Arc<dyn ChannelHandler>> {
+        let name = unsafe { CStr::from_ptr(name) }.to_str().ok()?;
+
+        // The handler is cloned out of the lock, so that it can drop its own
+        // channel while handling a message.
+        self.channels.read().get(name).cloned()
+    }
+
+    fn spawn<F>(&self, task: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if let Some(dispatcher) = &self.dispatcher {
+            // The task runs outside of the callback, the panic is reported with the
+            // name of the task.
+            dispatcher.dispatch(Box::new(move || {
+                catch_panic_with_name(type_name::<F>(), task);
+            }));
+        } else {
+            task();
+        }
+    }
+
+    fn dispatch<F>(&self, callback: F)
+    where
+        F: FnOnce(WebViewId, &MixWebviewHnadler) + Send + 'static,
+    {
+        let id = self.id;
+        let handler = self.handler.clone();
+        self.spawn(move || {
+            catch_panic_with_name(type_name::<F>(), || callback(id, &handler));
+        });
+    }
+
+    fn update_drag_cursor(&self, operation: DragOperations) {
+        self.drag_operation
+            .store(operation.bits(), Ordering::Release);
+
+        // The cursor shows the badge of the preferred operation, in the same
+        // order as Chromium.
+        let ty = if operation.contains(DragOperations::Copy) {
+            CursorType::DndCopy
+        } else if operation.contains(DragOperations::Link) {
+            CursorType::DndLink
+        } else if operation.contains(DragOperations::Move) {
+            CursorType::DndMove
+        } else {
+            CursorType::DndNone
+        };
+
+        self.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_cursor_change(id, ty),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_update_drag_cursor(id, operation);
+                handler.on_cursor_change(id, ty)
+            }
+        });
+
+        self.events.send(|| WebViewEvent::CursorChange(ty));
+    }
+}
+
+fn measure_frame<F>(metrics: Option<&MetricsCollector>, size: usize, handler: F)
+where
+    F: FnOnce(),
+{
+    match metrics {
+        Some(metrics) => metrics.frame(size, handler),
+        None => handler(),
+    }
+}
+
+fn measure_message<F>(metrics: Option<&MetricsCollector>, handler: F)
+where
+    F: FnOnce(),
+{
+    match metrics {
+        Some(metrics) => metrics.message(handler),
+        None => handler(),
+    }
+}
+
+pub(crate) enum MixWebviewHnadler {
+    WebViewHandler(Box<dyn WebViewHandler>),
+    #[cfg_attr(not(feature = "windowless"), allow(dead_code))]
+    WindowlessRenderWebViewHandler(Box<dyn WindowlessRenderWebViewHandler>),
+}
+
+extern "C" fn on_state_change_callback(
+    change: *const sys::WebViewStateChange,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || change.is_null() {
+            return;
+        }
+
+        let state = WebViewState::from(unsafe { &*change });
+        let is_closed = state == WebViewState::Close;
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        // The document that runs the pending DOM queries is replaced.
+        if let WebViewState::BeforeLoad {
+            is_main_frame: true,
+            ..
+        } = state
+        {
+            context.dom.reset();
+        }
+
+        // Only after all webviews are closed can the runtime be closed. Here, we clear
+        // the reference held by the current webview.
+        //
+        // If all webviews are closed, the runtime reference will be cleared,
+        // and only then will the runtime's Drop be triggered.
+        if is_closed {
+            context.closed.store(true, Ordering::Release);
+
+            let runtime = context.runtime.lock().take();
+            if let Some(runtime) = &runtime {
+                runtime.webviews.lock().remove(&context.id);
+            }
+        }
+
+        {
+            let state = state.clone();
+            context.dispatch(move |id, handler| match handler {
+                MixWebviewHnadler::WebViewHandler(handler) => handler.on_state_change(id, state),
+                MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                    handler.on_state_change(id, state)
+                }
+            });
+        }
+
+        context.events.send(|| WebViewEvent::StateChange(state));
+
+        // No more events are sent after the webview is closed, so the streams can
+        // be ended.
+        if is_closed {
+            context.dispatch(|id, handler| match handler {
+                MixWebviewHnadler::WebViewHandler(handler) => handler.on_closed(id),
+                MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => handler.on_closed(id),
+            });
+
+            context.events.close();
+            context.dom.close();
+        }
+    });
+}
+
+extern "C" fn on_ime_rect_callback(rect: sys::Rect, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        let rect = Rect {
+            x: rect.x as u32,
+            y: rect.y as u32,
+            width: rect.width as u32,
+            height: rect.height as u32,
+        };
+
+        context.dispatch(move |id, handler| {
+            if let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = handler {
+                handler.on_ime_rect(id, rect)
+            }
+        });
+
+        context.events.send(|| WebViewEvent::ImeRect(rect));
+    });
+}
+
+extern "C" fn on_frame_callback(frame: *const sys::Frame, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() || frame.is_null() {
+            return;
+        }
+
+        let raw_frame = unsafe { &*frame };
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        let frame = Frame {
+            x: raw_frame.x,
+            y: raw_frame.y,
+            width: raw_frame.width,
+            height: raw_frame.height,
+            buffer: unsafe {
+                std::slice::from_raw_parts(
+                    raw_frame.buffer as *const u8,
+                    raw_frame.width as usize * raw_frame.height as usize * 4,
+                )
+            },
+            ty: if raw_frame.is_popup {
+                FrameType::Popup
+            } else {
+                FrameType::View
+            },
+        };
+
+        let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = &*context.handler else {
+            return;
+        };
+
+        if context.dispatcher.is_none() {
+            measure_frame(context.metrics.as_deref(), frame.buffer.len(), || {
+                handler.on_frame(context.id, &frame)
+            });
+
+            return;
+        }
+
+        // The buffer is only valid during this call, so it is copied for the
+        // dispatcher.
+        let buffer = frame.buffer.to_vec();
+        let metrics = context.metrics.clone();
+        let (ty, x, y, width, height) = (frame.ty, frame.x, frame.y, frame.width, frame.height);
+
+        context.dispatch(move |id, handler| {
+            if let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = handler {
+                let frame = Frame {
+                    ty,
+                    x,
+                    y,
+                    width,
+                    height,
+                    buffer: &buffer,
+                };
+
+                measure_frame(metrics.as_deref(), buffer.len(), || {
+                    handler.on_frame(id, &frame)
+                });
+            }
+        });
+    });
+}
+
+extern "C" fn on_screen_info_callback(info: *mut sys::ScreenInfo, context: *mut c_void) -> bool {
+    catch_panic(|| {
+        if context.is_null() || info.is_null() {
+            return false;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        // The screen info is returned to CEF, so the handler cannot be dispatched.
+        let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = &*context.handler else {
+            return false;
+        };
+
+        let Some(value) = handler.on_screen_info(context.id) else {
+            return false;
+        };
+
+        let info = unsafe { &mut *info };
+        info.device_scale_factor = value.device_scale_factor;
+        info.depth = value.depth as _;
+        info.depth_per_component = value.depth_per_component as _;
+        info.is_monochrome = value.is_monochrome;
+        info.rect = value.rect.into();
+        info.available_rect = value.available_rect.into();
+
+        true
+    })
+    .unwrap_or(false)
+}
+
+extern "C" fn on_start_dragging_callback(
+    data: *const sys::DragData,
+    allowed_ops: u32,
+    x: c_int,
+    y: c_int,
+    context: *mut c_void,
+) -> bool {
+    catch_panic(|| {
+        if context.is_null() || data.is_null() {
+            return false;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        // The result is returned to CEF, so the handler cannot be dispatched.
+        let MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) = &*context.handler else {
+            return false;
+        };
+
+        let data = unsafe { &*data };
+        let to_string = |it: *const c_char| {
+            if it.is_null() {
+                None
+            } else {
+                Some(unsafe { CStr::from_ptr(it) }.to_string_lossy().to_string())
+            }
+        };
+
+        let files = if data.files.is_null() {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(data.files, data.files_size) }
+        };
+
+        let data = DragData {
+            files: files
+                .iter()
+                .filter_map(|it| to_string(*it))
+                .map(PathBuf::from)
+                .collect(),
+            text: to_string(data.text),
+            url: to_string(data.url),
+        };
+
+        handler.on_start_dragging(
+            context.id,
+            &data,
+            DragOperations::from_bits_truncate(allowed_ops),
+            Position { x, y },
+        )
+    })
+    .unwrap_or(false)
+}
+
+extern "C" fn on_update_drag_cursor_callback(operation: u32, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        context.update_drag_cursor(DragOperations::from_bits_truncate(operation));
+    });
+}
+
+extern "C" fn on_clipboard_access_callback(
+    origin: *const c_char,
+    access: sys::ClipboardAccess,
+    context: *mut c_void,
+) -> sys::ClipboardPolicy {
+    catch_panic(|| {
+        if context.is_null() || origin.is_null() {
+            return ClipboardPolicy::Deny;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let origin = unsafe { CStr::from_ptr(origin) }.to_string_lossy();
+        let access = ClipboardAccess::from(access);
+
+        // The result is returned to the page, so the handler cannot be dispatched.
+        match &*context.handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_clipboard_access(context.id, &origin, access)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_clipboard_access(context.id, &origin, access)
+            }
+        }
+    })
+    .unwrap_or(ClipboardPolicy::Deny)
+    .into()
+}
+
+extern "C" fn on_take_focus_callback(next: bool, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        context.events.send(|| WebViewEvent::TakeFocus(next));
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_take_focus(id, next),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_take_focus(id, next)
+            }
+        });
+    });
+}
+
+extern "C" fn on_set_focus_callback(source: sys::FocusSource, context: *mut c_void) -> bool {
+    catch_panic(|| {
+        if context.is_null() {
+            return false;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let source = FocusSource::from(source);
+
+        // The result is returned to CEF, so the handler cannot be dispatched.
+        match &*context.handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_set_focus(context.id, source),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_set_focus(context.id, source)
+            }
+        }
+    })
+    .unwrap_or(false)
+}
+
+extern "C" fn on_got_focus_callback(context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        context.events.send(|| WebViewEvent::GotFocus);
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_got_focus(id),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => handler.on_got_focus(id),
+        });
+    });
+}
+
+extern "C" fn on_find_result_callback(
+    count: c_int,
+    active_index: c_int,
+    selection_rect: sys::Rect,
+    _final_update: bool,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let count = count.max(0) as u32;
+        let active_index = active_index.max(0) as u32;
+        let selection_rect = Rect {
+            x: selection_rect.x as u32,
+            y: selection_rect.y as u32,
+            width: selection_rect.width as u32,
+            height: selection_rect.height as u32,
+        };
+
+        context.events.send(|| WebViewEvent::FindResult {
+            count,
+            active_index,
+            selection_rect,
+        });
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_find_result(id, count, active_index, selection_rect)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_find_result(id, count, active_index, selection_rect)
+            }
+        });
+    });
+}
+
+extern "C" fn on_print_start_callback(context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        context.events.send(|| WebViewEvent::PrintStart);
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_print_start(id),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_print_start(id)
+            }
+        });
+    });
+}
+
+extern "C" fn on_print_finished_callback(context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        context.events.send(|| WebViewEvent::PrintFinished);
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_print_finished(id),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_print_finished(id)
+            }
+        });
+    });
+}
+
+extern "C" fn on_print_dialog_callback(has_selection: bool, context: *mut c_void) -> bool {
+    catch_panic(|| {
+        if context.is_null() {
+            return false;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        // The result is returned to CEF, so the handler cannot be dispatched.
+        match &*context.handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_print_dialog(context.id, has_selection)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_print_dialog(context.id, has_selection)
+            }
+        }
+    })
+    .unwrap_or(false)
+}
+
+extern "C" fn on_print_job_callback(
+    document_name: *const c_char,
+    pdf_file_path: *const c_char,
+    context: *mut c_void,
+) -> bool {
+    catch_panic(|| {
+        if context.is_null() || document_name.is_null() || pdf_file_path.is_null() {
+            return false;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let document_name = unsafe { CStr::from_ptr(document_name) }.to_string_lossy();
+        let Ok(path) = unsafe { CStr::from_ptr(pdf_file_path) }.to_str() else {
+            return false;
+        };
+
+        // The file is deleted after the callback returns, so the handler cannot
+        // be dispatched.
+        match &*context.handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_print_job(context.id, &document_name, Path::new(path))
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_print_job(context.id, &document_name, Path::new(path))
+            }
+        }
+    })
+    .unwrap_or(false)
+}
+
+extern "C" fn get_frames_callback(info: sys::FrameInfo, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let frames = unsafe { &mut *(context as *mut Vec<FrameInfo>) };
+        frames.push(FrameInfo::from(&info));
+    });
+}
+
+extern "C" fn on_title_change_callback(title: *const c_char, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() || title.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        if let Ok(title) = unsafe { CStr::from_ptr(title) }.to_str() {
+            let value = title.to_string();
+            context.dispatch(move |id, handler| match handler {
+                MixWebviewHnadler::WebViewHandler(handler) => handler.on_title_change(id, &value),
+                MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                    handler.on_title_change(id, &value)
+                }
+            });
+
+            context
+                .events
+                .send(|| WebViewEvent::TitleChange(title.to_string()));
+        }
+    });
+}
+extern "C" fn on_fullscreen_change_callback(fullscreen: bool, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_fullscreen_change(id, fullscreen)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_fullscreen_change(id, fullscreen)
+            }
+        });
+
+        context
+            .events
+            .send(|| WebViewEvent::FullscreenChange(fullscreen));
+    });
+}
+
+extern "C" fn on_render_process_terminated_callback(
+    status: sys::RenderProcessTerminationStatus,
+    error_code: c_int,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let status = TerminationStatus::from(status);
+
+        // The page that runs the pending DOM queries is gone.
+        context.dom.reset();
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_render_process_terminated(id, status, error_code)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_render_process_terminated(id, status, error_code)
+            }
+        });
+
+        context
+            .events
+            .send(|| WebViewEvent::RenderProcessTerminated { status, error_code });
+    });
+}
+
+extern "C" fn on_page_error_callback(
+    error: *const sys::PageError,
+    frame: sys::FrameInfo,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || error.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let frame = FrameInfo::from(&frame);
+        let error = PageError::from(unsafe { &*error });
+
+        context.events.send(|| WebViewEvent::PageError {
+            error: error.clone(),
+            frame: frame.clone(),
+        });
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_page_error(id, &error, &frame),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_page_error(id, &error, &frame)
+            }
+        });
+    });
+}
+
+type DownloadImageCallback = Box<dyn FnOnce(Option<Image>) + Send>;
+
+extern "C" fn download_image_callback(image: *const sys::Image, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let callback = unsafe { Box::from_raw(context as *mut DownloadImageCallback) };
+        callback(if image.is_null() {
+            None
+        } else {
+            Some(Image::from(unsafe { &*image }))
+        });
+    });
+}
+
+type LayoutMetricsCallback = Box<dyn FnOnce(Option<LayoutMetrics>) + Send>;
+
+extern "C" fn layout_metrics_callback(metrics: *const sys::LayoutMetrics, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let callback = unsafe { Box::from_raw(context as *mut LayoutMetricsCallback) };
+        callback(if metrics.is_null() {
+            None
+        } else {
+            Some(LayoutMetrics::from(unsafe { &*metrics }))
+        });
+    });
+}
+
+extern "C" fn on_favicon_change_callback(
+    urls: *mut *const c_char,
+    size: usize,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let urls = if urls.is_null() {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(urls, size) }
+                .iter()
+                .map(|it| from_c_str(*it))
+                .collect::<Vec<_>>()
+        };
+
+        context
+            .events
+            .send(|| WebViewEvent::FaviconChange(urls.clone()));
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_favicon_change(id, &urls),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_favicon_change(id, &urls)
+            }
+        });
+    });
+}
+
+extern "C" fn on_status_message_callback(message: *const c_char, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let message = from_c_str(message);
+
+        context
+            .events
+            .send(|| WebViewEvent::StatusMessage(message.clone()));
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_status_message(id, &message),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_status_message(id, &message)
+            }
+        });
+    });
+}
+
+extern "C" fn on_main_frame_response_callback(
+    response: *const sys::HttpResponse,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || response.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let response = HttpResponse::from(unsafe { &*response });
+
+        context
+            .events
+            .send(|| WebViewEvent::MainFrameResponse(response.clone()));
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_main_frame_response(id, &response)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_main_frame_response(id, &response)
+            }
+        });
+    });
+}
+
+extern "C" fn on_pdf_load_callback(document: *const sys::PdfDocument, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() || document.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let document = unsafe { &*document };
+        let document = PdfDocument {
+            url: from_c_str(document.url),
+            page_count: document.page_count,
+            outline: PdfOutlineItem::from_array(
+                serde_json::from_str(&from_c_str(document.outline))
+                    .ok()
+                    .as_ref(),
+            ),
+        };
+
+        context
+            .events
+            .send(|| WebViewEvent::PdfLoad(document.clone()));
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_pdf_load(id, &document),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_pdf_load(id, &document)
+            }
+        });
+    });
+}
+
+extern "C" fn on_console_message_callback(
+    level: sys::LogLevel,
+    message: *const c_char,
+    source: *const c_char,
+    line: c_int,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let level = LogSeverity::from(level);
+        let message = from_c_str(message);
+        let source = from_c_str(source);
+        let line = line.max(0) as u32;
+
+        context.events.send(|| WebViewEvent::ConsoleMessage {
+            level,
+            message: message.clone(),
+            source: source.clone(),
+            line,
+        });
+
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => {
+                handler.on_console_message(id, level, &message, &source, line)
+            }
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_console_message(id, level, &message, &source, line)
+            }
+        });
+    });
+}
+
+extern "C" fn on_message_callback(
+    channel: *const c_char,
+    message: *const c_char,
+    frame: sys::FrameInfo,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || message.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let frame = FrameInfo::from(&frame);
+
+        if let Ok(message) = unsafe { CStr::from_ptr(message) }.to_str() {
+            let message = message.to_string();
+            let metrics = context.metrics.clone();
+
+            let ack = context.acknowledge(channel, &frame);
+
+            if !channel.is_null() {
+                if let Some(handler) = context.channel(channel) {
+                    context.spawn(move || {
+                        let _ack = ack;
+                        measure_message(metrics.as_deref(), || handler.on_message(&message, &frame))
+                    });
+                }
+
+                return;
+            }
+
+            context.events.send(|| WebViewEvent::Message {
+                message: message.clone(),
+                frame: frame.clone(),
+            });
+
+            context.dispatch(move |id, handler| {
+                let _ack = ack;
+                measure_message(metrics.as_deref(), || match handler {
+                    MixWebviewHnadler::WebViewHandler(handler) => {
+                        handler.on_message(id, &message, &frame)
+                    }
+                    MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                        handler.on_message(id, &message, &frame)
+                    }
+                })
+            });
+        }
+    });
+}
+
+extern "C" fn on_binary_message_callback(
+    channel: *const c_char,
+    message: *const u8,
+    size: usize,
+    frame: sys::FrameInfo,
+    context: *mut c_void,
+) {
+    catch_panic(|| {
+        if context.is_null() || message.is_null() {
+            return;
+        }
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        let message = unsafe { std::slice::from_raw_parts(message, size) }.to_vec();
+        let frame = FrameInfo::from(&frame);
+        let metrics = context.metrics.clone();
+        let ack = context.acknowledge(channel, &frame);
+
+        if !channel.is_null() {
+            if let Some(handler) = context.channel(channel) {
+                context.spawn(move || {
+                    let _ack = ack;
+                    measure_message(metrics.as_deref(), || {
+                        handler.on_binary_message(&message, &frame)
+                    })
+                });
+            }
+
+            return;
+        }
+
+        context.events.send(|| WebViewEvent::BinaryMessage {
+            message: message.clone(),
+            frame: frame.clone(),
+        });
+
+        context.dispatch(move |id, handler| {
+            let _ack = ack;
+            measure_message(metrics.as_deref(), || match handler {
+                MixWebviewHnadler::WebViewHandler(handler) => {
+                    handler.on_binary_message(id, &message, &frame)
+                }
+                MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                    handler.on_binary_message(id, &message, &frame)
+                }
+            })
+        });
+    });
+}
+
+extern "C" fn on_cursor_callback(ty: sys::CursorType, context: *mut c_void) {
+    catch_panic(|| {
+        if context.is_null() {
+            return;
+        }
+
+        let ty = unsafe { std::mem::transmute::<sys::CursorType, CursorType>(ty) };
+
+        let context = unsafe { &*(context as *mut WebViewContext) };
+        context.dispatch(move |id, handler| match handler {
+            MixWebviewHnadler::WebViewHandler(handler) => handler.on_cursor_change(id, ty),
+            MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => {
+                handler.on_cursor_change(id, ty)
+            }
+        });
+
+        context.events.send(|| WebViewEvent::CursorChange(ty));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(attr: WebViewAttributes) -> Option<&'static str> {
+        match attr.validate() {
+            Err(Error::InvalidAttribute { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn rejected_native_window(attr: WebViewAttributes) -> Option<&'static str> {
+        match attr.validate_native_window() {
+            Err(Error::InvalidAttribute { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn default_attributes_are_valid() {
+        assert!(WebViewAttributes::default().validate().is_ok());
+        assert!(
+            WebViewAttributes::default()
+                .validate_native_window()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn invalid_sizes_are_rejected() {
+        let attr = WebViewAttributes {
+            width: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("width"));
+
+        let attr = WebViewAttributes {
+            height: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("height"));
+
+        for device_scale_factor in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let attr = WebViewAttributes {
+                device_scale_factor,
+                ..Default::default()
+            };
+
+            assert_eq!(rejected(attr), Some("device_scale_factor"));
+        }
+
+        for windowless_frame_rate in [0, 241] {
+            let attr = WebViewAttributes {
+                windowless_frame_rate,
+                ..Default::default()
+            };
+
+            assert_eq!(rejected(attr), Some("windowless_frame_rate"));
+        }
+    }
+
+    #[test]
+    fn strings_with_nul_are_rejected() {
+        let attr = WebViewAttributes {
+            font_families: FontFamilies {
+                serif: Some("Serif\0".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("font_families"));
+
+        let attr = WebViewAttributes {
+            default_encoding: Some("utf\0-8".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("default_encoding"));
+
+        let attr = WebViewAttributes {
+            user_agent: Some("\0".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("user_agent"));
+
+        let attr = WebViewAttributes {
+            kiosk_policy: Some(KioskPolicy {
+                allowed_origins: vec!["https://example.com\0".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("kiosk_policy"));
+    }
+
+    #[test]
+    fn unsupported_window_handles_are_rejected() {
+        let attr = WebViewAttributes {
+            window_handle: Some(RawWindowHandle::Web(
+                raw_window_handle::WebWindowHandle::new(1),
+            )),
+            ..Default::default()
+        };
+
+        assert_eq!(rejected(attr), Some("window_handle"));
+    }
+
+    #[test]
+    fn windowless_options_are_rejected_for_native_windows() {
+        let attr = WebViewAttributes {
+            coalesce_mouse_moves: true,
+            ..Default::default()
+        };
+
+        assert!(attr.validate().is_ok());
+        assert_eq!(rejected_native_window(attr), Some("coalesce_mouse_moves"));
+
+        let attr = WebViewAttributes {
+            ctrl_wheel_zoom: true,
+            ..Default::default()
+        };
 
-    let context = unsafe { &*(context as *mut WebViewContext) };
-    match &context.handler {
-        MixWebviewHnadler::WebViewHandler(handler) => handler.on_cursor_change(ty),
-        MixWebviewHnadler::WindowlessRenderWebViewHandler(handler) => handler.on_cursor_change(ty),
+        assert_eq!(rejected_native_window(attr), Some("ctrl_wheel_zoom"));
     }
 }